@@ -7,7 +7,7 @@ use anyhow::{Context, Result};
 use clap::Parser;
 use karukan_engine::kana::normalize_nfkc;
 use karukan_engine::kanji::{
-    KanjiError, LlamaCppModel, build_jinen_prompt, clean_model_output, get_path_by_id,
+    KanjiError, LlamaCppModel, PromptFormat, clean_model_output, get_path_by_id,
     get_tokenizer_path_by_id, registry,
 };
 use serde::{Deserialize, Serialize};
@@ -148,19 +148,20 @@ fn calculate_min_cer(hypothesis: &str, references: &[String]) -> f64 {
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Load model
-    let model = if let Some(gguf_path) = &cli.gguf {
+    // Load model (custom GGUF files are assumed to use the jinen prompt format)
+    let (model, prompt_format) = if let Some(gguf_path) = &cli.gguf {
         let tok_path = cli
             .tokenizer_json
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("--tokenizer-json is required when using --gguf"))?;
         eprintln!("Loading GGUF from {}...", gguf_path.display());
-        LlamaCppModel::from_file_with_n_ctx(gguf_path, tok_path, cli.n_ctx)
-            .with_context(|| format!("Failed to load GGUF from {}", gguf_path.display()))?
+        let model = LlamaCppModel::from_file_with_n_ctx(gguf_path, tok_path, cli.n_ctx)
+            .with_context(|| format!("Failed to load GGUF from {}", gguf_path.display()))?;
+        (model, PromptFormat::Jinen)
     } else {
         let reg = registry();
         let variant_id = &cli.model;
-        let (_family, _variant) = reg
+        let (family, _variant) = reg
             .find_variant(variant_id)
             .ok_or(KanjiError::UnknownVariant(variant_id.to_string()))?;
 
@@ -169,7 +170,8 @@ fn main() -> Result<()> {
         let tok_path = get_tokenizer_path_by_id(variant_id)?;
         eprintln!("Model path: {}", path.display());
         eprintln!("Tokenizer: {}", tok_path.display());
-        LlamaCppModel::from_file_with_n_ctx(&path, &tok_path, cli.n_ctx)?
+        let model = LlamaCppModel::from_file_with_n_ctx(&path, &tok_path, cli.n_ctx)?;
+        (model, family.prompt_format.clone())
     };
 
     let eos = Some(model.eos_token_id().0);
@@ -203,7 +205,7 @@ fn main() -> Result<()> {
         };
 
         // Build prompt and generate
-        let prompt = build_jinen_prompt(reading, context);
+        let prompt = prompt_format.build(reading, context);
         let tokens = model
             .tokenize(&prompt)
             .with_context(|| format!("Failed to tokenize example {}", idx + 1))?;
@@ -6,7 +6,7 @@ use axum::{
     routing::get,
 };
 use clap::{Parser, Subcommand};
-use karukan_engine::dict::Dictionary;
+use karukan_engine::dict::{DictStats, Dictionary};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -75,6 +75,10 @@ enum Commands {
         /// Show all entries (dump entire dictionary)
         #[arg(short, long)]
         all: bool,
+
+        /// Show entry counts, reading length histogram and score range
+        #[arg(long)]
+        stats: bool,
     },
 }
 
@@ -211,6 +215,25 @@ async fn api_search(
     })
 }
 
+fn print_stats(stats: &DictStats) {
+    println!("entries:            {}", stats.entry_count);
+    println!("candidates:         {}", stats.candidate_count);
+    println!(
+        "candidates/reading: {:.2} (max {})",
+        stats.avg_candidates, stats.max_candidates
+    );
+    if let (Some(min), Some(max), Some(mean)) = (stats.score_min, stats.score_max, stats.score_mean)
+    {
+        println!("score:              {} .. {} (mean {:.2})", min, max, mean);
+    }
+    println!("reading length:");
+    for (len, &count) in stats.reading_length_histogram.iter().enumerate() {
+        if count > 0 {
+            println!("  {:>3}  {}", len, count);
+        }
+    }
+}
+
 fn mode_name(mode: SearchMode) -> &'static str {
     match mode {
         SearchMode::Reading => "reading",
@@ -219,18 +242,11 @@ fn mode_name(mode: SearchMode) -> &'static str {
     }
 }
 
-async fn run_view(
-    dicts: Vec<PathBuf>,
-    port: u16,
-    host: String,
-    query: Option<String>,
-    surface: bool,
-    prefix: bool,
-    all: bool,
-) -> Result<()> {
+/// Load the given dictionaries, merged into one when there are several
+fn load_dictionaries(dicts: &[PathBuf]) -> Result<Dictionary> {
     eprintln!("Loading dictionaries...");
     let mut loaded = Vec::new();
-    for path in &dicts {
+    for path in dicts {
         eprintln!("  Loading {:?}...", path);
         let dict = Dictionary::load_auto(path)?;
         loaded.push(dict);
@@ -244,6 +260,25 @@ async fn run_view(
     };
 
     eprintln!("Dictionary loaded.");
+    Ok(dict)
+}
+
+/// CLI mode: --stats
+fn run_stats(dicts: &[PathBuf]) -> Result<()> {
+    print_stats(&load_dictionaries(dicts)?.stats());
+    Ok(())
+}
+
+async fn run_view(
+    dicts: Vec<PathBuf>,
+    port: u16,
+    host: String,
+    query: Option<String>,
+    surface: bool,
+    prefix: bool,
+    all: bool,
+) -> Result<()> {
+    let dict = load_dictionaries(&dicts)?;
 
     // CLI mode: --all
     if all {
@@ -324,6 +359,9 @@ async fn main() -> Result<()> {
             output,
             format,
         } => run_build(input, output, format),
+        Commands::View {
+            dicts, stats: true, ..
+        } => run_stats(&dicts),
         Commands::View {
             dicts,
             port,
@@ -332,6 +370,7 @@ async fn main() -> Result<()> {
             surface,
             prefix,
             all,
+            stats: false,
         } => run_view(dicts, port, host, query, surface, prefix, all).await,
     }
 }
@@ -1,19 +1,24 @@
 use axum::{
     Json, Router,
-    extract::{DefaultBodyLimit, State},
+    extract::{
+        DefaultBodyLimit, Query, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 use clap::Parser;
-use karukan_engine::RomajiConverter;
 use karukan_engine::kana::hiragana_to_katakana;
 use karukan_engine::kanji::{
-    LlamaCppModel, LlamaToken, build_jinen_prompt, clean_model_output, get_tokenizer_path,
-    get_variant_path, registry,
+    ConversionConfig, KanjiError, LlamaCppModel, LlamaToken, ModelFamily, SamplingParams,
+    build_prompt, clean_model_output, get_tokenizer_path, get_variant_path, registry,
 };
+use karukan_engine::{ConversionEvent, Dictionary, LookupResult, RomajiConverter};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use tower_http::{
     cors::{Any, CorsLayer},
@@ -41,12 +46,28 @@ struct Args {
     /// Host to bind to
     #[arg(long, default_value = "127.0.0.1")]
     host: String,
+
+    /// Run a throwaway generation after loading each model so the first
+    /// request is not slowed down by lazy initialization (`--warmup false` to skip)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    warmup: bool,
+
+    /// System dictionary for /api/dict/lookup (binary, Mozc TSV, SKK or Sudachi CSV)
+    #[arg(long)]
+    dict: Option<PathBuf>,
+
+    /// Most new tokens generated per conversion. Each conversion gets the
+    /// reading's token count plus a margin, capped at this value.
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u64).range(1..))]
+    max_new_tokens: u64,
 }
 
 #[derive(Clone)]
 struct LlamaCppModelInfo {
     model: Arc<LlamaCppModel>,
     display_name: String,
+    /// Registry family the model belongs to (prompt format etc.)
+    family: &'static ModelFamily,
 }
 
 #[derive(Clone)]
@@ -54,8 +75,12 @@ struct AppState {
     converter: Arc<RwLock<RomajiConverter>>,
     /// llama.cpp models (keyed by model id, e.g., "llamacpp-jawiki-unigram-12k-q5")
     llamacpp_models: Arc<RwLock<HashMap<String, LlamaCppModelInfo>>>,
+    /// System dictionary (--dict), if one was loaded
+    dictionary: Option<Arc<Dictionary>>,
     /// Debug mode enabled (--debug flag)
     debug_mode: bool,
+    /// Generation limits (`max_new_tokens` from --max-new-tokens)
+    conversion: ConversionConfig,
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,6 +94,8 @@ struct ConvertRequest {
 struct ConvertResponse {
     output: String,
     buffer: String,
+    /// Event of each input character, in order ("Converted", "Buffered" or "PassThrough")
+    events: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -86,12 +113,35 @@ struct KanjiConvertRequest {
     /// - "d1_greedy": Depth-1 beam selection followed by greedy decoding (faster)
     #[serde(default)]
     beam_search_type: Option<String>,
+    /// Repetition penalty for greedy decoding (1.0 = disabled)
+    #[serde(default = "default_repeat_penalty")]
+    repeat_penalty: f32,
 }
 
 fn default_num_candidates() -> usize {
     1
 }
 
+#[derive(Debug, Deserialize)]
+struct KanjiConvertBatchRequest {
+    /// Readings to convert (hiragana), all with the same context
+    hiragana: Vec<String>,
+    #[serde(default)]
+    context: String,
+    #[serde(default = "default_num_candidates")]
+    num_candidates: usize,
+    /// Model to use (optional, uses default if not specified)
+    #[serde(default)]
+    model: Option<String>,
+    /// Repetition penalty for greedy decoding (1.0 = disabled)
+    #[serde(default = "default_repeat_penalty")]
+    repeat_penalty: f32,
+}
+
+fn default_repeat_penalty() -> f32 {
+    1.0
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct TokenVisualization {
     /// Token ID
@@ -142,11 +192,81 @@ struct KanjiConvertResponse {
     beam_search_type: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct KanjiConvertBatchResponse {
+    /// Candidate lists, one per input reading (in input order)
+    results: Vec<Vec<String>>,
+    /// Total inference time for the whole batch
+    inference_time_ms: f64,
+    model: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WsConvertRequest {
+    hiragana: String,
+    #[serde(default)]
+    context: String,
+    /// Model to use (optional, uses default if not specified)
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Frame sent over `/ws/convert`: partial text while generating, then the
+/// candidates (or an error) with `done: true`
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum WsConvertFrame {
+    Partial {
+        partial: String,
+        done: bool,
+    },
+    Final {
+        candidates: Vec<String>,
+        done: bool,
+        inference_time_ms: f64,
+    },
+    Error {
+        error: String,
+        done: bool,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct DictLookupQuery {
+    reading: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DictCandidateInfo {
+    surface: String,
+    score: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct DictEntryInfo {
+    reading: String,
+    candidates: Vec<DictCandidateInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct DictLookupResponse {
+    reading: String,
+    /// Entry whose reading is exactly the query
+    exact: Option<DictEntryInfo>,
+    /// Entries whose reading is a prefix of the query (shortest first)
+    prefix: Vec<DictEntryInfo>,
+}
+
 #[derive(Debug, Serialize)]
 struct ModelInfo {
     id: String,
     name: String,
     model_id: String,
+    family: String,
+    /// GGUF file is in the local HuggingFace cache
+    downloaded: bool,
+    /// Model is loaded and can be used for conversion
+    loaded: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -207,6 +327,10 @@ async fn main() {
                     model_id,
                     tok_path.display()
                 );
+                let gpu_layers = family.gpu_layers_for(variant);
+                if gpu_layers > 0 {
+                    tracing::info!("Offloading {} layers of '{}' to GPU", gpu_layers, model_id);
+                }
                 let load_result = if let Some(ref pre_tok) = family.pre_tokenizer_override {
                     tracing::info!(
                         "Model '{}' requires pre-tokenizer override: '{}'",
@@ -214,19 +338,34 @@ async fn main() {
                         pre_tok
                     );
                     LlamaCppModel::from_file_with_pre_tokenizer_override(
-                        &path_str, &tok_path, pre_tok,
+                        &path_str, &tok_path, pre_tok, gpu_layers,
                     )
                 } else {
-                    LlamaCppModel::from_file(&path_str, &tok_path)
+                    LlamaCppModel::from_file_with_gpu_layers(&path_str, &tok_path, gpu_layers)
                 };
                 match load_result {
                     Ok(model) => {
                         tracing::info!("llama.cpp model '{}' loaded successfully", model_id);
+                        if args.warmup {
+                            match warmup_model(&model, family) {
+                                Ok(elapsed) => tracing::info!(
+                                    "llama.cpp model '{}' warmed up in {:.1}ms",
+                                    model_id,
+                                    elapsed.as_secs_f64() * 1000.0
+                                ),
+                                Err(e) => tracing::warn!(
+                                    "Failed to warm up llama.cpp model '{}': {}",
+                                    model_id,
+                                    e
+                                ),
+                            }
+                        }
                         llamacpp_models.insert(
                             model_id.to_string(),
                             LlamaCppModelInfo {
                                 model: Arc::new(model),
                                 display_name: display_name.to_string(),
+                                family,
                             },
                         );
                     }
@@ -255,10 +394,29 @@ async fn main() {
         tracing::info!("Debug mode enabled - tokenization API available at /api/tokenize");
     }
 
+    let dictionary = args
+        .dict
+        .as_ref()
+        .and_then(|path| match Dictionary::load_auto(path) {
+            Ok(dict) => {
+                tracing::info!("Loaded dictionary from {}", path.display());
+                Some(Arc::new(dict))
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load dictionary {}: {}", path.display(), e);
+                None
+            }
+        });
+
     let state = AppState {
         converter: Arc::new(RwLock::new(RomajiConverter::new())),
         llamacpp_models: Arc::new(RwLock::new(llamacpp_models)),
+        dictionary,
         debug_mode: args.debug,
+        conversion: ConversionConfig {
+            max_new_tokens: args.max_new_tokens as usize,
+            ..ConversionConfig::default()
+        },
     };
 
     // Setup CORS
@@ -272,7 +430,13 @@ async fn main() {
         .route("/api/convert", post(convert_handler))
         .route("/api/reset", post(reset_handler))
         .route("/api/kanji/convert", post(kanji_convert_handler))
+        .route(
+            "/api/kanji/convert_batch",
+            post(kanji_convert_batch_handler),
+        )
+        .route("/ws/convert", get(ws_convert_handler))
         .route("/api/models", get(models_handler))
+        .route("/api/dict/lookup", get(dict_lookup_handler))
         .route("/health", get(health_handler));
 
     // Add debug-only routes
@@ -299,6 +463,60 @@ async fn main() {
         .expect("failed to run server");
 }
 
+/// New token budget for a katakana reading: its token count plus a margin,
+/// capped at `--max-new-tokens`
+fn token_budget(
+    conversion: &ConversionConfig,
+    model: &LlamaCppModel,
+    katakana: &str,
+) -> Result<usize, KanjiError> {
+    let input_tokens = model.tokenize(katakana)?.len();
+    Ok(conversion.new_token_budget(input_tokens))
+}
+
+/// Prompt tokens and new token budget for a katakana reading after `context`.
+///
+/// Every conversion endpoint goes through this, so a reading gets the same
+/// budget whether it is converted alone or in a batch.
+fn prompt_and_budget(
+    conversion: &ConversionConfig,
+    model: &LlamaCppModel,
+    family: &ModelFamily,
+    katakana: &str,
+    context: &str,
+) -> Result<(Vec<LlamaToken>, usize), KanjiError> {
+    let prompt = model.tokenize(&build_prompt(family, katakana, context))?;
+    Ok((prompt, token_budget(conversion, model, katakana)?))
+}
+
+/// [`prompt_and_budget`] for each hiragana reading of a batch
+fn batch_prompts_and_budgets(
+    conversion: &ConversionConfig,
+    model: &LlamaCppModel,
+    family: &ModelFamily,
+    hiragana: &[String],
+    context: &str,
+) -> Result<(Vec<Vec<LlamaToken>>, Vec<usize>), KanjiError> {
+    hiragana
+        .iter()
+        .map(|h| prompt_and_budget(conversion, model, family, &hiragana_to_katakana(h), context))
+        .collect::<Result<Vec<_>, _>>()
+        .map(|pairs| pairs.into_iter().unzip())
+}
+
+/// Prime the inference context and threads with a short throwaway generation.
+///
+/// Returns the time it took.
+fn warmup_model(
+    model: &LlamaCppModel,
+    family: &ModelFamily,
+) -> Result<std::time::Duration, KanjiError> {
+    let start = std::time::Instant::now();
+    let tokens = model.tokenize(&build_prompt(family, "テスト", ""))?;
+    model.generate(&tokens, 4, Some(model.eos_token_id().0))?;
+    Ok(start.elapsed())
+}
+
 async fn convert_handler(
     State(state): State<AppState>,
     Json(req): Json<ConvertRequest>,
@@ -311,18 +529,30 @@ async fn convert_handler(
     }
 
     // Process each character
-    for ch in req.input.chars() {
-        converter.push(ch);
-    }
+    let events = converter
+        .push_all(&req.input)
+        .iter()
+        .map(|event| event_name(event).to_string())
+        .collect();
 
     let response = ConvertResponse {
         output: converter.output().to_string(),
         buffer: converter.buffer().to_string(),
+        events,
     };
 
     Ok(Json(response))
 }
 
+/// Name of a romaji conversion event as reported by `/api/convert`
+fn event_name(event: &ConversionEvent) -> &'static str {
+    match event {
+        ConversionEvent::Converted(_) => "Converted",
+        ConversionEvent::Buffered => "Buffered",
+        ConversionEvent::PassThrough(_) => "PassThrough",
+    }
+}
+
 async fn reset_handler(State(state): State<AppState>) -> impl IntoResponse {
     let mut converter = state.converter.write().expect("lock poisoned");
     converter.reset();
@@ -348,20 +578,29 @@ fn resolve_default_model_id(models: &HashMap<String, LlamaCppModelInfo>) -> Stri
     }
 }
 
-async fn models_handler(State(state): State<AppState>) -> impl IntoResponse {
-    let llamacpp_models = state.llamacpp_models.read().expect("lock poisoned");
-
-    let mut models: Vec<ModelInfo> = llamacpp_models
-        .iter()
-        .map(|(model_id, info)| ModelInfo {
-            id: model_id.clone(),
-            name: info.display_name.clone(),
-            model_id: model_id.clone(),
+/// Every registered model, with whether it is downloaded and loaded
+fn model_infos(loaded: &HashMap<String, LlamaCppModelInfo>) -> Vec<ModelInfo> {
+    let mut models: Vec<ModelInfo> = registry()
+        .list()
+        .into_iter()
+        .map(|summary| ModelInfo {
+            loaded: loaded.contains_key(&summary.id),
+            model_id: summary.id.clone(),
+            id: summary.id,
+            name: summary.display_name,
+            family: summary.family,
+            downloaded: summary.downloaded,
         })
         .collect();
 
     // Sort models by name
     models.sort_by(|a, b| a.name.cmp(&b.name));
+    models
+}
+
+async fn models_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let llamacpp_models = state.llamacpp_models.read().expect("lock poisoned");
+    let models = model_infos(&llamacpp_models);
 
     let default_model = resolve_default_model_id(&llamacpp_models);
 
@@ -376,23 +615,291 @@ async fn kanji_convert_handler(
     Json(req): Json<KanjiConvertRequest>,
 ) -> Result<Json<KanjiConvertResponse>, (StatusCode, String)> {
     let katakana = hiragana_to_katakana(&req.hiragana);
+    let model_id = resolve_request_model_id(&state, req.model.as_deref())?;
+    llamacpp_convert(&state, &req, &katakana, &model_id).await
+}
 
-    // Determine which model to use
-    let model_id = if let Some(ref model_str) = req.model {
-        model_str.clone()
+/// Determine which model to use: the requested one, or the default loaded model
+fn resolve_request_model_id(
+    state: &AppState,
+    model: Option<&str>,
+) -> Result<String, (StatusCode, String)> {
+    if let Some(model_str) = model {
+        return Ok(model_str.to_string());
+    }
+    let llamacpp_models = state.llamacpp_models.read().expect("lock poisoned");
+    let default_id = resolve_default_model_id(&llamacpp_models);
+    if default_id.is_empty() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No models loaded".to_string(),
+        ));
+    }
+    Ok(default_id)
+}
+
+/// Convert many readings in one request.
+///
+/// Greedy conversions (`num_candidates` = 1) share one llama.cpp context;
+/// beam search runs per reading. Returns one candidate list per reading.
+async fn kanji_convert_batch_handler(
+    State(state): State<AppState>,
+    Json(req): Json<KanjiConvertBatchRequest>,
+) -> Result<Json<KanjiConvertBatchResponse>, (StatusCode, String)> {
+    let model_id = resolve_request_model_id(&state, req.model.as_deref())?;
+
+    let models_guard = state.llamacpp_models.read().expect("lock poisoned");
+    let model_info = models_guard.get(&model_id).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("llama.cpp model '{}' not loaded", model_id),
+        )
+    })?;
+    let model = &model_info.model;
+
+    let internal_error = |what: &str, e: KanjiError| {
+        tracing::error!("llama.cpp {} error: {}", what, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("{} error: {}", what, e),
+        )
+    };
+
+    let start = std::time::Instant::now();
+
+    let (prompts, budgets) = batch_prompts_and_budgets(
+        &state.conversion,
+        model,
+        model_info.family,
+        &req.hiragana,
+        &req.context,
+    )
+    .map_err(|e| internal_error("Tokenize", e))?;
+    let eos_token_id = Some(model.eos_token_id().0);
+    let beam_size = req.num_candidates.clamp(1, 20);
+
+    let mut results = Vec::with_capacity(prompts.len());
+    if beam_size == 1 {
+        let params = SamplingParams {
+            repeat_penalty: req.repeat_penalty,
+            ..SamplingParams::default()
+        };
+        let outputs = model
+            .generate_batch(&prompts, &budgets, eos_token_id, params)
+            .map_err(|e| internal_error("Generate", e))?;
+        for ((hiragana, prompt), output) in req.hiragana.iter().zip(&prompts).zip(outputs) {
+            let text = model
+                .decode(&output[prompt.len()..], true)
+                .map_err(|e| internal_error("Decode", e))?;
+            let text = clean_model_output(&text);
+            results.push(vec![if text.is_empty() {
+                hiragana.clone()
+            } else {
+                text
+            }]);
+        }
     } else {
-        let llamacpp_models = state.llamacpp_models.read().expect("lock poisoned");
-        let default_id = resolve_default_model_id(&llamacpp_models);
-        if default_id.is_empty() {
-            return Err((
-                StatusCode::SERVICE_UNAVAILABLE,
-                "No models loaded".to_string(),
-            ));
+        for ((hiragana, prompt), max_new_tokens) in req.hiragana.iter().zip(&prompts).zip(budgets) {
+            let beam_results = model
+                .generate_beam_search(prompt, max_new_tokens, eos_token_id, beam_size)
+                .map_err(|e| internal_error("Generate", e))?;
+            let mut candidates: Vec<String> = Vec::new();
+            for (generated_tokens, _score) in beam_results {
+                let text = model
+                    .decode(&generated_tokens, true)
+                    .map_err(|e| internal_error("Decode", e))?;
+                let text = clean_model_output(&text);
+                if !text.is_empty() && !candidates.contains(&text) {
+                    candidates.push(text);
+                }
+            }
+            if candidates.is_empty() {
+                candidates.push(hiragana.clone());
+            }
+            results.push(candidates);
         }
-        default_id
+    }
+
+    Ok(Json(KanjiConvertBatchResponse {
+        results,
+        inference_time_ms: start.elapsed().as_secs_f64() * 1000.0,
+        model: model_info.display_name.clone(),
+    }))
+}
+
+/// Look up a reading in the system dictionary (exact match and common prefixes)
+async fn dict_lookup_handler(
+    State(state): State<AppState>,
+    Query(query): Query<DictLookupQuery>,
+) -> Result<Json<DictLookupResponse>, (StatusCode, String)> {
+    let dict = state.dictionary.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No dictionary loaded (start the server with --dict <path>)".to_string(),
+        )
+    })?;
+
+    let entry_info = |result: LookupResult<'_>| DictEntryInfo {
+        reading: result.reading.to_string(),
+        candidates: result
+            .candidates
+            .iter()
+            .map(|c| DictCandidateInfo {
+                surface: c.surface.clone(),
+                score: c.score,
+            })
+            .collect(),
     };
 
-    llamacpp_convert(&state, &req, &katakana, &model_id).await
+    Ok(Json(DictLookupResponse {
+        exact: dict.exact_match_search(&query.reading).map(entry_info),
+        prefix: dict
+            .common_prefix_search(&query.reading)
+            .into_iter()
+            .map(entry_info)
+            .collect(),
+        reading: query.reading,
+    }))
+}
+
+/// Stream a greedy conversion over a WebSocket.
+///
+/// Each text message is a [`WsConvertRequest`]; the reply is a series of
+/// `{partial, done: false}` frames as tokens are generated, ending with
+/// `{candidates, done: true, inference_time_ms}` or `{error, done: true}`.
+async fn ws_convert_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
+    ws.on_upgrade(move |socket| {
+        serve_ws_convert(socket, move |req, on_partial| {
+            llamacpp_convert_streaming(&state, req, on_partial)
+        })
+    })
+}
+
+/// Answer conversion requests on `socket` until the client closes it.
+///
+/// `convert` runs on a blocking thread; the partials it reports are forwarded
+/// to the client while it is still generating.
+async fn serve_ws_convert<F>(mut socket: WebSocket, convert: F)
+where
+    F: Fn(&WsConvertRequest, &mut dyn FnMut(String)) -> Result<Vec<String>, (StatusCode, String)>
+        + Send
+        + Sync
+        + 'static,
+{
+    let convert = Arc::new(convert);
+    while let Some(Ok(message)) = socket.recv().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let req: WsConvertRequest = match serde_json::from_str(&text) {
+            Ok(req) => req,
+            Err(e) => {
+                let frame = WsConvertFrame::Error {
+                    error: format!("Invalid request: {}", e),
+                    done: true,
+                };
+                if send_ws_frame(&mut socket, &frame).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let convert = Arc::clone(&convert);
+        tokio::task::spawn_blocking(move || {
+            let start = std::time::Instant::now();
+            let mut on_partial = |partial| {
+                let _ = tx.send(WsConvertFrame::Partial {
+                    partial,
+                    done: false,
+                });
+            };
+            let frame = match convert(&req, &mut on_partial) {
+                Ok(candidates) => WsConvertFrame::Final {
+                    candidates,
+                    done: true,
+                    inference_time_ms: start.elapsed().as_secs_f64() * 1000.0,
+                },
+                Err((_, error)) => WsConvertFrame::Error { error, done: true },
+            };
+            let _ = tx.send(frame);
+        });
+
+        while let Some(frame) = rx.recv().await {
+            if send_ws_frame(&mut socket, &frame).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn send_ws_frame(socket: &mut WebSocket, frame: &WsConvertFrame) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(frame).expect("frame serializes");
+    socket.send(Message::Text(json.into())).await
+}
+
+/// Greedy llama.cpp conversion that reports the decoded text after every
+/// generated token (only when it changed)
+fn llamacpp_convert_streaming(
+    state: &AppState,
+    req: &WsConvertRequest,
+    on_partial: &mut dyn FnMut(String),
+) -> Result<Vec<String>, (StatusCode, String)> {
+    let model_id = resolve_request_model_id(state, req.model.as_deref())?;
+    let (model, family) = state
+        .llamacpp_models
+        .read()
+        .expect("lock poisoned")
+        .get(&model_id)
+        .map(|info| (Arc::clone(&info.model), info.family))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("llama.cpp model '{}' not loaded", model_id),
+            )
+        })?;
+
+    let internal_error = |what: &str, e: KanjiError| {
+        tracing::error!("llama.cpp {} error: {}", what, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("{} error: {}", what, e),
+        )
+    };
+
+    let katakana = hiragana_to_katakana(&req.hiragana);
+    let (input_tokens, max_new_tokens) =
+        prompt_and_budget(&state.conversion, &model, family, &katakana, &req.context)
+            .map_err(|e| internal_error("Tokenize", e))?;
+    let eos_token_id = Some(model.eos_token_id().0);
+
+    let mut generated = Vec::new();
+    let mut last_partial = String::new();
+    let output_tokens = model
+        .generate_streaming(&input_tokens, max_new_tokens, eos_token_id, |token| {
+            generated.push(token);
+            // A token may end in the middle of a character; wait for the rest
+            if let Ok(text) = model.decode(&generated, true)
+                && !text.contains('\u{FFFD}')
+                && text != last_partial
+            {
+                on_partial(text.clone());
+                last_partial = text;
+            }
+            ControlFlow::Continue(())
+        })
+        .map_err(|e| internal_error("Generate", e))?;
+
+    let output = model
+        .decode(&output_tokens[input_tokens.len()..], true)
+        .map_err(|e| internal_error("Decode", e))?;
+    let output = clean_model_output(&output);
+    Ok(vec![if output.is_empty() {
+        req.hiragana.clone()
+    } else {
+        output
+    }])
 }
 
 /// Tokenize request (debug mode only)
@@ -519,26 +1026,30 @@ async fn llamacpp_convert(
         tokens
     };
 
-    // Build prompt in jinen format
-    // Note: NFKC normalization is handled by the tokenizer's normalizer (tokenizer.json).
-    let prompt = build_jinen_prompt(katakana, &req.context);
-    tracing::debug!(
-        "llama.cpp prompt: katakana='{}', context='{}', prompt_len={}",
-        katakana,
-        req.context,
-        prompt.len()
-    );
-
     let start = std::time::Instant::now();
 
-    // Tokenize and generate
-    let input_tokens = model.tokenize(&prompt).map_err(|e| {
+    // Tokenize the prompt in the model family's format and generate
+    // Note: NFKC normalization is handled by the tokenizer's normalizer (tokenizer.json).
+    let (input_tokens, max_new_tokens) = prompt_and_budget(
+        &state.conversion,
+        model,
+        model_info.family,
+        katakana,
+        &req.context,
+    )
+    .map_err(|e| {
         tracing::error!("llama.cpp tokenize error: {}", e);
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             format!("Tokenize error: {}", e),
         )
     })?;
+    tracing::debug!(
+        "llama.cpp prompt: katakana='{}', context='{}', prompt_tokens={}",
+        katakana,
+        req.context,
+        input_tokens.len()
+    );
 
     let input_token_count = input_tokens.len();
 
@@ -556,8 +1067,12 @@ async fn llamacpp_convert(
     let (candidates, candidate_viz): (Vec<String>, Option<Vec<CandidateVisualization>>) =
         if beam_size == 1 {
             // Fast path: greedy decoding
+            let params = SamplingParams {
+                repeat_penalty: req.repeat_penalty,
+                ..SamplingParams::default()
+            };
             let output_tokens = model
-                .generate(&input_tokens, 64, eos_token_id)
+                .generate_with_params(&input_tokens, max_new_tokens, eos_token_id, params)
                 .map_err(|e| {
                     tracing::error!("llama.cpp generate error: {}", e);
                     (
@@ -585,7 +1100,7 @@ async fn llamacpp_convert(
                 // top greedy choice but have valid alternatives).
                 tracing::debug!("Greedy produced empty output, falling back to beam search");
                 let beam_results = model
-                    .generate_beam_search(&input_tokens, 64, eos_token_id, 3)
+                    .generate_beam_search(&input_tokens, max_new_tokens, eos_token_id, 3)
                     .map_err(|e| {
                         tracing::error!("llama.cpp beam search fallback error: {}", e);
                         (
@@ -643,9 +1158,14 @@ async fn llamacpp_convert(
                 .is_some_and(|t| t == "d1_greedy");
 
             let beam_results = if use_d1_greedy {
-                model.generate_beam_search_d1_greedy(&input_tokens, 64, eos_token_id, beam_size)
+                model.generate_beam_search_d1_greedy(
+                    &input_tokens,
+                    max_new_tokens,
+                    eos_token_id,
+                    beam_size,
+                )
             } else {
-                model.generate_beam_search(&input_tokens, 64, eos_token_id, beam_size)
+                model.generate_beam_search(&input_tokens, max_new_tokens, eos_token_id, beam_size)
             }
             .map_err(|e| {
                 tracing::error!("llama.cpp beam search error: {}", e);
@@ -732,3 +1252,270 @@ async fn llamacpp_convert(
         beam_search_type: Some(beam_search_type_used.to_string()),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio_tungstenite::{connect_async, tungstenite};
+
+    /// Stand-in for the model: reports the output one character at a time
+    fn fake_convert(
+        req: &WsConvertRequest,
+        on_partial: &mut dyn FnMut(String),
+    ) -> Result<Vec<String>, (StatusCode, String)> {
+        if req.hiragana.is_empty() {
+            return Err((StatusCode::BAD_REQUEST, "empty reading".to_string()));
+        }
+        let output = "漢字";
+        for (i, _) in output.char_indices().skip(1) {
+            on_partial(output[..i].to_string());
+        }
+        on_partial(output.to_string());
+        Ok(vec![output.to_string()])
+    }
+
+    /// Serve `/ws/convert` backed by [`fake_convert`] and return its URL
+    async fn spawn_server() -> String {
+        let app = Router::new().route(
+            "/ws/convert",
+            get(|ws: WebSocketUpgrade| async {
+                ws.on_upgrade(|socket| serve_ws_convert(socket, fake_convert))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("ws://{}/ws/convert", addr)
+    }
+
+    /// Send one request and collect the frames up to the one with `done: true`
+    async fn request_frames(url: &str, request: serde_json::Value) -> Vec<serde_json::Value> {
+        let (mut ws, _) = connect_async(url).await.unwrap();
+        ws.send(tungstenite::Message::text(request.to_string()))
+            .await
+            .unwrap();
+        let mut frames = Vec::new();
+        while let Some(message) = ws.next().await {
+            let tungstenite::Message::Text(text) = message.unwrap() else {
+                continue;
+            };
+            let frame: serde_json::Value = serde_json::from_str(&text).unwrap();
+            let done = frame["done"].as_bool().unwrap();
+            frames.push(frame);
+            if done {
+                break;
+            }
+        }
+        frames
+    }
+
+    #[tokio::test]
+    async fn test_ws_convert_streams_partials_before_final() {
+        let url = spawn_server().await;
+        let frames = request_frames(
+            &url,
+            serde_json::json!({"hiragana": "かんじ", "context": ""}),
+        )
+        .await;
+
+        let (last, partials) = frames.split_last().unwrap();
+        assert!(!partials.is_empty());
+        assert_eq!(partials[0]["partial"], "漢");
+        assert!(partials.iter().all(|f| f["done"] == false));
+        assert_eq!(last["done"], true);
+        assert_eq!(last["candidates"], serde_json::json!(["漢字"]));
+        assert!(last["inference_time_ms"].is_number());
+    }
+
+    #[tokio::test]
+    async fn test_ws_convert_reports_errors() {
+        let url = spawn_server().await;
+        let frames = request_frames(&url, serde_json::json!({"hiragana": ""})).await;
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0]["error"], "empty reading");
+
+        let frames = request_frames(&url, serde_json::json!({"context": ""})).await;
+        assert_eq!(frames.len(), 1);
+        assert!(
+            frames[0]["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid request")
+        );
+    }
+
+    fn state_with_dictionary(dictionary: Option<Dictionary>) -> AppState {
+        AppState {
+            converter: Arc::new(RwLock::new(RomajiConverter::new())),
+            llamacpp_models: Arc::new(RwLock::new(HashMap::new())),
+            dictionary: dictionary.map(Arc::new),
+            debug_mode: false,
+            conversion: ConversionConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_model_infos_list_registry() {
+        let models = model_infos(&HashMap::new());
+        assert_eq!(models.len(), registry().all_variant_ids().len());
+        assert!(models.iter().all(|m| !m.loaded && m.id == m.model_id));
+        assert!(models.iter().any(|m| m.id == registry().default_model));
+    }
+
+    async fn convert_events(state: &AppState, input: &str) -> Vec<String> {
+        let Json(res) = convert_handler(
+            State(state.clone()),
+            Json(ConvertRequest {
+                input: input.to_string(),
+                incremental: false,
+            }),
+        )
+        .await
+        .unwrap();
+        res.events
+    }
+
+    #[tokio::test]
+    async fn test_convert_reports_events() {
+        let state = state_with_dictionary(None);
+        assert_eq!(
+            convert_events(&state, "kya").await,
+            ["Buffered", "Buffered", "Converted"]
+        );
+        // "." converts to 。; "@" has no rule and passes through
+        assert_eq!(
+            convert_events(&state, "a.b").await,
+            ["Converted", "Converted", "Buffered"]
+        );
+        assert_eq!(
+            convert_events(&state, "a@b").await,
+            ["Converted", "PassThrough", "Buffered"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dict_lookup_exact_and_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dict.tsv");
+        std::fs::write(
+            &path,
+            "きょう\t今日\t名詞\t\nきょう\t京\t名詞\t\nき\t木\t名詞\t\nあした\t明日\t名詞\t\n",
+        )
+        .unwrap();
+        let state = state_with_dictionary(Some(Dictionary::load_auto(&path).unwrap()));
+
+        let Json(res) = dict_lookup_handler(
+            State(state),
+            Query(DictLookupQuery {
+                reading: "きょう".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+
+        let exact = res.exact.unwrap();
+        assert_eq!(exact.reading, "きょう");
+        let surfaces: Vec<_> = exact
+            .candidates
+            .iter()
+            .map(|c| c.surface.as_str())
+            .collect();
+        assert_eq!(surfaces, ["今日", "京"]);
+        let prefixes: Vec<_> = res.prefix.iter().map(|e| e.reading.as_str()).collect();
+        assert_eq!(prefixes, ["き", "きょう"]);
+    }
+
+    #[tokio::test]
+    async fn test_dict_lookup_without_dictionary() {
+        let err = dict_lookup_handler(
+            State(state_with_dictionary(None)),
+            Query(DictLookupQuery {
+                reading: "きょう".to_string(),
+            }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(err.1.contains("--dict"));
+    }
+
+    #[test]
+    fn test_warmup_flag() {
+        let args = Args::parse_from(["karukan-server"]);
+        assert!(args.warmup);
+        let args = Args::parse_from(["karukan-server", "--warmup", "false"]);
+        assert!(!args.warmup);
+        let args = Args::parse_from(["karukan-server", "--warmup=true"]);
+        assert!(args.warmup);
+    }
+
+    #[test]
+    fn test_max_new_tokens_flag() {
+        let args = Args::parse_from(["karukan-server"]);
+        assert_eq!(args.max_new_tokens, 64);
+        let args = Args::parse_from(["karukan-server", "--max-new-tokens", "32"]);
+        assert_eq!(args.max_new_tokens, 32);
+        assert!(Args::try_parse_from(["karukan-server", "--max-new-tokens", "0"]).is_err());
+    }
+
+    /// The default model and its family, or `None` if it cannot be
+    /// downloaded or loaded (e.g. offline), so model tests are skipped.
+    ///
+    /// Note: downloads the default model from HuggingFace on first run.
+    fn load_default_model() -> Option<(LlamaCppModel, &'static ModelFamily)> {
+        use karukan_engine::kanji::{get_path_by_id, get_tokenizer_path_by_id};
+        let reg = registry();
+        let (family, _) = reg.default_variant()?;
+        let path = get_path_by_id(&reg.default_model).ok()?;
+        let tok_path = get_tokenizer_path_by_id(&reg.default_model).ok()?;
+        let model = LlamaCppModel::from_file(&path, &tok_path).ok()?;
+        Some((model, family))
+    }
+
+    #[test]
+    fn test_warmed_model_converts() {
+        let Some((model, family)) = load_default_model() else {
+            eprintln!("skipping: default model unavailable");
+            return;
+        };
+
+        warmup_model(&model, family).expect("Warmup failed");
+
+        let tokens = model
+            .tokenize(&build_prompt(family, "トウキョウ", ""))
+            .unwrap();
+        let output = model
+            .generate(&tokens, 20, Some(model.eos_token_id().0))
+            .unwrap();
+        let text = model.decode(&output[tokens.len()..], true).unwrap();
+        assert!(!clean_model_output(&text).is_empty());
+    }
+
+    #[test]
+    fn test_batch_and_single_budgets_match() {
+        let Some((model, family)) = load_default_model() else {
+            eprintln!("skipping: default model unavailable");
+            return;
+        };
+        let conversion = ConversionConfig::default();
+        let context = "今日は";
+        let hiragana: Vec<String> = ["とうきょう", "かんじへんかん", "あ"]
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+
+        let (prompts, budgets) =
+            batch_prompts_and_budgets(&conversion, &model, family, &hiragana, context).unwrap();
+        for ((h, prompt), budget) in hiragana.iter().zip(&prompts).zip(&budgets) {
+            let katakana = hiragana_to_katakana(h);
+            let single =
+                prompt_and_budget(&conversion, &model, family, &katakana, context).unwrap();
+            assert_eq!(single, (prompt.clone(), *budget), "reading: {}", h);
+            assert_eq!(
+                *budget,
+                token_budget(&conversion, &model, &katakana).unwrap()
+            );
+        }
+    }
+}
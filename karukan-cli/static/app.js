@@ -58,9 +58,10 @@ async function loadModels() {
         currentModel = data.default;
 
         // Populate select with proper display names
+        // Models that aren't loaded are listed but can't be selected
         modelSelect.innerHTML = availableModels.map(model =>
-            `<option value="${model.id}" ${model.id === currentModel ? 'selected' : ''}>
-                ${model.name}
+            `<option value="${model.id}" ${model.id === currentModel ? 'selected' : ''} ${model.loaded ? '' : 'disabled'}>
+                ${model.name}${model.loaded ? '' : (model.downloaded ? ' (not loaded)' : ' (not downloaded)')}
             </option>`
         ).join('');
 
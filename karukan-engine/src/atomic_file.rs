@@ -0,0 +1,54 @@
+//! Crash-safe file replacement shared by the learning cache and user dictionary.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Write a file through a temporary file in the same directory that is then
+/// renamed over `path`, so a crash mid-write never leaves `path` truncated.
+///
+/// Each call gets its own uniquely named temporary file, so concurrent saves
+/// never write into each other's. Falls back to writing `path` directly (with
+/// a warning) if the rename crosses filesystems. The temporary file is removed
+/// on failure.
+pub(crate) fn write_atomically<E: From<std::io::Error>>(
+    path: &Path,
+    mut write: impl FnMut(&mut BufWriter<File>) -> Result<(), E>,
+) -> Result<(), E> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(parent)?;
+
+    let mut write_file = |file: File| -> Result<(), E> {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_all()?;
+        Ok(())
+    };
+
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = tempfile::Builder::new()
+        .prefix(&format!(".{}.", name))
+        .suffix(".tmp")
+        .tempfile_in(parent)?;
+    // Dropping `tmp` on an error path deletes the temporary file
+    write_file(tmp.as_file().try_clone()?)?;
+    match tmp.persist(path) {
+        Ok(_) => Ok(()),
+        Err(e) if e.error.kind() == std::io::ErrorKind::CrossesDevices => {
+            tracing::warn!(
+                "Cannot rename into {:?} ({}); writing it directly",
+                path,
+                e.error
+            );
+            drop(e.file);
+            write_file(File::create(path)?)
+        }
+        Err(e) => Err(e.error.into()),
+    }
+}
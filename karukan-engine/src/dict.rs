@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::Deref;
 use std::path::Path;
 
 use unicode_normalization::UnicodeNormalization;
@@ -26,13 +27,125 @@ use yada::builder::DoubleArrayBuilder;
 use crate::kana::katakana_to_hiragana;
 
 const MAGIC: &[u8; 4] = b"KRKN";
-const VERSION: u32 = 1;
+const VERSION: u32 = 2;
+
+/// UTF-8 byte order mark
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+/// Comment header that SKK dictionaries conventionally start with
+const SKK_HEADER: &[u8] = b";; ";
+/// Columns of a Sudachi CSV row up to the reading (column 11)
+const SUDACHI_COLUMNS: usize = 12;
+/// Bytes read to find the first line of a `.csv` file
+const CSV_SNIFF_LEN: u64 = 4096;
+
+/// Dictionary file format, as told apart by [`Dictionary::load_auto`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DictFormat {
+    /// Binary KRKN format
+    Binary,
+    /// SKK jisyo format
+    Skk,
+    /// Sudachi CSV format
+    SudachiCsv,
+    /// Mozc/Google IME TSV format
+    MozcTsv,
+}
+
+impl DictFormat {
+    /// Detect the format of the dictionary file at `path`.
+    pub(crate) fn detect(path: &Path) -> Result<Self> {
+        let mut head = Vec::with_capacity(UTF8_BOM.len() + SKK_HEADER.len());
+        File::open(path)?
+            .take((UTF8_BOM.len() + SKK_HEADER.len()) as u64)
+            .read_to_end(&mut head)?;
+
+        Ok(if head.starts_with(MAGIC) {
+            Self::Binary
+        } else if head
+            .strip_prefix(UTF8_BOM)
+            .unwrap_or(&head)
+            .starts_with(SKK_HEADER)
+        {
+            Self::Skk
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+            && Self::has_sudachi_row(path)?
+        {
+            Self::SudachiCsv
+        } else {
+            Self::MozcTsv
+        })
+    }
+
+    /// Whether the first non-blank line of `path` looks like a Sudachi row:
+    /// comma-separated, without tabs, with at least [`SUDACHI_COLUMNS`] columns.
+    /// Other `.csv` files (e.g. a Mozc TSV with that extension) are not Sudachi.
+    fn has_sudachi_row(path: &Path) -> Result<bool> {
+        let mut head = Vec::new();
+        File::open(path)?
+            .take(CSV_SNIFF_LEN)
+            .read_to_end(&mut head)?;
+        // The limit may cut a multi-byte character at the end
+        let head = String::from_utf8_lossy(&head);
+        Ok(head
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| !line.contains('\t') && line.split(',').count() >= SUDACHI_COLUMNS))
+    }
+}
+
+/// Upper bound on `max_edits` for [`Dictionary::fuzzy_search`]
+pub const MAX_FUZZY_EDITS: usize = 2;
+
+/// Kana that are typed in place of each other, used for fuzzy substitutions
+const FUZZY_CLASSES: &[&[char]] = &[
+    // Small and large kana
+    &['あ', 'ぁ'],
+    &['い', 'ぃ'],
+    &['う', 'ぅ'],
+    &['え', 'ぇ'],
+    &['お', 'ぉ'],
+    &['つ', 'っ'],
+    &['や', 'ゃ'],
+    &['ゆ', 'ゅ'],
+    &['よ', 'ょ'],
+    &['わ', 'ゎ'],
+    // Same sound, different kana
+    &['ず', 'づ'],
+    &['じ', 'ぢ'],
+    &['お', 'を'],
+    // Long vowel spellings (きょうと / きょおと / きょーと, せんせい / せんせえ)
+    &['う', 'お', 'ー'],
+    &['い', 'え', 'ー'],
+];
+
+/// Kana that are dropped or doubled by mistake (sokuon and long vowels),
+/// used for fuzzy insertions and deletions after the first character
+const FUZZY_OPTIONAL: &[char] = &['っ', 'ー', 'う', 'い'];
 
 /// A candidate surface form with its score.
 #[derive(Debug, Clone)]
 pub struct Candidate {
     pub surface: String,
     pub score: f32,
+    /// Part-of-speech tag (e.g. "名詞"), if the source provides one
+    pub pos: Option<String>,
+    /// Usage frequency (0 if unknown)
+    pub freq: u32,
+}
+
+impl Candidate {
+    /// Create a candidate without POS or frequency information.
+    pub fn new(surface: impl Into<String>, score: f32) -> Self {
+        Self {
+            surface: surface.into(),
+            score,
+            pos: None,
+            freq: 0,
+        }
+    }
 }
 
 /// A dictionary entry mapping a reading to its candidate surfaces.
@@ -49,17 +162,76 @@ pub struct LookupResult<'a> {
     pub candidates: &'a [Candidate],
 }
 
+/// Summary statistics of a dictionary, from [`Dictionary::stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictStats {
+    /// Number of readings
+    pub entry_count: usize,
+    /// Number of candidates over all readings
+    pub candidate_count: usize,
+    /// Mean number of candidates per reading (0 when empty)
+    pub avg_candidates: f64,
+    /// Largest number of candidates for one reading
+    pub max_candidates: usize,
+    /// Number of readings per reading length in characters (index = length)
+    pub reading_length_histogram: Vec<usize>,
+    /// Smallest candidate score (None when there are no candidates)
+    pub score_min: Option<f32>,
+    /// Largest candidate score (None when there are no candidates)
+    pub score_max: Option<f32>,
+    /// Mean candidate score (None when there are no candidates)
+    pub score_mean: Option<f64>,
+}
+
 /// A double-array trie dictionary for kana-kanji conversion.
+///
+/// A dictionary is immutable once built or loaded, so it is `Send + Sync`:
+/// share it across threads as `Arc<Dictionary>` and call the `*_search`
+/// methods concurrently from `&Dictionary` without a lock.
 pub struct Dictionary {
-    trie: DoubleArray<Vec<u8>>,
+    trie: DoubleArray<TrieBytes>,
     entries: Vec<DictEntry>,
 }
 
+// Lookups from several threads rely on this; keep it from regressing silently
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Dictionary>();
+};
+
+/// Backing storage of the double-array trie.
+enum TrieBytes {
+    /// Trie built or read into memory
+    Owned(Vec<u8>),
+    /// Trie region of a memory-mapped dictionary file (kept alive here)
+    #[cfg(feature = "mmap")]
+    Mapped {
+        map: memmap2::Mmap,
+        range: std::ops::Range<usize>,
+    },
+}
+
+impl Deref for TrieBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            TrieBytes::Owned(bytes) => bytes,
+            #[cfg(feature = "mmap")]
+            TrieBytes::Mapped { map, range } => &map[range.clone()],
+        }
+    }
+}
+
 // JSON deserialization types
 #[derive(Deserialize)]
 struct JsonCandidate {
     surface: String,
     score: f32,
+    #[serde(default)]
+    pos: Option<String>,
+    #[serde(default)]
+    freq: u32,
 }
 
 #[derive(Deserialize)]
@@ -68,30 +240,83 @@ struct JsonEntry {
     candidates: Vec<JsonCandidate>,
 }
 
+impl JsonEntry {
+    /// Convert to a DictEntry with a hiragana reading and candidates sorted by score
+    fn into_entry(self) -> DictEntry {
+        let mut candidates: Vec<Candidate> = self
+            .candidates
+            .into_iter()
+            .map(|jc| Candidate {
+                surface: jc.surface,
+                score: jc.score,
+                pos: jc.pos,
+                freq: jc.freq,
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+        DictEntry {
+            reading: katakana_to_hiragana(&self.reading),
+            candidates,
+        }
+    }
+}
+
+/// Visitor converting a JSON array of entries element by element
+struct JsonEntryStream;
+
+impl<'de> serde::de::Visitor<'de> for JsonEntryStream {
+    type Value = Vec<DictEntry>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of dictionary entries")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut entries = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(entry) = seq.next_element::<JsonEntry>()? {
+            entries.push(entry.into_entry());
+        }
+        Ok(entries)
+    }
+}
+
 impl Dictionary {
     /// Build a Dictionary from pre-sorted entries.
     ///
     /// Entries must already be sorted by `reading` bytes and deduplicated.
     /// This is the shared final step for all dictionary builders.
-    fn build_from_entries(entries: Vec<DictEntry>) -> Result<Self> {
-        let keyset: Vec<(&[u8], u32)> = entries
+    pub(crate) fn build_from_entries(entries: Vec<DictEntry>) -> Result<Self> {
+        let mut keyset: Vec<(&[u8], u32)> = entries
             .iter()
             .enumerate()
             .map(|(i, e)| (e.reading.as_bytes(), i as u32))
             .collect();
+        if keyset.is_empty() {
+            // The trie builder rejects an empty keyset. 0xFF never occurs in
+            // UTF-8 input, and its value has no entry, so nothing matches.
+            keyset.push((b"\xff", 0));
+        }
 
         let trie_bytes = DoubleArrayBuilder::build(&keyset)
             .ok_or_else(|| DictError::Format("failed to build double-array trie".to_string()))?;
 
         Ok(Dictionary {
-            trie: DoubleArray::new(trie_bytes),
+            trie: DoubleArray::new(TrieBytes::Owned(trie_bytes)),
             entries,
         })
     }
 
+    /// Create a dictionary with no readings.
+    pub fn empty() -> Self {
+        Self::build_from_entries(Vec::new()).expect("sentinel trie always builds")
+    }
+
     /// Build a Dictionary from a JSON file.
     ///
-    /// The JSON format is an array of `{reading, candidates: [{surface, score}]}`.
+    /// The JSON format is an array of `{reading, candidates: [{surface, score, pos?, freq?}]}`.
     /// Readings are converted from katakana to hiragana.
     pub fn build_from_json(path: impl AsRef<Path>) -> Result<Self> {
         let file = File::open(path.as_ref())?;
@@ -99,25 +324,29 @@ impl Dictionary {
         let json_entries: Vec<JsonEntry> = serde_json::from_reader(reader)?;
 
         // Convert to DictEntry with hiragana readings
-        let mut entries: Vec<DictEntry> = json_entries
+        let entries = json_entries
             .into_iter()
-            .map(|je| DictEntry {
-                reading: katakana_to_hiragana(&je.reading),
-                candidates: {
-                    let mut cands: Vec<Candidate> = je
-                        .candidates
-                        .into_iter()
-                        .map(|jc| Candidate {
-                            surface: jc.surface,
-                            score: jc.score,
-                        })
-                        .collect();
-                    cands.sort_by(|a, b| a.score.total_cmp(&b.score));
-                    cands
-                },
-            })
+            .map(JsonEntry::into_entry)
             .collect();
+        Self::build_from_json_entries(entries)
+    }
+
+    /// Build a Dictionary from a JSON file like [`build_from_json`](Self::build_from_json),
+    /// reading the array one entry at a time.
+    ///
+    /// Each JSON entry is converted as soon as it is parsed, so the parsed JSON
+    /// array is never held in memory next to the converted entries. Meant for
+    /// dictionaries of hundreds of megabytes.
+    pub fn build_from_json_streaming(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+        let mut de = serde_json::Deserializer::from_reader(BufReader::new(file));
+        let entries = serde::Deserializer::deserialize_seq(&mut de, JsonEntryStream)?;
+        de.end()?;
+        Self::build_from_json_entries(entries)
+    }
 
+    /// Sort converted JSON entries and build the trie.
+    fn build_from_json_entries(mut entries: Vec<DictEntry>) -> Result<Self> {
         // Sort by reading bytes for the trie builder
         entries.sort_by(|a, b| a.reading.as_bytes().cmp(b.reading.as_bytes()));
 
@@ -132,7 +361,7 @@ impl Dictionary {
     /// Format:
     /// ```text
     /// [4B] magic "KRKN"
-    /// [4B] version (1u32 LE)
+    /// [4B] version (2u32 LE)
     /// [4B] trie_len (u32 LE)
     /// [trie_len B] trie bytes
     /// [4B] num_entries (u32 LE)
@@ -144,7 +373,13 @@ impl Dictionary {
     ///     [2B] surface_len (u16 LE)
     ///     [surface_len B] surface (UTF-8)
     ///     [4B] score (f32 LE)
+    ///     [2B] pos_len (u16 LE, 0 = no POS)
+    ///     [pos_len B] pos (UTF-8)
+    ///     [4B] freq (u32 LE)
     /// ```
+    ///
+    /// Version 1 files lack the pos and freq fields; [`load`](Self::load)
+    /// still reads them, with no POS and frequency 0.
     pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
         let file = File::create(path.as_ref())?;
         let mut w = BufWriter::new(file);
@@ -171,6 +406,10 @@ impl Dictionary {
                 w.write_all(&(surface_bytes.len() as u16).to_le_bytes())?;
                 w.write_all(surface_bytes)?;
                 w.write_all(&cand.score.to_le_bytes())?;
+                let pos_bytes = cand.pos.as_deref().unwrap_or_default().as_bytes();
+                w.write_all(&(pos_bytes.len() as u16).to_le_bytes())?;
+                w.write_all(pos_bytes)?;
+                w.write_all(&cand.freq.to_le_bytes())?;
             }
         }
 
@@ -183,84 +422,44 @@ impl Dictionary {
         let file = File::open(path.as_ref())?;
         let mut r = BufReader::new(file);
 
-        // Magic
-        let mut magic = [0u8; 4];
-        r.read_exact(&mut magic)?;
-        if &magic != MAGIC {
-            return Err(DictError::Format(
-                "invalid magic: expected KRKN".to_string(),
-            ));
-        }
-
-        // Version
-        let mut buf4 = [0u8; 4];
-        r.read_exact(&mut buf4)?;
-        let version = u32::from_le_bytes(buf4);
-        if version != VERSION {
-            return Err(DictError::Format(format!("unsupported version: {version}")));
-        }
-
-        // Trie
-        r.read_exact(&mut buf4)?;
-        let trie_len = u32::from_le_bytes(buf4) as usize;
-        const MAX_TRIE_LEN: usize = 100 * 1024 * 1024; // 100 MB
-        if trie_len > MAX_TRIE_LEN {
-            return Err(DictError::Format(format!(
-                "trie_len too large: {} (max {})",
-                trie_len, MAX_TRIE_LEN
-            )));
-        }
+        let (version, trie_len) = read_header(&mut r)?;
         let mut trie_bytes = vec![0u8; trie_len];
         r.read_exact(&mut trie_bytes)?;
 
-        // Entries
-        r.read_exact(&mut buf4)?;
-        let num_entries = u32::from_le_bytes(buf4) as usize;
-        const MAX_ENTRIES: usize = 10_000_000;
-        if num_entries > MAX_ENTRIES {
-            return Err(DictError::Format(format!(
-                "num_entries too large: {} (max {})",
-                num_entries, MAX_ENTRIES
-            )));
-        }
-        let mut entries = Vec::with_capacity(num_entries);
-
-        let mut buf2 = [0u8; 2];
-        for _ in 0..num_entries {
-            // Reading
-            r.read_exact(&mut buf2)?;
-            let reading_len = u16::from_le_bytes(buf2) as usize;
-            let mut reading_bytes = vec![0u8; reading_len];
-            r.read_exact(&mut reading_bytes)?;
-            let reading = String::from_utf8(reading_bytes)
-                .map_err(|e| DictError::Format(format!("invalid UTF-8 in reading: {e}")))?;
-
-            // Candidates
-            r.read_exact(&mut buf2)?;
-            let num_candidates = u16::from_le_bytes(buf2) as usize;
-            let mut candidates = Vec::with_capacity(num_candidates);
-            for _ in 0..num_candidates {
-                r.read_exact(&mut buf2)?;
-                let surface_len = u16::from_le_bytes(buf2) as usize;
-                let mut surface_bytes = vec![0u8; surface_len];
-                r.read_exact(&mut surface_bytes)?;
-                let surface = String::from_utf8(surface_bytes)
-                    .map_err(|e| DictError::Format(format!("invalid UTF-8 in surface: {e}")))?;
+        Ok(Dictionary {
+            trie: DoubleArray::new(TrieBytes::Owned(trie_bytes)),
+            entries: read_entries(&mut r, version)?,
+        })
+    }
 
-                r.read_exact(&mut buf4)?;
-                let score = f32::from_le_bytes(buf4);
-                candidates.push(Candidate { surface, score });
-            }
+    /// Load a dictionary from a binary file by memory-mapping it.
+    ///
+    /// The trie is used in place from the mapping instead of being copied,
+    /// which cuts load time and memory for large dictionaries. The entries
+    /// table is parsed eagerly, so lookups behave exactly like [`load`](Self::load).
+    #[cfg(feature = "mmap")]
+    pub fn load_mmap(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path.as_ref())?;
+        // SAFETY: the dictionary file is not expected to be modified while mapped
+        let map = unsafe { memmap2::Mmap::map(&file)? };
 
-            candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
-            entries.push(DictEntry {
-                reading,
-                candidates,
-            });
+        let mut r: &[u8] = &map;
+        let (version, trie_len) = read_header(&mut r)?;
+        if r.len() < trie_len {
+            return Err(DictError::Format(format!(
+                "truncated trie: expected {} bytes, found {}",
+                trie_len,
+                r.len()
+            )));
         }
+        let trie_start = map.len() - r.len();
+        let entries = read_entries(&mut &r[trie_len..], version)?;
 
         Ok(Dictionary {
-            trie: DoubleArray::new(trie_bytes),
+            trie: DoubleArray::new(TrieBytes::Mapped {
+                map,
+                range: trie_start..trie_start + trie_len,
+            }),
             entries,
         })
     }
@@ -289,6 +488,110 @@ impl Dictionary {
         })
     }
 
+    /// Predictive search: returns all entries whose reading starts with `prefix`
+    /// (including an exact match), in reading byte order.
+    pub fn predictive_search(&self, prefix: &str) -> Vec<LookupResult<'_>> {
+        // Entries are sorted by reading bytes, so matches form a contiguous range
+        let start = self
+            .entries
+            .partition_point(|e| e.reading.as_bytes() < prefix.as_bytes());
+        self.entries[start..]
+            .iter()
+            .take_while(|e| e.reading.starts_with(prefix))
+            .map(|entry| LookupResult {
+                reading: &entry.reading,
+                candidates: &entry.candidates,
+            })
+            .collect()
+    }
+
+    /// Typo-tolerant search: returns entries whose reading is within
+    /// `max_edits` edits of `input`, nearest first (an exact match comes first).
+    ///
+    /// Edits are limited to the confusions in [`FUZZY_CLASSES`] (substitution)
+    /// and [`FUZZY_OPTIONAL`] (insertion or deletion), so "きょおと" finds
+    /// "きょうと" but not unrelated readings. `max_edits` is capped at
+    /// [`MAX_FUZZY_EDITS`]; 0 is an exact match.
+    pub fn fuzzy_search(&self, input: &str, max_edits: usize) -> Vec<LookupResult<'_>> {
+        let start: Vec<char> = input.chars().collect();
+        let mut seen = HashSet::from([start.clone()]);
+        let mut frontier = vec![start];
+        let mut results = Vec::new();
+        let max_edits = max_edits.min(MAX_FUZZY_EDITS);
+
+        // Breadth-first over edit distance, so each reading is found at its
+        // smallest distance and results come out nearest first
+        for distance in 0..=max_edits {
+            let mut next = Vec::new();
+            for reading in &frontier {
+                let key: String = reading.iter().collect();
+                results.extend(self.exact_match_search(&key));
+                if distance < max_edits {
+                    next.extend(
+                        fuzzy_variants(reading)
+                            .into_iter()
+                            .filter(|v| seen.insert(v.clone())),
+                    );
+                }
+            }
+            frontier = next;
+        }
+        results
+    }
+
+    /// Take the entries out of the dictionary (sorted by reading bytes).
+    pub(crate) fn into_entries(self) -> Vec<DictEntry> {
+        self.entries
+    }
+
+    /// Number of readings in the dictionary.
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Counts, reading length histogram and score range of the dictionary.
+    pub fn stats(&self) -> DictStats {
+        let mut candidate_count = 0;
+        let mut max_candidates = 0;
+        let mut reading_length_histogram = Vec::new();
+        let mut score_min: Option<f32> = None;
+        let mut score_max: Option<f32> = None;
+        let mut score_sum = 0.0f64;
+
+        for entry in &self.entries {
+            candidate_count += entry.candidates.len();
+            max_candidates = max_candidates.max(entry.candidates.len());
+
+            let len = entry.reading.chars().count();
+            if reading_length_histogram.len() <= len {
+                reading_length_histogram.resize(len + 1, 0);
+            }
+            reading_length_histogram[len] += 1;
+
+            for cand in &entry.candidates {
+                score_min = Some(score_min.map_or(cand.score, |m| m.min(cand.score)));
+                score_max = Some(score_max.map_or(cand.score, |m| m.max(cand.score)));
+                score_sum += cand.score as f64;
+            }
+        }
+
+        let avg_candidates = if self.entries.is_empty() {
+            0.0
+        } else {
+            candidate_count as f64 / self.entries.len() as f64
+        };
+        DictStats {
+            entry_count: self.entries.len(),
+            candidate_count,
+            avg_candidates,
+            max_candidates,
+            reading_length_histogram,
+            score_min,
+            score_max,
+            score_mean: (candidate_count > 0).then(|| score_sum / candidate_count as f64),
+        }
+    }
+
     /// Write all entries in the dictionary to `writer` (for inspection/debugging).
     ///
     /// Each line is tab-separated: `reading\tsurface\tscore`.
@@ -321,19 +624,41 @@ impl Dictionary {
         results
     }
 
+    /// Reading of each of `surfaces` by exact surface match, in one pass over
+    /// the entries. The lowest-score reading wins when a surface has several.
+    ///
+    /// Results are in the same order as `surfaces`.
+    pub fn readings_by_surface(&self, surfaces: &[&str]) -> Vec<Option<String>> {
+        let mut best: Vec<Option<(&str, f32)>> = vec![None; surfaces.len()];
+        for entry in &self.entries {
+            for cand in &entry.candidates {
+                for (i, _) in surfaces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, s)| **s == cand.surface)
+                {
+                    if best[i].is_none_or(|(_, score)| cand.score < score) {
+                        best[i] = Some((&entry.reading, cand.score));
+                    }
+                }
+            }
+        }
+        best.into_iter()
+            .map(|b| b.map(|(reading, _)| reading.to_string()))
+            .collect()
+    }
+
     /// Build a Dictionary from a Mozc/Google IME TSV file.
     ///
     /// The TSV format is `reading\tword\tPOS\tcomment` (tab-separated, 4 columns).
     /// Lines starting with `#` are comments, empty lines are skipped.
-    /// Readings are grouped and converted to `DictEntry` with score 0.0.
+    /// Readings are grouped and converted to `DictEntry` with score 0.0,
+    /// keeping the POS column on each candidate.
     pub fn build_from_mozc_tsv(path: impl AsRef<Path>) -> Result<Self> {
         let file = File::open(path.as_ref())?;
         let reader = BufReader::new(file);
 
-        // reading -> Vec<surface> (preserving insertion order)
-        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
-        let mut order: Vec<String> = Vec::new();
-
+        let mut pairs = Vec::new();
         for line in reader.lines() {
             let line = line?;
             let line = line.trim();
@@ -352,14 +677,89 @@ impl Dictionary {
             if reading.is_empty() || surface.is_empty() {
                 continue;
             }
+            let mut cand = Candidate::new(surface, 0.0);
+            cand.pos = cols.get(2).filter(|p| !p.is_empty()).map(|p| p.to_string());
+            pairs.push((reading, cand));
+        }
+
+        Self::build_from_ordered_pairs(pairs)
+    }
+
+    /// Build a Dictionary from an SKK jisyo file, skipping okuri-ari entries.
+    ///
+    /// See [`build_from_skk_with_okuri`](Self::build_from_skk_with_okuri).
+    pub fn build_from_skk(path: impl AsRef<Path>) -> Result<Self> {
+        Self::build_from_skk_with_okuri(path, false)
+    }
+
+    /// Build a Dictionary from an SKK jisyo file.
+    ///
+    /// Each line is `よみ /候補1/候補2;annotation/`; lines starting with `;` are
+    /// comments. Annotations, okuri blocks (`[る/送/]`) and `(concat ...)`
+    /// expressions are dropped, and candidates keep their order with score 0.0.
+    /// The file is read as UTF-8 (with or without BOM), falling back to EUC-JP.
+    ///
+    /// Okuri-ari readings end in an ASCII letter marking the okurigana (`おくr`).
+    /// They are skipped unless `keep_okuri` is set, in which case they are stored
+    /// under the base reading (`おく`).
+    pub fn build_from_skk_with_okuri(path: impl AsRef<Path>, keep_okuri: bool) -> Result<Self> {
+        let bytes = std::fs::read(path.as_ref())?;
+        let text = decode_skk(&bytes);
+
+        let mut pairs = Vec::new();
+        for line in text.lines() {
+            if line.is_empty() || line.starts_with(';') {
+                continue;
+            }
+            let Some((reading, rest)) = line.split_once(' ') else {
+                continue;
+            };
+            let reading = match skk_base_reading(reading) {
+                Some(base) if keep_okuri => base,
+                Some(_) => continue,
+                None => reading,
+            };
+            if reading.is_empty() || !rest.starts_with('/') {
+                continue;
+            }
 
-            let surfaces = groups.entry(reading.clone()).or_insert_with(|| {
+            let mut in_okuri_block = false;
+            for field in rest.split('/') {
+                if field.starts_with('[') {
+                    in_okuri_block = true;
+                    continue;
+                }
+                if field == "]" {
+                    in_okuri_block = false;
+                    continue;
+                }
+                // Candidates inside an okuri block repeat the ones before it
+                let surface = field.split(';').next().unwrap_or_default();
+                if in_okuri_block || surface.is_empty() || surface.starts_with("(concat ") {
+                    continue;
+                }
+                pairs.push((reading.to_string(), Candidate::new(surface, 0.0)));
+            }
+        }
+
+        Self::build_from_ordered_pairs(pairs)
+    }
+
+    /// Build a Dictionary from `(reading, candidate)` pairs, keeping the first-seen
+    /// order of surfaces within each reading.
+    fn build_from_ordered_pairs(pairs: Vec<(String, Candidate)>) -> Result<Self> {
+        // reading -> Vec<candidate> (preserving insertion order)
+        let mut groups: HashMap<String, Vec<Candidate>> = HashMap::new();
+        let mut order: Vec<String> = Vec::new();
+
+        for (reading, cand) in pairs {
+            let candidates = groups.entry(reading.clone()).or_insert_with(|| {
                 order.push(reading);
                 Vec::new()
             });
             // Deduplicate surfaces within the same reading
-            if !surfaces.contains(&surface) {
-                surfaces.push(surface);
+            if !candidates.iter().any(|c| c.surface == cand.surface) {
+                candidates.push(cand);
             }
         }
 
@@ -367,15 +767,9 @@ impl Dictionary {
         let mut entries: Vec<DictEntry> = order
             .into_iter()
             .filter_map(|reading| {
-                groups.remove(&reading).map(|surfaces| DictEntry {
+                groups.remove(&reading).map(|candidates| DictEntry {
                     reading,
-                    candidates: surfaces
-                        .into_iter()
-                        .map(|surface| Candidate {
-                            surface,
-                            score: 0.0,
-                        })
-                        .collect(),
+                    candidates,
                 })
             })
             .collect();
@@ -401,22 +795,74 @@ impl Dictionary {
         Self::build_from_entries(entries)
     }
 
+    /// Build a Dictionary from Sudachi lexicon CSV files.
+    ///
+    /// Files are parsed with [`parse_sudachi_csvs`], keeping the lowest cost
+    /// of a surface seen in several rows or files. Readings are converted to
+    /// hiragana and the cost becomes the score, so candidates are ordered by
+    /// cost (lower is better), like every other dictionary score.
+    pub fn build_from_sudachi_csvs(paths: &[impl AsRef<Path>]) -> Result<Self> {
+        Self::build_from_reading_map(parse_sudachi_csvs(paths)?)
+    }
+
+    /// Build a Dictionary from a Sudachi reading map (reading → {surface → cost}).
+    ///
+    /// Readings are converted from katakana to hiragana and the integer cost is
+    /// used directly as the score (lower is better).
+    fn build_from_reading_map(map: HashMap<String, HashMap<String, i32>>) -> Result<Self> {
+        let mut entries: Vec<DictEntry> = map
+            .into_iter()
+            .map(|(reading, surfaces)| {
+                let mut candidates: Vec<Candidate> = surfaces
+                    .into_iter()
+                    .map(|(surface, cost)| Candidate::new(surface, cost as f32))
+                    .collect();
+                sort_by_cost(&mut candidates);
+                DictEntry {
+                    reading: katakana_to_hiragana(&reading),
+                    candidates,
+                }
+            })
+            .collect();
+
+        // Sort by reading bytes for the trie builder
+        entries.sort_by(|a, b| a.reading.as_bytes().cmp(b.reading.as_bytes()));
+
+        // Readings that only differed in kana type now collide; merge them,
+        // keeping the lowest cost of a shared surface. The map's order is
+        // random, so re-sort to make the result independent of it.
+        entries.dedup_by(|b, a| {
+            if a.reading == b.reading {
+                for cand in std::mem::take(&mut b.candidates) {
+                    match a.candidates.iter_mut().find(|c| c.surface == cand.surface) {
+                        Some(c) => c.score = c.score.min(cand.score),
+                        None => a.candidates.push(cand),
+                    }
+                }
+                sort_by_cost(&mut a.candidates);
+                true
+            } else {
+                false
+            }
+        });
+
+        Self::build_from_entries(entries)
+    }
+
     /// Load a dictionary with auto-detection of format.
     ///
     /// If the file starts with the `KRKN` magic bytes, it is loaded as binary.
+    /// Files starting with a `;; ` comment header are parsed as SKK jisyo.
+    /// Files with a `.csv` extension whose first row has Sudachi's comma-separated
+    /// columns are parsed as Sudachi CSV.
     /// Otherwise, it is parsed as Mozc/Google IME TSV format.
     pub fn load_auto(path: impl AsRef<Path>) -> Result<Self> {
         let path = path.as_ref();
-        let mut file = File::open(path)?;
-        let mut magic = [0u8; 4];
-        let bytes_read = file.read(&mut magic)?;
-
-        if bytes_read >= 4 && &magic == MAGIC {
-            // Binary KRKN format
-            Dictionary::load(path)
-        } else {
-            // Mozc TSV format
-            Dictionary::build_from_mozc_tsv(path)
+        match DictFormat::detect(path)? {
+            DictFormat::Binary => Dictionary::load(path),
+            DictFormat::Skk => Dictionary::build_from_skk(path),
+            DictFormat::SudachiCsv => Dictionary::build_from_reading_map(parse_sudachi_csv(path)?),
+            DictFormat::MozcTsv => Dictionary::build_from_mozc_tsv(path),
         }
     }
 
@@ -464,6 +910,153 @@ impl Dictionary {
     }
 }
 
+/// Readings one fuzzy edit away from `reading`
+fn fuzzy_variants(reading: &[char]) -> Vec<Vec<char>> {
+    let mut variants = Vec::new();
+    for (i, &c) in reading.iter().enumerate() {
+        for class in FUZZY_CLASSES.iter().filter(|class| class.contains(&c)) {
+            for &other in class.iter().filter(|&&other| other != c) {
+                let mut v = reading.to_vec();
+                v[i] = other;
+                variants.push(v);
+            }
+        }
+        if i > 0 && FUZZY_OPTIONAL.contains(&c) {
+            let mut v = reading.to_vec();
+            v.remove(i);
+            variants.push(v);
+        }
+    }
+    for i in 1..=reading.len() {
+        for &c in FUZZY_OPTIONAL {
+            let mut v = reading.to_vec();
+            v.insert(i, c);
+            variants.push(v);
+        }
+    }
+    variants
+}
+
+/// Read the magic, version and trie length of a binary dictionary.
+fn read_header(r: &mut impl Read) -> Result<(u32, usize)> {
+    // Magic
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(DictError::Format(
+            "invalid magic: expected KRKN".to_string(),
+        ));
+    }
+
+    // Version
+    let mut buf4 = [0u8; 4];
+    r.read_exact(&mut buf4)?;
+    let version = u32::from_le_bytes(buf4);
+    if !(1..=VERSION).contains(&version) {
+        return Err(DictError::Format(format!("unsupported version: {version}")));
+    }
+
+    // Trie length
+    r.read_exact(&mut buf4)?;
+    let trie_len = u32::from_le_bytes(buf4) as usize;
+    const MAX_TRIE_LEN: usize = 100 * 1024 * 1024; // 100 MB
+    if trie_len > MAX_TRIE_LEN {
+        return Err(DictError::Format(format!(
+            "trie_len too large: {} (max {})",
+            trie_len, MAX_TRIE_LEN
+        )));
+    }
+    Ok((version, trie_len))
+}
+
+/// Read the entries table of a binary dictionary (following the trie).
+///
+/// Version 1 candidates have no pos/freq fields; they default to None and 0.
+fn read_entries(r: &mut impl Read, version: u32) -> Result<Vec<DictEntry>> {
+    let mut buf4 = [0u8; 4];
+    r.read_exact(&mut buf4)?;
+    let num_entries = u32::from_le_bytes(buf4) as usize;
+    const MAX_ENTRIES: usize = 10_000_000;
+    if num_entries > MAX_ENTRIES {
+        return Err(DictError::Format(format!(
+            "num_entries too large: {} (max {})",
+            num_entries, MAX_ENTRIES
+        )));
+    }
+    let mut entries = Vec::with_capacity(num_entries);
+
+    let mut buf2 = [0u8; 2];
+    for _ in 0..num_entries {
+        // Reading
+        r.read_exact(&mut buf2)?;
+        let reading_len = u16::from_le_bytes(buf2) as usize;
+        let mut reading_bytes = vec![0u8; reading_len];
+        r.read_exact(&mut reading_bytes)?;
+        let reading = String::from_utf8(reading_bytes)
+            .map_err(|e| DictError::Format(format!("invalid UTF-8 in reading: {e}")))?;
+
+        // Candidates
+        r.read_exact(&mut buf2)?;
+        let num_candidates = u16::from_le_bytes(buf2) as usize;
+        let mut candidates = Vec::with_capacity(num_candidates);
+        for _ in 0..num_candidates {
+            r.read_exact(&mut buf2)?;
+            let surface_len = u16::from_le_bytes(buf2) as usize;
+            let mut surface_bytes = vec![0u8; surface_len];
+            r.read_exact(&mut surface_bytes)?;
+            let surface = String::from_utf8(surface_bytes)
+                .map_err(|e| DictError::Format(format!("invalid UTF-8 in surface: {e}")))?;
+
+            r.read_exact(&mut buf4)?;
+            let score = f32::from_le_bytes(buf4);
+
+            let mut cand = Candidate::new(surface, score);
+            if version >= 2 {
+                r.read_exact(&mut buf2)?;
+                let pos_len = u16::from_le_bytes(buf2) as usize;
+                if pos_len > 0 {
+                    let mut pos_bytes = vec![0u8; pos_len];
+                    r.read_exact(&mut pos_bytes)?;
+                    cand.pos =
+                        Some(String::from_utf8(pos_bytes).map_err(|e| {
+                            DictError::Format(format!("invalid UTF-8 in pos: {e}"))
+                        })?);
+                }
+                r.read_exact(&mut buf4)?;
+                cand.freq = u32::from_le_bytes(buf4);
+            }
+            candidates.push(cand);
+        }
+
+        candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+        entries.push(DictEntry {
+            reading,
+            candidates,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Decode an SKK dictionary: UTF-8 (BOM stripped) if valid, otherwise EUC-JP.
+fn decode_skk(bytes: &[u8]) -> std::borrow::Cow<'_, str> {
+    let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.into(),
+        Err(_) => encoding_rs::EUC_JP.decode_without_bom_handling(bytes).0,
+    }
+}
+
+/// Base reading of an okuri-ari SKK reading (`おくr` → `おく`), or None if the
+/// reading has no okurigana marker.
+fn skk_base_reading(reading: &str) -> Option<&str> {
+    let base = reading.strip_suffix(|c: char| c.is_ascii_lowercase())?;
+    base.chars()
+        .last()
+        .is_some_and(|c| !c.is_ascii())
+        .then_some(base)
+}
+
 /// Unescape `\uXXXX` Unicode escape sequences in a string.
 ///
 /// Sudachi CSV files contain literal `\uXXXX` sequences (e.g. `\u0028` for `(`)
@@ -500,6 +1093,15 @@ fn unescape_unicode(s: &str) -> String {
     result
 }
 
+/// Sort candidates by cost (lower first), then by surface for a stable order
+fn sort_by_cost(candidates: &mut [Candidate]) {
+    candidates.sort_by(|a, b| {
+        a.score
+            .total_cmp(&b.score)
+            .then_with(|| a.surface.cmp(&b.surface))
+    });
+}
+
 /// Parse a single Sudachi CSV file into a map of reading → {surface → min_cost}.
 ///
 /// Sudachi CSV columns:
@@ -522,7 +1124,7 @@ pub fn parse_sudachi_csv(path: &Path) -> Result<HashMap<String, HashMap<String,
         }
 
         let cols: Vec<&str> = line.split(',').collect();
-        if cols.len() < 12 {
+        if cols.len() < SUDACHI_COLUMNS {
             continue;
         }
 
@@ -636,6 +1238,64 @@ mod tests {
         assert!(dict.entries.iter().any(|e| e.reading == "とうきょう"));
     }
 
+    fn dump(dict: &Dictionary) -> String {
+        let mut out = Vec::new();
+        dict.dump_all(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_build_from_json_streaming_matches() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+        let streamed = Dictionary::build_from_json_streaming(json_file.path()).unwrap();
+
+        assert_eq!(streamed.entry_count(), dict.entry_count());
+        assert_eq!(dump(&streamed), dump(&dict));
+        let result = streamed.exact_match_search("きょう").unwrap();
+        assert_eq!(result.candidates[0].surface, "京");
+    }
+
+    #[test]
+    fn test_build_from_json_streaming_rejects_invalid() {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(r#"{"reading": "きょう"}"#.as_bytes()).unwrap();
+        assert!(matches!(
+            Dictionary::build_from_json_streaming(f.path()),
+            Err(DictError::Json(_))
+        ));
+
+        // Trailing data after the array
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(b"[] []").unwrap();
+        assert!(Dictionary::build_from_json_streaming(f.path()).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_lookups_on_shared_dictionary() {
+        let json_file = create_test_json();
+        let dict = std::sync::Arc::new(Dictionary::build_from_json(json_file.path()).unwrap());
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let dict = std::sync::Arc::clone(&dict);
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let exact = dict.exact_match_search("きょう").unwrap();
+                        assert_eq!(exact.candidates.len(), 2);
+                        assert_eq!(exact.candidates[0].surface, "京");
+                        let prefixes = dict.common_prefix_search("きょうと");
+                        assert_eq!(prefixes.len(), 2);
+                        assert!(dict.exact_match_search("おおさか").is_none());
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
     #[test]
     fn test_exact_match_search() {
         let json_file = create_test_json();
@@ -666,6 +1326,105 @@ mod tests {
         assert!(readings.contains(&"きょうと"));
     }
 
+    #[test]
+    fn test_readings_by_surface() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+
+        // Exact surfaces only: 京 is not read from 京都
+        assert_eq!(
+            dict.readings_by_surface(&["京都", "京", "都"]),
+            vec![
+                Some("きょうと".to_string()),
+                Some("きょう".to_string()),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_predictive_search() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+
+        // "きょ" should match both "きょう" and "きょうと", but not "とうきょう"
+        let results = dict.predictive_search("きょ");
+        let readings: Vec<&str> = results.iter().map(|r| r.reading).collect();
+        assert_eq!(readings, vec!["きょう", "きょうと"]);
+
+        let results = dict.predictive_search("きょうと");
+        assert_eq!(results.len(), 1);
+        assert!(dict.predictive_search("おおさか").is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+
+        // お typed for the long vowel う: one edit away
+        assert!(dict.exact_match_search("きょおと").is_none());
+        assert!(dict.fuzzy_search("きょおと", 0).is_empty());
+        let results = dict.fuzzy_search("きょおと", 1);
+        let readings: Vec<&str> = results.iter().map(|r| r.reading).collect();
+        assert_eq!(readings, vec!["きょうと"]);
+
+        // Small/large kana and a dropped long vowel
+        let readings = |input, edits| {
+            dict.fuzzy_search(input, edits)
+                .iter()
+                .map(|r| r.reading.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(readings("きよう", 1), vec!["きょう"]);
+        assert_eq!(readings("ときょう", 1), vec!["とうきょう"]);
+        // Two edits: お for う and よ for ょ
+        assert!(readings("きよおと", 1).is_empty());
+        assert_eq!(readings("きよおと", 2), vec!["きょうと"]);
+        // Exact matches come first
+        assert_eq!(readings("きょう", 1)[0], "きょう");
+    }
+
+    #[test]
+    fn test_fuzzy_search_rejects_unrelated() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+
+        assert!(dict.fuzzy_search("おおさか", 2).is_empty());
+        assert!(dict.fuzzy_search("きゃうと", 2).is_empty());
+        assert!(dict.fuzzy_search("", 2).is_empty());
+        // Three edits away: max_edits is capped at MAX_FUZZY_EDITS
+        assert!(dict.fuzzy_search("きよおとー", usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+
+        let stats = dict.stats();
+        assert_eq!(stats.entry_count, 3);
+        assert_eq!(stats.candidate_count, 4);
+        assert!((stats.avg_candidates - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.max_candidates, 2);
+        // きょう (3), きょうと (4), とうきょう (5)
+        assert_eq!(stats.reading_length_histogram, vec![0, 0, 0, 1, 1, 1]);
+        assert_eq!(stats.score_min, Some(0.8));
+        assert_eq!(stats.score_max, Some(2.5));
+        assert!((stats.score_mean.unwrap() - 1.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        let stats = Dictionary::empty().stats();
+        assert_eq!(stats.entry_count, 0);
+        assert_eq!(stats.candidate_count, 0);
+        assert_eq!(stats.avg_candidates, 0.0);
+        assert!(stats.reading_length_histogram.is_empty());
+        assert_eq!(stats.score_min, None);
+        assert_eq!(stats.score_mean, None);
+    }
+
     #[test]
     fn test_save_and_load() {
         let json_file = create_test_json();
@@ -687,6 +1446,140 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn test_save_and_load_v2_pos_freq() {
+        let tsv_file = create_test_mozc_tsv();
+        let mut dict = Dictionary::build_from_mozc_tsv(tsv_file.path()).unwrap();
+        dict.entries[0].candidates[0].freq = 42;
+
+        let bin_file = NamedTempFile::new().unwrap();
+        dict.save(bin_file.path()).unwrap();
+        let bytes = std::fs::read(bin_file.path()).unwrap();
+        assert_eq!(&bytes[4..8], &2u32.to_le_bytes());
+
+        let loaded = Dictionary::load(bin_file.path()).unwrap();
+        let result = loaded.exact_match_search("きょう").unwrap();
+        assert_eq!(result.candidates[0].surface, "今日");
+        assert_eq!(result.candidates[0].pos.as_deref(), Some("名詞"));
+        assert_eq!(result.candidates[0].freq, 42);
+        assert_eq!(result.candidates[1].freq, 0);
+    }
+
+    /// Write `dict` in the version 1 layout (no pos/freq fields).
+    fn save_v1(dict: &Dictionary, path: &Path) {
+        let mut w = Vec::new();
+        w.extend_from_slice(MAGIC);
+        w.extend_from_slice(&1u32.to_le_bytes());
+        w.extend_from_slice(&(dict.trie.0.len() as u32).to_le_bytes());
+        w.extend_from_slice(&dict.trie.0);
+        w.extend_from_slice(&(dict.entries.len() as u32).to_le_bytes());
+        for entry in &dict.entries {
+            w.extend_from_slice(&(entry.reading.len() as u16).to_le_bytes());
+            w.extend_from_slice(entry.reading.as_bytes());
+            w.extend_from_slice(&(entry.candidates.len() as u16).to_le_bytes());
+            for cand in &entry.candidates {
+                w.extend_from_slice(&(cand.surface.len() as u16).to_le_bytes());
+                w.extend_from_slice(cand.surface.as_bytes());
+                w.extend_from_slice(&cand.score.to_le_bytes());
+            }
+        }
+        std::fs::write(path, w).unwrap();
+    }
+
+    #[test]
+    fn test_load_v1() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+        let bin_file = NamedTempFile::new().unwrap();
+        save_v1(&dict, bin_file.path());
+
+        let loaded = Dictionary::load(bin_file.path()).unwrap();
+        assert_eq!(loaded.entry_count(), 3);
+        let result = loaded.exact_match_search("きょう").unwrap();
+        assert_eq!(result.candidates[0].surface, "京");
+        assert!((result.candidates[0].score - 0.8).abs() < f32::EPSILON);
+        assert_eq!(result.candidates[0].pos, None);
+        assert_eq!(result.candidates[0].freq, 0);
+        assert_eq!(
+            loaded.exact_match_search("とうきょう").unwrap().candidates[0].surface,
+            "東京"
+        );
+
+        // Re-saving upgrades the file to the current version
+        loaded.save(bin_file.path()).unwrap();
+        let bytes = std::fs::read(bin_file.path()).unwrap();
+        assert_eq!(&bytes[4..8], &VERSION.to_le_bytes());
+        assert_eq!(Dictionary::load(bin_file.path()).unwrap().entry_count(), 3);
+    }
+
+    #[test]
+    fn test_load_rejects_future_version() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+        let bin_file = NamedTempFile::new().unwrap();
+        dict.save(bin_file.path()).unwrap();
+
+        let mut bytes = std::fs::read(bin_file.path()).unwrap();
+        bytes[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        std::fs::write(bin_file.path(), bytes).unwrap();
+        assert!(Dictionary::load(bin_file.path()).is_err());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_load_mmap_matches_load() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+        let bin_file = NamedTempFile::new().unwrap();
+        dict.save(bin_file.path()).unwrap();
+
+        let loaded = Dictionary::load(bin_file.path()).unwrap();
+        let mapped = Dictionary::load_mmap(bin_file.path()).unwrap();
+        assert_eq!(mapped.entry_count(), loaded.entry_count());
+
+        let summarize = |results: Vec<LookupResult<'_>>| -> Vec<(String, Vec<String>)> {
+            results
+                .iter()
+                .map(|r| {
+                    let surfaces = r.candidates.iter().map(|c| c.surface.clone()).collect();
+                    (r.reading.to_string(), surfaces)
+                })
+                .collect()
+        };
+        for input in ["きょう", "きょうと", "とうきょう", "きょうはいい", "あ"] {
+            assert_eq!(
+                summarize(mapped.common_prefix_search(input)),
+                summarize(loaded.common_prefix_search(input))
+            );
+            assert_eq!(
+                summarize(mapped.exact_match_search(input).into_iter().collect()),
+                summarize(loaded.exact_match_search(input).into_iter().collect())
+            );
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_load_mmap_rejects_truncated_file() {
+        let json_file = create_test_json();
+        let dict = Dictionary::build_from_json(json_file.path()).unwrap();
+        let bin_file = NamedTempFile::new().unwrap();
+        dict.save(bin_file.path()).unwrap();
+
+        let bytes = std::fs::read(bin_file.path()).unwrap();
+        std::fs::write(bin_file.path(), &bytes[..16]).unwrap();
+        assert!(Dictionary::load_mmap(bin_file.path()).is_err());
+    }
+
+    #[test]
+    fn test_empty_dictionary() {
+        let dict = Dictionary::empty();
+        assert_eq!(dict.entry_count(), 0);
+        assert!(dict.exact_match_search("きょう").is_none());
+        assert!(dict.common_prefix_search("きょう").is_empty());
+        assert!(dict.predictive_search("き").is_empty());
+    }
+
     #[test]
     fn test_no_match() {
         let json_file = create_test_json();
@@ -894,6 +1787,182 @@ col0,col1,col2,4500,今日,col5,col6,col7,col8,col9,col10,キョウ
         assert_eq!(result.candidates[0].surface, "今日");
     }
 
+    const TEST_SKK: &str = ";; -*- coding: utf-8 -*-\n\
+                            ;; okuri-ari entries.\n\
+                            おくr /送;send/贈/[る/送/]/\n\
+                            おくt /送/\n\
+                            ;; okuri-nasi entries.\n\
+                            きょう /今日/京;capital of Japan/(concat \"a\\057b\")/今日/\n\
+                            とうきょう /東京/\n";
+
+    fn create_test_skk(bytes: &[u8]) -> NamedTempFile {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(bytes).unwrap();
+        f.flush().unwrap();
+        f
+    }
+
+    fn surfaces(dict: &Dictionary, reading: &str) -> Vec<String> {
+        dict.exact_match_search(reading)
+            .map(|r| r.candidates.iter().map(|c| c.surface.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_build_from_skk() {
+        let skk = create_test_skk(TEST_SKK.as_bytes());
+        let dict = Dictionary::build_from_skk(skk.path()).unwrap();
+
+        // Annotation stripped, order preserved, duplicates and concat dropped
+        assert_eq!(surfaces(&dict, "きょう"), vec!["今日", "京"]);
+        assert_eq!(surfaces(&dict, "とうきょう"), vec!["東京"]);
+        assert!(
+            dict.exact_match_search("きょう")
+                .unwrap()
+                .candidates
+                .iter()
+                .all(|c| c.score == 0.0)
+        );
+        // Okuri-ari entries are skipped by default
+        assert!(surfaces(&dict, "おく").is_empty());
+        assert!(surfaces(&dict, "おくr").is_empty());
+        assert_eq!(dict.entry_count(), 2);
+    }
+
+    #[test]
+    fn test_build_from_skk_keep_okuri() {
+        let skk = create_test_skk(TEST_SKK.as_bytes());
+        let dict = Dictionary::build_from_skk_with_okuri(skk.path(), true).unwrap();
+
+        // Both okuri-ari lines merge under the base reading
+        assert_eq!(surfaces(&dict, "おく"), vec!["送", "贈"]);
+        assert_eq!(surfaces(&dict, "きょう"), vec!["今日", "京"]);
+    }
+
+    #[test]
+    fn test_build_from_skk_euc_jp() {
+        let (bytes, _, _) = encoding_rs::EUC_JP.encode(TEST_SKK);
+        let skk = create_test_skk(&bytes);
+        let dict = Dictionary::build_from_skk(skk.path()).unwrap();
+        assert_eq!(surfaces(&dict, "きょう"), vec!["今日", "京"]);
+    }
+
+    #[test]
+    fn test_build_from_skk_utf8_bom() {
+        let skk = create_test_skk(&[UTF8_BOM, TEST_SKK.as_bytes()].concat());
+        let dict = Dictionary::build_from_skk(skk.path()).unwrap();
+        assert_eq!(surfaces(&dict, "とうきょう"), vec!["東京"]);
+    }
+
+    #[test]
+    fn test_load_auto_skk() {
+        let skk = create_test_skk(TEST_SKK.as_bytes());
+        let dict = Dictionary::load_auto(skk.path()).unwrap();
+        assert_eq!(surfaces(&dict, "きょう"), vec!["今日", "京"]);
+    }
+
+    #[test]
+    fn test_skk_base_reading() {
+        assert_eq!(skk_base_reading("おくr"), Some("おく"));
+        assert_eq!(skk_base_reading("きょう"), None);
+        // All-ASCII readings (abbrev entries) have no okurigana
+        assert_eq!(skk_base_reading("http"), None);
+    }
+
+    #[test]
+    fn test_build_from_sudachi_csvs() {
+        let csv1 = create_test_sudachi_csv();
+        let mut csv2 = NamedTempFile::new().unwrap();
+        csv2.write_all(
+            "col0,col1,col2,3500,大阪,col5,col6,col7,col8,col9,col10,オオサカ\n\
+             col0,col1,col2,5500,強,col5,col6,col7,col8,col9,col10,キョウ\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        csv2.flush().unwrap();
+
+        let dict = Dictionary::build_from_sudachi_csvs(&[csv1.path(), csv2.path()]).unwrap();
+        assert_eq!(dict.entry_count(), 4);
+
+        // Lowest cost first; 今日 keeps its minimum cost of 4500
+        let result = dict.exact_match_search("きょう").unwrap();
+        let ranked: Vec<(&str, f32)> = result
+            .candidates
+            .iter()
+            .map(|c| (c.surface.as_str(), c.score))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![("今日", 4500.0), ("強", 5500.0), ("京", 6000.0)]
+        );
+        assert_eq!(surfaces(&dict, "おおさか"), vec!["大阪"]);
+        assert_eq!(surfaces(&dict, "きょうと"), vec!["京都"]);
+    }
+
+    #[test]
+    fn test_build_from_reading_map_merges_kana_types() {
+        // かな and カナ collide once converted; 仮名 keeps its lower cost
+        for _ in 0..8 {
+            let map = HashMap::from([
+                (
+                    "かな".to_string(),
+                    HashMap::from([("仮名".to_string(), 5000), ("かな".to_string(), 7000)]),
+                ),
+                (
+                    "カナ".to_string(),
+                    HashMap::from([("仮名".to_string(), 3000), ("カナ".to_string(), 4000)]),
+                ),
+            ]);
+            let dict = Dictionary::build_from_reading_map(map).unwrap();
+            assert_eq!(dict.entry_count(), 1);
+            let result = dict.exact_match_search("かな").unwrap();
+            let ranked: Vec<(&str, f32)> = result
+                .candidates
+                .iter()
+                .map(|c| (c.surface.as_str(), c.score))
+                .collect();
+            assert_eq!(
+                ranked,
+                vec![("仮名", 3000.0), ("カナ", 4000.0), ("かな", 7000.0)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_auto_sudachi_csv() {
+        let csv = create_test_sudachi_csv();
+        let path = csv.path().with_extension("csv");
+        std::fs::copy(csv.path(), &path).unwrap();
+
+        let dict = Dictionary::load_auto(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Readings are hiragana, candidates ordered by min cost
+        let result = dict.exact_match_search("きょう").unwrap();
+        let surfaces: Vec<&str> = result
+            .candidates
+            .iter()
+            .map(|c| c.surface.as_str())
+            .collect();
+        assert_eq!(surfaces, vec!["今日", "京"]);
+        assert!((result.candidates[0].score - 4500.0).abs() < f32::EPSILON);
+        assert!(dict.exact_match_search("とうきょう").is_some());
+        assert_eq!(dict.entry_count(), 3);
+    }
+
+    #[test]
+    fn test_load_auto_csv_without_sudachi_columns_is_tsv() {
+        // A user dictionary in Mozc TSV that happens to be named .csv
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("user.csv");
+        std::fs::write(&path, "\nきょう\t今日\t名詞\t\nかるかん\t軽羹\t名詞\t\n").unwrap();
+
+        assert_eq!(DictFormat::detect(&path).unwrap(), DictFormat::MozcTsv);
+        let dict = Dictionary::load_auto(&path).unwrap();
+        assert_eq!(surfaces(&dict, "きょう"), vec!["今日"]);
+        assert_eq!(surfaces(&dict, "かるかん"), vec!["軽羹"]);
+    }
+
     #[test]
     fn test_merge_dictionaries() {
         // Create two TSV dictionaries
@@ -1,5 +1,10 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
 use unicode_normalization::UnicodeNormalization;
 
+use crate::romaji::{RomajiConfig, build_rules};
+
 /// Apply NFKC normalization to text.
 ///
 /// This is needed for models whose tokenizer does NOT support full-width ASCII
@@ -40,6 +45,251 @@ pub fn katakana_to_hiragana(text: &str) -> String {
         .collect()
 }
 
+/// Convert printable ASCII to full-width forms (e.g. "abc!" → "ａｂｃ！").
+///
+/// A space becomes an ideographic space (U+3000); other characters are
+/// passed through unchanged.
+pub fn ascii_to_fullwidth(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            ' ' => '\u{3000}',
+            // ASCII '!'..='~' (U+0021-U+007E) -> Fullwidth (U+FF01-U+FF5E)
+            '!'..='~' => std::char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Full-width katakana/punctuation and their half-width forms (same order)
+const FULLWIDTH_KANA: &str = "ァアィイゥウェエォオカキクケコサシスセソタチッツテトナニヌネノハヒフヘホ\
+                              マミムメモャヤュユョヨラリルレロワヲン。「」、・ー゛゜";
+const HALFWIDTH_KANA: &str = "ｧｱｨｲｩｳｪｴｫｵｶｷｸｹｺｻｼｽｾｿﾀﾁｯﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎ\
+                              ﾏﾐﾑﾒﾓｬﾔｭﾕｮﾖﾗﾘﾙﾚﾛﾜｦﾝ｡｢｣､･ｰﾞﾟ";
+/// Katakana with dakuten and their base (same order)
+const DAKUTEN_KANA: &str = "ガギグゲゴザジズゼゾダヂヅデドバビブベボヴヷヺ";
+const DAKUTEN_BASE: &str = "カキクケコサシスセソタチツテトハヒフヘホウワヲ";
+/// Katakana with handakuten and their base (same order)
+const HANDAKUTEN_KANA: &str = "パピプペポ";
+const HANDAKUTEN_BASE: &str = "ハヒフヘホ";
+
+/// Map `c` from one table to the character at the same position in another
+fn map_kana(c: char, from: &str, to: &str) -> Option<char> {
+    from.chars()
+        .position(|x| x == c)
+        .and_then(|i| to.chars().nth(i))
+}
+
+/// Convert full-width katakana to half-width katakana (e.g. "ガッコウ" → "ｶﾞｯｺｳ").
+///
+/// Dakuten/handakuten become separate half-width marks (ガ → ｶﾞ, パ → ﾊﾟ);
+/// combining marks (U+3099/U+309A) of decomposed input are converted the same
+/// way. The long vowel mark and 。「」、・ are converted too. Characters without
+/// a half-width form (ヮ, ヵ, ヶ, hiragana, ...) are passed through unchanged.
+pub fn katakana_to_halfwidth(text: &str) -> String {
+    let half = |c: char| map_kana(c, FULLWIDTH_KANA, HALFWIDTH_KANA);
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if let Some(base) = map_kana(c, DAKUTEN_KANA, DAKUTEN_BASE).and_then(half) {
+            out.push(base);
+            out.push('ﾞ');
+        } else if let Some(base) = map_kana(c, HANDAKUTEN_KANA, HANDAKUTEN_BASE).and_then(half) {
+            out.push(base);
+            out.push('ﾟ');
+        } else {
+            out.push(match c {
+                '\u{3099}' => 'ﾞ',
+                '\u{309A}' => 'ﾟ',
+                _ => half(c).unwrap_or(c),
+            });
+        }
+    }
+    out
+}
+
+/// Convert half-width katakana to full-width katakana (e.g. "ｶﾞｯｺｳ" → "ガッコウ").
+///
+/// A base character followed by ﾞ/ﾟ is composed into a single character
+/// (ｶﾞ → ガ). A mark that cannot be composed becomes ゛/゜. Inverse of
+/// [`katakana_to_halfwidth`] for composed input.
+pub fn halfwidth_to_katakana(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let Some(full) = map_kana(c, HALFWIDTH_KANA, FULLWIDTH_KANA) else {
+            out.push(c);
+            continue;
+        };
+        let composed = match chars.peek() {
+            Some('ﾞ') => map_kana(full, DAKUTEN_BASE, DAKUTEN_KANA),
+            Some('ﾟ') => map_kana(full, HANDAKUTEN_BASE, HANDAKUTEN_KANA),
+            _ => None,
+        };
+        if let Some(composed) = composed {
+            chars.next();
+            out.push(composed);
+        } else {
+            out.push(full);
+        }
+    }
+    out
+}
+
+/// Heuristic: does a hiragana reading look like a loanword that should be
+/// written in katakana?
+///
+/// Native hiragana words rarely contain the long vowel mark `ー`, `ゔ`, or a
+/// small vowel following another kana (`ふぁ`, `てぃ`, `うぇ`, ...), which are
+/// typical of transcribed foreign words. Readings containing anything other
+/// than hiragana and `ー` are never treated as loanwords.
+pub fn looks_like_loanword(reading: &str) -> bool {
+    let is_kana = |c: char| matches!(c, '\u{3041}'..='\u{3096}' | 'ー');
+    if reading.chars().count() < 2 || !reading.chars().all(is_kana) {
+        return false;
+    }
+    let mut prev: Option<char> = None;
+    for c in reading.chars() {
+        match c {
+            'ー' | 'ゔ' => return true,
+            'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' if prev.is_some() => return true,
+            _ => {}
+        }
+        prev = Some(c);
+    }
+    false
+}
+
+/// Vowel (あいうえお) of a hiragana character, if it has one.
+fn hiragana_vowel(c: char) -> Option<char> {
+    const ROWS: [(char, &str); 5] = [
+        ('あ', "あかさたなはまやらわがざだばぱぁゃゎ"),
+        ('い', "いきしちにひみりぎじぢびぴぃ"),
+        ('う', "うくすつぬふむゆるぐずづぶぷぅゅゔ"),
+        ('え', "えけせてねへめれげぜでべぺぇ"),
+        ('お', "おこそとのほもよろをごぞどぼぽぉょ"),
+    ];
+    ROWS.iter()
+        .find(|(_, row)| row.contains(c))
+        .map(|(vowel, _)| *vowel)
+}
+
+/// Replace a vowel that repeats the preceding kana's vowel with `ー`
+/// (e.g. `らあめん` → `らーめん`, `きゃあ` → `きゃー`).
+///
+/// Used for katakana-mode output, where `raamen` and `ra-men` should both
+/// produce ラーメン. The mapping is one character to one character, so
+/// character offsets into the input stay valid.
+pub fn vowel_repeat_to_long_vowel(text: &str) -> String {
+    let mut prev_vowel = None;
+    text.chars()
+        .map(|c| {
+            let vowel = hiragana_vowel(c);
+            let out = if matches!(c, 'あ' | 'い' | 'う' | 'え' | 'お') && prev_vowel == Some(c)
+            {
+                'ー'
+            } else {
+                c
+            };
+            prev_vowel = vowel;
+            out
+        })
+        .collect()
+}
+
+/// Spellings preferred over the shorter Kunrei ones (si, ti, tu) in reverse maps
+const HEPBURN_PREFERRED: [&str; 3] = ["shi", "chi", "tsu"];
+
+/// Kana → romaji map derived from the default romaji rules, so it stays in
+/// sync with `build_rules()`. When several spellings produce the same kana,
+/// the plainest one wins: no alternative prefixes (c but not ch, q, l) or apostrophes,
+/// Hepburn for し/ち/つ, then the shortest, then alphabetical.
+fn reverse_romaji_map() -> &'static HashMap<String, String> {
+    static MAP: OnceLock<HashMap<String, String>> = OnceLock::new();
+    MAP.get_or_init(|| {
+        let rank = |romaji: &str| {
+            let alternative = (romaji.starts_with(['c', 'q', 'l']) && !romaji.starts_with("ch"))
+                || romaji.contains('\'');
+            let preferred = HEPBURN_PREFERRED.contains(&romaji);
+            (alternative, !preferred, romaji.len(), romaji.to_string())
+        };
+        let mut map: HashMap<String, String> = HashMap::new();
+        for (romaji, kana) in build_rules(&RomajiConfig::default()).entries() {
+            match map.get(&kana) {
+                Some(existing) if rank(existing) <= rank(&romaji) => {}
+                _ => {
+                    map.insert(kana, romaji);
+                }
+            }
+        }
+        map
+    })
+}
+
+/// A unit of hiragana text for reverse transliteration
+enum RomajiToken<'a> {
+    Romaji(&'a str),
+    /// っ: doubles the next consonant
+    Sokuon,
+    /// ん: spelled depending on what follows
+    SyllabicN,
+    Other(char),
+}
+
+/// Convert hiragana to romaji (e.g. "こんにちは" → "konnichiha").
+///
+/// Uses the reverse of the romaji input rules: youon are matched as a unit
+/// (きゃ → kya), っ doubles the following consonant (がっこう → gakkou), and
+/// ん is "nn" before a vowel or y and "n" otherwise. Characters without a
+/// rule (katakana, kanji, ASCII, ...) are passed through unchanged.
+pub fn hiragana_to_romaji(text: &str) -> String {
+    let map = reverse_romaji_map();
+    let chars: Vec<char> = text.chars().collect();
+
+    let mut tokens = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let (token, len) = match chars[i] {
+            'っ' => (RomajiToken::Sokuon, 1),
+            'ん' => (RomajiToken::SyllabicN, 1),
+            c => {
+                // Prefer two-character matches (youon) over single kana
+                let pair: Option<String> = chars.get(i..i + 2).map(|p| p.iter().collect());
+                if let Some(romaji) = pair.and_then(|p| map.get(&p)) {
+                    (RomajiToken::Romaji(romaji), 2)
+                } else if let Some(romaji) = map.get(&c.to_string()) {
+                    (RomajiToken::Romaji(romaji), 1)
+                } else {
+                    (RomajiToken::Other(c), 1)
+                }
+            }
+        };
+        tokens.push(token);
+        i += len;
+    }
+
+    let mut out = String::with_capacity(text.len() * 2);
+    for (idx, token) in tokens.iter().enumerate() {
+        let next = match tokens.get(idx + 1) {
+            Some(RomajiToken::Romaji(r)) => r.chars().next(),
+            _ => None,
+        };
+        match token {
+            RomajiToken::Romaji(r) => out.push_str(r),
+            RomajiToken::Sokuon => match next {
+                Some(c) if c.is_ascii_alphabetic() && !matches!(c, 'a' | 'i' | 'u' | 'e' | 'o') => {
+                    out.push(c)
+                }
+                _ => out.push_str("xtu"),
+            },
+            RomajiToken::SyllabicN => match next {
+                Some('a' | 'i' | 'u' | 'e' | 'o' | 'y') => out.push_str("nn"),
+                _ => out.push('n'),
+            },
+            RomajiToken::Other(c) => out.push(*c),
+        }
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +351,138 @@ mod tests {
             "\u{ee02}context\u{ee00}input\u{ee01}"
         );
     }
+
+    #[test]
+    fn test_looks_like_loanword() {
+        assert!(looks_like_loanword("らーめん"));
+        assert!(looks_like_loanword("ふぁいる"));
+        assert!(looks_like_loanword("ぱーてぃ"));
+        assert!(looks_like_loanword("ゔぁいおりん"));
+
+        assert!(!looks_like_loanword("こんにちは"));
+        assert!(!looks_like_loanword("らめん"));
+        // Leading small vowel is not a foreign sound combination
+        assert!(!looks_like_loanword("ぁあ"));
+        // Single characters and non-hiragana text are never loanwords
+        assert!(!looks_like_loanword("ー"));
+        assert!(!looks_like_loanword("ラーメン"));
+        assert!(!looks_like_loanword("ら-めん"));
+        assert!(!looks_like_loanword(""));
+    }
+
+    #[test]
+    fn test_vowel_repeat_to_long_vowel() {
+        assert_eq!(vowel_repeat_to_long_vowel("らあめん"), "らーめん");
+        assert_eq!(vowel_repeat_to_long_vowel("らーめん"), "らーめん");
+        assert_eq!(vowel_repeat_to_long_vowel("らめん"), "らめん");
+        assert_eq!(vowel_repeat_to_long_vowel("こおひい"), "こーひー");
+        assert_eq!(vowel_repeat_to_long_vowel("きゃあ"), "きゃー");
+        assert_eq!(vowel_repeat_to_long_vowel("らああ"), "らーー");
+        // Different vowels, ん, っ and a leading vowel are kept
+        assert_eq!(vowel_repeat_to_long_vowel("あい"), "あい");
+        assert_eq!(vowel_repeat_to_long_vowel("けいと"), "けいと");
+        assert_eq!(vowel_repeat_to_long_vowel("んあ"), "んあ");
+        assert_eq!(vowel_repeat_to_long_vowel("かっあ"), "かっあ");
+        assert_eq!(vowel_repeat_to_long_vowel("んー"), "んー");
+        assert_eq!(vowel_repeat_to_long_vowel(""), "");
+    }
+
+    #[test]
+    fn test_hiragana_to_romaji() {
+        assert_eq!(hiragana_to_romaji("こんにちは"), "konnichiha");
+        assert_eq!(hiragana_to_romaji("がっこう"), "gakkou");
+        assert_eq!(hiragana_to_romaji("きゃく"), "kyaku");
+        assert_eq!(hiragana_to_romaji("しゃしん"), "shashin");
+        assert_eq!(hiragana_to_romaji("つづける"), "tsudukeru");
+        assert_eq!(hiragana_to_romaji("ぱんだ"), "panda");
+        assert_eq!(hiragana_to_romaji("ぎゅうにゅう"), "gyuunyuu");
+        assert_eq!(hiragana_to_romaji("まっちゃ"), "maccha");
+        assert_eq!(hiragana_to_romaji("らーめん"), "ra-men");
+        assert_eq!(hiragana_to_romaji("ふぁいる"), "fairu");
+        assert_eq!(hiragana_to_romaji("ぁ"), "xa");
+    }
+
+    #[test]
+    fn test_hiragana_to_romaji_syllabic_n() {
+        assert_eq!(hiragana_to_romaji("きんえん"), "kinnen");
+        assert_eq!(hiragana_to_romaji("こんや"), "konnya");
+        assert_eq!(hiragana_to_romaji("さんぽ"), "sanpo");
+        assert_eq!(hiragana_to_romaji("ほん"), "hon");
+    }
+
+    #[test]
+    fn test_hiragana_to_romaji_passthrough() {
+        assert_eq!(hiragana_to_romaji(""), "");
+        assert_eq!(hiragana_to_romaji("カナ漢字abc"), "カナ漢字abc");
+        assert_eq!(hiragana_to_romaji("あっ"), "axtu");
+        assert_eq!(hiragana_to_romaji("あっ!"), "axtu!");
+        assert_eq!(hiragana_to_romaji("ねこ、いぬ。"), "neko,inu.");
+    }
+
+    #[test]
+    fn test_hiragana_to_romaji_round_trip() {
+        use crate::RomajiConverter;
+        for word in [
+            "がっこう",
+            "きゃく",
+            "しゃしん",
+            "ぱんだ",
+            "らーめん",
+            "きんえん",
+            "まっちゃ",
+        ] {
+            let mut conv = RomajiConverter::new();
+            for ch in hiragana_to_romaji(word).chars() {
+                conv.push(ch);
+            }
+            conv.flush();
+            assert_eq!(conv.output(), word);
+        }
+    }
+
+    #[test]
+    fn test_katakana_to_halfwidth() {
+        assert_eq!(katakana_to_halfwidth("カタカナ"), "ｶﾀｶﾅ");
+        assert_eq!(katakana_to_halfwidth("ガッコウ"), "ｶﾞｯｺｳ");
+        assert_eq!(katakana_to_halfwidth("パン"), "ﾊﾟﾝ");
+        assert_eq!(katakana_to_halfwidth("ヴァイオリン"), "ｳﾞｧｲｵﾘﾝ");
+        assert_eq!(katakana_to_halfwidth("ラーメン、「ヨシ」。"), "ﾗｰﾒﾝ､｢ﾖｼ｣｡");
+        // Decomposed input: combining marks become half-width marks
+        assert_eq!(katakana_to_halfwidth("カ\u{3099}ハ\u{309A}"), "ｶﾞﾊﾟ");
+        // No half-width form: passed through
+        assert_eq!(
+            katakana_to_halfwidth("ヵヶヮかなabc漢字"),
+            "ヵヶヮかなabc漢字"
+        );
+    }
+
+    #[test]
+    fn test_halfwidth_to_katakana() {
+        assert_eq!(halfwidth_to_katakana("ｶﾀｶﾅ"), "カタカナ");
+        assert_eq!(halfwidth_to_katakana("ｶﾞｯｺｳ"), "ガッコウ");
+        assert_eq!(halfwidth_to_katakana("ﾊﾟﾝ"), "パン");
+        assert_eq!(halfwidth_to_katakana("ｳﾞ"), "ヴ");
+        // Marks that cannot be composed stay as separate full-width marks
+        assert_eq!(halfwidth_to_katakana("ｱﾞﾏﾟ"), "ア゛マ゜");
+        assert_eq!(halfwidth_to_katakana("ﾞ"), "゛");
+        assert_eq!(halfwidth_to_katakana("abc漢字"), "abc漢字");
+        // Decomposed full-width input normalizes to composed form
+        assert_eq!(
+            halfwidth_to_katakana(&katakana_to_halfwidth("カ\u{3099}")),
+            "ガ"
+        );
+    }
+
+    #[test]
+    fn test_halfwidth_round_trip() {
+        let all = [FULLWIDTH_KANA, DAKUTEN_KANA, HANDAKUTEN_KANA].concat();
+        assert_eq!(halfwidth_to_katakana(&katakana_to_halfwidth(&all)), all);
+    }
+
+    #[test]
+    fn test_ascii_to_fullwidth() {
+        assert_eq!(ascii_to_fullwidth("ramen"), "ｒａｍｅｎ");
+        assert_eq!(ascii_to_fullwidth("A1 ~!"), "Ａ１\u{3000}～！");
+        assert_eq!(ascii_to_fullwidth("かな"), "かな");
+    }
 }
@@ -1,24 +1,88 @@
-//! Backend interface for kanji conversion using llama.cpp
+//! Conversion backends for kanji conversion (llama.cpp by default)
 
 use super::error::KanjiError;
 use super::hf_download::{get_tokenizer_path, get_variant_path};
-use super::llamacpp::LlamaCppModel;
-use super::model_config::{ModelFamily, VariantConfig, registry};
+use super::llamacpp::{LlamaCppModel, NllScorerPool, SamplingParams};
+use super::model_config::{ModelFamily, PromptFormat, VariantConfig, registry};
 use super::{CONTEXT_TOKEN, INPUT_START_TOKEN, OUTPUT_START_TOKEN};
 use crate::kana::hiragana_to_katakana;
+use llama_cpp_2::token::LlamaToken;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Scoring workers of a [`LlamaCppBackend`]. Each keeps a llama.cpp context,
+/// so this stays small; rerank scores only a page of surfaces at a time.
+const SCORER_POOL_SIZE: usize = 2;
 
 type Result<T> = super::error::Result<T>;
 
 /// Configuration for kanji conversion
 #[derive(Debug, Clone)]
 pub struct ConversionConfig {
-    /// Maximum number of new tokens to generate
+    /// Maximum number of new tokens to generate (ceiling of the per-reading budget)
     pub max_new_tokens: usize,
+    /// New tokens allowed beyond the reading's own token count
+    pub new_tokens_margin: usize,
+    /// Repetition penalty for greedy decoding (1.0 = disabled).
+    /// Values above 1.0 keep the model from looping on long readings (e.g. "々々々").
+    pub repeat_penalty: f32,
+    /// How generated text is cleaned into a candidate
+    pub clean: CleanOptions,
 }
 
 impl Default for ConversionConfig {
     fn default() -> Self {
-        Self { max_new_tokens: 50 }
+        Self {
+            max_new_tokens: 50,
+            new_tokens_margin: 16,
+            repeat_penalty: 1.0,
+            clean: CleanOptions::default(),
+        }
+    }
+}
+
+impl ConversionConfig {
+    /// New token budget for a reading of `input_tokens` tokens
+    pub fn new_token_budget(&self, input_tokens: usize) -> usize {
+        new_token_budget(input_tokens, self.new_tokens_margin, self.max_new_tokens)
+    }
+}
+
+/// New token budget for a reading: its token count plus `margin`, at most `ceiling`
+///
+/// The conversion is about as long as the reading, so short readings stop
+/// early instead of spending a fixed budget (which matters for beam search),
+/// and long readings get room up to the ceiling. The budget is never below
+/// 1, since generation always samples at least one token.
+pub fn new_token_budget(input_tokens: usize, margin: usize, ceiling: usize) -> usize {
+    input_tokens.saturating_add(margin).clamp(1, ceiling.max(1))
+}
+
+/// Rules for turning decoded model output into a candidate
+///
+/// The default only trims whitespace, since special tokens are already
+/// skipped when decoding. Model families that leak markers or ramble past
+/// the conversion can enable the stricter rules.
+#[derive(Debug, Clone, Copy)]
+pub struct CleanOptions {
+    /// Remove the jinen special tokens (context / input / output markers)
+    pub strip_special_tokens: bool,
+    /// Trim leading and trailing whitespace
+    pub trim_whitespace: bool,
+    /// Keep only the first non-blank line
+    pub first_line_only: bool,
+    /// Drop every character this returns `false` for
+    pub allowed_chars: Option<fn(char) -> bool>,
+}
+
+impl Default for CleanOptions {
+    fn default() -> Self {
+        Self {
+            strip_special_tokens: false,
+            trim_whitespace: true,
+            first_line_only: false,
+            allowed_chars: None,
+        }
     }
 }
 
@@ -30,12 +94,42 @@ pub fn build_jinen_prompt(katakana: &str, context: &str) -> String {
     )
 }
 
+/// Build the prompt for a katakana reading in `family`'s prompt format
+pub fn build_prompt(family: &ModelFamily, katakana: &str, context: &str) -> String {
+    family.prompt_format.build(katakana, context)
+}
+
 /// Clean model output by trimming whitespace.
 ///
 /// Special tokens (BOS/EOS) are handled at the decode level via
 /// `skip_special_tokens` rather than string replacement.
 pub fn clean_model_output(text: &str) -> String {
-    text.trim().to_string()
+    clean_model_output_with(text, &CleanOptions::default())
+}
+
+/// Clean model output with explicit [`CleanOptions`]
+pub fn clean_model_output_with(text: &str, options: &CleanOptions) -> String {
+    let mut text: String = if options.strip_special_tokens {
+        text.chars()
+            .filter(|c| ![CONTEXT_TOKEN, INPUT_START_TOKEN, OUTPUT_START_TOKEN].contains(c))
+            .collect()
+    } else {
+        text.to_string()
+    };
+    if options.first_line_only {
+        text = text
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default()
+            .to_string();
+    }
+    if let Some(allowed) = options.allowed_chars {
+        text.retain(allowed);
+    }
+    if options.trim_whitespace {
+        text = text.trim().to_string();
+    }
+    text
 }
 
 /// Inference backend configuration (llama.cpp GGUF format with external tokenizer)
@@ -45,6 +139,10 @@ pub struct Backend {
     tokenizer_json_path: String,
     /// Display name for the model (variant id for registry models, "custom" for GGUF paths)
     display_name: String,
+    /// Layers to offload to the GPU (0 = CPU only)
+    gpu_layers: u32,
+    /// Prompt layout of the model family
+    prompt_format: PromptFormat,
 }
 
 impl Backend {
@@ -58,6 +156,8 @@ impl Backend {
             gguf_path: path.to_string_lossy().to_string(),
             tokenizer_json_path: tokenizer_path.to_string_lossy().to_string(),
             display_name: variant.id.clone(),
+            gpu_layers: family.gpu_layers_for(variant),
+            prompt_format: family.prompt_format.clone(),
         })
     }
 
@@ -72,11 +172,245 @@ impl Backend {
     }
 }
 
-/// Kanji converter using llama.cpp backend
-pub struct KanaKanjiConverter {
-    model: LlamaCppModel,
+/// A kana-kanji conversion engine behind [`KanaKanjiConverter`]
+///
+/// [`LlamaCppBackend`] (a jinen GGUF model) is the default. Embedders can plug
+/// in their own engine (an ONNX model, a remote API, a mock in tests) with
+/// [`KanaKanjiConverter::from_backend`]. Readings are passed in katakana; the
+/// converter drops empty and duplicate candidates and falls back to the reading.
+pub trait ConversionBackend: Send + Sync {
+    /// Up to `n` candidates for a katakana reading, given the left `context`
+    fn convert(&self, katakana: &str, context: &str, n: usize) -> Result<Vec<String>>;
+
+    /// Like [`Self::convert`], but multi-candidate search stops at `deadline`
+    /// and returns what it has found so far. `None` means no time limit.
+    ///
+    /// The default ignores the deadline.
+    fn convert_with_deadline(
+        &self,
+        katakana: &str,
+        context: &str,
+        n: usize,
+        _deadline: Option<Instant>,
+    ) -> Result<Vec<String>> {
+        self.convert(katakana, context, n)
+    }
+
+    /// Human-readable model name for display
+    fn model_display_name(&self) -> &str;
+
+    /// Convert many readings with the same context.
+    ///
+    /// Results are in input order; an empty list means the reading failed.
+    /// The default calls [`Self::convert`] for each reading.
+    fn convert_batch(&self, katakana: &[&str], context: &str, n: usize) -> Vec<Vec<String>> {
+        katakana
+            .iter()
+            .map(|k| self.convert(k, context, n).unwrap_or_default())
+            .collect()
+    }
+
+    /// Number of input tokens for a katakana reading (default: one per character)
+    fn count_input_tokens(&self, katakana: &str) -> Result<usize> {
+        Ok(katakana.chars().count())
+    }
+
+    /// Per-character NLL of each surface for a katakana reading after `context`
+    /// (lower is more likely)
+    fn score_candidates(
+        &self,
+        _katakana: &str,
+        _context: &str,
+        _surfaces: &[&str],
+    ) -> Result<Vec<f32>> {
+        Err(KanjiError::Unsupported("candidate scoring"))
+    }
+
+    /// Set the number of threads for inference (0 = default). Ignored by default.
+    fn set_n_threads(&mut self, _n: u32) {}
+}
+
+/// The default [`ConversionBackend`]: a jinen model run with llama.cpp
+pub struct LlamaCppBackend {
+    model: Arc<LlamaCppModel>,
     config: ConversionConfig,
     display_name: String,
+    prompt_format: PromptFormat,
+    /// Started on the first [`ConversionBackend::score_candidates`] call
+    scorers: OnceLock<NllScorerPool>,
+}
+
+impl LlamaCppBackend {
+    /// Load the model described by `backend`
+    pub fn new(backend: Backend, config: ConversionConfig) -> Result<Self> {
+        let model = LlamaCppModel::from_file_with_gpu_layers(
+            &backend.gguf_path,
+            &backend.tokenizer_json_path,
+            backend.gpu_layers,
+        )?;
+        Ok(LlamaCppBackend {
+            model: Arc::new(model),
+            config,
+            display_name: backend.display_name,
+            prompt_format: backend.prompt_format,
+            scorers: OnceLock::new(),
+        })
+    }
+
+    /// Tokenize the prompt for a katakana reading and left context
+    fn prompt_tokens(&self, katakana: &str, context: &str) -> Result<Vec<LlamaToken>> {
+        let prompt = self.prompt_format.build(katakana, context);
+        self.model.tokenize(&prompt)
+    }
+
+    /// New token budget for a katakana reading
+    fn token_budget(&self, katakana: &str) -> Result<usize> {
+        let input_tokens = self.model.tokenize(katakana)?.len();
+        Ok(self.config.new_token_budget(input_tokens))
+    }
+
+    /// Sampling parameters for single-candidate greedy decoding
+    fn greedy_params(&self) -> SamplingParams {
+        SamplingParams {
+            repeat_penalty: self.config.repeat_penalty,
+            ..SamplingParams::default()
+        }
+    }
+
+    /// Decode a greedy generation into a candidate list
+    fn greedy_candidates(&self, generated: &[LlamaToken]) -> Result<Vec<String>> {
+        let text = self.model.decode(generated, true)?;
+        Ok(vec![clean_model_output_with(&text, &self.config.clean)])
+    }
+}
+
+impl ConversionBackend for LlamaCppBackend {
+    fn convert(&self, katakana: &str, context: &str, n: usize) -> Result<Vec<String>> {
+        self.convert_with_deadline(katakana, context, n, None)
+    }
+
+    /// Greedy decoding (`n == 1`) always runs to the end; beam search keeps
+    /// the beams generated so far once `deadline` passes.
+    fn convert_with_deadline(
+        &self,
+        katakana: &str,
+        context: &str,
+        n: usize,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<String>> {
+        let tokens = self.prompt_tokens(katakana, context)?;
+        let max_new_tokens = self.token_budget(katakana)?;
+        let eos = Some(self.model.eos_token_id().0);
+
+        if n == 1 {
+            // Single candidate: use greedy decoding (faster)
+            let output_tokens = self.model.generate_with_params(
+                &tokens,
+                max_new_tokens,
+                eos,
+                self.greedy_params(),
+            )?;
+            return self.greedy_candidates(&output_tokens[tokens.len()..]);
+        }
+
+        // Multiple candidates: use beam search
+        let results = self.model.generate_beam_search_with_deadline(
+            &tokens,
+            max_new_tokens,
+            eos,
+            n,
+            deadline,
+        )?;
+        results
+            .into_iter()
+            .map(|(output_tokens, _score)| {
+                let text = self.model.decode(&output_tokens, true)?;
+                Ok(clean_model_output_with(&text, &self.config.clean))
+            })
+            .collect()
+    }
+
+    fn model_display_name(&self) -> &str {
+        &self.display_name
+    }
+
+    /// Single-candidate (greedy) conversions share one llama.cpp context instead
+    /// of creating one per reading; beam search (`n > 1`) runs per reading.
+    fn convert_batch(&self, katakana: &[&str], context: &str, n: usize) -> Vec<Vec<String>> {
+        let per_reading = || {
+            katakana
+                .iter()
+                .map(|k| self.convert(k, context, n).unwrap_or_default())
+                .collect()
+        };
+        if n != 1 {
+            return per_reading();
+        }
+
+        let prompts: Result<Vec<_>> = katakana
+            .iter()
+            .map(|k| self.prompt_tokens(k, context))
+            .collect();
+        // Each reading keeps the budget `convert` would give it
+        let budgets: Result<Vec<usize>> = katakana.iter().map(|k| self.token_budget(k)).collect();
+        let eos = Some(self.model.eos_token_id().0);
+        let outputs = prompts.and_then(|prompts| {
+            let outputs =
+                self.model
+                    .generate_batch(&prompts, &budgets?, eos, self.greedy_params())?;
+            Ok(prompts.into_iter().zip(outputs).collect::<Vec<_>>())
+        });
+        let Ok(outputs) = outputs else {
+            return vec![Vec::new(); katakana.len()];
+        };
+
+        outputs
+            .into_iter()
+            .map(|(prompt, output)| {
+                self.greedy_candidates(&output[prompt.len()..])
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    fn count_input_tokens(&self, katakana: &str) -> Result<usize> {
+        Ok(self.model.tokenize(katakana)?.len())
+    }
+
+    /// Surfaces are scored after the same prompt that conversion uses, by a
+    /// pool whose scoring contexts are kept between calls.
+    fn score_candidates(
+        &self,
+        katakana: &str,
+        context: &str,
+        surfaces: &[&str],
+    ) -> Result<Vec<f32>> {
+        let prompt = self.prompt_format.build(katakana, context);
+        self.scorers
+            .get_or_init(|| {
+                NllScorerPool::new(
+                    Arc::clone(&self.model),
+                    self.model.n_ctx(),
+                    SCORER_POOL_SIZE,
+                )
+            })
+            .score_after(&prompt, surfaces)
+    }
+
+    fn set_n_threads(&mut self, n: u32) {
+        // Stop the scoring workers so the model is no longer shared; the pool
+        // is started again on the next scoring call
+        self.scorers.take();
+        match Arc::get_mut(&mut self.model) {
+            Some(model) => model.set_n_threads(n),
+            None => tracing::warn!("Model is shared, keeping its thread count"),
+        }
+    }
+}
+
+/// Kana-kanji converter over a [`ConversionBackend`] (llama.cpp by default)
+pub struct KanaKanjiConverter {
+    backend: Box<dyn ConversionBackend>,
 }
 
 impl KanaKanjiConverter {
@@ -87,17 +421,19 @@ impl KanaKanjiConverter {
 
     /// Create a new converter with the specified backend and configuration
     pub fn with_config(backend: Backend, config: ConversionConfig) -> Result<Self> {
-        let model = LlamaCppModel::from_file(&backend.gguf_path, &backend.tokenizer_json_path)?;
-        Ok(KanaKanjiConverter {
-            model,
-            config,
-            display_name: backend.display_name,
-        })
+        Ok(Self::from_backend(LlamaCppBackend::new(backend, config)?))
+    }
+
+    /// Create a converter over a custom conversion backend
+    pub fn from_backend(backend: impl ConversionBackend + 'static) -> Self {
+        KanaKanjiConverter {
+            backend: Box::new(backend),
+        }
     }
 
     /// Set the number of threads for inference (0 = default).
     pub fn set_n_threads(&mut self, n: u32) {
-        self.model.set_n_threads(n);
+        self.backend.set_n_threads(n);
     }
 
     /// Convert hiragana to kanji candidates
@@ -114,74 +450,171 @@ impl KanaKanjiConverter {
         reading: &str,
         context: &str,
         num_candidates: usize,
+    ) -> Result<Vec<String>> {
+        self.convert_with_deadline(reading, context, num_candidates, None)
+    }
+
+    /// Convert hiragana to kanji candidates, stopping the search at `deadline`
+    ///
+    /// Same as [`Self::convert`], but beam search returns the candidates found
+    /// so far once `deadline` passes. `None` means no time limit.
+    pub fn convert_with_deadline(
+        &self,
+        reading: &str,
+        context: &str,
+        num_candidates: usize,
+        deadline: Option<Instant>,
     ) -> Result<Vec<String>> {
         // Convert hiragana to katakana (model expects katakana input)
         let katakana = hiragana_to_katakana(reading);
+        let candidates =
+            self.backend
+                .convert_with_deadline(&katakana, context, num_candidates, deadline)?;
+        Ok(Self::finish_candidates(reading, candidates))
+    }
 
-        // Build prompt in jinen format
-        let prompt = build_jinen_prompt(&katakana, context);
-
-        // Tokenize
-        let tokens = self.model.tokenize(&prompt)?;
-        let eos = Some(self.model.eos_token_id().0);
-
-        let mut candidates = Vec::with_capacity(num_candidates);
-
-        if num_candidates == 1 {
-            // Single candidate: use greedy decoding (faster)
-            let output_tokens = self
-                .model
-                .generate(&tokens, self.config.max_new_tokens, eos)?;
-            let generated = &output_tokens[tokens.len()..];
-            let text = self.model.decode(generated, true)?;
-            let clean = clean_model_output(&text);
-
-            if !clean.is_empty() {
-                candidates.push(clean);
-            }
-        } else {
-            // Multiple candidates: use beam search
-            let results = self.model.generate_beam_search(
-                &tokens,
-                self.config.max_new_tokens,
-                eos,
-                num_candidates,
-            )?;
-
-            for (output_tokens, _score) in results {
-                let text = self.model.decode(&output_tokens, true)?;
-                let clean = clean_model_output(&text);
+    /// Convert many readings with the same context in one call
+    ///
+    /// The llama.cpp backend shares one context for single-candidate (greedy)
+    /// conversions instead of creating one per reading. Results are in input
+    /// order and match [`Self::convert`] for each reading. A reading whose
+    /// conversion fails falls back to the reading itself.
+    pub fn convert_batch(
+        &self,
+        readings: &[&str],
+        context: &str,
+        num_candidates: usize,
+    ) -> Vec<Vec<String>> {
+        let katakana: Vec<String> = readings.iter().map(|r| hiragana_to_katakana(r)).collect();
+        let katakana: Vec<&str> = katakana.iter().map(String::as_str).collect();
+        let mut outputs = self
+            .backend
+            .convert_batch(&katakana, context, num_candidates)
+            .into_iter();
+        readings
+            .iter()
+            .map(|r| Self::finish_candidates(r, outputs.next().unwrap_or_default()))
+            .collect()
+    }
 
-                if !clean.is_empty() && !candidates.contains(&clean) {
-                    candidates.push(clean);
-                }
+    /// Drop empty and duplicate candidates; the reading itself if none are left
+    fn finish_candidates(reading: &str, raw: Vec<String>) -> Vec<String> {
+        let mut candidates: Vec<String> = Vec::with_capacity(raw.len());
+        for candidate in raw {
+            if !candidate.is_empty() && !candidates.contains(&candidate) {
+                candidates.push(candidate);
             }
         }
-
-        // If no candidates, return the original reading
         if candidates.is_empty() {
             candidates.push(reading.to_string());
         }
+        candidates
+    }
 
-        Ok(candidates)
+    /// Score candidate surfaces for a hiragana reading after the left
+    /// `context` by per-character NLL
+    ///
+    /// Lower is more likely under the model. The result is in the same order
+    /// as `surfaces`.
+    pub fn score_candidates(
+        &self,
+        reading: &str,
+        context: &str,
+        surfaces: &[&str],
+    ) -> Result<Vec<f32>> {
+        self.backend
+            .score_candidates(&hiragana_to_katakana(reading), context, surfaces)
     }
 
     /// Get a human-readable model name for display
     pub fn model_display_name(&self) -> &str {
-        &self.display_name
+        self.backend.model_display_name()
     }
 
     /// Count only the input (reading) tokens, excluding context and special tokens
     pub fn count_input_tokens(&self, reading: &str) -> Result<usize> {
-        let katakana = hiragana_to_katakana(reading);
-        let tokens = self.model.tokenize(&katakana)?;
-        Ok(tokens.len())
+        self.backend
+            .count_input_tokens(&hiragana_to_katakana(reading))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Returns canned candidates and records the readings it was given
+    struct MockBackend {
+        candidates: Vec<String>,
+        readings: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockBackend {
+        fn new(candidates: &[&str]) -> Self {
+            MockBackend {
+                candidates: candidates.iter().map(|c| c.to_string()).collect(),
+                readings: Arc::default(),
+            }
+        }
+    }
+
+    impl ConversionBackend for MockBackend {
+        fn convert(&self, katakana: &str, _context: &str, n: usize) -> Result<Vec<String>> {
+            self.readings.lock().unwrap().push(katakana.to_string());
+            Ok(self.candidates.iter().take(n).cloned().collect())
+        }
+
+        fn model_display_name(&self) -> &str {
+            "mock"
+        }
+    }
+
+    #[test]
+    fn test_build_prompt_jinen_unchanged() {
+        let (family, _) = registry().default_variant().unwrap();
+        assert_eq!(
+            build_prompt(family, "カンジ", "今日は"),
+            build_jinen_prompt("カンジ", "今日は")
+        );
+        assert_eq!(
+            PromptFormat::Jinen.build("カンジ", ""),
+            "\u{ee02}\u{ee00}カンジ\u{ee01}"
+        );
+    }
+
+    #[test]
+    fn test_mock_backend_conversion() {
+        let converter =
+            KanaKanjiConverter::from_backend(MockBackend::new(&["漢字", "", "漢字", "感じ"]));
+        assert_eq!(converter.model_display_name(), "mock");
+        assert_eq!(
+            converter.convert("かんじ", "", 4).unwrap(),
+            vec!["漢字", "感じ"]
+        );
+        assert_eq!(converter.convert("かんじ", "", 1).unwrap(), vec!["漢字"]);
+        assert_eq!(converter.count_input_tokens("かんじ").unwrap(), 3);
+        assert!(matches!(
+            converter.score_candidates("かんじ", "", &["漢字"]),
+            Err(KanjiError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_mock_backend_receives_katakana() {
+        let backend = MockBackend::new(&[]);
+        let readings = Arc::clone(&backend.readings);
+        let converter = KanaKanjiConverter::from_backend(backend);
+        // No candidates: the reading itself
+        assert_eq!(converter.convert("かんじ", "", 3).unwrap(), vec!["かんじ"]);
+        assert_eq!(
+            converter.convert_batch(&["とうきょう", "にほん"], "", 1),
+            vec![vec!["とうきょう"], vec!["にほん"]]
+        );
+        assert_eq!(
+            *readings.lock().unwrap(),
+            ["カンジ", "トウキョウ", "ニホン"]
+        );
+    }
 
     #[test]
 
@@ -259,4 +692,50 @@ mod tests {
             output
         );
     }
+
+    #[test]
+
+    fn test_convert_batch_matches_convert() {
+        let backend =
+            Backend::from_variant_id("jinen-v1-xsmall-q5").expect("Failed to download GGUF");
+        let converter = KanaKanjiConverter::new(backend).expect("Failed to create converter");
+        let readings = ["かんじ", "とうきょう", "にほんご"];
+
+        for num_candidates in [1, 3] {
+            let batch = converter.convert_batch(&readings, "", num_candidates);
+            assert_eq!(batch.len(), readings.len());
+            for (reading, candidates) in readings.iter().zip(&batch) {
+                let single = converter
+                    .convert(reading, "", num_candidates)
+                    .expect("Conversion failed");
+                assert_eq!(candidates, &single, "Mismatch for {}", reading);
+            }
+        }
+
+        assert!(converter.convert_batch(&[], "", 1).is_empty());
+    }
+
+    #[test]
+    fn test_convert_with_deadline_truncates_beam_search() {
+        let backend =
+            Backend::from_variant_id("jinen-v1-xsmall-q5").expect("Failed to download GGUF");
+        let converter = KanaKanjiConverter::new(backend).expect("Failed to create converter");
+        let reading = "きょうはいいてんきですね";
+
+        let full = converter
+            .convert(reading, "", 3)
+            .expect("Conversion failed");
+        // An expired deadline keeps only the first token of each beam
+        let truncated = converter
+            .convert_with_deadline(reading, "", 3, Some(Instant::now()))
+            .expect("Conversion failed");
+        assert!(!truncated.is_empty());
+        let longest = |c: &[String]| c.iter().map(|s| s.chars().count()).max().unwrap();
+        assert!(
+            longest(&truncated) < longest(&full),
+            "{:?} not shorter than {:?}",
+            truncated,
+            full
+        );
+    }
 }
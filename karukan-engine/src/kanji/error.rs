@@ -9,6 +9,18 @@ pub enum KanjiError {
     #[error("download failed")]
     Download(#[source] Box<dyn std::error::Error + Send + Sync>),
 
+    #[error(
+        "'{filename}' from {repo_id} is not in the local cache and downloads are disabled (offline mode)"
+    )]
+    NotCached { repo_id: String, filename: String },
+
+    #[error("checksum mismatch for {}: expected sha256 {expected}, got {actual}", path.display())]
+    ChecksumMismatch {
+        path: std::path::PathBuf,
+        expected: String,
+        actual: String,
+    },
+
     #[error("model load failed")]
     ModelLoad(#[source] Box<dyn std::error::Error + Send + Sync>),
 
@@ -17,6 +29,12 @@ pub enum KanjiError {
 
     #[error("inference failed")]
     Inference(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("{tokens} input tokens do not fit the context window of {n_ctx} tokens")]
+    ContextOverflow { tokens: usize, n_ctx: usize },
+
+    #[error("{0} is not supported by this conversion backend")]
+    Unsupported(&'static str),
 }
 
 pub type Result<T> = std::result::Result<T, KanjiError>;
@@ -2,17 +2,54 @@
 //!
 //! Downloads GGUF models from HuggingFace Hub and caches them locally.
 //! Model definitions are loaded from `models.toml` via [`super::model_config`].
+//!
+//! In offline mode ([`set_offline`] or `KARUKAN_OFFLINE=1`) only the local
+//! cache is used, and a missing file is an error instead of a download.
+//! Variants with a `sha256` in `models.toml` are checked after download.
+//! A successful check is remembered in karukan's own cache directory (not the
+//! HuggingFace cache), so an unchanged file is not hashed again.
 
 use super::error::KanjiError;
 use super::model_config::{ModelFamily, VariantConfig, registry};
 type Result<T> = super::error::Result<T>;
-use hf_hub::{Repo, RepoType, api::sync::ApiBuilder};
-use std::path::PathBuf;
+use directories::ProjectDirs;
+use hf_hub::api::sync::{ApiBuilder, ApiRepo};
+use hf_hub::{Cache, Repo, RepoType};
+use ring::digest;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Environment variable that enables offline mode (`1`, `true`, `yes` or `on`)
+pub const OFFLINE_ENV: &str = "KARUKAN_OFFLINE";
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable offline mode for this process.
+///
+/// `KARUKAN_OFFLINE` enables it regardless of this setting.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether downloads are refused ([`set_offline`] or `KARUKAN_OFFLINE`)
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+        || std::env::var(OFFLINE_ENV).is_ok_and(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        })
+}
 
 /// Download a GGUF model from HuggingFace Hub
 ///
 /// Returns the local path to the downloaded file.
 /// The file is cached in the HuggingFace cache directory (~/.cache/huggingface/hub/).
+/// In offline mode the cached file is returned, or [`KanjiError::NotCached`]
+/// if there is none.
 ///
 /// # Arguments
 /// * `repo_id` - HuggingFace repository ID
@@ -20,17 +57,21 @@ use std::path::PathBuf;
 ///
 /// # Environment Variables
 /// * `HF_TOKEN` - HuggingFace API token (required for private repositories)
+/// * `KARUKAN_OFFLINE` - Never download, only use the local cache
 pub fn download_gguf(repo_id: &str, filename: &str) -> Result<PathBuf> {
-    // Check for HF_TOKEN environment variable
-    let mut builder = ApiBuilder::new();
-    if let Ok(token) = std::env::var("HF_TOKEN") {
-        builder = builder.with_token(Some(token));
+    fetch_file(Cache::default(), repo_id, filename, is_offline())
+}
+
+/// Path of `filename` in `cache`, downloading it unless `offline`
+fn fetch_file(cache: Cache, repo_id: &str, filename: &str, offline: bool) -> Result<PathBuf> {
+    if offline {
+        return cached_file(&cache, repo_id, filename).ok_or_else(|| KanjiError::NotCached {
+            repo_id: repo_id.to_string(),
+            filename: filename.to_string(),
+        });
     }
-    let api = builder
-        .build()
-        .map_err(|e| KanjiError::Download(e.into()))?;
 
-    let repo = api.repo(Repo::new(repo_id.to_string(), RepoType::Model));
+    let repo = api_repo(cache, repo_id)?;
 
     tracing::info!("Downloading {} from {}...", filename, repo_id);
 
@@ -43,12 +84,200 @@ pub fn download_gguf(repo_id: &str, filename: &str) -> Result<PathBuf> {
     Ok(path)
 }
 
-/// Get local path for a variant, downloading if not cached.
+/// HuggingFace Hub client for `repo_id`, storing files in `cache`
+fn api_repo(cache: Cache, repo_id: &str) -> Result<ApiRepo> {
+    // Check for HF_TOKEN environment variable
+    let mut builder = ApiBuilder::from_cache(cache);
+    if let Ok(token) = std::env::var("HF_TOKEN") {
+        builder = builder.with_token(Some(token));
+    }
+    let api = builder
+        .build()
+        .map_err(|e| KanjiError::Download(e.into()))?;
+    Ok(api.repo(Repo::new(repo_id.to_string(), RepoType::Model)))
+}
+
+/// Path of `filename` if it is already in `cache`
+fn cached_file(cache: &Cache, repo_id: &str, filename: &str) -> Option<PathBuf> {
+    cache
+        .repo(Repo::new(repo_id.to_string(), RepoType::Model))
+        .get(filename)
+}
+
+/// Get local path for a variant, downloading if not cached (unless offline).
+///
+/// The file is checked against the variant's `sha256`, if configured.
 pub fn get_variant_path(family: &ModelFamily, variant: &VariantConfig) -> Result<PathBuf> {
-    download_gguf(&family.repo_id, &variant.filename)
+    fetch_verified(
+        Cache::default(),
+        &family.repo_id,
+        &variant.filename,
+        variant.sha256.as_deref(),
+        is_offline(),
+        verified_dir().as_deref(),
+    )
+}
+
+/// Directory for verified-checksum stamps (`~/.cache/karukan/verified` on Linux)
+fn verified_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "karukan", "karukan").map(|dirs| dirs.cache_dir().join("verified"))
+}
+
+/// [`fetch_file`], then check the file against `sha256` (if any).
+///
+/// A mismatching file (e.g. a truncated download) is downloaded again once,
+/// unless offline, before giving up with [`KanjiError::ChecksumMismatch`].
+/// Successful checks are stamped in `stamp_dir`, if given.
+fn fetch_verified(
+    cache: Cache,
+    repo_id: &str,
+    filename: &str,
+    sha256: Option<&str>,
+    offline: bool,
+    stamp_dir: Option<&Path>,
+) -> Result<PathBuf> {
+    let path = fetch_file(cache.clone(), repo_id, filename, offline)?;
+    let Some(expected) = sha256 else {
+        tracing::debug!(
+            "No sha256 configured for {}, skipping verification",
+            filename
+        );
+        return Ok(path);
+    };
+    match verify_sha256(&path, expected, stamp_dir) {
+        Err(e @ KanjiError::ChecksumMismatch { .. }) if !offline => {
+            tracing::warn!("{}; downloading {} again", e, filename);
+            let path = api_repo(cache, repo_id)?
+                .download(filename)
+                .map_err(|e| KanjiError::Download(e.into()))?;
+            verify_sha256(&path, expected, stamp_dir)?;
+            Ok(path)
+        }
+        result => result.map(|()| path),
+    }
+}
+
+/// Check that the file at `path` has the SHA-256 `expected` (hex, any case).
+///
+/// Skips hashing if a [`VerifiedStamp`] in `stamp_dir` records this digest for
+/// the file as it is now; records a new stamp after a successful check.
+fn verify_sha256(path: &Path, expected: &str, stamp_dir: Option<&Path>) -> Result<()> {
+    let expected_hex = expected.trim().to_ascii_lowercase();
+    let stamp = stamp_dir.and_then(|dir| VerifiedStamp::of(dir, path));
+    if let Some(stamp) = &stamp
+        && stamp.matches(&expected_hex)
+    {
+        tracing::debug!("{:?} already verified, skipping sha256", path);
+        return Ok(());
+    }
+
+    let actual = sha256_hex(path).map_err(|e| KanjiError::Download(e.into()))?;
+    if actual == expected_hex {
+        if let Some(stamp) = stamp
+            && let Err(e) = stamp.write(&actual)
+        {
+            tracing::debug!("Could not write {:?}: {}", stamp.path, e);
+        }
+        Ok(())
+    } else {
+        Err(KanjiError::ChecksumMismatch {
+            path: path.to_path_buf(),
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+/// Record of a file whose checksum was verified.
+///
+/// The stamp file holds `"<sha256> <file state>"`. It is named after a hash of
+/// the resolved file path (the hf-hub blob, not the snapshot symlink), so every
+/// snapshot of the same blob shares it. The file state is the size, mtime and,
+/// on Unix, inode and ctime; ctime changes on every write and cannot be set
+/// back, so a same-size rewrite in place invalidates the stamp.
+struct VerifiedStamp {
+    /// Stamp file in the stamp directory
+    path: PathBuf,
+    /// Current state of the verified file
+    state: String,
 }
 
-/// Get the local path to `tokenizer.json` for a model family, downloading if necessary.
+impl VerifiedStamp {
+    /// Stamp location and current state for the file at `file`
+    fn of(dir: &Path, file: &Path) -> Option<Self> {
+        let blob = file.canonicalize().ok()?;
+        let meta = blob.metadata().ok()?;
+        let mtime = meta
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        let state = format!("{} {}{}", meta.len(), mtime, inode_and_ctime(&meta));
+        let name = digest::digest(&digest::SHA256, blob.as_os_str().as_encoded_bytes());
+        Some(Self {
+            path: dir.join(hex(name.as_ref())),
+            state,
+        })
+    }
+
+    /// Whether the stamp records `sha256` for the file in its current state
+    fn matches(&self, sha256: &str) -> bool {
+        std::fs::read_to_string(&self.path)
+            .is_ok_and(|s| s.trim() == format!("{} {}", sha256, self.state))
+    }
+
+    /// Record that the file in its current state has `sha256`
+    fn write(&self, sha256: &str) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, format!("{} {}\n", sha256, self.state))
+    }
+}
+
+/// `" <inode> <ctime>"` of a file, for [`VerifiedStamp`]
+#[cfg(unix)]
+fn inode_and_ctime(meta: &std::fs::Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    format!(" {} {}.{}", meta.ino(), meta.ctime(), meta.ctime_nsec())
+}
+
+/// Not available outside Unix; size and mtime only
+#[cfg(not(unix))]
+fn inode_and_ctime(_meta: &std::fs::Metadata) -> String {
+    String::new()
+}
+
+/// Lowercase hex SHA-256 of a file, read in chunks
+fn sha256_hex(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut context = digest::Context::new(&digest::SHA256);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        context.update(&buf[..n]);
+    }
+    Ok(hex(context.finish().as_ref()))
+}
+
+/// Lowercase hex encoding of `bytes`
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Local path of a variant if it is already in the HuggingFace cache.
+///
+/// Looks where [`download_gguf`] stores files, without touching the network.
+pub fn cached_variant_path(family: &ModelFamily, variant: &VariantConfig) -> Option<PathBuf> {
+    cached_file(&Cache::default(), &family.repo_id, &variant.filename)
+}
+
+/// Get the local path to `tokenizer.json` for a model family, downloading if
+/// necessary (unless offline).
 pub fn get_tokenizer_path(family: &ModelFamily) -> Result<PathBuf> {
     download_gguf(&family.repo_id, "tokenizer.json")
 }
@@ -99,6 +328,188 @@ mod tests {
         assert!(result.is_err());
     }
 
+    /// Cache containing `filename` for `repo_id`, laid out like hf-hub's
+    fn cache_with(repo_id: &str, filename: &str) -> (tempfile::TempDir, Cache) {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf());
+        let repo = cache.repo(Repo::new(repo_id.to_string(), RepoType::Model));
+        repo.create_ref("abc123").unwrap();
+        let snapshot = dir
+            .path()
+            .join(Repo::new(repo_id.to_string(), RepoType::Model).folder_name())
+            .join("snapshots/abc123");
+        std::fs::create_dir_all(&snapshot).unwrap();
+        std::fs::write(snapshot.join(filename), b"gguf").unwrap();
+        (dir, cache)
+    }
+
+    #[test]
+    fn test_offline_uses_cached_file() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model-Q5_K_M.gguf");
+        let path = fetch_file(cache, "example/model.gguf", "model-Q5_K_M.gguf", true).unwrap();
+        assert!(path.ends_with("snapshots/abc123/model-Q5_K_M.gguf"));
+        assert_eq!(std::fs::read(path).unwrap(), b"gguf");
+    }
+
+    #[test]
+    fn test_offline_missing_file_is_error() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model-Q5_K_M.gguf");
+        let err =
+            fetch_file(cache.clone(), "example/model.gguf", "tokenizer.json", true).unwrap_err();
+        assert!(matches!(
+            &err,
+            KanjiError::NotCached { repo_id, filename }
+                if repo_id == "example/model.gguf" && filename == "tokenizer.json"
+        ));
+        assert!(err.to_string().contains("offline"));
+
+        // Unknown repo: no refs at all
+        let err = fetch_file(cache, "example/other.gguf", "model.gguf", true).unwrap_err();
+        assert!(matches!(err, KanjiError::NotCached { .. }));
+    }
+
+    /// SHA-256 of the fixture contents `b"gguf"`
+    const GGUF_SHA256: &str = "1cb1b7e0f8b96cee3445e317b8064d8805bf35c7dc7de82cddcb9f78d4c95e0e";
+
+    #[test]
+    fn test_checksum_match() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        let path = fetch_verified(
+            cache,
+            "example/model.gguf",
+            "model.gguf",
+            Some(&GGUF_SHA256.to_ascii_uppercase()),
+            true,
+            None,
+        )
+        .unwrap();
+        assert_eq!(sha256_hex(&path).unwrap(), GGUF_SHA256);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        let wrong = "0".repeat(64);
+        let err = fetch_verified(
+            cache,
+            "example/model.gguf",
+            "model.gguf",
+            Some(&wrong),
+            true,
+            None,
+        )
+        .unwrap_err();
+        match err {
+            KanjiError::ChecksumMismatch {
+                path,
+                expected,
+                actual,
+            } => {
+                assert!(path.ends_with("model.gguf"));
+                assert_eq!(expected, wrong);
+                assert_eq!(actual, GGUF_SHA256);
+            }
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verified_stamp_records_digest_outside_hf_cache() {
+        let (dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        let stamps = tempfile::tempdir().unwrap();
+        let path = fetch_verified(
+            cache,
+            "example/model.gguf",
+            "model.gguf",
+            Some(GGUF_SHA256),
+            true,
+            Some(stamps.path()),
+        )
+        .unwrap();
+
+        let stamp = VerifiedStamp::of(stamps.path(), &path).unwrap();
+        assert!(stamp.matches(GGUF_SHA256));
+        let content = std::fs::read_to_string(&stamp.path).unwrap();
+        assert!(content.starts_with(GGUF_SHA256), "{}", content);
+        // A stamp for another digest does not count
+        assert!(!stamp.matches(&"0".repeat(64)));
+
+        // Nothing is written into the HuggingFace cache
+        let file = path.canonicalize().unwrap();
+        let file_dir = file.parent().unwrap();
+        assert_eq!(std::fs::read_dir(file_dir).unwrap().count(), 1);
+        assert!(file_dir.starts_with(dir.path().canonicalize().unwrap()));
+    }
+
+    #[test]
+    fn test_changed_file_is_hashed_again() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        let stamps = tempfile::tempdir().unwrap();
+        let path = cached_file(&cache, "example/model.gguf", "model.gguf").unwrap();
+        assert!(verify_sha256(&path, GGUF_SHA256, Some(stamps.path())).is_ok());
+
+        // A changed mtime invalidates the stamp
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime + std::time::Duration::from_secs(1))
+            .unwrap();
+        assert!(
+            !VerifiedStamp::of(stamps.path(), &path)
+                .unwrap()
+                .matches(GGUF_SHA256)
+        );
+        assert!(verify_sha256(&path, GGUF_SHA256, Some(stamps.path())).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_same_size_rewrite_is_hashed_again() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        let stamps = tempfile::tempdir().unwrap();
+        let path = cached_file(&cache, "example/model.gguf", "model.gguf").unwrap();
+        assert!(verify_sha256(&path, GGUF_SHA256, Some(stamps.path())).is_ok());
+
+        // Same size and mtime but different contents: ctime still changes
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(&path, b"GGUF").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        assert!(matches!(
+            verify_sha256(&path, GGUF_SHA256, Some(stamps.path())),
+            Err(KanjiError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_failed_check_leaves_no_stamp() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        let stamps = tempfile::tempdir().unwrap();
+        let path = cached_file(&cache, "example/model.gguf", "model.gguf").unwrap();
+        assert!(verify_sha256(&path, &"0".repeat(64), Some(stamps.path())).is_err());
+        assert!(
+            !VerifiedStamp::of(stamps.path(), &path)
+                .unwrap()
+                .path
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_no_checksum_skips_verification() {
+        let (_dir, cache) = cache_with("example/model.gguf", "model.gguf");
+        assert!(
+            fetch_verified(cache, "example/model.gguf", "model.gguf", None, true, None).is_ok()
+        );
+    }
+
     #[test]
     fn test_tokenizer_path_by_id_unknown() {
         let result = get_tokenizer_path_by_id("nonexistent-model-id");
@@ -14,9 +14,14 @@ use llama_cpp_2::model::LlamaModel;
 use llama_cpp_2::model::params::LlamaModelParams;
 use llama_cpp_2::sampling::LlamaSampler;
 use llama_cpp_2::token::LlamaToken;
+use std::borrow::Cow;
 use std::num::NonZeroU32;
+use std::ops::ControlFlow;
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use std::thread::JoinHandle;
+use std::time::Instant;
 
 /// Global llama.cpp backend (can only be initialized once)
 static LLAMA_BACKEND: OnceLock<std::result::Result<LlamaBackend, String>> = OnceLock::new();
@@ -36,6 +41,11 @@ fn get_backend() -> Result<&'static LlamaBackend> {
     }
 }
 
+/// Whether `deadline` has passed (never when there is no deadline)
+fn deadline_passed(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|d| Instant::now() >= d)
+}
+
 /// Convert bytes to hex display format for partial UTF-8 sequences
 fn bytes_to_hex_display(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("<{:02X}>", b)).collect()
@@ -59,6 +69,81 @@ struct BeamState {
     score: f32,
 }
 
+/// Sampling parameters for [`LlamaCppModel::generate_with_params`]
+///
+/// A temperature of 0 (the default) selects greedy decoding; `top_p`, `top_k`
+/// and `seed` are ignored in that case. The repetition penalty applies to
+/// both greedy decoding and sampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingParams {
+    /// Softmax temperature (0 = greedy)
+    pub temperature: f32,
+    /// Nucleus sampling threshold (1.0 = disabled)
+    pub top_p: f32,
+    /// Keep only the k most likely tokens (0 = disabled)
+    pub top_k: usize,
+    /// Seed for the random sampler
+    pub seed: u64,
+    /// Penalty for tokens already generated (1.0 = disabled)
+    pub repeat_penalty: f32,
+    /// Number of most recent generated tokens the repetition penalty looks at
+    pub repeat_last_n: i32,
+}
+
+impl Default for SamplingParams {
+    fn default() -> Self {
+        Self {
+            temperature: 0.0,
+            top_p: 1.0,
+            top_k: 0,
+            seed: 0,
+            repeat_penalty: 1.0,
+            repeat_last_n: 64,
+        }
+    }
+}
+
+impl SamplingParams {
+    /// Whether these parameters reduce to greedy decoding
+    pub fn is_greedy(&self) -> bool {
+        self.temperature <= 0.0
+    }
+
+    /// Build the llama.cpp sampler chain:
+    /// penalties → (greedy | top-k → top-p → temperature → dist)
+    fn sampler(&self, model: &LlamaModel) -> LlamaSampler {
+        let mut chain = Vec::new();
+        if self.repeat_penalty != 1.0 {
+            chain.push(LlamaSampler::penalties(
+                model.n_vocab(),
+                self.repeat_last_n,
+                self.repeat_penalty,
+                0.0,
+                0.0,
+            ));
+        }
+        if self.is_greedy() {
+            chain.push(LlamaSampler::greedy());
+        } else {
+            if self.top_k > 0 {
+                chain.push(LlamaSampler::top_k(
+                    i32::try_from(self.top_k).unwrap_or(i32::MAX),
+                ));
+            }
+            if self.top_p < 1.0 {
+                chain.push(LlamaSampler::top_p(self.top_p, 1));
+            }
+            chain.push(LlamaSampler::temp(self.temperature));
+            // llama.cpp seeds with 32 bits; fold the upper half in
+            chain.push(LlamaSampler::dist((self.seed ^ (self.seed >> 32)) as u32));
+        }
+        if chain.len() == 1 {
+            return chain.pop().expect("chain has one sampler");
+        }
+        LlamaSampler::chain_simple(chain)
+    }
+}
+
 /// llama.cpp based GPT-2 model for GGUF inference
 pub struct LlamaCppModel {
     model: LlamaModel,
@@ -75,10 +160,21 @@ impl LlamaCppModel {
     ///
     /// GPT-2 models use CPU only (Metal has issues with GPT-2).
     pub fn from_file<P: AsRef<Path>, T: AsRef<Path>>(path: P, tokenizer_json: T) -> Result<Self> {
+        // GPT-2 has Metal issues, use CPU
+        Self::from_file_with_gpu_layers(path, tokenizer_json, 0)
+    }
+
+    /// Load a GGUF model, offloading `n_gpu_layers` layers to the GPU.
+    ///
+    /// Only for models that run correctly on the GPU; see [`Self::from_file`].
+    pub fn from_file_with_gpu_layers<P: AsRef<Path>, T: AsRef<Path>>(
+        path: P,
+        tokenizer_json: T,
+        n_gpu_layers: u32,
+    ) -> Result<Self> {
         let backend = get_backend()?;
 
-        // GPT-2 has Metal issues, use CPU
-        let model_params = LlamaModelParams::default().with_n_gpu_layers(0);
+        let model_params = LlamaModelParams::default().with_n_gpu_layers(n_gpu_layers);
 
         let model = LlamaModel::load_from_file(backend, path.as_ref(), &model_params)
             .map_err(|e| KanjiError::ModelLoad(e.into()))?;
@@ -96,11 +192,13 @@ impl LlamaCppModel {
     ///
     /// Some models use custom pre-tokenizer types (e.g., `gpt2-small-japanese-char`)
     /// that llama.cpp doesn't recognize. This method overrides the `tokenizer.ggml.pre`
-    /// metadata key to a compatible type before loading.
+    /// metadata key to a compatible type before loading. `n_gpu_layers` is as in
+    /// [`Self::from_file_with_gpu_layers`] (0 = CPU only).
     pub fn from_file_with_pre_tokenizer_override<P: AsRef<Path>, T: AsRef<Path>>(
         path: P,
         tokenizer_json: T,
         pre_tokenizer: &str,
+        n_gpu_layers: u32,
     ) -> Result<Self> {
         use llama_cpp_2::model::params::kv_overrides::ParamOverrideValue;
         use std::ffi::CString;
@@ -108,7 +206,7 @@ impl LlamaCppModel {
 
         let backend = get_backend()?;
 
-        let mut params = pin!(LlamaModelParams::default().with_n_gpu_layers(0));
+        let mut params = pin!(LlamaModelParams::default().with_n_gpu_layers(n_gpu_layers));
 
         let key =
             CString::new("tokenizer.ggml.pre").map_err(|e| KanjiError::ModelLoad(e.into()))?;
@@ -163,6 +261,11 @@ impl LlamaCppModel {
         self.n_threads = n;
     }
 
+    /// Context window size used for inference
+    pub fn n_ctx(&self) -> u32 {
+        self.n_ctx
+    }
+
     /// Build LlamaContextParams with configured n_threads
     fn context_params(&self) -> LlamaContextParams {
         let params = LlamaContextParams::default().with_n_ctx(Some(
@@ -177,6 +280,41 @@ impl LlamaCppModel {
         }
     }
 
+    /// Make a prompt fit the context window along with the tokens to generate.
+    ///
+    /// The oldest tokens of the left context (right after `CONTEXT_TOKEN`) are
+    /// dropped first; when the reading alone leaves less room than
+    /// `max_new_tokens`, fewer tokens are generated instead. Returns the prompt
+    /// to decode and the generation budget, or `ContextOverflow` when not even
+    /// one new token fits.
+    fn fit_prompt<'t>(
+        &self,
+        input_tokens: &'t [LlamaToken],
+        max_new_tokens: usize,
+    ) -> Result<(Cow<'t, [LlamaToken]>, usize)> {
+        use super::{CONTEXT_TOKEN, INPUT_START_TOKEN};
+
+        let n_ctx = self.n_ctx as usize;
+        if input_tokens.len() + max_new_tokens <= n_ctx {
+            return Ok((Cow::Borrowed(input_tokens), max_new_tokens));
+        }
+        let marker = |c: char| -> Result<Option<LlamaToken>> {
+            Ok(self.tokenize(&c.to_string())?.last().copied())
+        };
+        let context_region = match (marker(CONTEXT_TOKEN)?, marker(INPUT_START_TOKEN)?) {
+            (Some(context), Some(input_start)) => {
+                context_region(input_tokens, context, input_start)
+            }
+            _ => None,
+        };
+        fit_tokens(input_tokens, context_region, max_new_tokens, n_ctx)
+            .map(|(tokens, budget)| (Cow::Owned(tokens), budget))
+            .ok_or(KanjiError::ContextOverflow {
+                tokens: input_tokens.len(),
+                n_ctx,
+            })
+    }
+
     /// Tokenize a string using the external tokenizer
     pub fn tokenize(&self, text: &str) -> Result<Vec<LlamaToken>> {
         let encoding = self
@@ -234,12 +372,50 @@ impl LlamaCppModel {
         input_tokens: &[LlamaToken],
         max_new_tokens: usize,
         eos_token_id: Option<i32>,
+    ) -> Result<Vec<LlamaToken>> {
+        self.generate_streaming(input_tokens, max_new_tokens, eos_token_id, |_| {
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Generate tokens with greedy decoding, reporting each token as it is sampled
+    ///
+    /// `on_token` is called with every generated token (EOS excluded). Returning
+    /// `ControlFlow::Break` stops generation after that token. Returns the input
+    /// tokens followed by the generated ones, like [`Self::generate`].
+    pub fn generate_streaming(
+        &self,
+        input_tokens: &[LlamaToken],
+        max_new_tokens: usize,
+        eos_token_id: Option<i32>,
+        mut on_token: impl FnMut(LlamaToken) -> ControlFlow<()>,
     ) -> Result<Vec<LlamaToken>> {
         self.generate_with_sampler(
             input_tokens,
             max_new_tokens,
             eos_token_id,
             LlamaSampler::greedy(),
+            &mut on_token,
+        )
+    }
+
+    /// Generate tokens with the given sampling parameters
+    ///
+    /// Falls back to greedy decoding (same output as [`Self::generate`]) when
+    /// `params.temperature` is 0. Sampling is reproducible for a fixed seed.
+    pub fn generate_with_params(
+        &self,
+        input_tokens: &[LlamaToken],
+        max_new_tokens: usize,
+        eos_token_id: Option<i32>,
+        params: SamplingParams,
+    ) -> Result<Vec<LlamaToken>> {
+        self.generate_with_sampler(
+            input_tokens,
+            max_new_tokens,
+            eos_token_id,
+            params.sampler(&self.model),
+            &mut |_| ControlFlow::Continue(()),
         )
     }
 
@@ -262,7 +438,35 @@ impl LlamaCppModel {
         eos_token_id: Option<i32>,
         beam_size: usize,
     ) -> Result<Vec<(Vec<LlamaToken>, f32)>> {
-        self.generate_beam_search_impl(input_tokens, max_new_tokens, eos_token_id, beam_size)
+        self.generate_beam_search_with_deadline(
+            input_tokens,
+            max_new_tokens,
+            eos_token_id,
+            beam_size,
+            None,
+        )
+    }
+
+    /// Beam search that stops expanding once `deadline` passes
+    ///
+    /// Same as [`Self::generate_beam_search`], but when the deadline is reached
+    /// the best beams found so far (finished or not) are returned instead of
+    /// continuing to `max_new_tokens`. `None` means no time limit.
+    pub fn generate_beam_search_with_deadline(
+        &self,
+        input_tokens: &[LlamaToken],
+        max_new_tokens: usize,
+        eos_token_id: Option<i32>,
+        beam_size: usize,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<(Vec<LlamaToken>, f32)>> {
+        self.generate_beam_search_impl(
+            input_tokens,
+            max_new_tokens,
+            eos_token_id,
+            beam_size,
+            deadline,
+        )
     }
 
     /// Generate multiple candidates using depth-1 beam selection followed by greedy decoding
@@ -284,12 +488,34 @@ impl LlamaCppModel {
         max_new_tokens: usize,
         eos_token_id: Option<i32>,
         beam_size: usize,
+    ) -> Result<Vec<(Vec<LlamaToken>, f32)>> {
+        self.generate_beam_search_d1_greedy_with_deadline(
+            input_tokens,
+            max_new_tokens,
+            eos_token_id,
+            beam_size,
+            None,
+        )
+    }
+
+    /// Depth-1 beam + greedy decoding that stops once `deadline` passes
+    ///
+    /// Same as [`Self::generate_beam_search_d1_greedy`], but when the deadline is
+    /// reached each beam is returned as generated so far. `None` means no time limit.
+    pub fn generate_beam_search_d1_greedy_with_deadline(
+        &self,
+        input_tokens: &[LlamaToken],
+        max_new_tokens: usize,
+        eos_token_id: Option<i32>,
+        beam_size: usize,
+        deadline: Option<Instant>,
     ) -> Result<Vec<(Vec<LlamaToken>, f32)>> {
         self.generate_beam_search_d1_greedy_batch(
             input_tokens,
             max_new_tokens,
             eos_token_id,
             beam_size,
+            deadline,
         )
     }
 
@@ -303,8 +529,11 @@ impl LlamaCppModel {
         max_new_tokens: usize,
         eos_token_id: Option<i32>,
         beam_size: usize,
+        deadline: Option<Instant>,
     ) -> Result<Vec<(Vec<LlamaToken>, f32)>> {
         let backend = get_backend()?;
+        let (input_tokens, max_new_tokens) = self.fit_prompt(input_tokens, max_new_tokens)?;
+        let input_tokens = &input_tokens[..];
 
         // Set n_batch and n_ubatch large enough to avoid batch splitting
         // which causes "coupled sequences" error
@@ -377,13 +606,19 @@ impl LlamaCppModel {
         let mut samplers: Vec<LlamaSampler> =
             (0..beam_size).map(|_| LlamaSampler::greedy()).collect();
 
-        for _step in 0..(max_new_tokens - 1) {
+        for step in 0..(max_new_tokens - 1) {
             // Count active beams
             let active_count = beam_finished.iter().filter(|&&f| !f).count();
             if active_count == 0 {
                 break;
             }
 
+            // Out of time: keep the beams as generated so far
+            if deadline_passed(deadline) {
+                tracing::debug!("d1 greedy beam search: deadline reached at step {}", step);
+                break;
+            }
+
             // Sample next token for each active beam
             // Track which beams added tokens to know their logit positions
             let mut active_beams: Vec<usize> = Vec::new();
@@ -447,7 +682,7 @@ impl LlamaCppModel {
     ///    - For each active beam, get top-k candidate next tokens
     ///    - Score each candidate: beam_score + log_prob(new_token)
     ///    - Keep only the best beam_size candidates globally
-    /// 3. Repeat until all beams reach EOS or max_new_tokens
+    /// 3. Repeat until all beams reach EOS, max_new_tokens, or the deadline
     ///
     /// True beam search implementation without KV cache sharing.
     /// This implementation processes full sequences at each step to avoid
@@ -458,8 +693,11 @@ impl LlamaCppModel {
         max_new_tokens: usize,
         eos_token_id: Option<i32>,
         beam_size: usize,
+        deadline: Option<Instant>,
     ) -> Result<Vec<(Vec<LlamaToken>, f32)>> {
         let model_eos = self.model.token_eos();
+        let (input_tokens, max_new_tokens) = self.fit_prompt(input_tokens, max_new_tokens)?;
+        let input_tokens = &input_tokens[..];
 
         // Step 1: Get initial logits
         let initial_logits = self.eval_sequence(input_tokens)?;
@@ -486,11 +724,17 @@ impl LlamaCppModel {
         let expand_k = beam_size.max(4);
 
         // Step 2: Main beam search loop
-        for _step in 0..(max_new_tokens - 1) {
+        for step in 0..(max_new_tokens - 1) {
             if beams.is_empty() {
                 break;
             }
 
+            // Out of time: return the best beams found so far
+            if deadline_passed(deadline) {
+                tracing::debug!("beam search: deadline reached at step {}", step);
+                break;
+            }
+
             // Early termination check
             if finished_beams.len() >= beam_size {
                 let best_finished = finished_beams
@@ -632,13 +876,14 @@ impl LlamaCppModel {
             || self.model.is_eog_token(token)
     }
 
-    /// Generate tokens with a custom sampler
+    /// Generate tokens with a custom sampler, calling `on_token` after each token
     fn generate_with_sampler(
         &self,
         input_tokens: &[LlamaToken],
         max_new_tokens: usize,
         eos_token_id: Option<i32>,
-        mut sampler: LlamaSampler,
+        sampler: LlamaSampler,
+        on_token: &mut dyn FnMut(LlamaToken) -> ControlFlow<()>,
     ) -> Result<Vec<LlamaToken>> {
         let backend = get_backend()?;
         let ctx_params = self.context_params();
@@ -648,12 +893,72 @@ impl LlamaCppModel {
             .new_context(backend, ctx_params)
             .map_err(|e| KanjiError::Inference(e.into()))?;
 
+        self.generate_in_context(
+            &mut ctx,
+            input_tokens,
+            max_new_tokens,
+            eos_token_id,
+            sampler,
+            on_token,
+        )
+    }
+
+    /// Generate tokens for several inputs, reusing one context
+    ///
+    /// Each input is decoded independently (the KV cache is cleared between
+    /// inputs) with a fresh sampler built from `params` and its own entry of
+    /// `max_new_tokens`, so every result is identical to calling
+    /// [`Self::generate_with_params`] on that input alone.
+    pub fn generate_batch(
+        &self,
+        inputs: &[Vec<LlamaToken>],
+        max_new_tokens: &[usize],
+        eos_token_id: Option<i32>,
+        params: SamplingParams,
+    ) -> Result<Vec<Vec<LlamaToken>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let backend = get_backend()?;
+        let mut ctx = self
+            .model
+            .new_context(backend, self.context_params())
+            .map_err(|e| KanjiError::Inference(e.into()))?;
+
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for (input_tokens, &max_new_tokens) in inputs.iter().zip(max_new_tokens) {
+            ctx.clear_kv_cache();
+            outputs.push(self.generate_in_context(
+                &mut ctx,
+                input_tokens,
+                max_new_tokens,
+                eos_token_id,
+                params.sampler(&self.model),
+                &mut |_| ControlFlow::Continue(()),
+            )?);
+        }
+        Ok(outputs)
+    }
+
+    /// Generate tokens in an existing context (with an empty KV cache),
+    /// calling `on_token` after each generated token
+    fn generate_in_context(
+        &self,
+        ctx: &mut llama_cpp_2::context::LlamaContext<'_>,
+        input_tokens: &[LlamaToken],
+        max_new_tokens: usize,
+        eos_token_id: Option<i32>,
+        mut sampler: LlamaSampler,
+        on_token: &mut dyn FnMut(LlamaToken) -> ControlFlow<()>,
+    ) -> Result<Vec<LlamaToken>> {
         let mut batch = LlamaBatch::new(512, 1);
+        // The result starts with the caller's input even if the prompt is truncated
         let mut generated = input_tokens.to_vec();
+        let (prompt, max_new_tokens) = self.fit_prompt(input_tokens, max_new_tokens)?;
 
         // Process input tokens
-        for (i, token) in input_tokens.iter().enumerate() {
-            let is_last = i == input_tokens.len() - 1;
+        for (i, token) in prompt.iter().enumerate() {
+            let is_last = i == prompt.len() - 1;
             batch
                 .add(*token, i as i32, &[0], is_last)
                 .map_err(|e| KanjiError::Inference(e.into()))?;
@@ -662,14 +967,14 @@ impl LlamaCppModel {
         ctx.decode(&mut batch)
             .map_err(|e| KanjiError::Inference(e.into()))?;
 
-        let mut n_cur = input_tokens.len();
+        let mut n_cur = prompt.len();
 
         // Get model's EOS token for comparison
         let model_eos = self.model.token_eos();
 
         // Generate new tokens
         for _ in 0..max_new_tokens {
-            let new_token = sampler.sample(&ctx, -1);
+            let new_token = sampler.sample(ctx, -1);
 
             // Check for EOS using the provided token ID
             if let Some(eos) = eos_token_id
@@ -690,6 +995,11 @@ impl LlamaCppModel {
 
             generated.push(new_token);
 
+            // Let the caller observe the token and stop early
+            if on_token(new_token).is_break() {
+                break;
+            }
+
             // Prepare next batch with just the new token
             batch.clear();
             batch
@@ -710,11 +1020,40 @@ impl LlamaCppModel {
     }
 }
 
+/// Range of the left context in a jinen prompt: the tokens between the first
+/// `context` marker and the following `input_start` marker
+fn context_region(
+    tokens: &[LlamaToken],
+    context: LlamaToken,
+    input_start: LlamaToken,
+) -> Option<std::ops::Range<usize>> {
+    let start = tokens.iter().position(|&t| t == context)? + 1;
+    let end = start + tokens[start..].iter().position(|&t| t == input_start)?;
+    Some(start..end)
+}
+
+/// Drop tokens from the front of `context` (a range of `tokens`) until the
+/// prompt plus `max_new_tokens` fits in `n_ctx`, then shrink the generation
+/// budget if it still does not. None when not even one new token fits.
+fn fit_tokens(
+    tokens: &[LlamaToken],
+    context: Option<std::ops::Range<usize>>,
+    max_new_tokens: usize,
+    n_ctx: usize,
+) -> Option<(Vec<LlamaToken>, usize)> {
+    let overflow = (tokens.len() + max_new_tokens).saturating_sub(n_ctx);
+    let (start, dropped) = context.map_or((0, 0), |c| (c.start, overflow.min(c.len())));
+    let mut fitted = tokens[..start].to_vec();
+    fitted.extend_from_slice(&tokens[start + dropped..]);
+    let budget = max_new_tokens.min(n_ctx.checked_sub(fitted.len())?);
+    (budget > 0).then_some((fitted, budget))
+}
+
 /// Reusable NLL scorer that keeps a single llama.cpp context alive.
 ///
 /// Creating a `LlamaContext` is expensive. This struct amortizes the cost by
 /// creating one context and clearing the KV cache between calls.
-/// Use one `NllScorer` per thread for parallel scoring.
+/// Use one `NllScorer` per thread for parallel scoring (see [`NllScorerPool`]).
 pub struct NllScorer<'a> {
     model: &'a LlamaCppModel,
     ctx: llama_cpp_2::context::LlamaContext<'a>,
@@ -744,19 +1083,20 @@ impl<'a> NllScorer<'a> {
         })
     }
 
-    /// Compute per-character NLL for a single (reading, surface) pair.
+    /// Compute per-character NLL for a single (reading, surface) pair, in the
+    /// jinen prompt format without context.
     ///
     /// Reuses the internal context by clearing the KV cache between calls.
     pub fn compute_nll(&mut self, reading_katakana: &str, surface: &str) -> Result<f32> {
-        use super::{CONTEXT_TOKEN, INPUT_START_TOKEN, OUTPUT_START_TOKEN};
+        self.compute_nll_after(&super::build_jinen_prompt(reading_katakana, ""), surface)
+    }
 
-        let prompt = format!(
-            "{}{}{}{}{}",
-            CONTEXT_TOKEN, "", INPUT_START_TOKEN, reading_katakana, OUTPUT_START_TOKEN
-        );
+    /// Compute per-character NLL of `surface` following an already built
+    /// conversion `prompt` (see [`PromptFormat::build`](super::PromptFormat::build)).
+    pub fn compute_nll_after(&mut self, prompt: &str, surface: &str) -> Result<f32> {
         let full_text = format!("{}{}", prompt, surface);
 
-        let prompt_tokens = self.model.tokenize(&prompt)?;
+        let prompt_tokens = self.model.tokenize(prompt)?;
         let full_tokens = self.model.tokenize(&full_text)?;
 
         if full_tokens.len() <= prompt_tokens.len() {
@@ -764,6 +1104,13 @@ impl<'a> NllScorer<'a> {
         }
 
         let n_tokens = full_tokens.len();
+        // The prompt's left context is not trimmed, so an over-long pair cannot be scored
+        if n_tokens > self.ctx.n_ctx() as usize {
+            return Err(KanjiError::ContextOverflow {
+                tokens: n_tokens,
+                n_ctx: self.ctx.n_ctx() as usize,
+            });
+        }
 
         self.ctx.clear_kv_cache();
 
@@ -808,3 +1155,198 @@ impl<'a> NllScorer<'a> {
         Ok(total_nll / n_chars as f32)
     }
 }
+
+/// A scoring request for a [`NllScorerPool`] worker
+struct ScoreJob {
+    /// Position of the result in the caller's output
+    index: usize,
+    prompt: String,
+    surface: String,
+    reply: mpsc::Sender<(usize, Result<f32>)>,
+}
+
+/// A fixed set of worker threads, each keeping one [`NllScorer`] for the
+/// pool's lifetime, for scoring many (reading, surface) pairs in parallel.
+///
+/// llama.cpp contexts cannot move between threads, so each worker creates its
+/// scorer on the first job it takes and reuses it for every later call; only
+/// the first scoring pays for context creation. The workers share the model
+/// through the `Arc`, so it stays loaded until the pool is dropped. Dropping
+/// the pool stops the workers and frees their contexts.
+pub struct NllScorerPool {
+    /// Job queue shared by the workers (`None` once dropping)
+    jobs: Option<mpsc::Sender<ScoreJob>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl NllScorerPool {
+    /// Start a pool of `size` workers (0 = one per available CPU), each
+    /// scoring with a context of `n_ctx` tokens.
+    pub fn new(model: Arc<LlamaCppModel>, n_ctx: u32, size: usize) -> Self {
+        let size = if size == 0 {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        } else {
+            size
+        };
+        let (jobs, queue) = mpsc::channel();
+        let queue = Arc::new(Mutex::new(queue));
+        let workers = (0..size)
+            .map(|_| {
+                let model = Arc::clone(&model);
+                let queue = Arc::clone(&queue);
+                std::thread::spawn(move || score_worker(&model, n_ctx, &queue))
+            })
+            .collect();
+        Self {
+            jobs: Some(jobs),
+            workers,
+        }
+    }
+
+    /// Number of worker threads (and scorers)
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Compute [`NllScorer::compute_nll`] for every `(reading_katakana, surface)`
+    /// pair, spread across the pool. Results are in the same order as `pairs`.
+    pub fn score_all(&self, pairs: &[(String, String)]) -> Result<Vec<f32>> {
+        self.score(
+            pairs.iter().map(|(reading, surface)| {
+                (super::build_jinen_prompt(reading, ""), surface.clone())
+            }),
+        )
+    }
+
+    /// Compute [`NllScorer::compute_nll_after`] of every surface after the
+    /// same `prompt`, spread across the pool. Results are in input order.
+    pub fn score_after(&self, prompt: &str, surfaces: &[&str]) -> Result<Vec<f32>> {
+        self.score(
+            surfaces
+                .iter()
+                .map(|surface| (prompt.to_string(), surface.to_string())),
+        )
+    }
+
+    /// Queue one job per `(prompt, surface)` and collect the scores in order
+    fn score(&self, requests: impl ExactSizeIterator<Item = (String, String)>) -> Result<Vec<f32>> {
+        let Some(jobs) = &self.jobs else {
+            return Err(workers_gone());
+        };
+        let mut scores = vec![0.0; requests.len()];
+        let (reply, results) = mpsc::channel();
+        for (index, (prompt, surface)) in requests.enumerate() {
+            let job = ScoreJob {
+                index,
+                prompt,
+                surface,
+                reply: reply.clone(),
+            };
+            jobs.send(job).map_err(|_| workers_gone())?;
+        }
+        // Only the queued jobs hold senders now, so a job lost with its worker
+        // ends the wait below instead of blocking forever
+        drop(reply);
+        for _ in 0..scores.len() {
+            let (index, score) = results.recv().map_err(|_| workers_gone())?;
+            scores[index] = score?;
+        }
+        Ok(scores)
+    }
+}
+
+impl Drop for NllScorerPool {
+    fn drop(&mut self) {
+        // Closing the queue ends each worker's loop
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Error for scoring jobs whose worker is gone
+fn workers_gone() -> KanjiError {
+    KanjiError::Inference("NLL scoring worker stopped".into())
+}
+
+/// Worker loop of [`NllScorerPool`]: take jobs until the queue closes.
+///
+/// A panic while scoring is reported as an error for that job, and the
+/// scorer is rebuilt for the next one.
+fn score_worker(model: &LlamaCppModel, n_ctx: u32, queue: &Mutex<mpsc::Receiver<ScoreJob>>) {
+    let mut scorer: Option<NllScorer<'_>> = None;
+    loop {
+        // Hold the lock only while waiting, so other workers can score
+        let job = match queue.lock() {
+            Ok(queue) => queue.recv(),
+            Err(_) => return,
+        };
+        let Ok(job) = job else {
+            return;
+        };
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut current = match scorer.take() {
+                Some(current) => current,
+                None => NllScorer::new(model, n_ctx)?,
+            };
+            let nll = current.compute_nll_after(&job.prompt, &job.surface);
+            scorer = Some(current);
+            nll
+        }))
+        .unwrap_or_else(|_| Err(KanjiError::Inference("NLL scoring panicked".into())));
+        // The caller may have stopped waiting after an earlier error
+        let _ = job.reply.send((job.index, result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(ids: &[i32]) -> Vec<LlamaToken> {
+        ids.iter().map(|&id| LlamaToken(id)).collect()
+    }
+
+    // Prompt layout: [CONTEXT=1] context... [INPUT_START=2] reading... [OUTPUT_START=3]
+    const CONTEXT: LlamaToken = LlamaToken(1);
+    const INPUT_START: LlamaToken = LlamaToken(2);
+
+    #[test]
+    fn test_context_region() {
+        let prompt = tokens(&[1, 10, 11, 12, 2, 20, 3]);
+        assert_eq!(context_region(&prompt, CONTEXT, INPUT_START), Some(1..4));
+        let prompt = tokens(&[1, 2, 20, 3]);
+        assert_eq!(context_region(&prompt, CONTEXT, INPUT_START), Some(1..1));
+        let prompt = tokens(&[20, 21]);
+        assert_eq!(context_region(&prompt, CONTEXT, INPUT_START), None);
+    }
+
+    #[test]
+    fn test_fit_tokens_keeps_fitting_prompt() {
+        let prompt = tokens(&[1, 10, 11, 2, 20, 3]);
+        let fitted = fit_tokens(&prompt, Some(1..3), 4, 10);
+        assert_eq!(fitted, Some((prompt, 4)));
+    }
+
+    #[test]
+    fn test_fit_tokens_drops_oldest_context() {
+        let prompt = tokens(&[1, 10, 11, 12, 13, 2, 20, 3]);
+        let fitted = fit_tokens(&prompt, Some(1..5), 4, 10);
+        assert_eq!(fitted, Some((tokens(&[1, 12, 13, 2, 20, 3]), 4)));
+    }
+
+    #[test]
+    fn test_fit_tokens_shrinks_budget_after_context() {
+        let prompt = tokens(&[1, 10, 2, 20, 21, 22, 23, 3]);
+        let fitted = fit_tokens(&prompt, Some(1..2), 4, 10);
+        assert_eq!(fitted, Some((tokens(&[1, 2, 20, 21, 22, 23, 3]), 3)));
+    }
+
+    #[test]
+    fn test_fit_tokens_overflowing_reading() {
+        let prompt = tokens(&[1, 2, 20, 21, 22, 23, 24, 25, 26, 27, 3]);
+        assert_eq!(fit_tokens(&prompt, Some(1..1), 4, 10), None);
+        assert_eq!(fit_tokens(&prompt, None, 4, 10), None);
+    }
+}
@@ -7,15 +7,20 @@ pub mod llamacpp;
 pub mod model_config;
 
 pub use backend::{
-    Backend, ConversionConfig, KanaKanjiConverter, build_jinen_prompt, clean_model_output,
+    Backend, CleanOptions, ConversionBackend, ConversionConfig, KanaKanjiConverter,
+    LlamaCppBackend, build_jinen_prompt, build_prompt, clean_model_output, clean_model_output_with,
+    new_token_budget,
 };
 pub use error::KanjiError;
 pub use hf_download::{
-    download_gguf, get_path_by_id, get_tokenizer_path, get_tokenizer_path_by_id, get_variant_path,
+    OFFLINE_ENV, cached_variant_path, download_gguf, get_path_by_id, get_tokenizer_path,
+    get_tokenizer_path_by_id, get_variant_path, is_offline, set_offline,
 };
 pub use llama_cpp_2::token::LlamaToken;
-pub use llamacpp::{LlamaCppModel, NllScorer};
-pub use model_config::{ModelFamily, ModelRegistry, VariantConfig, registry};
+pub use llamacpp::{LlamaCppModel, NllScorer, NllScorerPool, SamplingParams};
+pub use model_config::{
+    ModelFamily, ModelRegistry, ModelSummary, PromptFormat, VariantConfig, registry,
+};
 
 /// Special tokens for jinen format
 pub const CONTEXT_TOKEN: char = '\u{ee02}';
@@ -3,10 +3,12 @@
 //! All supported GGUF models are defined in `models.toml` at the crate root.
 //! This module deserializes that file and provides a global registry for lookup.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
+use super::hf_download::cached_variant_path;
+
 /// Top-level config parsed from `models.toml`
 #[derive(Debug, Deserialize)]
 pub struct ModelRegistry {
@@ -23,10 +25,45 @@ pub struct ModelFamily {
     pub display_name: String,
     #[serde(default)]
     pub pre_tokenizer_override: Option<String>,
+    /// Layers to offload to the GPU for every variant (unset = CPU only)
+    #[serde(default)]
+    pub gpu_layers: Option<u32>,
+    /// Prompt layout the family was trained on (default: jinen special tokens)
+    #[serde(default)]
+    pub prompt_format: PromptFormat,
     /// Quantisation variants keyed by short name (e.g. "q5", "f16")
     pub variants: HashMap<String, VariantConfig>,
 }
 
+/// Prompt layout of a model family
+///
+/// In `models.toml`: `prompt_format = "jinen"` or
+/// `prompt_format = { template = "{context}<sep>{input}<out>" }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptFormat {
+    /// jinen special tokens: context, input start, output start
+    #[default]
+    Jinen,
+    /// Free-form template with `{context}` and `{input}` placeholders
+    Template(String),
+}
+
+impl PromptFormat {
+    /// Build the prompt for a katakana reading and left context
+    pub fn build(&self, katakana: &str, context: &str) -> String {
+        match self {
+            PromptFormat::Jinen => super::build_jinen_prompt(katakana, context),
+            // Split on `{input}` first so a context containing "{input}" is kept verbatim
+            PromptFormat::Template(template) => template
+                .split("{input}")
+                .map(|part| part.replace("{context}", context))
+                .collect::<Vec<_>>()
+                .join(katakana),
+        }
+    }
+}
+
 /// A single downloadable GGUF variant
 #[derive(Debug, Deserialize)]
 pub struct VariantConfig {
@@ -36,6 +73,35 @@ pub struct VariantConfig {
     pub filename: String,
     /// Human-readable name shown in UI
     pub display_name: String,
+    /// Layers to offload to the GPU, overriding the family's `gpu_layers`
+    #[serde(default)]
+    pub gpu_layers: Option<u32>,
+    /// Expected SHA-256 of the GGUF file (hex), checked after download when set
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+/// A registered variant as listed for model pickers (e.g. the server's `/api/models`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModelSummary {
+    /// Unique variant id (e.g. "jinen-v1-xsmall-q5")
+    pub id: String,
+    /// Human-readable name shown in UI
+    pub display_name: String,
+    /// Family key in `models.toml` (e.g. "jinen-v1-xsmall")
+    pub family: String,
+    /// Whether this is the registry's `default_model`
+    pub default: bool,
+    /// Whether the GGUF file is already in the local HuggingFace cache
+    pub downloaded: bool,
+}
+
+impl ModelFamily {
+    /// GPU layers to load `variant` with: the variant's hint, else the
+    /// family's, else 0 (CPU only; GPT-2 models have Metal issues).
+    pub fn gpu_layers_for(&self, variant: &VariantConfig) -> u32 {
+        variant.gpu_layers.or(self.gpu_layers).unwrap_or(0)
+    }
 }
 
 static REGISTRY: OnceLock<ModelRegistry> = OnceLock::new();
@@ -79,6 +145,27 @@ impl ModelRegistry {
         ids
     }
 
+    /// Summaries of all variants, sorted by id.
+    ///
+    /// `downloaded` only checks the local cache; nothing is downloaded.
+    pub fn list(&self) -> Vec<ModelSummary> {
+        let mut summaries: Vec<ModelSummary> = self
+            .models
+            .iter()
+            .flat_map(|(family_name, family)| {
+                family.variants.values().map(move |variant| ModelSummary {
+                    id: variant.id.clone(),
+                    display_name: variant.display_name.clone(),
+                    family: family_name.clone(),
+                    default: variant.id == self.default_model,
+                    downloaded: cached_variant_path(family, variant).is_some(),
+                })
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.id.cmp(&b.id));
+        summaries
+    }
+
     /// Iterate over all `(family, variant)` pairs.
     pub fn iter_variants(&self) -> impl Iterator<Item = (&ModelFamily, &VariantConfig)> {
         self.models
@@ -153,6 +240,190 @@ mod tests {
         assert!(reg.find_variant("nonexistent-model").is_none());
     }
 
+    #[test]
+    fn test_gpu_layers_default_to_cpu() {
+        let reg = registry();
+        for (family, variant) in reg.iter_variants() {
+            assert_eq!(family.gpu_layers_for(variant), 0, "{}", variant.id);
+        }
+    }
+
+    #[test]
+    fn test_gpu_layers_round_trip() {
+        let reg: ModelRegistry = toml::from_str(
+            r#"
+default_model = "a-q5"
+
+[models.a]
+repo_id = "example/a.gguf"
+display_name = "a"
+gpu_layers = 12
+
+[models.a.variants.q5]
+id = "a-q5"
+filename = "a-Q5_K_M.gguf"
+display_name = "a (Q5_K_M)"
+
+[models.a.variants.f16]
+id = "a-f16"
+filename = "a-F16.gguf"
+display_name = "a (F16)"
+gpu_layers = 99
+
+[models.b]
+repo_id = "example/b.gguf"
+display_name = "b"
+
+[models.b.variants.q5]
+id = "b-q5"
+filename = "b-Q5_K_M.gguf"
+display_name = "b (Q5_K_M)"
+"#,
+        )
+        .expect("parse");
+
+        let (family, variant) = reg.find_variant("a-q5").unwrap();
+        assert_eq!(family.gpu_layers, Some(12));
+        assert_eq!(variant.gpu_layers, None);
+        assert_eq!(family.gpu_layers_for(variant), 12);
+
+        let (family, variant) = reg.find_variant("a-f16").unwrap();
+        assert_eq!(variant.gpu_layers, Some(99));
+        assert_eq!(family.gpu_layers_for(variant), 99);
+
+        let (family, variant) = reg.find_variant("b-q5").unwrap();
+        assert_eq!(family.gpu_layers, None);
+        assert_eq!(family.gpu_layers_for(variant), 0);
+    }
+
+    #[test]
+    fn test_prompt_format_parse() {
+        let reg: ModelRegistry = toml::from_str(
+            r#"
+default_model = "a-q5"
+
+[models.a]
+repo_id = "example/a.gguf"
+display_name = "a"
+
+[models.a.variants.q5]
+id = "a-q5"
+filename = "a-Q5_K_M.gguf"
+display_name = "a (Q5_K_M)"
+
+[models.b]
+repo_id = "example/b.gguf"
+display_name = "b"
+prompt_format = "jinen"
+
+[models.b.variants.q5]
+id = "b-q5"
+filename = "b-Q5_K_M.gguf"
+display_name = "b (Q5_K_M)"
+
+[models.c]
+repo_id = "example/c.gguf"
+display_name = "c"
+prompt_format = { template = "{context}<in>{input}<out>" }
+
+[models.c.variants.q5]
+id = "c-q5"
+filename = "c-Q5_K_M.gguf"
+display_name = "c (Q5_K_M)"
+"#,
+        )
+        .expect("parse");
+
+        assert_eq!(reg.models["a"].prompt_format, PromptFormat::Jinen);
+        assert_eq!(reg.models["b"].prompt_format, PromptFormat::Jinen);
+        assert_eq!(
+            reg.models["c"].prompt_format,
+            PromptFormat::Template("{context}<in>{input}<out>".to_string())
+        );
+        for (family, _) in registry().iter_variants() {
+            assert_eq!(family.prompt_format, PromptFormat::Jinen);
+        }
+    }
+
+    #[test]
+    fn test_build_prompt_template() {
+        let format = PromptFormat::Template("<ctx>{context}<in>{input}<out>".to_string());
+        assert_eq!(
+            format.build("カンジ", "今日は"),
+            "<ctx>今日は<in>カンジ<out>"
+        );
+        // Placeholders in the substituted text are left alone
+        assert_eq!(
+            format.build("カンジ", "{input}"),
+            "<ctx>{input}<in>カンジ<out>"
+        );
+        let format = PromptFormat::Template("{input}={input}".to_string());
+        assert_eq!(format.build("ア", ""), "ア=ア");
+    }
+
+    #[test]
+    fn test_list_covers_all_variants() {
+        let reg = registry();
+        let summaries = reg.list();
+        let mut ids: Vec<&str> = summaries.iter().map(|s| s.id.as_str()).collect();
+        let mut expected = reg.all_variant_ids();
+        expected.sort();
+        assert_eq!(ids, expected, "list() should be sorted by id");
+        ids.dedup();
+        assert_eq!(ids.len(), summaries.len());
+
+        let (family, variant) = reg.find_variant("jinen-v1-xsmall-q5").unwrap();
+        let summary = summaries.iter().find(|s| s.id == variant.id).unwrap();
+        assert_eq!(summary.family, "jinen-v1-xsmall");
+        assert_eq!(summary.display_name, variant.display_name);
+        assert_eq!(
+            summary.downloaded,
+            cached_variant_path(family, variant).is_some()
+        );
+    }
+
+    #[test]
+    fn test_list_marks_default() {
+        let defaults: Vec<String> = registry()
+            .list()
+            .into_iter()
+            .filter(|s| s.default)
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(defaults, ["jinen-v1-small-q5"]);
+    }
+
+    #[test]
+    fn test_list_not_downloaded() {
+        let reg: ModelRegistry = toml::from_str(
+            r#"
+default_model = "missing-q5"
+
+[models.missing]
+repo_id = "example/not-a-real-repo.gguf"
+display_name = "missing"
+
+[models.missing.variants.q5]
+id = "missing-q5"
+filename = "missing-Q5_K_M.gguf"
+display_name = "missing (Q5_K_M)"
+"#,
+        )
+        .expect("parse");
+
+        let summaries = reg.list();
+        assert_eq!(
+            summaries,
+            [ModelSummary {
+                id: "missing-q5".to_string(),
+                display_name: "missing (Q5_K_M)".to_string(),
+                family: "missing".to_string(),
+                default: true,
+                downloaded: false,
+            }]
+        );
+    }
+
     #[test]
     fn test_variant_ids_unique() {
         let reg = registry();
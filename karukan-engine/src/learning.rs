@@ -3,12 +3,31 @@
 //! Records which surface forms the user chose for each reading, and
 //! boosts those candidates on subsequent conversions. Persisted as a
 //! simple TSV file (`reading\tsurface\tfrequency\tlast_access`).
+//!
+//! Selections made right after another committed surface are also kept as
+//! bigrams, written as `@bigram\tprev_surface\treading\tsurface\tfrequency\tlast_access`.
+//! Older parsers, which read columns three and four as the frequency and
+//! timestamp, skip those rows unless the bigram's reading and surface are
+//! both all digits. Such a row then loads as a stray entry for the reading
+//! `@bigram`, which no typed reading can match.
+//!
+//! For backup and syncing, the cache can also be exported to and imported
+//! from a JSON array of `{reading, surface, frequency, last_access}` objects;
+//! bigram rows carry an extra `prev_surface` field.
+//!
+//! [`SharedLearningCache`] wraps the cache for use from several threads.
 
 use std::collections::HashMap;
+use std::hash::Hash;
 use std::io::{BufRead, Write};
 use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_file::write_atomically;
+
 /// A single learned conversion entry.
 #[derive(Debug, Clone)]
 pub struct LearningEntry {
@@ -20,14 +39,60 @@ pub struct LearningEntry {
     pub last_access: u64,
 }
 
+/// One learned entry in the portable JSON format.
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonLearningEntry {
+    reading: String,
+    surface: String,
+    frequency: u32,
+    last_access: u64,
+    /// Previously committed surface, for bigram entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prev_surface: Option<String>,
+}
+
+/// Marker in the first column of a bigram row in the TSV file.
+const BIGRAM_MARKER: &str = "@bigram";
+
+/// Weight of a bigram score relative to the unigram score of the same surface.
+const BIGRAM_WEIGHT: f64 = 2.0;
+
+/// Weights used to score learned entries.
+///
+/// The score is `recency_weight * 0.5^(age / half_life_days)
+/// + frequency_weight * ln(1 + frequency)`, so a larger `frequency_weight`
+/// favours often-used surfaces and a larger `recency_weight` favours recent ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LearningScoreParams {
+    /// Weight of the recency term (1.0 for an entry selected just now)
+    pub recency_weight: f64,
+    /// Weight of the logarithmic frequency term
+    pub frequency_weight: f64,
+    /// Age in days after which the recency term halves (must be positive)
+    pub half_life_days: f64,
+}
+
+impl Default for LearningScoreParams {
+    fn default() -> Self {
+        Self {
+            recency_weight: 10.0,
+            frequency_weight: 1.0,
+            half_life_days: 7.0,
+        }
+    }
+}
+
 /// In-memory cache of user learning data.
 ///
 /// Keyed by reading (hiragana). Each reading maps to a list of surface
-/// entries with frequency and recency metadata.
-#[derive(Debug)]
+/// entries with frequency and recency metadata. Bigram entries are keyed
+/// by the previously committed surface and the reading.
+#[derive(Debug, Clone)]
 pub struct LearningCache {
     entries: HashMap<String, Vec<LearningEntry>>,
+    bigrams: HashMap<(String, String), Vec<LearningEntry>>,
     max_entries: usize,
+    params: LearningScoreParams,
     dirty: bool,
 }
 
@@ -37,31 +102,97 @@ impl LearningCache {
 
     /// Create an empty cache with the given entry limit.
     pub fn new(max_entries: usize) -> Self {
+        Self::with_params(max_entries, LearningScoreParams::default())
+    }
+
+    /// Create an empty cache with the given entry limit and scoring weights.
+    pub fn with_params(max_entries: usize, params: LearningScoreParams) -> Self {
         Self {
             entries: HashMap::new(),
+            bigrams: HashMap::new(),
             max_entries,
+            params,
             dirty: false,
         }
     }
 
+    /// Scoring weights used for lookups and eviction.
+    pub fn params(&self) -> &LearningScoreParams {
+        &self.params
+    }
+
     /// Record a user selection. Increments frequency and updates last_access.
     pub fn record(&mut self, reading: &str, surface: &str) {
         let now = now_unix();
-        let entries = self.entries.entry(reading.to_string()).or_default();
-
-        if let Some(entry) = entries.iter_mut().find(|e| e.surface == surface) {
-            entry.frequency += 1;
-            entry.last_access = now;
-        } else {
-            entries.push(LearningEntry {
-                surface: surface.to_string(),
-                frequency: 1,
-                last_access: now,
-            });
-        }
+        bump(
+            self.entries.entry(reading.to_string()).or_default(),
+            surface,
+            now,
+        );
         self.dirty = true;
     }
 
+    /// Record a user selection made right after `prev_surface` was committed.
+    ///
+    /// Always records the unigram; with a previous surface, the bigram
+    /// `(prev_surface, reading) → surface` is recorded as well.
+    pub fn record_with_context(
+        &mut self,
+        prev_surface: Option<&str>,
+        reading: &str,
+        surface: &str,
+    ) {
+        self.record(reading, surface);
+        if let Some(prev) = prev_surface {
+            let key = (prev.to_string(), reading.to_string());
+            bump(self.bigrams.entry(key).or_default(), surface, now_unix());
+        }
+    }
+
+    /// Forget a learned `surface` for `reading`, including its bigrams.
+    ///
+    /// Returns true if anything was removed.
+    pub fn forget(&mut self, reading: &str, surface: &str) -> bool {
+        let mut removed = false;
+        if let Some(entries) = self.entries.get_mut(reading) {
+            let before = entries.len();
+            entries.retain(|e| e.surface != surface);
+            removed |= entries.len() != before;
+            if entries.is_empty() {
+                self.entries.remove(reading);
+            }
+        }
+        self.bigrams.retain(|(_, r), entries| {
+            if r == reading {
+                let before = entries.len();
+                entries.retain(|e| e.surface != surface);
+                removed |= entries.len() != before;
+            }
+            !entries.is_empty()
+        });
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Drop entries (unigrams and bigrams) last selected more than `days`
+    /// days ago, pruning readings left without surfaces.
+    ///
+    /// Returns the number of entries removed.
+    pub fn evict_older_than(&mut self, days: u64) -> usize {
+        let cutoff = now_unix().saturating_sub(days.saturating_mul(86400));
+        let removed =
+            retain_since(&mut self.entries, cutoff) + retain_since(&mut self.bigrams, cutoff);
+        self.dirty |= removed > 0;
+        removed
+    }
+
+    /// Forget everything learned, unigrams and bigrams alike.
+    pub fn clear(&mut self) {
+        self.dirty |= !self.entries.is_empty() || !self.bigrams.is_empty();
+        self.entries.clear();
+        self.bigrams.clear();
+    }
+
     /// Exact-match lookup: returns `(surface, score)` pairs sorted by score descending.
     pub fn lookup(&self, reading: &str) -> Vec<(String, f64)> {
         let now = now_unix();
@@ -70,12 +201,40 @@ impl LearningCache {
         };
         let mut scored: Vec<(String, f64)> = entries
             .iter()
-            .map(|e| (e.surface.clone(), score(e, now)))
+            .map(|e| (e.surface.clone(), score(e, now, &self.params)))
             .collect();
         scored.sort_by(|a, b| b.1.total_cmp(&a.1));
         scored
     }
 
+    /// Exact-match lookup in the context of the previously committed surface.
+    ///
+    /// Surfaces learned after `prev_surface` get their bigram score (weighted)
+    /// added to the unigram score. Falls back to [`lookup`](Self::lookup) when
+    /// there is no context or no bigram for it.
+    pub fn lookup_with_context(
+        &self,
+        prev_surface: Option<&str>,
+        reading: &str,
+    ) -> Vec<(String, f64)> {
+        let mut scored = self.lookup(reading);
+        let Some(bigrams) = prev_surface
+            .and_then(|prev| self.bigrams.get(&(prev.to_string(), reading.to_string())))
+        else {
+            return scored;
+        };
+        let now = now_unix();
+        for entry in bigrams {
+            let boost = score(entry, now, &self.params) * BIGRAM_WEIGHT;
+            match scored.iter_mut().find(|(s, _)| *s == entry.surface) {
+                Some((_, total)) => *total += boost,
+                None => scored.push((entry.surface.clone(), boost)),
+            }
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored
+    }
+
     /// Prefix-match lookup: returns `(reading, surface, score)` triples
     /// for all readings that start with `prefix`, sorted by score descending.
     pub fn prefix_lookup(&self, prefix: &str) -> Vec<(String, String, f64)> {
@@ -84,7 +243,11 @@ impl LearningCache {
         for (reading, entries) in &self.entries {
             if reading.starts_with(prefix) {
                 for entry in entries {
-                    results.push((reading.clone(), entry.surface.clone(), score(entry, now)));
+                    results.push((
+                        reading.clone(),
+                        entry.surface.clone(),
+                        score(entry, now, &self.params),
+                    ));
                 }
             }
         }
@@ -97,9 +260,18 @@ impl LearningCache {
     /// Format: `reading\tsurface\tfrequency\tlast_access`
     /// Lines starting with `#` are comments.
     pub fn load(path: &Path, max_entries: usize) -> anyhow::Result<Self> {
+        Self::load_with_params(path, max_entries, LearningScoreParams::default())
+    }
+
+    /// Load a learning cache from a TSV file, scoring entries with `params`.
+    pub fn load_with_params(
+        path: &Path,
+        max_entries: usize,
+        params: LearningScoreParams,
+    ) -> anyhow::Result<Self> {
         let file = std::fs::File::open(path)?;
         let reader = std::io::BufReader::new(file);
-        let mut cache = Self::new(max_entries);
+        let mut cache = Self::with_params(max_entries, params);
 
         for line in reader.lines() {
             let line = line?;
@@ -108,29 +280,30 @@ impl LearningCache {
                 continue;
             }
             let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() < 4 {
+            if parts.first() == Some(&BIGRAM_MARKER) {
+                if let [_, prev, reading, rest @ ..] = parts.as_slice()
+                    && let Some(entry) = parse_entry(rest)
+                {
+                    cache
+                        .bigrams
+                        .entry((prev.to_string(), reading.to_string()))
+                        .or_default()
+                        .push(entry);
+                }
                 continue;
             }
-            let reading = parts[0];
-            let surface = parts[1];
-            let frequency: u32 = match parts[2].parse() {
-                Ok(v) => v,
-                Err(_) => continue,
+            let [reading, rest @ ..] = parts.as_slice() else {
+                continue;
             };
-            let last_access: u64 = match parts[3].parse() {
-                Ok(v) => v,
-                Err(_) => continue,
+            let Some(entry) = parse_entry(rest) else {
+                continue;
             };
 
             cache
                 .entries
                 .entry(reading.to_string())
                 .or_default()
-                .push(LearningEntry {
-                    surface: surface.to_string(),
-                    frequency,
-                    last_access,
-                });
+                .push(entry);
         }
 
         // Not dirty — just loaded from disk
@@ -141,13 +314,18 @@ impl LearningCache {
     /// Save the cache to a TSV file, evicting low-score entries if over capacity.
     pub fn save(&mut self, path: &Path) -> anyhow::Result<()> {
         self.evict();
+        self.write_tsv(path)?;
+        self.dirty = false;
+        Ok(())
+    }
 
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
+    /// Write the cache to a TSV file as-is, atomically replacing it.
+    fn write_tsv(&self, path: &Path) -> anyhow::Result<()> {
+        write_atomically(path, |writer| self.write_tsv_rows(writer))
+    }
 
-        let file = std::fs::File::create(path)?;
-        let mut writer = std::io::BufWriter::new(file);
+    /// Write the TSV header and rows.
+    fn write_tsv_rows(&self, writer: &mut impl Write) -> anyhow::Result<()> {
         writeln!(writer, "# karukan learning cache v1")?;
 
         // Sort readings for deterministic output
@@ -166,11 +344,100 @@ impl LearningCache {
             }
         }
 
-        writer.flush()?;
-        self.dirty = false;
+        let mut contexts: Vec<&(String, String)> = self.bigrams.keys().collect();
+        contexts.sort();
+
+        for key in contexts {
+            if let Some(entries) = self.bigrams.get(key) {
+                for entry in entries {
+                    writeln!(
+                        writer,
+                        "{}\t{}\t{}\t{}\t{}\t{}",
+                        BIGRAM_MARKER,
+                        key.0,
+                        key.1,
+                        entry.surface,
+                        entry.frequency,
+                        entry.last_access
+                    )?;
+                }
+            }
+        }
+
         Ok(())
     }
 
+    /// Export the cache as a JSON array, sorted like the TSV file.
+    ///
+    /// Timestamps are written as Unix seconds. Unlike [`save`](Self::save),
+    /// nothing is evicted.
+    pub fn export_json(&self, path: &Path) -> anyhow::Result<()> {
+        let mut rows = Vec::with_capacity(self.entry_count() + self.bigram_count());
+        let mut readings: Vec<&String> = self.entries.keys().collect();
+        readings.sort();
+        for reading in readings {
+            for entry in &self.entries[reading] {
+                rows.push(JsonLearningEntry {
+                    reading: reading.clone(),
+                    surface: entry.surface.clone(),
+                    frequency: entry.frequency,
+                    last_access: entry.last_access,
+                    prev_surface: None,
+                });
+            }
+        }
+        let mut contexts: Vec<&(String, String)> = self.bigrams.keys().collect();
+        contexts.sort();
+        for key in contexts {
+            for entry in &self.bigrams[key] {
+                rows.push(JsonLearningEntry {
+                    reading: key.1.clone(),
+                    surface: entry.surface.clone(),
+                    frequency: entry.frequency,
+                    last_access: entry.last_access,
+                    prev_surface: Some(key.0.clone()),
+                });
+            }
+        }
+
+        write_atomically(path, |writer| {
+            Ok(serde_json::to_writer_pretty(writer, &rows)?)
+        })
+    }
+
+    /// Import a JSON export and merge it into this cache.
+    ///
+    /// Entries already present have their frequencies summed and keep the
+    /// later `last_access`; new entries are added as-is. Returns the number
+    /// of imported rows.
+    pub fn import_json(&mut self, path: &Path) -> anyhow::Result<usize> {
+        let file = std::fs::File::open(path)?;
+        let rows: Vec<JsonLearningEntry> = serde_json::from_reader(std::io::BufReader::new(file))?;
+        let count = rows.len();
+        for row in rows {
+            let entry = LearningEntry {
+                surface: row.surface,
+                frequency: row.frequency,
+                last_access: row.last_access,
+            };
+            let entries = match row.prev_surface {
+                Some(prev) => self.bigrams.entry((prev, row.reading)).or_default(),
+                None => self.entries.entry(row.reading).or_default(),
+            };
+            merge_entry(entries, entry);
+        }
+        self.dirty |= count > 0;
+        Ok(count)
+    }
+
+    /// Load a cache from a JSON export.
+    pub fn load_json(path: &Path, max_entries: usize) -> anyhow::Result<Self> {
+        let mut cache = Self::new(max_entries);
+        cache.import_json(path)?;
+        cache.dirty = false;
+        Ok(cache)
+    }
+
     /// Whether there are unsaved changes.
     pub fn is_dirty(&self) -> bool {
         self.dirty
@@ -181,45 +448,205 @@ impl LearningCache {
         self.entries.values().map(|v| v.len()).sum()
     }
 
-    /// Evict lowest-score entries until total count is within `max_entries`.
+    /// Total number of (previous surface, reading, surface) bigrams.
+    pub fn bigram_count(&self) -> usize {
+        self.bigrams.values().map(|v| v.len()).sum()
+    }
+
+    /// Evict lowest-score entries until the unigram and bigram counts are
+    /// each within `max_entries`.
     fn evict(&mut self) {
-        let total = self.entry_count();
-        if total <= self.max_entries {
-            return;
+        let now = now_unix();
+        evict_map(&mut self.entries, self.max_entries, now, &self.params);
+        evict_map(&mut self.bigrams, self.max_entries, now, &self.params);
+    }
+}
+
+/// A [`LearningCache`] shared between threads, e.g. by the conversion server.
+///
+/// Cloning the handle shares the same cache. Lookups hold the read lock only
+/// while scoring and return owned results; [`record`](Self::record) takes the
+/// write lock briefly to bump one entry, and [`save_if_dirty`](Self::save_if_dirty)
+/// writes a snapshot so the file I/O happens without holding the cache lock.
+#[derive(Debug, Clone)]
+pub struct SharedLearningCache {
+    inner: Arc<RwLock<LearningCache>>,
+    /// Serializes saves, so a newer snapshot is never overwritten by an older one
+    save_lock: Arc<Mutex<()>>,
+}
+
+impl SharedLearningCache {
+    /// Wrap a cache for shared use.
+    pub fn new(cache: LearningCache) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(cache)),
+            save_lock: Arc::new(Mutex::new(())),
         }
+    }
 
-        let now = now_unix();
-        // Collect all entries with their (reading, index, score)
-        let mut all: Vec<(String, usize, f64)> = Vec::with_capacity(total);
-        for (reading, entries) in &self.entries {
-            for (i, entry) in entries.iter().enumerate() {
-                all.push((reading.clone(), i, score(entry, now)));
+    /// Record a user selection (takes the write lock briefly).
+    pub fn record(&self, reading: &str, surface: &str) {
+        self.inner
+            .write()
+            .expect("lock poisoned")
+            .record(reading, surface);
+    }
+
+    /// Record a selection made after `prev_surface` (takes the write lock briefly).
+    pub fn record_with_context(&self, prev_surface: Option<&str>, reading: &str, surface: &str) {
+        self.inner
+            .write()
+            .expect("lock poisoned")
+            .record_with_context(prev_surface, reading, surface);
+    }
+
+    /// See [`LearningCache::lookup`].
+    pub fn lookup(&self, reading: &str) -> Vec<(String, f64)> {
+        self.inner.read().expect("lock poisoned").lookup(reading)
+    }
+
+    /// See [`LearningCache::lookup_with_context`].
+    pub fn lookup_with_context(
+        &self,
+        prev_surface: Option<&str>,
+        reading: &str,
+    ) -> Vec<(String, f64)> {
+        self.inner
+            .read()
+            .expect("lock poisoned")
+            .lookup_with_context(prev_surface, reading)
+    }
+
+    /// See [`LearningCache::prefix_lookup`].
+    pub fn prefix_lookup(&self, prefix: &str) -> Vec<(String, String, f64)> {
+        self.inner
+            .read()
+            .expect("lock poisoned")
+            .prefix_lookup(prefix)
+    }
+
+    /// Save to a TSV file if there are unsaved changes; meant to be called
+    /// periodically. Returns whether the file was written.
+    ///
+    /// Eviction and the snapshot happen under the write lock; the file is
+    /// written after it is released. Concurrent saves take turns, each writing
+    /// a snapshot taken in its turn. If writing fails the cache stays dirty.
+    pub fn save_if_dirty(&self, path: &Path) -> anyhow::Result<bool> {
+        let _turn = self.save_lock.lock().expect("lock poisoned");
+        let snapshot = {
+            let mut cache = self.inner.write().expect("lock poisoned");
+            if !cache.dirty {
+                return Ok(false);
             }
+            cache.evict();
+            cache.dirty = false;
+            cache.clone()
+        };
+        if let Err(e) = snapshot.write_tsv(path) {
+            self.inner.write().expect("lock poisoned").dirty = true;
+            return Err(e);
         }
-        // Sort by score ascending (lowest first = eviction candidates)
-        all.sort_by(|a, b| a.2.total_cmp(&b.2));
-
-        let to_remove = total - self.max_entries;
-        // Collect indices to remove, grouped by reading
-        let mut remove_set: HashMap<String, Vec<usize>> = HashMap::new();
-        for &(ref reading, idx, _) in all.iter().take(to_remove) {
-            remove_set.entry(reading.clone()).or_default().push(idx);
+        Ok(true)
+    }
+
+    /// Run `f` with exclusive access to the underlying cache.
+    pub fn with_cache<R>(&self, f: impl FnOnce(&mut LearningCache) -> R) -> R {
+        f(&mut self.inner.write().expect("lock poisoned"))
+    }
+}
+
+/// Bump `surface` in an entry list, adding it with frequency 1 if new.
+fn bump(entries: &mut Vec<LearningEntry>, surface: &str, now: u64) {
+    if let Some(entry) = entries.iter_mut().find(|e| e.surface == surface) {
+        entry.frequency += 1;
+        entry.last_access = now;
+    } else {
+        entries.push(LearningEntry {
+            surface: surface.to_string(),
+            frequency: 1,
+            last_access: now,
+        });
+    }
+}
+
+/// Merge `entry` into an entry list, summing frequencies and keeping the
+/// later access time when the surface is already present.
+fn merge_entry(entries: &mut Vec<LearningEntry>, entry: LearningEntry) {
+    if let Some(existing) = entries.iter_mut().find(|e| e.surface == entry.surface) {
+        existing.frequency = existing.frequency.saturating_add(entry.frequency);
+        existing.last_access = existing.last_access.max(entry.last_access);
+    } else {
+        entries.push(entry);
+    }
+}
+
+/// Parse the `surface\tfrequency\tlast_access` tail of a TSV row.
+fn parse_entry(parts: &[&str]) -> Option<LearningEntry> {
+    let [surface, frequency, last_access, ..] = parts else {
+        return None;
+    };
+    Some(LearningEntry {
+        surface: surface.to_string(),
+        frequency: frequency.parse().ok()?,
+        last_access: last_access.parse().ok()?,
+    })
+}
+
+/// Keep only entries of `map` accessed at or after `cutoff`, dropping empty
+/// keys. Returns the number of entries removed.
+fn retain_since<K: Eq + Hash>(map: &mut HashMap<K, Vec<LearningEntry>>, cutoff: u64) -> usize {
+    let mut removed = 0;
+    map.retain(|_, entries| {
+        let before = entries.len();
+        entries.retain(|e| e.last_access >= cutoff);
+        removed += before - entries.len();
+        !entries.is_empty()
+    });
+    removed
+}
+
+/// Evict lowest-score entries of `map` until its total count is within `max_entries`.
+fn evict_map<K: Clone + Eq + Hash>(
+    map: &mut HashMap<K, Vec<LearningEntry>>,
+    max_entries: usize,
+    now: u64,
+    params: &LearningScoreParams,
+) {
+    let total: usize = map.values().map(|v| v.len()).sum();
+    if total <= max_entries {
+        return;
+    }
+
+    // Collect all entries with their (key, index, score)
+    let mut all: Vec<(K, usize, f64)> = Vec::with_capacity(total);
+    for (key, entries) in map.iter() {
+        for (i, entry) in entries.iter().enumerate() {
+            all.push((key.clone(), i, score(entry, now, params)));
         }
+    }
+    // Sort by score ascending (lowest first = eviction candidates)
+    all.sort_by(|a, b| a.2.total_cmp(&b.2));
 
-        // Remove entries in reverse index order to preserve indices
-        for (reading, indices) in &mut remove_set {
-            indices.sort_unstable();
-            indices.reverse();
-            if let Some(entries) = self.entries.get_mut(reading) {
-                for &idx in indices.iter() {
-                    if idx < entries.len() {
-                        entries.remove(idx);
-                    }
-                }
-                if entries.is_empty() {
-                    self.entries.remove(reading);
+    let to_remove = total - max_entries;
+    // Collect indices to remove, grouped by key
+    let mut remove_set: HashMap<K, Vec<usize>> = HashMap::new();
+    for (key, idx, _) in all.into_iter().take(to_remove) {
+        remove_set.entry(key).or_default().push(idx);
+    }
+
+    // Remove entries in reverse index order to preserve indices
+    for (key, indices) in &mut remove_set {
+        indices.sort_unstable();
+        indices.reverse();
+        if let Some(entries) = map.get_mut(key) {
+            for &idx in indices.iter() {
+                if idx < entries.len() {
+                    entries.remove(idx);
                 }
             }
+            if entries.is_empty() {
+                map.remove(key);
+            }
         }
     }
 }
@@ -227,16 +654,13 @@ impl LearningCache {
 /// Compute a candidate score: recency-weighted with frequency bonus.
 ///
 /// Inspired by mozc's UserHistoryPredictor: recent selections rank higher,
-/// with a logarithmic frequency term to reward repeated use.
-fn score(entry: &LearningEntry, now: u64) -> f64 {
-    let age_days = if now > entry.last_access {
-        (now - entry.last_access) / 86400
-    } else {
-        0
-    };
-    let recency = 1.0 / (1.0 + age_days as f64);
+/// with a logarithmic frequency term to reward repeated use. Recency decays
+/// exponentially with the configured half-life.
+fn score(entry: &LearningEntry, now: u64, params: &LearningScoreParams) -> f64 {
+    let age_days = now.saturating_sub(entry.last_access) as f64 / 86400.0;
+    let recency = 0.5f64.powf(age_days / params.half_life_days);
     let freq = (entry.frequency as f64).ln_1p();
-    recency * 10.0 + freq
+    recency * params.recency_weight + freq * params.frequency_weight
 }
 
 /// Current time as Unix timestamp in seconds.
@@ -359,6 +783,7 @@ mod tests {
     #[test]
     fn test_score_recency() {
         let now = now_unix();
+        let params = LearningScoreParams::default();
         let recent = LearningEntry {
             surface: "A".to_string(),
             frequency: 1,
@@ -369,12 +794,13 @@ mod tests {
             frequency: 1,
             last_access: now.saturating_sub(30 * 86400), // 30 days ago
         };
-        assert!(score(&recent, now) > score(&old, now));
+        assert!(score(&recent, now, &params) > score(&old, now, &params));
     }
 
     #[test]
     fn test_score_frequency() {
         let now = now_unix();
+        let params = LearningScoreParams::default();
         let high_freq = LearningEntry {
             surface: "A".to_string(),
             frequency: 100,
@@ -385,7 +811,7 @@ mod tests {
             frequency: 1,
             last_access: now,
         };
-        assert!(score(&high_freq, now) > score(&low_freq, now));
+        assert!(score(&high_freq, now, &params) > score(&low_freq, now, &params));
     }
 
     #[test]
@@ -423,6 +849,123 @@ mod tests {
         assert_eq!(results[0].0, "今日");
     }
 
+    #[test]
+    fn test_context_changes_top_candidate() {
+        let mut cache = LearningCache::new(100);
+        // "公演" is the more frequent choice overall...
+        cache.record_with_context(Some("劇団の"), "こうえん", "公演");
+        cache.record_with_context(Some("劇団の"), "こうえん", "公演");
+        cache.record_with_context(Some("子どもと"), "こうえん", "公園");
+
+        assert_eq!(cache.lookup("こうえん")[0].0, "公演");
+        assert_eq!(
+            cache.lookup_with_context(Some("劇団の"), "こうえん")[0].0,
+            "公演"
+        );
+        // ...but "公園" wins right after "子どもと"
+        assert_eq!(
+            cache.lookup_with_context(Some("子どもと"), "こうえん")[0].0,
+            "公園"
+        );
+    }
+
+    #[test]
+    fn test_lookup_with_context_falls_back_to_unigram() {
+        let mut cache = LearningCache::new(100);
+        cache.record_with_context(Some("子どもと"), "こうえん", "公園");
+        cache.record("こうえん", "公演");
+        cache.record("こうえん", "公演");
+
+        assert_eq!(cache.bigram_count(), 1);
+        assert_eq!(
+            cache.lookup_with_context(None, "こうえん"),
+            cache.lookup("こうえん")
+        );
+        assert_eq!(
+            cache.lookup_with_context(Some("劇団の"), "こうえん"),
+            cache.lookup("こうえん")
+        );
+    }
+
+    #[test]
+    fn test_bigrams_save_and_load() {
+        let mut cache = LearningCache::new(100);
+        cache.record("こうえん", "公演");
+        cache.record("こうえん", "公演");
+        cache.record_with_context(Some("子どもと"), "こうえん", "公園");
+
+        let file = NamedTempFile::new().unwrap();
+        cache.save(file.path()).unwrap();
+        let content = std::fs::read_to_string(file.path()).unwrap();
+        assert!(content.contains("@bigram\t子どもと\tこうえん\t公園\t1\t"));
+
+        let loaded = LearningCache::load(file.path(), 100).unwrap();
+        assert_eq!(loaded.entry_count(), 2);
+        assert_eq!(loaded.bigram_count(), 1);
+        assert_eq!(
+            loaded.lookup_with_context(Some("子どもと"), "こうえん")[0].0,
+            "公園"
+        );
+    }
+
+    #[test]
+    fn test_bigram_rows_skipped_by_unigram_format() {
+        // A bigram row read with the v1 column layout has a non-numeric frequency
+        let row = "@bigram\t子どもと\tこうえん\t公園\t1\t1700000000";
+        let parts: Vec<&str> = row.split('\t').collect();
+        assert!(parse_entry(&parts[1..]).is_none());
+    }
+
+    #[test]
+    fn test_forget() {
+        let mut cache = LearningCache::new(100);
+        cache.record_with_context(Some("子どもと"), "こうえん", "公園");
+        cache.record("こうえん", "公演");
+        let file = NamedTempFile::new().unwrap();
+        cache.save(file.path()).unwrap();
+
+        assert!(cache.forget("こうえん", "公園"));
+        assert!(cache.is_dirty());
+        assert!(!cache.forget("こうえん", "公園"));
+        assert_eq!(cache.lookup("こうえん").len(), 1);
+        assert_eq!(cache.bigram_count(), 0);
+
+        assert!(cache.forget("こうえん", "公演"));
+        assert_eq!(cache.entry_count(), 0);
+    }
+
+    #[test]
+    fn test_forget_nonexistent() {
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        let file = NamedTempFile::new().unwrap();
+        cache.save(file.path()).unwrap();
+
+        assert!(!cache.forget("きょう", "京"));
+        assert!(!cache.forget("あした", "明日"));
+        assert!(!cache.is_dirty());
+        assert_eq!(cache.entry_count(), 1);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        cache.record_with_context(Some("子どもと"), "こうえん", "公園");
+        let file = NamedTempFile::new().unwrap();
+        cache.save(file.path()).unwrap();
+
+        cache.clear();
+        assert!(cache.is_dirty());
+        assert_eq!(cache.entry_count(), 0);
+        assert_eq!(cache.bigram_count(), 0);
+        assert!(cache.lookup("きょう").is_empty());
+
+        cache.save(file.path()).unwrap();
+        cache.clear();
+        assert!(!cache.is_dirty());
+    }
+
     #[test]
     fn test_tsv_malformed_lines_skipped() {
         let file = NamedTempFile::new().unwrap();
@@ -436,4 +979,362 @@ mod tests {
         // Only the first valid line should be loaded
         assert_eq!(cache.entry_count(), 1);
     }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        cache.record("きょう", "今日");
+        cache.record("きょう", "京");
+        cache.record_with_context(Some("子どもと"), "こうえん", "公園");
+
+        let file = NamedTempFile::new().unwrap();
+        cache.export_json(file.path()).unwrap();
+        let rows: Vec<serde_json::Value> =
+            serde_json::from_str(&std::fs::read_to_string(file.path()).unwrap()).unwrap();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0]["last_access"].is_u64());
+
+        let loaded = LearningCache::load_json(file.path(), 100).unwrap();
+        assert!(!loaded.is_dirty());
+        assert_eq!(loaded.entry_count(), 3);
+        assert_eq!(loaded.bigram_count(), 1);
+        assert_eq!(loaded.lookup("きょう"), cache.lookup("きょう"));
+        assert_eq!(
+            loaded.lookup_with_context(Some("子どもと"), "こうえん"),
+            cache.lookup_with_context(Some("子どもと"), "こうえん")
+        );
+    }
+
+    #[test]
+    fn test_json_import_merges_overlapping_entries() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            r#"[
+                {"reading": "きょう", "surface": "今日", "frequency": 3, "last_access": 1700000000},
+                {"reading": "きょう", "surface": "京", "frequency": 2, "last_access": 1600000000},
+                {"reading": "あした", "surface": "明日", "frequency": 1, "last_access": 1500000000}
+            ]"#,
+        )
+        .unwrap();
+
+        let mut cache = LearningCache::new(100);
+        cache.entries.insert(
+            "きょう".to_string(),
+            vec![
+                LearningEntry {
+                    surface: "今日".to_string(),
+                    frequency: 2,
+                    last_access: 1650000000,
+                },
+                LearningEntry {
+                    surface: "京".to_string(),
+                    frequency: 4,
+                    last_access: 1750000000,
+                },
+            ],
+        );
+
+        assert_eq!(cache.import_json(file.path()).unwrap(), 3);
+        assert!(cache.is_dirty());
+        assert_eq!(cache.entry_count(), 3);
+
+        let find = |surface: &str| {
+            cache.entries["きょう"]
+                .iter()
+                .find(|e| e.surface == surface)
+                .unwrap()
+                .clone()
+        };
+        let today = find("今日");
+        assert_eq!((today.frequency, today.last_access), (5, 1700000000));
+        let kyo = find("京");
+        assert_eq!((kyo.frequency, kyo.last_access), (6, 1750000000));
+        assert_eq!(cache.entries["あした"][0].frequency, 1);
+    }
+
+    #[test]
+    fn test_json_import_invalid_file() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "きょう\t今日\t1\t1700000000\n").unwrap();
+        let mut cache = LearningCache::new(100);
+        assert!(cache.import_json(file.path()).is_err());
+        assert!(!cache.is_dirty());
+    }
+
+    #[test]
+    fn test_score_half_life() {
+        let now = now_unix();
+        let params = LearningScoreParams {
+            recency_weight: 1.0,
+            frequency_weight: 0.0,
+            half_life_days: 10.0,
+        };
+        let entry = |days: u64| LearningEntry {
+            surface: "A".to_string(),
+            frequency: 1,
+            last_access: now - days * 86400,
+        };
+        assert!((score(&entry(0), now, &params) - 1.0).abs() < 1e-9);
+        assert!((score(&entry(10), now, &params) - 0.5).abs() < 1e-9);
+        assert!((score(&entry(20), now, &params) - 0.25).abs() < 1e-9);
+    }
+
+    /// "京" was chosen often long ago, "今日" once just now.
+    fn cache_with_stale_frequent_entry(params: LearningScoreParams) -> LearningCache {
+        let now = now_unix();
+        let mut cache = LearningCache::with_params(1, params);
+        cache.entries.insert(
+            "きょう".to_string(),
+            vec![
+                LearningEntry {
+                    surface: "京".to_string(),
+                    frequency: 50,
+                    last_access: now - 60 * 86400,
+                },
+                LearningEntry {
+                    surface: "今日".to_string(),
+                    frequency: 1,
+                    last_access: now,
+                },
+            ],
+        );
+        cache
+    }
+
+    #[test]
+    fn test_params_reorder_lookup() {
+        let recency_first = LearningScoreParams {
+            recency_weight: 10.0,
+            frequency_weight: 1.0,
+            half_life_days: 7.0,
+        };
+        let frequency_first = LearningScoreParams {
+            recency_weight: 1.0,
+            frequency_weight: 5.0,
+            half_life_days: 365.0,
+        };
+
+        let cache = cache_with_stale_frequent_entry(recency_first);
+        assert_eq!(cache.params(), &recency_first);
+        assert_eq!(cache.lookup("きょう")[0].0, "今日");
+        assert_eq!(cache.prefix_lookup("きょ")[0].1, "今日");
+
+        let cache = cache_with_stale_frequent_entry(frequency_first);
+        assert_eq!(cache.lookup("きょう")[0].0, "京");
+        assert_eq!(cache.prefix_lookup("きょ")[0].1, "京");
+    }
+
+    #[test]
+    fn test_params_decide_eviction() {
+        let file = NamedTempFile::new().unwrap();
+
+        let mut cache = cache_with_stale_frequent_entry(LearningScoreParams::default());
+        cache.save(file.path()).unwrap();
+        assert_eq!(cache.lookup("きょう")[0].0, "今日");
+        assert_eq!(cache.entry_count(), 1);
+
+        let mut cache = cache_with_stale_frequent_entry(LearningScoreParams {
+            recency_weight: 0.0,
+            frequency_weight: 1.0,
+            half_life_days: 7.0,
+        });
+        cache.save(file.path()).unwrap();
+        assert_eq!(cache.lookup("きょう")[0].0, "京");
+        assert_eq!(cache.entry_count(), 1);
+    }
+
+    #[test]
+    fn test_shared_cache_concurrent_records() {
+        let shared = SharedLearningCache::new(LearningCache::new(1000));
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let shared = shared.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        shared.record("きょう", if i % 2 == 0 { "今日" } else { "京" });
+                        shared.record(&format!("よみ{t}"), "読み");
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        shared.with_cache(|cache| {
+            let total: u32 = cache.entries["きょう"].iter().map(|e| e.frequency).sum();
+            assert_eq!(total, 800);
+            assert!(cache.entries["きょう"].iter().all(|e| e.frequency == 400));
+            assert_eq!(cache.entry_count(), 10);
+            for t in 0..8 {
+                assert_eq!(cache.entries[&format!("よみ{t}")][0].frequency, 100);
+            }
+        });
+        assert_eq!(shared.lookup("きょう").len(), 2);
+    }
+
+    #[test]
+    fn test_shared_cache_save_if_dirty() {
+        let file = NamedTempFile::new().unwrap();
+        let shared = SharedLearningCache::new(LearningCache::new(100));
+        assert!(!shared.save_if_dirty(file.path()).unwrap());
+
+        shared.record_with_context(Some("子どもと"), "こうえん", "公園");
+        assert!(shared.save_if_dirty(file.path()).unwrap());
+        assert!(!shared.save_if_dirty(file.path()).unwrap());
+
+        let loaded = LearningCache::load(file.path(), 100).unwrap();
+        assert_eq!(loaded.entry_count(), 1);
+        assert_eq!(loaded.bigram_count(), 1);
+    }
+
+    #[test]
+    fn test_shared_cache_stays_dirty_when_save_fails() {
+        let shared = SharedLearningCache::new(LearningCache::new(100));
+        shared.record("きょう", "今日");
+        let dir = tempfile::tempdir().unwrap();
+        // A directory cannot be opened as a file
+        assert!(shared.save_if_dirty(dir.path()).is_err());
+        assert!(shared.with_cache(|cache| cache.is_dirty()));
+    }
+
+    #[test]
+    fn test_evict_older_than() {
+        let now = now_unix();
+        let entry = |surface: &str, days: u64| LearningEntry {
+            surface: surface.to_string(),
+            frequency: 1,
+            last_access: now - days * 86400,
+        };
+        let mut cache = LearningCache::new(100);
+        cache.entries.insert(
+            "きょう".to_string(),
+            vec![entry("今日", 1), entry("京", 45)],
+        );
+        cache
+            .entries
+            .insert("ごしょく".to_string(), vec![entry("誤植", 100)]);
+        cache
+            .entries
+            .insert("あした".to_string(), vec![entry("明日", 29)]);
+        cache.bigrams.insert(
+            ("子どもと".to_string(), "こうえん".to_string()),
+            vec![entry("公園", 31)],
+        );
+
+        assert_eq!(cache.evict_older_than(30), 3);
+        assert!(cache.is_dirty());
+        assert_eq!(cache.entry_count(), 2);
+        assert_eq!(cache.bigram_count(), 0);
+        assert_eq!(cache.lookup("きょう").len(), 1);
+        assert_eq!(cache.lookup("きょう")[0].0, "今日");
+        assert_eq!(cache.lookup("あした").len(), 1);
+        // Readings with no surfaces left are pruned
+        assert!(!cache.entries.contains_key("ごしょく"));
+        assert!(cache.bigrams.is_empty());
+    }
+
+    #[test]
+    fn test_evict_older_than_keeps_recent() {
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        let file = NamedTempFile::new().unwrap();
+        cache.save(file.path()).unwrap();
+
+        assert_eq!(cache.evict_older_than(1), 0);
+        assert!(!cache.is_dirty());
+        assert_eq!(cache.entry_count(), 1);
+    }
+
+    #[test]
+    fn test_save_replaces_file_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("learning.tsv");
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        cache.save(&path).unwrap();
+        cache.record("あした", "明日");
+        cache.save(&path).unwrap();
+
+        assert_eq!(LearningCache::load(&path, 100).unwrap().entry_count(), 2);
+        // Only the cache file is left; the temporary file was renamed over it
+        assert_eq!(dir_entries(dir.path()), ["learning.tsv"]);
+    }
+
+    /// Names of the files in `dir`, sorted
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_failed_write_leaves_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("learning.tsv");
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        cache.save(&path).unwrap();
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        // Interrupted halfway through writing the new contents
+        let result = write_atomically(&path, |writer| {
+            writeln!(writer, "# karukan learning cache v1\nあした\t明日")?;
+            anyhow::bail!("interrupted")
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(dir_entries(dir.path()), ["learning.tsv"]);
+    }
+
+    #[test]
+    fn test_failed_rename_leaves_destination_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        // A non-empty directory cannot be replaced by a file
+        let path = dir.path().join("learning.tsv");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("keep"), "original").unwrap();
+
+        let mut cache = LearningCache::new(100);
+        cache.record("きょう", "今日");
+        assert!(cache.save(&path).is_err());
+        assert!(cache.is_dirty());
+        assert_eq!(
+            std::fs::read_to_string(path.join("keep")).unwrap(),
+            "original"
+        );
+        assert_eq!(dir_entries(dir.path()), ["learning.tsv"]);
+    }
+
+    #[test]
+    fn test_concurrent_saves_do_not_share_a_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("learning.tsv");
+        let shared = SharedLearningCache::new(LearningCache::new(1000));
+
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let (shared, path) = (&shared, &path);
+                s.spawn(move || {
+                    for i in 0..20 {
+                        shared.record(&format!("よみ{t}"), &format!("表記{i}"));
+                        shared.save_if_dirty(path).unwrap();
+                    }
+                });
+            }
+        });
+
+        // The last save wrote a snapshot holding every record
+        let loaded = LearningCache::load(&path, 1000).unwrap();
+        assert_eq!(loaded.entry_count(), 8 * 20);
+        for t in 0..8 {
+            assert_eq!(loaded.lookup(&format!("よみ{t}")).len(), 20);
+        }
+        assert_eq!(dir_entries(dir.path()), ["learning.tsv"]);
+    }
 }
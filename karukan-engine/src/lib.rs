@@ -1,11 +1,21 @@
+mod atomic_file;
 pub mod dict;
 pub mod kana;
 pub mod kanji;
 pub mod learning;
 pub mod romaji;
+pub mod user_dict;
 
 pub use dict::{Candidate as DictCandidate, DictEntry, Dictionary, LookupResult};
-pub use kana::{hiragana_to_katakana, katakana_to_hiragana, normalize_nfkc};
-pub use kanji::{Backend, KanaKanjiConverter};
-pub use learning::LearningCache;
-pub use romaji::{BackspaceResult, ConversionEvent, RomajiConverter};
+pub use kana::{
+    ascii_to_fullwidth, halfwidth_to_katakana, hiragana_to_katakana, hiragana_to_romaji,
+    katakana_to_halfwidth, katakana_to_hiragana, looks_like_loanword, normalize_nfkc,
+    vowel_repeat_to_long_vowel,
+};
+pub use kanji::{Backend, ConversionBackend, KanaKanjiConverter};
+pub use learning::{LearningCache, LearningScoreParams, SharedLearningCache};
+pub use romaji::{
+    BackspaceResult, ConversionEvent, PunctuationWidth, RomajiConfig, RomajiConverter,
+    RomajiRulesError, RomajiTable,
+};
+pub use user_dict::UserDictionary;
@@ -0,0 +1,58 @@
+//! Romaji conversion options
+
+use serde::{Deserialize, Serialize};
+
+/// Output width for the `?` and `!` punctuation rules
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctuationWidth {
+    /// Full-width: `?` → ？, `!` → ！
+    #[default]
+    Full,
+    /// Half-width: `?` → ?, `!` → !
+    Half,
+}
+
+/// Romanization system used for the syllable rows of the conversion table
+///
+/// Punctuation, sokuon (っ) and ん handling are shared by all tables.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RomajiTable {
+    /// Hepburn (shi, chi, tsu, fu, ji) with the common IME extensions.
+    /// Kunrei spellings are accepted too.
+    #[default]
+    Hepburn,
+    /// Kunrei-shiki (si, ti, tu, hu, zi; di/du for ぢ/づ per its second table).
+    /// Hepburn spellings (shi, chi, tsu, fu, ji) are not accepted.
+    Kunrei,
+    /// Nihon-shiki: Kunrei plus the historical distinctions
+    /// (wi → ゐ, we → ゑ, kwa → くゎ, gwa → ぐゎ)
+    Nihon,
+    /// AZIK: Hepburn plus one-key extensions for frequent syllables
+    /// (kz → かん, kq → かい, kp → こう, ; → っ, q → ん, xa → しゃ).
+    /// Small kana use the l prefix.
+    Azik,
+}
+
+/// User-configurable options for [`RomajiConverter`](super::RomajiConverter)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomajiConfig {
+    /// Convert a lone trailing "n" to ん on flush instead of passing it through
+    /// as a literal "n" (e.g. "karukan" → "かるかん")
+    pub trailing_n_to_hiragana: bool,
+    /// Width of the `?` / `!` punctuation output
+    pub punctuation_width: PunctuationWidth,
+    /// Romanization system for the syllable rows
+    pub table: RomajiTable,
+}
+
+impl Default for RomajiConfig {
+    fn default() -> Self {
+        Self {
+            trailing_n_to_hiragana: true,
+            punctuation_width: PunctuationWidth::default(),
+            table: RomajiTable::default(),
+        }
+    }
+}
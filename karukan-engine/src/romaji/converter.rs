@@ -1,3 +1,9 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use super::config::{RomajiConfig, RomajiTable};
+use super::custom::{RomajiRulesError, parse_rules};
 use super::rules::build_rules;
 use super::trie::TrieNode;
 use crate::kana::hiragana_to_katakana;
@@ -30,16 +36,48 @@ pub struct RomajiConverter {
     trie: TrieNode,
     buffer: String,
     output: String,
+    config: RomajiConfig,
 }
 
 impl RomajiConverter {
     /// Create a new converter with default rules
     pub fn new() -> Self {
+        Self::with_config(RomajiConfig::default())
+    }
+
+    /// Create a new converter with the given options
+    pub fn with_config(config: RomajiConfig) -> Self {
         Self {
-            trie: build_rules(),
+            trie: build_rules(&config),
             buffer: String::new(),
             output: String::new(),
+            config,
+        }
+    }
+
+    /// Create a new converter using the given romanization table
+    pub fn with_table(table: RomajiTable) -> Self {
+        Self::with_config(RomajiConfig {
+            table,
+            ..RomajiConfig::default()
+        })
+    }
+
+    /// Layer user-defined rules from a `sequence\toutput` TSV file on top of
+    /// the current table. Custom rules override built-in ones on collision.
+    ///
+    /// The whole file is validated before any rule is applied.
+    pub fn load_custom_rules(&mut self, path: &Path) -> Result<(), RomajiRulesError> {
+        let rules = parse_rules(BufReader::new(File::open(path)?))?;
+        for (sequence, output) in &rules {
+            self.trie.insert(sequence, output);
         }
+        Ok(())
+    }
+
+    /// Get the conversion options
+    pub fn config(&self) -> &RomajiConfig {
+        &self.config
     }
 
     /// Push a character and attempt conversion
@@ -54,6 +92,11 @@ impl RomajiConverter {
         self.try_convert()
     }
 
+    /// Push each character of `s`, returning the event of every push in order
+    pub fn push_all(&mut self, s: &str) -> Vec<ConversionEvent> {
+        s.chars().map(|ch| self.push(ch)).collect()
+    }
+
     /// Convert with the given hiragana and recursively process any remaining buffer.
     /// Returns a Converted event combining the hiragana with any further conversions.
     fn convert_with_remainder(&mut self, hiragana: String) -> ConversionEvent {
@@ -84,7 +127,9 @@ impl RomajiConverter {
         }
 
         // Special case: 'n' before consonant -> ん
-        if char_count >= 2 {
+        // Both shortcuts below yield to the table when it has a rule for the
+        // buffer (AZIK: nk → にん, kk → きん, tt → たち)
+        if char_count >= 2 && !self.trie.contains_prefix(&self.buffer) {
             let last = chars[char_count - 1];
             let second_last = chars[char_count - 2];
 
@@ -104,7 +149,11 @@ impl RomajiConverter {
             }
 
             // Double consonant rule: same consonant twice (except 'n') -> っ + consonant
-            if last == second_last && !matches!(last, 'a' | 'i' | 'u' | 'e' | 'o' | 'n') {
+            // Symbols are left to the trie so custom rules like "@@" can match.
+            if last == second_last
+                && last.is_ascii_alphabetic()
+                && !matches!(last, 'a' | 'i' | 'u' | 'e' | 'o' | 'n')
+            {
                 // Convert to sokuon and keep the last consonant
                 self.buffer = last.to_string();
                 self.output.push('っ');
@@ -198,6 +247,15 @@ impl RomajiConverter {
         let mut result = String::new();
 
         while !self.buffer.is_empty() {
+            // A lone trailing "n" is ambiguous while typing, but at flush time
+            // nothing can follow it, so treat it as ん when configured.
+            if self.config.trailing_n_to_hiragana && self.buffer == "n" {
+                result.push('ん');
+                self.output.push('ん');
+                self.buffer.clear();
+                break;
+            }
+
             let search = self.trie.search_longest(&self.buffer);
 
             if let Some(h) = search.output {
@@ -269,6 +327,7 @@ impl Default for RomajiConverter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::romaji::PunctuationWidth;
 
     #[test]
     fn test_basic_conversion() {
@@ -366,6 +425,89 @@ mod tests {
         assert_eq!(conv.buffer(), "");
     }
 
+    #[test]
+    fn test_flush_trailing_n() {
+        let mut conv = RomajiConverter::new();
+        "karukan".chars().for_each(|c| {
+            conv.push(c);
+        });
+        assert_eq!(conv.buffer(), "n");
+        assert_eq!(conv.flush(), "ん");
+        assert_eq!(conv.output(), "かるかん");
+
+        // Only a lone "n" is affected
+        conv.reset();
+        "kany".chars().for_each(|c| {
+            conv.push(c);
+        });
+        conv.flush();
+        assert_eq!(conv.output(), "かny");
+    }
+
+    #[test]
+    fn test_flush_trailing_n_disabled() {
+        let mut conv = RomajiConverter::with_config(RomajiConfig {
+            trailing_n_to_hiragana: false,
+            ..RomajiConfig::default()
+        });
+        "karukan".chars().for_each(|c| {
+            conv.push(c);
+        });
+        assert_eq!(conv.flush(), "n");
+        assert_eq!(conv.output(), "かるかn");
+    }
+
+    #[test]
+    fn test_push_all_events() {
+        let mut conv = RomajiConverter::new();
+        assert_eq!(
+            conv.push_all("kya"),
+            vec![
+                ConversionEvent::Buffered,
+                ConversionEvent::Buffered,
+                ConversionEvent::Converted("きゃ".to_string()),
+            ]
+        );
+
+        // "." has a rule (。); "@" has none and passes through
+        conv.reset();
+        assert_eq!(
+            conv.push_all("a.b"),
+            vec![
+                ConversionEvent::Converted("あ".to_string()),
+                ConversionEvent::Converted("。".to_string()),
+                ConversionEvent::Buffered,
+            ]
+        );
+        conv.reset();
+        assert_eq!(
+            conv.push_all("a@b"),
+            vec![
+                ConversionEvent::Converted("あ".to_string()),
+                ConversionEvent::PassThrough('@'),
+                ConversionEvent::Buffered,
+            ]
+        );
+        assert_eq!(conv.output(), "あ@");
+        assert_eq!(conv.buffer(), "b");
+    }
+
+    #[test]
+    fn test_flush_trailing_n_words_disabled() {
+        for (input, expected) in [("pan", "ぱn"), ("n", "n")] {
+            let mut conv = RomajiConverter::with_config(RomajiConfig {
+                trailing_n_to_hiragana: false,
+                ..RomajiConfig::default()
+            });
+            input.chars().for_each(|c| {
+                conv.push(c);
+            });
+            conv.flush();
+            assert_eq!(conv.output(), expected, "{input}");
+            assert_eq!(conv.buffer(), "");
+        }
+    }
+
     #[test]
     fn test_backspace() {
         let mut conv = RomajiConverter::new();
@@ -425,6 +567,19 @@ mod tests {
         assert_eq!(conv.buffer(), "");
     }
 
+    #[test]
+    fn test_half_width_punctuation() {
+        let mut conv = RomajiConverter::with_config(RomajiConfig {
+            punctuation_width: PunctuationWidth::Half,
+            ..RomajiConfig::default()
+        });
+        for ch in "nani?sugoi!".chars() {
+            conv.push(ch);
+        }
+        assert_eq!(conv.output(), "なに?すごい!");
+        assert_eq!(conv.buffer(), "");
+    }
+
     #[test]
     fn test_watashiha() {
         let mut conv = RomajiConverter::new();
@@ -473,4 +628,50 @@ mod tests {
         assert_eq!(conv.buffer(), "k");
         assert_eq!(conv.full_text_katakana(), "カk");
     }
+
+    fn write_rules(tsv: &str) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(tsv.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_custom_rules_override_builtin() {
+        let file = write_rules("# custom\nka\tカ\n@@\t＠\nz;\t；\n");
+        let mut conv = RomajiConverter::new();
+        conv.load_custom_rules(file.path()).unwrap();
+
+        for ch in "kaki@@z;".chars() {
+            conv.push(ch);
+        }
+        assert_eq!(conv.output(), "カき＠；");
+    }
+
+    #[test]
+    fn test_sokuon_only_for_doubled_letters() {
+        // "@" is buffered as the start of "@a"; a doubled "@" is not a
+        // consonant, so it passes through instead of becoming っ
+        let file = write_rules("@a\tあっと\n");
+        let mut conv = RomajiConverter::new();
+        conv.load_custom_rules(file.path()).unwrap();
+
+        for ch in "@@a".chars() {
+            conv.push(ch);
+        }
+        assert_eq!(conv.output(), "@あっと");
+        assert_eq!(conv.buffer(), "");
+    }
+
+    #[test]
+    fn test_custom_rules_invalid_file_is_not_applied() {
+        let file = write_rules("ka\tカ\nかな\tkana\n");
+        let mut conv = RomajiConverter::new();
+        let err = conv.load_custom_rules(file.path()).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+
+        conv.push('k');
+        conv.push('a');
+        assert_eq!(conv.output(), "か");
+    }
 }
@@ -0,0 +1,84 @@
+//! User-defined romaji rules loaded from a TSV file
+
+use std::io::BufRead;
+
+/// Errors that can occur while loading custom romaji rules.
+#[derive(Debug, thiserror::Error)]
+pub enum RomajiRulesError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("line {line}: {message}")]
+    Invalid { line: usize, message: String },
+}
+
+/// Parse `sequence\toutput` rules.
+///
+/// Lines starting with `#` are comments, empty lines are skipped. Sequences
+/// must be non-empty ASCII and are lowercased, since the converter lowercases
+/// its input.
+pub(super) fn parse_rules(reader: impl BufRead) -> Result<Vec<(String, String)>, RomajiRulesError> {
+    let mut rules = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let invalid = |message: &str| RomajiRulesError::Invalid {
+            line: idx + 1,
+            message: message.to_string(),
+        };
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((sequence, output)) = line.split_once('\t') else {
+            return Err(invalid("expected `sequence<TAB>output`"));
+        };
+        // Ignore any extra columns
+        let output = output.split('\t').next().unwrap_or_default();
+        if sequence.is_empty() {
+            return Err(invalid("empty sequence"));
+        }
+        if !sequence.is_ascii() {
+            return Err(invalid(&format!("sequence {sequence:?} is not ASCII")));
+        }
+        if output.is_empty() {
+            return Err(invalid(&format!("empty output for {sequence:?}")));
+        }
+        rules.push((sequence.to_ascii_lowercase(), output.to_string()));
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let tsv = "# comment\n@@\t＠\n\nZ;\t；\textra\n";
+        let rules = parse_rules(tsv.as_bytes()).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                ("@@".to_string(), "＠".to_string()),
+                ("z;".to_string(), "；".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rules_errors() {
+        let err = parse_rules("ka\tか\nか\tka\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, RomajiRulesError::Invalid { line: 2, .. }));
+        assert!(err.to_string().contains("not ASCII"));
+
+        let err = parse_rules("\tか\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("empty sequence"));
+
+        let err = parse_rules("ka\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, RomajiRulesError::Invalid { line: 1, .. }));
+
+        let err = parse_rules("ka\t\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("empty output"));
+    }
+}
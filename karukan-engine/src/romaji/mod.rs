@@ -1,6 +1,12 @@
+mod config;
 mod converter;
+mod custom;
 mod rules;
 mod trie;
 
+pub use config::{PunctuationWidth, RomajiConfig, RomajiTable};
 pub use converter::{BackspaceResult, ConversionEvent, RomajiConverter};
+pub use custom::RomajiRulesError;
 pub use trie::SearchResult;
+
+pub(crate) use rules::build_rules;
@@ -1,7 +1,8 @@
+use super::config::{PunctuationWidth, RomajiConfig, RomajiTable};
 use super::trie::TrieNode;
 
 /// Build the conversion rules trie
-pub fn build_rules() -> TrieNode {
+pub fn build_rules(config: &RomajiConfig) -> TrieNode {
     let mut trie = TrieNode::new();
 
     // Vowels
@@ -63,20 +64,9 @@ pub fn build_rules() -> TrieNode {
 
     // S-row
     trie.insert("sa", "さ");
-    trie.insert("si", "し");
     trie.insert("su", "す");
     trie.insert("se", "せ");
     trie.insert("so", "そ");
-    trie.insert("shi", "し");
-    trie.insert("sha", "しゃ");
-    trie.insert("shu", "しゅ");
-    trie.insert("she", "しぇ");
-    trie.insert("sho", "しょ");
-    trie.insert("sya", "しゃ");
-    trie.insert("syi", "しぃ");
-    trie.insert("syu", "しゅ");
-    trie.insert("sye", "しぇ");
-    trie.insert("syo", "しょ");
     // sw series (すぁ行)
     trie.insert("swa", "すぁ");
     trie.insert("swi", "すぃ");
@@ -86,25 +76,9 @@ pub fn build_rules() -> TrieNode {
 
     // Z-row (dakuten)
     trie.insert("za", "ざ");
-    trie.insert("zi", "じ");
     trie.insert("zu", "ず");
     trie.insert("ze", "ぜ");
     trie.insert("zo", "ぞ");
-    trie.insert("ji", "じ");
-    trie.insert("ja", "じゃ");
-    trie.insert("ju", "じゅ");
-    trie.insert("je", "じぇ");
-    trie.insert("jo", "じょ");
-    trie.insert("zya", "じゃ");
-    trie.insert("zyi", "じぃ");
-    trie.insert("zyu", "じゅ");
-    trie.insert("zye", "じぇ");
-    trie.insert("zyo", "じょ");
-    trie.insert("jya", "じゃ");
-    trie.insert("jyi", "じぃ");
-    trie.insert("jyu", "じゅ");
-    trie.insert("jye", "じぇ");
-    trie.insert("jyo", "じょ");
     // zw series (ずぁ行)
     trie.insert("zwa", "ずぁ");
     trie.insert("zwi", "ずぃ");
@@ -114,30 +88,13 @@ pub fn build_rules() -> TrieNode {
 
     // T-row
     trie.insert("ta", "た");
-    trie.insert("ti", "ち");
-    trie.insert("tu", "つ");
     trie.insert("te", "て");
     trie.insert("to", "と");
-    trie.insert("chi", "ち");
-    trie.insert("tsu", "つ");
-    trie.insert("cha", "ちゃ");
-    trie.insert("chu", "ちゅ");
-    trie.insert("che", "ちぇ");
-    trie.insert("cho", "ちょ");
-    trie.insert("tya", "ちゃ");
-    trie.insert("tyi", "ちぃ");
-    trie.insert("tyu", "ちゅ");
-    trie.insert("tye", "ちぇ");
-    trie.insert("tyo", "ちょ");
     trie.insert("cya", "ちゃ");
     trie.insert("cyi", "ちぃ");
     trie.insert("cyu", "ちゅ");
     trie.insert("cye", "ちぇ");
     trie.insert("cyo", "ちょ");
-    trie.insert("tsa", "つぁ");
-    trie.insert("tsi", "つぃ");
-    trie.insert("tse", "つぇ");
-    trie.insert("tso", "つぉ");
     // th series (てゃ行)
     trie.insert("tha", "てゃ");
     trie.insert("thi", "てぃ");
@@ -199,22 +156,13 @@ pub fn build_rules() -> TrieNode {
     // H-row
     trie.insert("ha", "は");
     trie.insert("hi", "ひ");
-    trie.insert("hu", "ふ");
     trie.insert("he", "へ");
     trie.insert("ho", "ほ");
-    trie.insert("fu", "ふ");
     trie.insert("hya", "ひゃ");
     trie.insert("hyi", "ひぃ");
     trie.insert("hyu", "ひゅ");
     trie.insert("hye", "ひぇ");
     trie.insert("hyo", "ひょ");
-    trie.insert("fa", "ふぁ");
-    trie.insert("fi", "ふぃ");
-    trie.insert("fe", "ふぇ");
-    trie.insert("fo", "ふぉ");
-    trie.insert("fya", "ふゃ");
-    trie.insert("fyu", "ふゅ");
-    trie.insert("fyo", "ふょ");
     // hw series (ふぁ行 alternative)
     trie.insert("hwa", "ふぁ");
     trie.insert("hwi", "ふぃ");
@@ -338,6 +286,8 @@ pub fn build_rules() -> TrieNode {
     trie.insert("xka", "ヵ");
     trie.insert("xke", "ヶ");
 
+    insert_table_rows(&mut trie, config.table);
+
     // Long vowel mark
     trie.insert("-", "ー");
 
@@ -345,8 +295,16 @@ pub fn build_rules() -> TrieNode {
     trie.insert(",", "、");
     trie.insert(".", "。");
     trie.insert("/", "・");
-    trie.insert("?", "？");
-    trie.insert("!", "！");
+    match config.punctuation_width {
+        PunctuationWidth::Full => {
+            trie.insert("?", "？");
+            trie.insert("!", "！");
+        }
+        PunctuationWidth::Half => {
+            trie.insert("?", "?");
+            trie.insert("!", "!");
+        }
+    }
     trie.insert("~", "〜");
 
     // Brackets
@@ -365,16 +323,186 @@ pub fn build_rules() -> TrieNode {
     trie.insert("z[", "『");
     trie.insert("z]", "』");
 
+    // AZIK goes last: its ず/じ extensions (zh, zj, zk, zl) replace the arrows
+    if config.table == RomajiTable::Azik {
+        insert_azik_rules(&mut trie);
+    }
+
     trie
 }
 
+/// Syllable rows that differ between romanization tables.
+///
+/// The shared rows above hold the spellings common to all tables (di/du for
+/// ぢ/づ included). Hepburn and AZIK accept both Hepburn and Kunrei spellings,
+/// as IMEs usually do; Kunrei and Nihon-shiki only accept their own, and
+/// Nihon-shiki adds the historical distinctions.
+fn insert_table_rows(trie: &mut TrieNode, table: RomajiTable) {
+    insert_kunrei_rows(trie);
+    match table {
+        RomajiTable::Hepburn | RomajiTable::Azik => insert_hepburn_rows(trie),
+        RomajiTable::Kunrei => {}
+        RomajiTable::Nihon => {
+            trie.insert("wi", "ゐ");
+            trie.insert("we", "ゑ");
+            trie.insert("kwa", "くゎ");
+            trie.insert("kwi", "くゐ");
+            trie.insert("kwe", "くゑ");
+            trie.insert("kwo", "くを");
+            trie.insert("gwa", "ぐゎ");
+            trie.insert("gwi", "ぐゐ");
+            trie.insert("gwe", "ぐゑ");
+            trie.insert("gwo", "ぐを");
+        }
+    }
+}
+
+/// Kunrei-shiki spellings of the s, z, t, and h rows (si, zi, ti, tu, hu)
+fn insert_kunrei_rows(trie: &mut TrieNode) {
+    trie.insert("si", "し");
+    trie.insert("sya", "しゃ");
+    trie.insert("syi", "しぃ");
+    trie.insert("syu", "しゅ");
+    trie.insert("sye", "しぇ");
+    trie.insert("syo", "しょ");
+
+    trie.insert("zi", "じ");
+    trie.insert("zya", "じゃ");
+    trie.insert("zyi", "じぃ");
+    trie.insert("zyu", "じゅ");
+    trie.insert("zye", "じぇ");
+    trie.insert("zyo", "じょ");
+
+    trie.insert("ti", "ち");
+    trie.insert("tu", "つ");
+    trie.insert("tya", "ちゃ");
+    trie.insert("tyi", "ちぃ");
+    trie.insert("tyu", "ちゅ");
+    trie.insert("tye", "ちぇ");
+    trie.insert("tyo", "ちょ");
+
+    trie.insert("hu", "ふ");
+}
+
+/// Hepburn spellings (shi, ji, chi, tsu, fu) and the f-row built on fu
+fn insert_hepburn_rows(trie: &mut TrieNode) {
+    trie.insert("shi", "し");
+    trie.insert("sha", "しゃ");
+    trie.insert("shu", "しゅ");
+    trie.insert("she", "しぇ");
+    trie.insert("sho", "しょ");
+
+    trie.insert("ji", "じ");
+    trie.insert("ja", "じゃ");
+    trie.insert("ju", "じゅ");
+    trie.insert("je", "じぇ");
+    trie.insert("jo", "じょ");
+    trie.insert("jya", "じゃ");
+    trie.insert("jyi", "じぃ");
+    trie.insert("jyu", "じゅ");
+    trie.insert("jye", "じぇ");
+    trie.insert("jyo", "じょ");
+
+    trie.insert("chi", "ち");
+    trie.insert("tsu", "つ");
+    trie.insert("cha", "ちゃ");
+    trie.insert("chu", "ちゅ");
+    trie.insert("che", "ちぇ");
+    trie.insert("cho", "ちょ");
+    trie.insert("tsa", "つぁ");
+    trie.insert("tsi", "つぃ");
+    trie.insert("tse", "つぇ");
+    trie.insert("tso", "つぉ");
+
+    trie.insert("fu", "ふ");
+    trie.insert("fa", "ふぁ");
+    trie.insert("fi", "ふぃ");
+    trie.insert("fe", "ふぇ");
+    trie.insert("fo", "ふぉ");
+    trie.insert("fya", "ふゃ");
+    trie.insert("fyu", "ふゅ");
+    trie.insert("fyo", "ふょ");
+}
+
+/// AZIK extension keys typed after a consonant: (key, vowel of the syllable, appended kana).
+///
+/// kz → かん, kk → きん, kj → くん, kd → けん, kl → こん (撥音拡張);
+/// kq → かい, kh → くう, kw → けい, kp → こう (二重母音拡張).
+const AZIK_EXTENSIONS: [(char, char, &str); 9] = [
+    ('z', 'a', "ん"),
+    ('k', 'i', "ん"),
+    ('j', 'u', "ん"),
+    ('d', 'e', "ん"),
+    ('l', 'o', "ん"),
+    ('q', 'a', "い"),
+    ('h', 'u', "う"),
+    ('w', 'e', "い"),
+    ('p', 'o', "う"),
+];
+
+/// Consonant prefixes that take the AZIK extensions
+const AZIK_CONSONANTS: [&str; 31] = [
+    "k", "s", "t", "n", "h", "m", "y", "r", "w", "g", "z", "d", "b", "p", "f", "j", "x", "ky",
+    "sy", "ty", "ny", "hy", "my", "ry", "gy", "zy", "dy", "by", "py", "ch", "sh",
+];
+
+/// Common-word shortcuts from the AZIK special extensions
+const AZIK_WORDS: [(&str, &str); 6] = [
+    ("ds", "です"),
+    ("ms", "ます"),
+    ("kt", "こと"),
+    ("tt", "たち"),
+    ("wt", "わた"),
+    ("mn", "もの"),
+];
+
+/// Layer the AZIK rules over the Hepburn rows already in `trie`.
+///
+/// The extensions are derived from the existing syllables (kz = ka + ん), so
+/// they follow whatever the rows spell. x becomes the sh-row (small kana
+/// stay on the l prefix), ; is っ and a lone q is ん.
+fn insert_azik_rules(trie: &mut TrieNode) {
+    for (vowel, kana) in [
+        ('a', "しゃ"),
+        ('i', "し"),
+        ('u', "しゅ"),
+        ('e', "しぇ"),
+        ('o', "しょ"),
+    ] {
+        trie.insert(&format!("x{}", vowel), kana);
+    }
+
+    for consonant in AZIK_CONSONANTS {
+        for (key, vowel, suffix) in AZIK_EXTENSIONS {
+            let romaji = format!("{}{}", consonant, vowel);
+            let search = trie.search_longest(&romaji);
+            let syllable = search
+                .output
+                .filter(|_| search.matched_len == romaji.len())
+                .map(str::to_string);
+            if let Some(syllable) = syllable {
+                trie.insert(
+                    &format!("{}{}", consonant, key),
+                    &format!("{}{}", syllable, suffix),
+                );
+            }
+        }
+    }
+
+    for (romaji, output) in AZIK_WORDS {
+        trie.insert(romaji, output);
+    }
+    trie.insert(";", "っ");
+    trie.insert("q", "ん");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_basic_vowels() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("a").output.unwrap(), "あ");
         assert_eq!(trie.search_longest("i").output.unwrap(), "い");
         assert_eq!(trie.search_longest("u").output.unwrap(), "う");
@@ -384,7 +512,7 @@ mod tests {
 
     #[test]
     fn test_k_row() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("ka").output.unwrap(), "か");
         assert_eq!(trie.search_longest("ki").output.unwrap(), "き");
         assert_eq!(trie.search_longest("ku").output.unwrap(), "く");
@@ -394,7 +522,7 @@ mod tests {
 
     #[test]
     fn test_youon() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("kya").output.unwrap(), "きゃ");
         assert_eq!(trie.search_longest("sha").output.unwrap(), "しゃ");
         assert_eq!(trie.search_longest("cha").output.unwrap(), "ちゃ");
@@ -410,7 +538,7 @@ mod tests {
 
     #[test]
     fn test_small_characters() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("la").output.unwrap(), "ぁ");
         assert_eq!(trie.search_longest("li").output.unwrap(), "ぃ");
         assert_eq!(trie.search_longest("lu").output.unwrap(), "ぅ");
@@ -424,7 +552,7 @@ mod tests {
 
     #[test]
     fn test_n_variants() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("nn").output.unwrap(), "ん");
         assert_eq!(trie.search_longest("n'").output.unwrap(), "ん");
         assert_eq!(trie.search_longest("xn").output.unwrap(), "ん");
@@ -432,7 +560,7 @@ mod tests {
 
     #[test]
     fn test_c_row() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("ca").output.unwrap(), "か");
         assert_eq!(trie.search_longest("ci").output.unwrap(), "し");
         assert_eq!(trie.search_longest("cu").output.unwrap(), "く");
@@ -445,7 +573,7 @@ mod tests {
 
     #[test]
     fn test_q_row() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("qa").output.unwrap(), "くぁ");
         assert_eq!(trie.search_longest("qi").output.unwrap(), "くぃ");
         assert_eq!(trie.search_longest("qu").output.unwrap(), "く");
@@ -455,7 +583,7 @@ mod tests {
 
     #[test]
     fn test_kw_gw_series() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         // kw series
         assert_eq!(trie.search_longest("kwa").output.unwrap(), "くぁ");
         assert_eq!(trie.search_longest("kwi").output.unwrap(), "くぃ");
@@ -472,7 +600,7 @@ mod tests {
 
     #[test]
     fn test_sw_zw_series() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         // sw series
         assert_eq!(trie.search_longest("swa").output.unwrap(), "すぁ");
         assert_eq!(trie.search_longest("swi").output.unwrap(), "すぃ");
@@ -489,7 +617,7 @@ mod tests {
 
     #[test]
     fn test_th_dh_tw_dw_series() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         // th series
         assert_eq!(trie.search_longest("tha").output.unwrap(), "てゃ");
         assert_eq!(trie.search_longest("thi").output.unwrap(), "てぃ");
@@ -516,7 +644,7 @@ mod tests {
 
     #[test]
     fn test_hw_series() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("hwa").output.unwrap(), "ふぁ");
         assert_eq!(trie.search_longest("hwi").output.unwrap(), "ふぃ");
         assert_eq!(trie.search_longest("hwe").output.unwrap(), "ふぇ");
@@ -526,7 +654,7 @@ mod tests {
 
     #[test]
     fn test_w_row_modern() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         // Modern wi/we should be うぃ/うぇ
         assert_eq!(trie.search_longest("wi").output.unwrap(), "うぃ");
         assert_eq!(trie.search_longest("we").output.unwrap(), "うぇ");
@@ -537,7 +665,7 @@ mod tests {
 
     #[test]
     fn test_small_ka_ke() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("xka").output.unwrap(), "ヵ");
         assert_eq!(trie.search_longest("xke").output.unwrap(), "ヶ");
         assert_eq!(trie.search_longest("lka").output.unwrap(), "ヵ");
@@ -546,7 +674,7 @@ mod tests {
 
     #[test]
     fn test_z_special_symbols() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("z/").output.unwrap(), "・");
         assert_eq!(trie.search_longest("z.").output.unwrap(), "…");
         assert_eq!(trie.search_longest("z,").output.unwrap(), "‥");
@@ -561,7 +689,7 @@ mod tests {
 
     #[test]
     fn test_brackets_and_punctuation() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("[").output.unwrap(), "「");
         assert_eq!(trie.search_longest("]").output.unwrap(), "」");
         assert_eq!(trie.search_longest(",").output.unwrap(), "、");
@@ -570,12 +698,89 @@ mod tests {
         assert_eq!(trie.search_longest("~").output.unwrap(), "〜");
     }
 
+    #[test]
+    fn test_punctuation_width() {
+        let trie = build_rules(&RomajiConfig::default());
+        assert_eq!(trie.search_longest("?").output.unwrap(), "？");
+        assert_eq!(trie.search_longest("!").output.unwrap(), "！");
+
+        let trie = build_rules(&RomajiConfig {
+            punctuation_width: PunctuationWidth::Half,
+            ..RomajiConfig::default()
+        });
+        assert_eq!(trie.search_longest("?").output.unwrap(), "?");
+        assert_eq!(trie.search_longest("!").output.unwrap(), "!");
+    }
+
     #[test]
     fn test_tsu_variants() {
-        let trie = build_rules();
+        let trie = build_rules(&RomajiConfig::default());
         assert_eq!(trie.search_longest("tsa").output.unwrap(), "つぁ");
         assert_eq!(trie.search_longest("tsi").output.unwrap(), "つぃ");
         assert_eq!(trie.search_longest("tse").output.unwrap(), "つぇ");
         assert_eq!(trie.search_longest("tso").output.unwrap(), "つぉ");
     }
+
+    #[test]
+    fn test_table_rows() {
+        let nihon = build_rules(&RomajiConfig {
+            table: RomajiTable::Nihon,
+            ..RomajiConfig::default()
+        });
+        assert_eq!(nihon.search_longest("wi").output.unwrap(), "ゐ");
+        assert_eq!(nihon.search_longest("kwa").output.unwrap(), "くゎ");
+        assert_eq!(nihon.search_longest("si").output.unwrap(), "し");
+
+        let kunrei = build_rules(&RomajiConfig {
+            table: RomajiTable::Kunrei,
+            ..RomajiConfig::default()
+        });
+        assert_eq!(kunrei.search_longest("wi").output.unwrap(), "うぃ");
+        assert_eq!(kunrei.search_longest("kwa").output.unwrap(), "くぁ");
+
+        // Kunrei rows are in every table; Hepburn rows only in Hepburn and AZIK
+        let hepburn = build_rules(&RomajiConfig::default());
+        for (romaji, kana) in [
+            ("si", "し"),
+            ("ti", "ち"),
+            ("tu", "つ"),
+            ("hu", "ふ"),
+            ("zi", "じ"),
+            ("sya", "しゃ"),
+        ] {
+            assert_eq!(kunrei.search_longest(romaji).output.unwrap(), kana);
+            assert_eq!(nihon.search_longest(romaji).output.unwrap(), kana);
+            assert_eq!(hepburn.search_longest(romaji).output.unwrap(), kana);
+        }
+        for romaji in ["shi", "chi", "tsu", "fu", "ji", "ja", "sha", "cha"] {
+            assert!(hepburn.search_longest(romaji).output.is_some(), "{romaji}");
+            assert!(kunrei.search_longest(romaji).output.is_none(), "{romaji}");
+            assert!(nihon.search_longest(romaji).output.is_none(), "{romaji}");
+        }
+    }
+
+    #[test]
+    fn test_azik_rules() {
+        let azik = build_rules(&RomajiConfig {
+            table: RomajiTable::Azik,
+            ..RomajiConfig::default()
+        });
+        // Extensions are derived from the rows, including y-combinations
+        assert_eq!(azik.search_longest("kz").output.unwrap(), "かん");
+        assert_eq!(azik.search_longest("kyp").output.unwrap(), "きょう");
+        assert_eq!(azik.search_longest("shp").output.unwrap(), "しょう");
+        assert_eq!(azik.search_longest("xp").output.unwrap(), "しょう");
+        // x is the sh-row; small kana keep the l prefix
+        assert_eq!(azik.search_longest("xa").output.unwrap(), "しゃ");
+        assert_eq!(azik.search_longest("la").output.unwrap(), "ぁ");
+        // The ず/じ extensions win over the z arrows; other z symbols remain
+        assert_eq!(azik.search_longest("zk").output.unwrap(), "じん");
+        assert_eq!(azik.search_longest("z.").output.unwrap(), "…");
+        assert_eq!(azik.search_longest(";").output.unwrap(), "っ");
+
+        // Other tables are unaffected
+        let hepburn = build_rules(&RomajiConfig::default());
+        assert_eq!(hepburn.search_longest("xa").output.unwrap(), "ぁ");
+        assert!(hepburn.search_longest("kz").output.is_none());
+    }
 }
@@ -38,6 +38,33 @@ impl TrieNode {
         node.output = Some(hiragana.to_string());
     }
 
+    /// Whether any rule starts with `prefix` (or is exactly `prefix`)
+    pub fn contains_prefix(&self, prefix: &str) -> bool {
+        let mut node = self;
+        for ch in prefix.chars() {
+            match node.children.get(&ch) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// All `(romaji, output)` rules in the trie
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let mut entries = Vec::new();
+        let mut stack = vec![(String::new(), self)];
+        while let Some((prefix, node)) = stack.pop() {
+            if let Some(output) = &node.output {
+                entries.push((prefix.clone(), output.clone()));
+            }
+            for (ch, child) in &node.children {
+                stack.push((format!("{}{}", prefix, ch), child));
+            }
+        }
+        entries
+    }
+
     /// Search for the longest matching prefix in the trie
     pub fn search_longest(&self, input: &str) -> SearchResult<'_> {
         let mut node = self;
@@ -96,6 +123,18 @@ mod tests {
         assert_eq!(result.output.unwrap(), "き");
     }
 
+    #[test]
+    fn test_trie_contains_prefix() {
+        let mut trie = TrieNode::new();
+        trie.insert("kya", "きゃ");
+
+        assert!(trie.contains_prefix("k"));
+        assert!(trie.contains_prefix("ky"));
+        assert!(trie.contains_prefix("kya"));
+        assert!(!trie.contains_prefix("kk"));
+        assert!(!trie.contains_prefix("kyaa"));
+    }
+
     #[test]
     fn test_trie_longest_match() {
         let mut trie = TrieNode::new();
@@ -0,0 +1,327 @@
+//! Editable user dictionary.
+//!
+//! Entries live in a `BTreeMap` that can be changed at runtime; the
+//! double-array trie [`Dictionary`] used for lookups is rebuilt after each
+//! change. User dictionaries are small, so a full rebuild is cheap.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::atomic_file::write_atomically;
+use crate::dict::{Candidate, DictEntry, DictError, DictFormat, Dictionary};
+
+/// A user dictionary that supports adding and removing entries.
+pub struct UserDictionary {
+    /// reading → candidates in priority order (BTreeMap keeps readings sorted for the trie builder)
+    entries: BTreeMap<String, Vec<Candidate>>,
+    /// Lookup trie rebuilt from `entries`
+    dict: Dictionary,
+}
+
+impl UserDictionary {
+    /// Create an empty user dictionary.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            dict: Dictionary::empty(),
+        }
+    }
+
+    /// Create a user dictionary holding the entries of `dict`.
+    pub fn from_dictionary(dict: Dictionary) -> Result<Self, DictError> {
+        let entries = dict
+            .into_entries()
+            .into_iter()
+            .map(|e| (e.reading, e.candidates))
+            .collect();
+        let mut user = Self::new();
+        user.entries = entries;
+        user.rebuild()?;
+        Ok(user)
+    }
+
+    /// Load a user dictionary file (any format accepted by [`Dictionary::load_auto`]).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DictError> {
+        Self::from_dictionary(Dictionary::load_auto(path)?)
+    }
+
+    /// The lookup dictionary reflecting the current entries.
+    pub fn dictionary(&self) -> &Dictionary {
+        &self.dict
+    }
+
+    /// Add `surface` for `reading` (after existing candidates).
+    ///
+    /// Returns false if the pair was already present. Empty or whitespace-only
+    /// readings are rejected.
+    pub fn add(&mut self, reading: &str, surface: &str) -> Result<bool, DictError> {
+        if reading.trim().is_empty() {
+            return Err(DictError::Format(format!(
+                "empty reading for \"{}\"",
+                surface
+            )));
+        }
+        if self.contains(reading, surface) {
+            return Ok(false);
+        }
+        let previous = self.entries.clone();
+        self.entries
+            .entry(reading.to_string())
+            .or_default()
+            .push(Candidate::new(surface, 0.0));
+        self.rebuild_or_restore(previous)?;
+        Ok(true)
+    }
+
+    /// Remove `surface` from `reading`, dropping the reading once it has no candidates.
+    ///
+    /// Returns false if the pair was not present.
+    pub fn remove(&mut self, reading: &str, surface: &str) -> Result<bool, DictError> {
+        if !self.contains(reading, surface) {
+            return Ok(false);
+        }
+        let previous = self.entries.clone();
+        if let Some(candidates) = self.entries.get_mut(reading) {
+            candidates.retain(|c| c.surface != surface);
+            if candidates.is_empty() {
+                self.entries.remove(reading);
+            }
+        }
+        self.rebuild_or_restore(previous)?;
+        Ok(true)
+    }
+
+    /// Whether `surface` is registered for `reading`.
+    pub fn contains(&self, reading: &str, surface: &str) -> bool {
+        self.entries
+            .get(reading)
+            .is_some_and(|c| c.iter().any(|c| c.surface == surface))
+    }
+
+    /// Number of readings in the dictionary.
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Remove `surface` for `reading` from the Mozc TSV file at `path` in place,
+    /// keeping comments and the other lines as written.
+    ///
+    /// Returns false, leaving the file untouched, if no line matched. Files in
+    /// other formats cannot be edited and are rejected.
+    pub fn remove_from_file(
+        path: impl AsRef<Path>,
+        reading: &str,
+        surface: &str,
+    ) -> Result<bool, DictError> {
+        let path = path.as_ref();
+        let format = DictFormat::detect(path)?;
+        if format != DictFormat::MozcTsv {
+            return Err(DictError::Format(format!(
+                "{} is a {:?} dictionary, not an editable Mozc TSV",
+                path.display(),
+                format
+            )));
+        }
+        let text = std::fs::read_to_string(path)?;
+        let mut kept = String::with_capacity(text.len());
+        let mut removed = false;
+        for line in text.split_inclusive('\n') {
+            let mut cols = line.trim().split('\t');
+            if !line.trim_start().starts_with('#')
+                && cols.next() == Some(reading)
+                && cols.next() == Some(surface)
+            {
+                removed = true;
+            } else {
+                kept.push_str(line);
+            }
+        }
+        if removed {
+            write_atomically(path, |w| w.write_all(kept.as_bytes()))?;
+        }
+        Ok(removed)
+    }
+
+    /// Save as a Mozc TSV file (`reading\tsurface\tPOS\t`), which
+    /// [`load`](Self::load) reads back.
+    ///
+    /// The file is replaced atomically, so a failed save leaves it intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DictError> {
+        write_atomically(path.as_ref(), |w| {
+            writeln!(w, "# karukan user dictionary")?;
+            for (reading, candidates) in &self.entries {
+                for cand in candidates {
+                    writeln!(
+                        w,
+                        "{}\t{}\t{}\t",
+                        reading,
+                        cand.surface,
+                        cand.pos.as_deref().unwrap_or_default()
+                    )?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Rebuild after an edit, putting back the `previous` entries (and
+    /// keeping the current trie) if the rebuild fails.
+    fn rebuild_or_restore(
+        &mut self,
+        previous: BTreeMap<String, Vec<Candidate>>,
+    ) -> Result<(), DictError> {
+        self.rebuild().inspect_err(|_| self.entries = previous)
+    }
+
+    /// Rebuild the lookup trie from the entries, keeping the current trie on error.
+    fn rebuild(&mut self) -> Result<(), DictError> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|(reading, candidates)| DictEntry {
+                reading: reading.clone(),
+                candidates: candidates.clone(),
+            })
+            .collect();
+        // Readings are unique and sorted by the BTreeMap, which is all the builder needs
+        self.dict = Dictionary::build_from_entries(entries)?;
+        Ok(())
+    }
+}
+
+impl Default for UserDictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    fn surfaces(user: &UserDictionary, reading: &str) -> Vec<String> {
+        user.dictionary()
+            .exact_match_search(reading)
+            .map(|r| r.candidates.iter().map(|c| c.surface.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_add_and_lookup() {
+        let mut user = UserDictionary::new();
+        assert!(user.dictionary().exact_match_search("ねこ").is_none());
+
+        assert!(user.add("ねこ", "猫").unwrap());
+        assert!(user.add("ねこ", "ネコ").unwrap());
+        assert!(!user.add("ねこ", "猫").unwrap());
+        assert_eq!(surfaces(&user, "ねこ"), vec!["猫", "ネコ"]);
+        assert!(user.contains("ねこ", "猫"));
+        assert_eq!(user.entry_count(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut user = UserDictionary::new();
+        user.add("ねこ", "猫").unwrap();
+        user.add("ねこ", "ネコ").unwrap();
+        user.add("いぬ", "犬").unwrap();
+
+        assert!(user.remove("ねこ", "猫").unwrap());
+        assert!(!user.remove("ねこ", "猫").unwrap());
+        assert!(!user.remove("とり", "鳥").unwrap());
+        assert_eq!(surfaces(&user, "ねこ"), vec!["ネコ"]);
+
+        // Removing the last candidate drops the reading
+        assert!(user.remove("ねこ", "ネコ").unwrap());
+        assert!(user.dictionary().exact_match_search("ねこ").is_none());
+        assert_eq!(user.entry_count(), 1);
+        assert_eq!(surfaces(&user, "いぬ"), vec!["犬"]);
+    }
+
+    #[test]
+    fn test_add_rejects_empty_reading() {
+        let mut user = UserDictionary::new();
+        user.add("ねこ", "猫").unwrap();
+
+        assert!(matches!(user.add("", "空"), Err(DictError::Format(_))));
+        assert!(matches!(user.add(" \t", "空"), Err(DictError::Format(_))));
+        // Existing entries are still looked up
+        assert_eq!(surfaces(&user, "ねこ"), vec!["猫"]);
+        assert_eq!(user.entry_count(), 1);
+    }
+
+    #[test]
+    fn test_save_and_reload() {
+        let mut user = UserDictionary::new();
+        user.add("ねこ", "猫").unwrap();
+        user.add("ねこ", "ネコ").unwrap();
+        user.add("いぬ", "犬").unwrap();
+        user.remove("いぬ", "犬").unwrap();
+
+        let file = NamedTempFile::new().unwrap();
+        user.save(file.path()).unwrap();
+
+        let reloaded = UserDictionary::load(file.path()).unwrap();
+        assert_eq!(reloaded.entry_count(), 1);
+        assert_eq!(surfaces(&reloaded, "ねこ"), vec!["猫", "ネコ"]);
+        assert!(reloaded.dictionary().exact_match_search("いぬ").is_none());
+    }
+
+    #[test]
+    fn test_save_replaces_file_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("user.tsv");
+        std::fs::write(&path, "いぬ\t犬\t\t\n").unwrap();
+
+        let mut user = UserDictionary::load(&path).unwrap();
+        user.add("ねこ", "猫").unwrap();
+        user.save(&path).unwrap();
+
+        assert_eq!(UserDictionary::load(&path).unwrap().entry_count(), 2);
+        // Only the dictionary is left; the temporary file was renamed over it
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_remove_from_file() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            "# my words\nねこ\t猫\t名詞\t\nねこ\tネコ\t\t\nいぬ\t犬\t名詞\tpet\n",
+        )
+        .unwrap();
+
+        assert!(UserDictionary::remove_from_file(file.path(), "ねこ", "猫").unwrap());
+        assert!(!UserDictionary::remove_from_file(file.path(), "ねこ", "猫").unwrap());
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "# my words\nねこ\tネコ\t\t\nいぬ\t犬\t名詞\tpet\n"
+        );
+        let reloaded = UserDictionary::load(file.path()).unwrap();
+        assert_eq!(surfaces(&reloaded, "ねこ"), vec!["ネコ"]);
+    }
+
+    #[test]
+    fn test_remove_from_file_rejects_other_formats() {
+        let file = NamedTempFile::with_suffix(".csv").unwrap();
+        std::fs::write(file.path(), "c0,c1,c2,3000,猫,c5,c6,c7,c8,c9,c10,ネコ\n").unwrap();
+        assert!(matches!(
+            UserDictionary::remove_from_file(file.path(), "ねこ", "猫"),
+            Err(DictError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_dictionary_keeps_pos() {
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "ねこ\t猫\t名詞\t\n").unwrap();
+        let mut user = UserDictionary::load(file.path()).unwrap();
+        user.add("ねこ", "ネコ").unwrap();
+
+        let result = user.dictionary().exact_match_search("ねこ").unwrap();
+        assert_eq!(result.candidates[0].pos.as_deref(), Some("名詞"));
+        assert_eq!(result.candidates[1].pos, None);
+    }
+}
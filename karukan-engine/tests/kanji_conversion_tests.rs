@@ -23,6 +23,19 @@ fn is_valid_japanese(s: &str) -> bool {
     })
 }
 
+/// Length of the longest run of one repeated character
+fn longest_char_run(s: &str) -> usize {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev = None;
+    for c in s.chars() {
+        run = if prev == Some(c) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        prev = Some(c);
+    }
+    longest
+}
+
 // Re-export clean_model_output for test use
 use karukan_engine::kanji::clean_model_output as clean_output;
 
@@ -38,6 +51,109 @@ mod unit_tests {
         let config = ConversionConfig::default();
         assert_eq!(config.max_new_tokens, 50);
     }
+
+    #[test]
+    fn test_sampling_params_default_is_greedy() {
+        use karukan_engine::kanji::SamplingParams;
+        assert!(SamplingParams::default().is_greedy());
+        let params = SamplingParams {
+            temperature: 0.8,
+            ..SamplingParams::default()
+        };
+        assert!(!params.is_greedy());
+    }
+
+    #[test]
+    fn test_longest_char_run() {
+        assert_eq!(longest_char_run(""), 0);
+        assert_eq!(longest_char_run("人々"), 1);
+        assert_eq!(longest_char_run("人々々々々"), 4);
+    }
+
+    #[test]
+    fn test_conversion_config_repeat_penalty_disabled_by_default() {
+        assert_eq!(ConversionConfig::default().repeat_penalty, 1.0);
+    }
+
+    #[test]
+    fn test_new_token_budget() {
+        let config = ConversionConfig::default();
+        assert_eq!(config.new_tokens_margin, 16);
+        // Short readings get a small budget
+        assert_eq!(config.new_token_budget(0), 16);
+        assert_eq!(config.new_token_budget(1), 17);
+        assert_eq!(config.new_token_budget(10), 26);
+        // Long readings are capped at max_new_tokens
+        assert_eq!(config.new_token_budget(34), 50);
+        assert_eq!(config.new_token_budget(35), 50);
+        assert_eq!(config.new_token_budget(200), 50);
+
+        use karukan_engine::kanji::new_token_budget;
+        assert_eq!(new_token_budget(5, 8, 64), 13);
+        assert_eq!(new_token_budget(60, 8, 64), 64);
+        assert_eq!(new_token_budget(usize::MAX, 8, 64), 64);
+    }
+
+    #[test]
+    fn test_new_token_budget_at_least_one() {
+        use karukan_engine::kanji::new_token_budget;
+        assert_eq!(new_token_budget(0, 0, 64), 1);
+        assert_eq!(new_token_budget(5, 8, 0), 1);
+        assert_eq!(new_token_budget(0, 0, 0), 1);
+
+        let config = ConversionConfig {
+            max_new_tokens: 0,
+            ..ConversionConfig::default()
+        };
+        assert_eq!(config.new_token_budget(10), 1);
+    }
+
+    #[test]
+    fn test_clean_output_default_only_trims() {
+        assert_eq!(clean_output("  漢字\n"), "漢字");
+        // Valid content is preserved as is, including inner whitespace and newlines
+        assert_eq!(clean_output("Hello World"), "Hello World");
+        assert_eq!(clean_output("一行目\n二行目"), "一行目\n二行目");
+        assert_eq!(clean_output("１２３、ＡＢＣ！"), "１２３、ＡＢＣ！");
+    }
+
+    #[test]
+    fn test_clean_output_strips_special_tokens() {
+        use karukan_engine::kanji::{CleanOptions, clean_model_output_with};
+        let text = "\u{ee01}東京\u{ee02}";
+        assert_eq!(clean_output(text), text);
+        let options = CleanOptions {
+            strip_special_tokens: true,
+            ..CleanOptions::default()
+        };
+        assert_eq!(clean_model_output_with(text, &options), "東京");
+    }
+
+    #[test]
+    fn test_clean_output_first_line_only() {
+        use karukan_engine::kanji::{CleanOptions, clean_model_output_with};
+        let options = CleanOptions {
+            first_line_only: true,
+            ..CleanOptions::default()
+        };
+        assert_eq!(
+            clean_model_output_with("\n 今日は\n明日は\n", &options),
+            "今日は"
+        );
+        assert_eq!(clean_model_output_with("今日は\n", &options), "今日は");
+        assert_eq!(clean_model_output_with("\n\n", &options), "");
+    }
+
+    #[test]
+    fn test_clean_output_allowed_chars() {
+        use karukan_engine::kanji::{CleanOptions, clean_model_output_with};
+        let options = CleanOptions {
+            allowed_chars: Some(|c| is_valid_japanese(&c.to_string())),
+            ..CleanOptions::default()
+        };
+        assert_eq!(clean_model_output_with("漢字ã\u{fffd}", &options), "漢字");
+        assert_eq!(clean_model_output_with("今日は。", &options), "今日は。");
+    }
 }
 
 // ============================================================================
@@ -87,6 +203,118 @@ mod llamacpp_tests {
         assert!(is_valid_japanese(&clean), "Invalid output: {}", clean);
     }
 
+    #[test]
+    fn test_generate_streaming_matches_generate() {
+        use std::ops::ControlFlow;
+        let model = load_model().expect("Failed to load");
+        let prompt = build_prompt("トウキョウ");
+        let tokens = model.tokenize(&prompt).expect("Tokenize failed");
+        let eos = Some(model.eos_token_id().0);
+
+        let batch = model.generate(&tokens, 20, eos).expect("Generate failed");
+
+        let mut streamed = Vec::new();
+        let output = model
+            .generate_streaming(&tokens, 20, eos, |token| {
+                streamed.push(token);
+                ControlFlow::Continue(())
+            })
+            .expect("Generate failed");
+        assert_eq!(output, batch);
+        assert_eq!(streamed, batch[tokens.len()..]);
+
+        // Breaking after the first token truncates the output
+        let mut seen = 0;
+        let truncated = model
+            .generate_streaming(&tokens, 20, eos, |_| {
+                seen += 1;
+                ControlFlow::Break(())
+            })
+            .expect("Generate failed");
+        assert_eq!(seen, 1);
+        assert_eq!(truncated, batch[..tokens.len() + 1]);
+    }
+
+    #[test]
+    fn test_generate_with_params_zero_temperature_matches_greedy() {
+        use karukan_engine::kanji::SamplingParams;
+        let model = load_model().expect("Failed to load");
+        let prompt = build_prompt("トウキョウ");
+        let tokens = model.tokenize(&prompt).expect("Tokenize failed");
+        let eos = Some(model.eos_token_id().0);
+
+        let greedy = model.generate(&tokens, 20, eos).expect("Generate failed");
+        let params = SamplingParams {
+            temperature: 0.0,
+            top_p: 0.9,
+            top_k: 40,
+            seed: 42,
+            ..SamplingParams::default()
+        };
+        let sampled = model
+            .generate_with_params(&tokens, 20, eos, params)
+            .expect("Generate failed");
+        assert_eq!(sampled, greedy);
+    }
+
+    #[test]
+    fn test_generate_with_params_sampling() {
+        use karukan_engine::kanji::SamplingParams;
+        let model = load_model().expect("Failed to load");
+        let prompt = build_prompt("ヘンカン");
+        let tokens = model.tokenize(&prompt).expect("Tokenize failed");
+        let eos = Some(model.eos_token_id().0);
+
+        let params = SamplingParams {
+            temperature: 0.8,
+            top_p: 0.9,
+            top_k: 40,
+            seed: 42,
+            ..SamplingParams::default()
+        };
+        let first = model
+            .generate_with_params(&tokens, 20, eos, params)
+            .expect("Generate failed");
+        let output = model
+            .decode(&first[tokens.len()..], true)
+            .expect("Decode failed");
+        let clean = clean_output(&output);
+        assert!(is_valid_japanese(&clean), "Invalid output: {}", clean);
+
+        // A fixed seed reproduces the same output
+        let second = model
+            .generate_with_params(&tokens, 20, eos, params)
+            .expect("Generate failed");
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_repeat_penalty_stops_looping() {
+        use karukan_engine::kanji::SamplingParams;
+        let model = load_model().expect("Failed to load");
+        // Long repeated readings make the model loop on "々" without a penalty
+        let prompt = build_prompt(&"ヒトビト".repeat(6));
+        let tokens = model.tokenize(&prompt).expect("Tokenize failed");
+        let eos = Some(model.eos_token_id().0);
+
+        let params = SamplingParams {
+            repeat_penalty: 1.3,
+            ..SamplingParams::default()
+        };
+        let output_tokens = model
+            .generate_with_params(&tokens, 50, eos, params)
+            .expect("Generate failed");
+        let output = model
+            .decode(&output_tokens[tokens.len()..], true)
+            .expect("Decode failed");
+        let clean = clean_output(&output);
+        assert!(
+            longest_char_run(&clean) <= 4,
+            "Repeated character run in output: {}",
+            clean
+        );
+    }
+
     #[test]
     fn test_expected_conversions() {
         let model = load_model().expect("Failed to load");
@@ -145,6 +373,36 @@ mod llamacpp_tests {
         println!("へんかん -> {:?}", candidates);
     }
 
+    #[test]
+    fn test_beam_search_deadline_returns_early() {
+        use std::time::Instant;
+        let model = load_model().expect("Failed to load");
+        let prompt = build_prompt("ヘンカン");
+        let tokens = model.tokenize(&prompt).expect("Tokenize failed");
+        let eos = Some(model.eos_token_id().0);
+
+        // An already-expired deadline stops expansion after the initial beams
+        let deadline = Some(Instant::now());
+        let results = model
+            .generate_beam_search_with_deadline(&tokens, 4, eos, 2, deadline)
+            .expect("Beam search failed");
+        let d1_results = model
+            .generate_beam_search_d1_greedy_with_deadline(&tokens, 4, eos, 2, deadline)
+            .expect("d1 greedy beam search failed");
+
+        for results in [results, d1_results] {
+            assert!(!results.is_empty(), "No results");
+            for (t, score) in &results {
+                assert!(t.len() <= 4);
+                assert!(score.is_finite());
+                // Cut-short beams may end in the middle of a multi-byte character
+                let text = clean_output(&model.decode(t, true).expect("Decode failed"));
+                let text = text.trim_end_matches('\u{FFFD}');
+                assert!(is_valid_japanese(text), "Invalid: {}", text);
+            }
+        }
+    }
+
     #[test]
     fn test_beam_search_multiple_inputs() {
         let model = load_model().expect("Failed to load");
@@ -488,4 +746,66 @@ mod llamacpp_tests {
             );
         }
     }
+
+    #[test]
+    fn test_nll_scorer_pool_matches_sequential() {
+        use karukan_engine::kanji::{NllScorer, NllScorerPool};
+        use std::sync::Arc;
+        let model = Arc::new(load_model().expect("Failed to load"));
+        let pairs: Vec<(String, String)> = [
+            ("トウキョウ", "東京"),
+            ("トウキョウ", "東京都"),
+            ("ワセダ", "早稲田"),
+            ("ヘンカン", "変換"),
+            ("ヘンカン", "返還"),
+            ("キョウ", "今日"),
+            ("キョウ", "京"),
+        ]
+        .iter()
+        .map(|(r, s)| (r.to_string(), s.to_string()))
+        .collect();
+
+        let mut scorer = NllScorer::new(&model, model.n_ctx()).expect("Scorer failed");
+        let sequential: Vec<f32> = pairs
+            .iter()
+            .map(|(r, s)| scorer.compute_nll(r, s).expect("Scoring failed"))
+            .collect();
+
+        let pool = NllScorerPool::new(Arc::clone(&model), model.n_ctx(), 3);
+        assert_eq!(pool.size(), 3);
+        // The second call reuses the workers' scorers
+        for _ in 0..2 {
+            let parallel = pool.score_all(&pairs).expect("Scoring failed");
+            assert_eq!(parallel.len(), pairs.len());
+            for (p, s) in parallel.iter().zip(&sequential) {
+                assert!((p - s).abs() < 1e-4, "parallel {} != sequential {}", p, s);
+            }
+        }
+
+        assert!(pool.score_all(&[]).expect("Scoring failed").is_empty());
+    }
+
+    #[test]
+    fn test_overlong_context_is_truncated() {
+        let model = load_model().expect("Failed to load");
+        let context = "今日は天気が良いので公園に散歩に行きました。".repeat(40);
+        let prompt = build_jinen_prompt("トウキョウ", &context);
+        let tokens = model.tokenize(&prompt).expect("Tokenize failed");
+        assert!(tokens.len() > model.n_ctx() as usize);
+
+        let eos = Some(model.eos_token_id().0);
+        let output_tokens = model.generate(&tokens, 20, eos).expect("Generate failed");
+        assert_eq!(output_tokens[..tokens.len()], tokens[..]);
+        let output = model
+            .decode(&output_tokens[tokens.len()..], true)
+            .expect("Decode failed");
+        let clean = clean_output(&output);
+        assert!(!clean.is_empty());
+        assert!(is_valid_japanese(&clean), "Invalid output: {}", clean);
+
+        let beams = model
+            .generate_beam_search(&tokens, 20, eos, 3)
+            .expect("Beam search failed");
+        assert!(!beams.is_empty());
+    }
 }
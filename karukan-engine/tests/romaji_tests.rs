@@ -1,4 +1,4 @@
-use karukan_engine::RomajiConverter;
+use karukan_engine::{RomajiConfig, RomajiConverter, RomajiTable};
 
 #[test]
 fn test_vowels() {
@@ -391,6 +391,47 @@ fn test_z_row_dakuten() {
     assert_eq!(conv.output(), "ぞ");
 }
 
+#[test]
+fn test_z_symbols_mixed_with_z_row() {
+    let mut conv = RomajiConverter::new();
+
+    for (input, expected) in [
+        ("zhzya", "←じゃ"),
+        ("zazh", "ざ←"),
+        ("zizl", "じ→"),
+        ("zuz.", "ず…"),
+        ("zyazk", "じゃ↑"),
+        ("zwazl", "ずぁ→"),
+        ("zhzu", "←ず"),
+        ("z.z.", "……"),
+        // "zj" is ↓, so the following 'a' stands alone
+        ("zhzja", "←↓あ"),
+        ("zhja", "←じゃ"),
+        // Doubled z is a sokuon; the second z still starts a symbol
+        ("zzh", "っ←"),
+        ("zza", "っざ"),
+    ] {
+        conv.reset();
+        conv.push_all(input);
+        assert_eq!(conv.output(), expected, "input: {}", input);
+        assert_eq!(conv.buffer(), "", "input: {}", input);
+    }
+
+    // A lone z (or zy/zw) waits for the next key instead of guessing
+    for pending in ["z", "zy", "zw"] {
+        conv.reset();
+        conv.push_all(pending);
+        assert_eq!(conv.output(), "");
+        assert_eq!(conv.buffer(), pending);
+    }
+
+    // A symbol converts as soon as it completes, leaving the next z pending
+    conv.reset();
+    conv.push_all("zhz");
+    assert_eq!(conv.output(), "←");
+    assert_eq!(conv.buffer(), "z");
+}
+
 #[test]
 fn test_d_row_dakuten() {
     let mut conv = RomajiConverter::new();
@@ -651,6 +692,36 @@ fn test_small_characters() {
     assert_eq!(conv.output(), "っ");
 }
 
+#[test]
+fn test_small_characters_mid_word() {
+    let mut conv = RomajiConverter::new();
+
+    // A small kana after a converted syllable leaves the earlier output alone
+    for (input, expected) in [
+        ("tela", "てぁ"),
+        ("texa", "てぁ"),
+        // "nn" has already become ん before the l/x prefix
+        ("nnla", "んぁ"),
+        ("nnxa", "んぁ"),
+        // 'n' before the prefix consonant is ん, not a pending な-row
+        ("nla", "んぁ"),
+        ("xtuka", "っか"),
+        ("ltuka", "っか"),
+    ] {
+        conv.reset();
+        conv.push_all(input);
+        assert_eq!(conv.output(), expected, "input: {}", input);
+        assert_eq!(conv.buffer(), "", "input: {}", input);
+    }
+
+    // Fed in two steps, the standalone っ is kept before the next syllable
+    conv.reset();
+    conv.push_all("xtu");
+    assert_eq!(conv.output(), "っ");
+    conv.push_all("ka");
+    assert_eq!(conv.output(), "っか");
+}
+
 #[test]
 fn test_real_words() {
     let mut conv = RomajiConverter::new();
@@ -709,7 +780,7 @@ fn test_real_words() {
     });
     assert_eq!(conv.output(), "あんにんどうふ"); // "annin doufu" - almond jelly
 
-    // "karukan" (single n at end) -> "かるかn" after flush
+    // "karukan" (single n at end) -> "かるかん" after flush (trailing n → ん)
     conv.reset();
     "karukan".chars().for_each(|c| {
         conv.push(c);
@@ -717,7 +788,18 @@ fn test_real_words() {
     assert_eq!(conv.output(), "かるか");
     assert_eq!(conv.buffer(), "n"); // Trailing 'n' buffered (ambiguous)
     conv.flush();
-    assert_eq!(conv.output(), "かるかn"); // Ambiguous 'n' outputs as-is
+    assert_eq!(conv.output(), "かるかん");
+
+    // With trailing_n_to_hiragana disabled -> "かるかn" after flush
+    let mut raw = RomajiConverter::with_config(RomajiConfig {
+        trailing_n_to_hiragana: false,
+        ..RomajiConfig::default()
+    });
+    "karukan".chars().for_each(|c| {
+        raw.push(c);
+    });
+    raw.flush();
+    assert_eq!(raw.output(), "かるかn"); // Ambiguous 'n' outputs as-is
 
     // "karukann" (nn at end) -> "かるかん" immediately (nn converts right away)
     conv.reset();
@@ -954,3 +1036,156 @@ fn test_zenninn_kanji_conversion() {
     let result = kanji_conv.convert(hiragana, "", 1);
     println!("Kanji result: {:?}", result);
 }
+
+/// Convert a whole word with the given table, flushing any trailing input
+fn convert_word(table: RomajiTable, romaji: &str) -> String {
+    let mut conv = RomajiConverter::with_table(table);
+    for ch in romaji.chars() {
+        conv.push(ch);
+    }
+    conv.flush();
+    conv.output().to_string()
+}
+
+#[test]
+fn test_hepburn_table_words() {
+    let cases = [
+        ("shinbun", "しんぶん"),
+        ("chikatetsu", "ちかてつ"),
+        ("tsukue", "つくえ"),
+        ("fuji", "ふじ"),
+        ("jisho", "じしょ"),
+        ("kyoushitsu", "きょうしつ"),
+        ("ocha", "おちゃ"),
+        ("gakkou", "がっこう"),
+        ("jinja", "じんじゃ"),
+        ("hanadi", "はなぢ"),
+        ("wasureru", "わすれる"),
+        ("uisukii", "ういすきい"),
+    ];
+    for (romaji, kana) in cases {
+        assert_eq!(
+            convert_word(RomajiTable::Hepburn, romaji),
+            kana,
+            "{}",
+            romaji
+        );
+    }
+}
+
+#[test]
+fn test_kunrei_table_words() {
+    let cases = [
+        ("sinbun", "しんぶん"),
+        ("tikatetu", "ちかてつ"),
+        ("tukue", "つくえ"),
+        ("huzi", "ふじ"),
+        ("zisyo", "じしょ"),
+        ("kyousitu", "きょうしつ"),
+        ("otya", "おちゃ"),
+        ("gakkou", "がっこう"),
+        ("zinzya", "じんじゃ"),
+        ("hanadi", "はなぢ"),
+        ("tudukeru", "つづける"),
+        ("syasin", "しゃしん"),
+    ];
+    for (romaji, kana) in cases {
+        assert_eq!(
+            convert_word(RomajiTable::Kunrei, romaji),
+            kana,
+            "{}",
+            romaji
+        );
+    }
+}
+
+#[test]
+fn test_kunrei_and_hepburn_differ() {
+    // Hepburn accepts Kunrei spellings too
+    for (romaji, kana) in [
+        ("sinbun", "しんぶん"),
+        ("tikatetu", "ちかてつ"),
+        ("huzi", "ふじ"),
+    ] {
+        assert_eq!(
+            convert_word(RomajiTable::Hepburn, romaji),
+            kana,
+            "{}",
+            romaji
+        );
+    }
+    // Kunrei has no Hepburn spellings, so their letters are left as typed
+    let cases = [
+        ("shinbun", "sひんぶん"),
+        ("chikatetsu", "cひかてtす"),
+        ("fuji", "fうjい"),
+        ("jisho", "jいsほ"),
+        ("tsukue", "tすくえ"),
+    ];
+    for (romaji, kana) in cases {
+        assert_eq!(
+            convert_word(RomajiTable::Kunrei, romaji),
+            kana,
+            "{}",
+            romaji
+        );
+        assert_eq!(convert_word(RomajiTable::Nihon, romaji), kana, "{}", romaji);
+        assert_ne!(
+            convert_word(RomajiTable::Hepburn, romaji),
+            kana,
+            "{}",
+            romaji
+        );
+    }
+}
+
+#[test]
+fn test_nihon_table_words() {
+    let cases = [
+        ("sinbun", "しんぶん"),
+        ("tikatetu", "ちかてつ"),
+        ("huzi", "ふじ"),
+        ("hanadi", "はなぢ"),
+        ("tudukeru", "つづける"),
+        ("dyuu", "ぢゅう"),
+        ("wi", "ゐ"),
+        ("kwazi", "くゎじ"),
+        ("gwaikoku", "ぐゎいこく"),
+        ("wemono", "ゑもの"),
+        ("wosamu", "をさむ"),
+        ("syasin", "しゃしん"),
+    ];
+    for (romaji, kana) in cases {
+        assert_eq!(convert_word(RomajiTable::Nihon, romaji), kana, "{}", romaji);
+    }
+}
+
+#[test]
+fn test_azik_table_words() {
+    let cases = [
+        ("kz", "かん"),
+        ("sk", "しん"),
+        ("kj", "くん"),
+        ("td", "てん"),
+        ("hl", "ほん"),
+        ("kq", "かい"),
+        ("kh", "くう"),
+        ("sw", "せい"),
+        ("kp", "こう"),
+        ("kyp", "きょう"),
+        ("xp", "しょう"),
+        ("ga;kp", "がっこう"),
+        ("nkgd", "にんげん"),
+        ("hq", "はい"),
+        ("ttnoms", "たちのます"),
+        // nk is にん, so ん before k is typed q
+        ("beqkyousimasu", "べんきょうします"),
+        ("sinnbunn", "しんぶん"),
+    ];
+    for (romaji, kana) in cases {
+        assert_eq!(convert_word(RomajiTable::Azik, romaji), kana, "{}", romaji);
+    }
+
+    // Doubled consonants without an AZIK rule still give っ
+    assert_eq!(convert_word(RomajiTable::Azik, "zassi"), "ざっし");
+}
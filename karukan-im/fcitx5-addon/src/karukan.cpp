@@ -18,6 +18,44 @@ constexpr uint32_t kControlMask = 4;  // ControlMask
 constexpr uint32_t kAltMask = 8;      // Mod1Mask
 constexpr uint32_t kSuperMask = 64;   // Mod4Mask
 
+// fcitx text format of a KARUKAN_PREEDIT_* style
+static TextFormatFlags preeditFormat(uint32_t attr) {
+    switch (attr) {
+    case KARUKAN_PREEDIT_UNDERLINE_DOUBLE:
+        return TextFormatFlags(TextFormatFlag::Underline) | TextFormatFlag::Bold;
+    case KARUKAN_PREEDIT_HIGHLIGHT:
+        return TextFormatFlag::HighLight;
+    case KARUKAN_PREEDIT_REVERSE:
+        return TextFormatFlags(TextFormatFlag::HighLight) | TextFormatFlag::Bold;
+    default:
+        return TextFormatFlag::Underline;
+    }
+}
+
+// Append the preedit with the engine's styled ranges; text outside them is underlined
+static void appendStyledPreedit(Text& preedit, ::KarukanEngine* rustEngine,
+                                const std::string& text) {
+    uint32_t pos = 0;
+    uint32_t count = karukan_engine_get_preedit_attribute_count(rustEngine);
+    for (uint32_t i = 0; i < count; ++i) {
+        uint32_t start = 0;
+        uint32_t end = 0;
+        uint32_t attr = 0;
+        if (!karukan_engine_get_preedit_attribute(rustEngine, i, &start, &end, &attr) ||
+            start < pos || end <= start || end > text.size()) {
+            continue;
+        }
+        if (start > pos) {
+            preedit.append(text.substr(pos, start - pos), TextFormatFlag::Underline);
+        }
+        preedit.append(text.substr(start, end - start), preeditFormat(attr));
+        pos = end;
+    }
+    if (pos < text.size()) {
+        preedit.append(text.substr(pos), TextFormatFlag::Underline);
+    }
+}
+
 // --- KarukanCandidateWord ---
 
 KarukanCandidateWord::KarukanCandidateWord(KarukanEngine* engine, Text text, int index,
@@ -49,6 +87,23 @@ void KarukanCandidateList::updateCandidates(::KarukanEngine* rustEngine) {
     uint32_t count = karukan_engine_get_candidate_count(rustEngine);
     uint32_t cursor = karukan_engine_get_candidate_cursor(rustEngine);
 
+    // Match the configured page size; with 10 candidates the 0 key selects the last one.
+    // When expanded (not paged), the whole list is one page and only the first
+    // page_size rows get selection keys.
+    uint32_t keyCount = karukan_engine_get_candidate_page_size(rustEngine);
+    uint32_t pageSize = keyCount;
+    if (!karukan_engine_candidates_paged(rustEngine) && count > 0) {
+        pageSize = count;
+    }
+    if (pageSize > 0 && pageSize != static_cast<uint32_t>(this->pageSize())) {
+        setPageSize(static_cast<int>(pageSize));
+        std::vector<std::string> labels;
+        for (uint32_t i = 1; i <= pageSize; i++) {
+            labels.push_back(i <= keyCount ? std::to_string(i % 10) + ". " : "");
+        }
+        setLabels(labels);
+    }
+
     for (uint32_t i = 0; i < count; i++) {
         const char* text = karukan_engine_get_candidate(rustEngine, i);
         if (text) {
@@ -160,9 +215,9 @@ void KarukanState::keyEvent(KeyEvent& keyEvent) {
     updateUI();
 }
 
-void KarukanState::reset() {
+void KarukanState::reset(uint8_t reason) {
     if (rustEngine_) {
-        karukan_engine_reset(rustEngine_);
+        karukan_reset_with_reason(rustEngine_, reason);
     }
 
     ic_->inputPanel().reset();
@@ -177,6 +232,25 @@ void KarukanState::updateUI() {
 
     auto& inputPanel = ic_->inputPanel();
 
+    // Delete already-committed text (e.g. undo of the last commit) before the commit.
+    // Apps without SurroundingText support get BackSpace/Delete key presses instead.
+    uint32_t deleteBefore = 0;
+    uint32_t deleteAfter = 0;
+    if (karukan_take_delete_surrounding(rustEngine_, &deleteBefore, &deleteAfter)) {
+        if (ic_->capabilityFlags().test(CapabilityFlag::SurroundingText)) {
+            ic_->deleteSurroundingText(-static_cast<int>(deleteBefore), deleteBefore + deleteAfter);
+        } else {
+            for (uint32_t i = 0; i < deleteBefore; i++) {
+                ic_->forwardKey(Key(FcitxKey_BackSpace), false);
+                ic_->forwardKey(Key(FcitxKey_BackSpace), true);
+            }
+            for (uint32_t i = 0; i < deleteAfter; i++) {
+                ic_->forwardKey(Key(FcitxKey_Delete), false);
+                ic_->forwardKey(Key(FcitxKey_Delete), true);
+            }
+        }
+    }
+
     // On commit: send committed text, then reset the input panel to clear
     // preedit/candidates/aux in one shot.
     // New preedit/candidates/aux are re-set below if the engine produced them.
@@ -196,7 +270,7 @@ void KarukanState::updateUI() {
 
         Text preedit;
         if (preeditText && preeditLen > 0) {
-            preedit.append(std::string(preeditText, preeditLen), TextFormatFlag::Underline);
+            appendStyledPreedit(preedit, rustEngine_, std::string(preeditText, preeditLen));
             preedit.setCursor(static_cast<int>(preeditCaret));
         }
 
@@ -261,7 +335,10 @@ void KarukanEngine::reset(const InputMethodEntry& entry, InputContextEvent& even
     auto* ic = event.inputContext();
     auto* state = ic->propertyFor(&factory_);
 
-    state->reset();
+    // Other resets may arrive between activate and the first key; only a
+    // focus change makes the surrounding context stale
+    state->reset(event.type() == EventType::InputContextFocusOut ? KARUKAN_RESET_FOCUS_OUT
+                                                                 : KARUKAN_RESET_SPURIOUS);
 }
 
 void KarukanEngine::activate(const InputMethodEntry& entry, InputContextEvent& event) {
@@ -285,6 +362,18 @@ void KarukanEngine::activate(const InputMethodEntry& entry, InputContextEvent& e
     }
 }
 
+void KarukanEngine::reloadConfig() {
+    // Re-read config.toml in every input context (e.g. after `fcitx5-remote -r`).
+    // Models and the learning cache stay loaded.
+    instance_->inputContextManager().foreach([this](InputContext* ic) {
+        auto* state = ic->propertyFor(&factory_);
+        if (state->rustEngine()) {
+            karukan_reload_settings(state->rustEngine(), nullptr);
+        }
+        return true;
+    });
+}
+
 void KarukanEngine::deactivate(const InputMethodEntry& entry, InputContextEvent& event) {
     FCITX_UNUSED(entry);
 
@@ -50,7 +50,7 @@ public:
     ~KarukanState() override;
 
     void keyEvent(KeyEvent& keyEvent);
-    void reset();
+    void reset(uint8_t reason = KARUKAN_RESET_SPURIOUS);
     void updateUI();
 
     ::KarukanEngine* rustEngine() { return rustEngine_; }
@@ -72,6 +72,7 @@ public:
     void reset(const InputMethodEntry& entry, InputContextEvent& event) override;
     void activate(const InputMethodEntry& entry, InputContextEvent& event) override;
     void deactivate(const InputMethodEntry& entry, InputContextEvent& event) override;
+    void reloadConfig() override;
 
     Instance* instance() { return instance_; }
 
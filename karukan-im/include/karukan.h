@@ -8,6 +8,7 @@
 #ifndef KARUKAN_H
 #define KARUKAN_H
 
+#include <stdbool.h>
 #include <stdint.h>
 
 #ifdef __cplusplus
@@ -31,6 +32,25 @@ KarukanEngine* karukan_engine_new(void);
  */
 int karukan_engine_init(KarukanEngine* engine);
 
+/*
+ * Reload the user dictionary without restarting the engine.
+ * path is a dictionary file or a directory of dictionary files; NULL reloads
+ * the default user dictionary directory. A conversion in progress keeps its
+ * candidates; new lookups use the reloaded dictionary.
+ * Returns 0 on success, -1 on failure (the previous dictionary is kept).
+ */
+int karukan_engine_reload_user_dict(KarukanEngine* engine, const char* path);
+
+/*
+ * Re-read the settings file and apply the settings that can change at runtime
+ * (candidate count, page size, strategy, display, key bindings). Models,
+ * dictionaries, the learning cache and the romaji table stay as loaded; changes
+ * to them are logged and take effect after a restart.
+ * path is a config.toml file; NULL reads the default config file.
+ * Returns 0 on success, -1 on failure (the previous settings are kept).
+ */
+int karukan_reload_settings(KarukanEngine* engine, const char* path);
+
 /*
  * Destroy a Karukan engine instance and free its resources.
  */
@@ -62,6 +82,38 @@ int karukan_engine_process_key(
  */
 void karukan_engine_reset(KarukanEngine* engine);
 
+/* Reset reasons for karukan_reset_with_reason */
+#define KARUKAN_RESET_SPURIOUS 0      /* no real change: surrounding context is kept */
+#define KARUKAN_RESET_FOCUS_OUT 1     /* focus moved away: context is cleared */
+#define KARUKAN_RESET_CURSOR_MOVED 2  /* cursor moved: context is cleared */
+
+/*
+ * Reset the engine state like karukan_engine_reset(), clearing the surrounding
+ * context as well unless the reason is KARUKAN_RESET_SPURIOUS.
+ * Returns 1 if the engine was reset, 0 for an unknown reason or a NULL engine.
+ */
+int karukan_reset_with_reason(KarukanEngine* engine, uint8_t reason);
+
+/* Input mode codes for karukan_set_input_mode / karukan_get_input_mode */
+#define KARUKAN_MODE_HIRAGANA 0       /* hiragana (default) */
+#define KARUKAN_MODE_KATAKANA 1       /* katakana */
+#define KARUKAN_MODE_KATAKANA_LOCK 2  /* katakana lock: Space commits, no conversion */
+#define KARUKAN_MODE_ALPHABET 3       /* direct alphabet input */
+#define KARUKAN_MODE_KANA_DIRECT 4    /* JIS kana keyboard: keys typed as kana */
+
+/*
+ * Set the input mode (KARUKAN_MODE_*), e.g. from a UI menu.
+ * Pending romaji is flushed and leaving katakana keeps the composing text as
+ * katakana. Updated preedit/aux text are available through the usual getters.
+ * Returns 1 if the mode was set, 0 for an unknown mode or a NULL engine.
+ */
+int karukan_set_input_mode(KarukanEngine* engine, uint8_t mode);
+
+/*
+ * Get the current input mode (KARUKAN_MODE_*).
+ */
+uint8_t karukan_get_input_mode(const KarukanEngine* engine);
+
 /*
  * Set the surrounding text context from the editor.
  * This provides the actual text around the cursor for better conversion accuracy.
@@ -104,6 +156,25 @@ uint32_t karukan_engine_get_preedit_len(const KarukanEngine* engine);
  */
 uint32_t karukan_engine_get_preedit_caret(const KarukanEngine* engine);
 
+/* Preedit styles of karukan_engine_get_preedit_attribute */
+#define KARUKAN_PREEDIT_UNDERLINE 0         /* composing text, unselected segments */
+#define KARUKAN_PREEDIT_UNDERLINE_DOUBLE 1  /* double underline */
+#define KARUKAN_PREEDIT_HIGHLIGHT 2         /* selected segment, pending romaji */
+#define KARUKAN_PREEDIT_REVERSE 3           /* prefix shared by all candidates */
+
+/*
+ * Get the number of styled ranges of the preedit.
+ */
+uint32_t karukan_engine_get_preedit_attribute_count(const KarukanEngine* engine);
+
+/*
+ * Get a styled range of the preedit: its byte range [start, end) and its
+ * KARUKAN_PREEDIT_* style (any pointer may be NULL).
+ * Returns false if the index is out of range.
+ */
+bool karukan_engine_get_preedit_attribute(const KarukanEngine* engine, uint32_t index,
+                                          uint32_t* start, uint32_t* end, uint32_t* attr);
+
 /* --- Commit text --- */
 
 /*
@@ -123,6 +194,29 @@ const char* karukan_engine_get_commit(const KarukanEngine* engine);
  */
 uint32_t karukan_engine_get_commit_len(const KarukanEngine* engine);
 
+/* Receives the committed text (valid only during the call) and user_data. */
+typedef void (*KarukanCommitCallback)(const char* text, void* user_data);
+
+/*
+ * Register a callback invoked for each commit, instead of polling with
+ * karukan_engine_has_commit(). Pass NULL to unregister.
+ * The callback runs inside the call that produced the commit (e.g.
+ * karukan_engine_process_key) and must not call back into the engine.
+ * While registered, commits are not reported by karukan_engine_has_commit().
+ */
+void karukan_set_commit_callback(KarukanEngine* engine, KarukanCommitCallback callback,
+                                 void* user_data);
+
+/*
+ * Take the pending request to delete already-committed text (e.g. Ctrl+Z undo).
+ * Returns true and stores the number of characters to delete before/after the
+ * cursor (either pointer may be NULL); the request is cleared.
+ *
+ * Ordering: apply the deletion before the pending commit (and preedit) from
+ * the same key, so the commit never lands inside the deleted text.
+ */
+bool karukan_take_delete_surrounding(KarukanEngine* engine, uint32_t* before, uint32_t* after);
+
 /* --- Candidates --- */
 
 /*
@@ -155,11 +249,69 @@ const char* karukan_engine_get_candidate(const KarukanEngine* engine, uint32_t i
  */
 const char* karukan_engine_get_candidate_annotation(const KarukanEngine* engine, uint32_t index);
 
+/* Candidate source codes returned by karukan_engine_get_candidate_source */
+#define KARUKAN_SOURCE_UNKNOWN 0          /* not built by the conversion engine */
+#define KARUKAN_SOURCE_USER_DICTIONARY 1  /* user dictionary */
+#define KARUKAN_SOURCE_LEARNING 2         /* learning cache */
+#define KARUKAN_SOURCE_PHRASE 3           /* phrase dictionary */
+#define KARUKAN_SOURCE_MODEL 4            /* model inference */
+#define KARUKAN_SOURCE_DICTIONARY 5       /* system dictionary */
+#define KARUKAN_SOURCE_PREDICTION 6       /* completion of a longer reading */
+#define KARUKAN_SOURCE_SYMBOL 7           /* symbol palette */
+#define KARUKAN_SOURCE_DATE 8             /* date/time macro */
+#define KARUKAN_SOURCE_NUMBER 9           /* numeric form of a digit reading */
+#define KARUKAN_SOURCE_FALLBACK 10        /* hiragana/katakana fallback */
+#define KARUKAN_SOURCE_LOANWORD 11        /* katakana form of a loanword reading */
+
+/*
+ * Get a candidate's source (KARUKAN_SOURCE_*) by index, for styling
+ * candidates by origin. Returns KARUKAN_SOURCE_UNKNOWN if index is out of range.
+ */
+uint8_t karukan_engine_get_candidate_source(const KarukanEngine* engine, uint32_t index);
+
 /*
  * Get the current candidate cursor position (selected index).
  */
 uint32_t karukan_engine_get_candidate_cursor(const KarukanEngine* engine);
 
+/*
+ * Get the current candidate page (0-indexed).
+ */
+uint32_t karukan_engine_get_candidate_page(const KarukanEngine* engine);
+
+/*
+ * Get the total number of candidate pages.
+ */
+uint32_t karukan_engine_get_candidate_page_count(const KarukanEngine* engine);
+
+/*
+ * Get the number of candidates per page (conversion.page_size, 1-10).
+ * With 10, the selection keys are 1-9 and 0.
+ */
+uint32_t karukan_engine_get_candidate_page_size(const KarukanEngine* engine);
+
+/*
+ * Check whether the candidates are a single page (true, the default) or the
+ * whole list (false, toggled with Ctrl+Tab) to be shown in a scrollable window.
+ * When not paged, the candidate cursor is the index in the whole list.
+ */
+bool karukan_engine_candidates_paged(const KarukanEngine* engine);
+
+/*
+ * Go to the next / previous candidate page (wrapping around), e.g. from
+ * the candidate window's page buttons.
+ * Returns 1 if the page changed, 0 if not converting.
+ * After this call, check the has_* functions as with process_key.
+ */
+int karukan_engine_next_page(KarukanEngine* engine);
+int karukan_engine_prev_page(KarukanEngine* engine);
+
+/*
+ * Go to a candidate page (0-indexed).
+ * Returns 1 if the page changed, 0 if not converting or page is out of range.
+ */
+int karukan_engine_goto_page(KarukanEngine* engine, uint32_t page);
+
 /* --- Auxiliary text (reading hint) --- */
 
 /*
@@ -191,6 +343,36 @@ uint64_t karukan_engine_get_last_conversion_ms(const KarukanEngine* engine);
  */
 uint64_t karukan_engine_get_last_process_key_ms(const KarukanEngine* engine);
 
+/* --- Metrics --- */
+
+/* Strategy codes of KarukanMetrics.strategy */
+#define KARUKAN_STRATEGY_NONE 0              /* no model conversion has run yet */
+#define KARUKAN_STRATEGY_MAIN_MODEL_ONLY 1   /* main model greedy */
+#define KARUKAN_STRATEGY_LIGHT_MODEL_ONLY 2  /* light model greedy */
+#define KARUKAN_STRATEGY_PARALLEL_BEAM 3     /* main greedy + light beam in parallel */
+#define KARUKAN_STRATEGY_MAIN_MODEL_BEAM 4   /* main model beam search */
+#define KARUKAN_STRATEGY_DRAFT_THEN_RERANK 5 /* light beam reranked by main model */
+
+/* Metrics of the last processed key, for a status display */
+typedef struct KarukanMetrics {
+    uint64_t conversion_ms;    /* last conversion time (inference only) */
+    uint64_t process_key_ms;   /* last process_key time (end-to-end) */
+    uint32_t candidate_count;  /* candidates in the candidate window (0 when hidden) */
+    uint32_t strategy;         /* KARUKAN_STRATEGY_* */
+} KarukanMetrics;
+
+/*
+ * Get the name of the loaded model(s), e.g. "main+light" ("unknown" before init).
+ * Returns a pointer to a null-terminated UTF-8 string (valid until the next call).
+ */
+const char* karukan_get_model_name(KarukanEngine* engine);
+
+/*
+ * Fill metrics with the metrics of the last processed key.
+ * Returns false if engine or metrics is NULL.
+ */
+bool karukan_get_last_metrics(const KarukanEngine* engine, KarukanMetrics* metrics);
+
 /* --- Learning cache --- */
 
 /*
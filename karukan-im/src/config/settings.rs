@@ -3,24 +3,186 @@
 //! Manages user-configurable settings for the IME.
 //! Default values are defined in `config/default.toml`.
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use directories::ProjectDirs;
+use karukan_engine::{LearningScoreParams, PunctuationWidth, RomajiTable};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
+
+use crate::core::keybindings::KeyAction;
+use crate::core::macros::JST_OFFSET_MINUTES;
 
 /// Default configuration TOML embedded from config/default.toml
 const DEFAULT_CONFIG_TOML: &str = include_str!("../../config/default.toml");
 
+/// Smallest non-zero `conversion.max_latency_ms`; below this every main model
+/// conversion would count as too slow
+const MIN_LATENCY_MS: u64 = 10;
+
+/// Errors from loading or validating the settings file
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("invalid TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[error("unknown key `{key}`")]
+    UnknownKey { key: String },
+
+    #[error("unknown value `{value}` (expected {expected})")]
+    UnknownValue { value: String, expected: String },
+
+    #[error("{key} = {value} is out of range (expected {expected})")]
+    OutOfRange {
+        key: &'static str,
+        value: u64,
+        expected: String,
+    },
+
+    #[error("{key} = {value} is out of range (expected {expected})")]
+    FloatOutOfRange {
+        key: &'static str,
+        value: f64,
+        expected: &'static str,
+    },
+}
+
+/// Names serde accepts for `T`: the fields of a derived struct or the
+/// variants of a derived enum (after `rename_all`)
+fn serde_names<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    use serde::de::{self, Visitor};
+
+    /// Deserializer that records the names it is asked for and then fails
+    struct NameCollector<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for NameCollector<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct or enum"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("names collected"))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            variants: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = variants;
+            Err(de::Error::custom("names collected"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map identifier ignored_any
+        }
+    }
+
+    let mut names: &'static [&'static str] = &[];
+    let _ = T::deserialize(NameCollector(&mut names));
+    names
+}
+
+/// Reject keys of the `value` table that are not fields of `T`
+fn check_keys<'de, T: Deserialize<'de>>(value: &toml::Value) -> Result<(), SettingsError> {
+    let Some(table) = value.as_table() else {
+        return Ok(());
+    };
+    let fields = serde_names::<T>();
+    match table.keys().find(|key| !fields.contains(&key.as_str())) {
+        Some(key) => Err(SettingsError::UnknownKey { key: key.clone() }),
+        None => Ok(()),
+    }
+}
+
+/// Reject a string `value` that is not a variant of the enum `T`
+fn check_variant<'de, T: Deserialize<'de>>(
+    value: Option<&toml::Value>,
+) -> Result<(), SettingsError> {
+    let Some(value) = value.and_then(toml::Value::as_str) else {
+        return Ok(());
+    };
+    let variants = serde_names::<T>();
+    if variants.contains(&value) {
+        return Ok(());
+    }
+    let expected = variants
+        .iter()
+        .map(|v| format!("`{}`", v))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(SettingsError::UnknownValue {
+        value: value.to_string(),
+        expected: format!("one of {}", expected),
+    })
+}
+
+/// Check the user's TOML for keys and enum values [`Settings`] does not know,
+/// so they get their own error instead of a generic TOML one
+fn check_names(user: &toml::Value) -> Result<(), SettingsError> {
+    check_keys::<Settings>(user)?;
+    let section = |name: &str| user.get(name).filter(|v| v.is_table());
+    if let Some(conv) = section("conversion") {
+        check_keys::<ConversionSettings>(conv)?;
+        check_variant::<StrategyMode>(conv.get("strategy"))?;
+        check_variant::<DictionaryMode>(conv.get("dictionary_mode"))?;
+    }
+    if let Some(learning) = section("learning") {
+        check_keys::<LearningSettings>(learning)?;
+    }
+    if let Some(romaji) = section("romaji") {
+        check_keys::<RomajiSettings>(romaji)?;
+        check_variant::<PunctuationWidth>(romaji.get("punctuation_width"))?;
+        check_variant::<RomajiTable>(romaji.get("table"))?;
+    }
+    if let Some(display) = section("display") {
+        check_keys::<DisplaySettings>(display)?;
+        if let Some(labels) = display.get("labels") {
+            check_keys::<CandidateLabels>(labels)?;
+        }
+    }
+    if let Some(keybindings) = section("keybindings") {
+        check_keys::<KeyBindingSettings>(keybindings)?;
+        check_variant::<KeyBindingScheme>(keybindings.get("scheme"))?;
+    }
+    Ok(())
+}
+
 /// Configuration settings for the IME
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Settings {
     /// Conversion settings
     pub conversion: ConversionSettings,
     /// Learning cache settings
     pub learning: LearningSettings,
+    /// Romaji input settings
+    pub romaji: RomajiSettings,
+    /// Display settings
+    pub display: DisplaySettings,
+    /// Key binding settings
+    #[serde(default)]
+    pub keybindings: KeyBindingSettings,
 }
 
 /// Conversion strategy mode
@@ -34,22 +196,62 @@ pub enum StrategyMode {
     Light,
     /// Main: use main model only (no light model loaded)
     Main,
+    /// Rerank: light model beam search on Space, reordered by the main model's NLL
+    Rerank,
+}
+
+/// How the files in `conversion.dictionaries` are combined
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DictionaryMode {
+    /// Merge all files into a single dictionary (earlier files have higher priority)
+    #[default]
+    Merge,
+    /// Keep each file as a separate dictionary, searched in list order
+    Layer,
+}
+
+/// Preset key binding scheme
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyBindingScheme {
+    /// Karukan defaults (Ctrl+N/P, Ctrl+A/E/B/F, Ctrl+K)
+    #[default]
+    Default,
+    /// Default plus Ctrl+M/J commit, Ctrl+G cancel, Ctrl+H backspace
+    Emacs,
+    /// ATOK-style (Ctrl+E/X candidates, Ctrl+S/D caret, Ctrl+I katakana)
+    Atok,
+    /// macOS Kotoeri-style (emacs motion, Ctrl+H backspace)
+    Kotoeri,
 }
 
 /// Conversion-related settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ConversionSettings {
     /// Conversion strategy mode (adaptive, light, main)
     #[serde(default)]
     pub strategy: StrategyMode,
-    /// Number of candidates to show on Space conversion
+    /// Number of model candidates to request on Space conversion (at most 20;
+    /// shown `page_size` at a time)
     pub num_candidates: usize,
     /// Use surrounding text (text left of cursor) as context for conversion
     pub use_context: bool,
     /// Maximum number of surrounding text characters passed to the conversion API
     pub max_context_length: usize,
+    /// NFKC-normalize surrounding text before using it as context
+    pub normalize_context: bool,
     /// Path to dictionary binary file (optional, defaults to data_dir/dict.bin)
     pub dict_path: Option<String>,
+    /// Path to phrase dictionary file (optional, defaults to data_dir/phrases.tsv)
+    pub phrase_dict_path: Option<String>,
+    /// Extra dictionary files (KRKN binary, Mozc TSV, or Sudachi CSV) loaded at startup
+    #[serde(default)]
+    pub dictionaries: Vec<String>,
+    /// How extra dictionaries are combined (merge, layer)
+    #[serde(default)]
+    pub dictionary_mode: DictionaryMode,
     /// Model variant id (optional, defaults to registry default)
     pub model: Option<String>,
     /// Beam search model variant id (used on Space conversion, default model if unset)
@@ -60,18 +262,177 @@ pub struct ConversionSettings {
     pub beam_width: usize,
     /// Maximum acceptable latency in milliseconds for auto-suggest (0 = disabled)
     /// When a main model conversion exceeds this, the engine adaptively switches to light_model
+    /// Beam search also stops at this limit with the candidates found so far
     pub max_latency_ms: u64,
     /// Number of threads for llama.cpp inference (0 = all cores, llama.cpp default)
     pub n_threads: u32,
+    /// Offer katakana first for loanword-like readings (e.g. らーめん) typed in hiragana mode
+    pub loanword_katakana: bool,
+    /// Offer dictionary completions of the reading typed so far while composing
+    #[serde(default)]
+    pub predictive: bool,
+    /// Number of model conversion results cached for the session (0 = disabled)
+    #[serde(default = "default_cache_size")]
+    pub cache_size: usize,
+    /// Reorder non-learning conversion candidates by model NLL (slower)
+    #[serde(default)]
+    pub rerank_with_model: bool,
+    /// Split the reading into dictionary words on Space, one segment each
+    #[serde(default)]
+    pub auto_segment: bool,
+    /// Number of candidates per page (1-10; 10 adds the 0 key for the 10th candidate)
+    pub page_size: usize,
+    /// Skip live conversion for keys typed within this many ms of the previous one (0 = disabled)
+    #[serde(default)]
+    pub debounce_ms: u64,
+    /// Local time offset from UTC in minutes for the date/time candidates
+    /// (きょう, いま); defaults to Japan Standard Time (540)
+    #[serde(default = "default_utc_offset_minutes")]
+    pub utc_offset_minutes: i32,
+}
+
+fn default_cache_size() -> usize {
+    256
+}
+
+fn default_utc_offset_minutes() -> i32 {
+    JST_OFFSET_MINUTES
 }
 
 /// Learning cache settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LearningSettings {
     /// Whether learning is enabled
     pub enabled: bool,
     /// Maximum number of total entries in the learning cache
     pub max_entries: usize,
+    /// Forget entries not selected for this many days (0 = disabled)
+    #[serde(default)]
+    pub max_age_days: u64,
+    /// Weight of how recently an entry was selected in its score
+    #[serde(default = "default_recency_weight")]
+    pub recency_weight: f64,
+    /// Weight of how often an entry was selected in its score
+    #[serde(default = "default_frequency_weight")]
+    pub frequency_weight: f64,
+    /// Days after which the recency weight of an entry halves (must be positive)
+    #[serde(default = "default_half_life_days")]
+    pub half_life_days: f64,
+}
+
+impl LearningSettings {
+    /// Scoring weights for the learning cache
+    pub fn score_params(&self) -> LearningScoreParams {
+        LearningScoreParams {
+            recency_weight: self.recency_weight,
+            frequency_weight: self.frequency_weight,
+            half_life_days: self.half_life_days,
+        }
+    }
+}
+
+fn default_recency_weight() -> f64 {
+    LearningScoreParams::default().recency_weight
+}
+
+fn default_frequency_weight() -> f64 {
+    LearningScoreParams::default().frequency_weight
+}
+
+fn default_half_life_days() -> f64 {
+    LearningScoreParams::default().half_life_days
+}
+
+/// Romaji input settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RomajiSettings {
+    /// Convert a lone trailing "n" to ん when the romaji buffer is flushed (commit/convert)
+    pub trailing_n_to_hiragana: bool,
+    /// Width of `?` / `!` output (full, half)
+    #[serde(default)]
+    pub punctuation_width: PunctuationWidth,
+    /// Romanization table (hepburn, kunrei, nihon, azik)
+    #[serde(default)]
+    pub table: RomajiTable,
+    /// Custom romaji rules file (`sequence\toutput` TSV), layered over the table
+    pub custom_romaji: Option<String>,
+    /// In katakana mode, write a repeated vowel as ー (raamen → ラーメン, same as ra-men)
+    pub katakana_long_vowel: bool,
+}
+
+/// Key binding settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBindingSettings {
+    /// Preset scheme (default, emacs, atok, kotoeri)
+    #[serde(default)]
+    pub scheme: KeyBindingScheme,
+    /// Per-action overrides (e.g. `next_candidate = ["Ctrl+J"]`), replacing the scheme's keys
+    #[serde(default)]
+    pub custom: BTreeMap<KeyAction, Vec<String>>,
+}
+
+/// Display settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplaySettings {
+    /// Show the typed romaji after the kana in the preedit (for learners)
+    pub show_romaji: bool,
+    /// Start the conversion aux text with the reading, e.g. "きょう [1/3]" (for screen readers)
+    #[serde(default)]
+    pub aux_show_reading: bool,
+    /// Show the top candidate inline on the first Space and open the candidate
+    /// window on the second
+    #[serde(default)]
+    pub candidate_preview: bool,
+    /// Annotation shown next to each candidate, per source
+    #[serde(default)]
+    pub labels: CandidateLabels,
+}
+
+/// Candidate annotation labels per candidate source (an empty label shows no annotation)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CandidateLabels {
+    /// User dictionary
+    pub user_dictionary: String,
+    /// Learning cache
+    pub learning: String,
+    /// Phrase dictionary
+    pub phrase: String,
+    /// Model inference
+    pub model: String,
+    /// System and extra dictionaries
+    pub dictionary: String,
+    /// Predictive completions
+    pub prediction: String,
+    /// Symbol palette
+    pub symbol: String,
+    /// Date/time macros
+    pub date: String,
+    /// Numeric forms
+    pub number: String,
+    /// Katakana forms of loanword readings
+    pub loanword: String,
+}
+
+impl Default for CandidateLabels {
+    fn default() -> Self {
+        Self {
+            user_dictionary: "\u{1F464} \u{30E6}\u{30FC}\u{30B6}\u{30FC}".to_string(), // 👤 ユーザー
+            learning: "\u{1F4DD} \u{5B66}\u{7FD2}".to_string(),                        // 📝 学習
+            phrase: "\u{1F4AC} \u{5B9A}\u{578B}\u{6587}".to_string(),                  // 💬 定型文
+            model: "\u{1F916} AI".to_string(),                                         // 🤖 AI
+            dictionary: "\u{1F4DA} \u{8F9E}\u{66F8}".to_string(),                      // 📚 辞書
+            prediction: "\u{1F52E} \u{4E88}\u{6E2C}".to_string(),                      // 🔮 予測
+            symbol: "\u{1F523} \u{8A18}\u{53F7}".to_string(),                          // 🔣 記号
+            date: "\u{1F4C5} \u{65E5}\u{4ED8}".to_string(),                            // 📅 日付
+            number: "\u{1F522} \u{6570}\u{5B57}".to_string(),                          // 🔢 数字
+            loanword: "\u{1F524} \u{30AB}\u{30BF}\u{30AB}\u{30CA}".to_string(), // 🔤 カタカナ
+        }
+    }
 }
 
 impl Default for Settings {
@@ -99,12 +460,21 @@ fn merge_toml(base: &mut toml::Value, overlay: &toml::Value) {
 }
 
 /// Parse user TOML content merged on top of default.toml.
-fn parse_with_defaults(user_content: &str) -> Result<Settings> {
+fn parse_with_defaults(user_content: &str) -> Result<Settings, SettingsError> {
     let mut base: toml::Value = toml::from_str(DEFAULT_CONFIG_TOML)?;
     let user: toml::Value = toml::from_str(user_content)?;
+    check_names(&user)?;
     merge_toml(&mut base, &user);
-    let settings: Settings = base.try_into()?;
-    Ok(settings)
+    Ok(base.try_into()?)
+}
+
+/// Read and parse a settings file merged on top of default.toml.
+fn read_with_defaults(path: &Path) -> Result<Settings, SettingsError> {
+    let content = fs::read_to_string(path).map_err(|source| SettingsError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    parse_with_defaults(&content)
 }
 
 /// Get the project directories for karukan-im.
@@ -136,6 +506,13 @@ impl Settings {
         Self::data_dir().map(|dir| dir.join("user_dicts"))
     }
 
+    /// Get the phrase dictionary file path.
+    ///
+    /// Default: `~/.local/share/karukan-im/phrases.tsv`
+    pub fn phrase_dict_file() -> Option<PathBuf> {
+        Self::data_dir().map(|dir| dir.join("phrases.tsv"))
+    }
+
     /// Get the learning cache file path.
     ///
     /// Default: `~/.local/share/karukan-im/learning.tsv`
@@ -157,14 +534,148 @@ impl Settings {
         }
 
         debug!("Loading config from {:?}", config_file);
-        let content = fs::read_to_string(&config_file)?;
-        parse_with_defaults(&content)
+        Ok(read_with_defaults(&config_file)?)
     }
 
     /// Load settings from a specific file, merged on top of defaults.
     pub fn load_from(path: &Path) -> Result<Self> {
-        let content = fs::read_to_string(path)?;
-        parse_with_defaults(&content)
+        Ok(read_with_defaults(path)?)
+    }
+
+    /// Load settings from the default configuration file and check that every
+    /// value is in range. Unlike `load`, the error says which key is wrong.
+    pub fn load_validated() -> Result<Self, SettingsError> {
+        let Some(config_file) = Self::config_file().filter(|f| f.exists()) else {
+            return Ok(Self::default());
+        };
+        Self::load_validated_from(&config_file)
+    }
+
+    /// Load settings from a specific file and check that every value is in range.
+    pub fn load_validated_from(path: &Path) -> Result<Self, SettingsError> {
+        let settings = read_with_defaults(path)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Check values that parse but cannot work (zero candidates, a latency
+    /// budget too small for any model, ...)
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let conv = &self.conversion;
+        let checks = [
+            (
+                "conversion.num_candidates",
+                conv.num_candidates as u64,
+                conv.num_candidates >= 1,
+                "at least 1".to_string(),
+            ),
+            (
+                "conversion.beam_width",
+                conv.beam_width as u64,
+                conv.beam_width >= 1,
+                "at least 1".to_string(),
+            ),
+            (
+                "conversion.max_latency_ms",
+                conv.max_latency_ms,
+                conv.max_latency_ms == 0 || conv.max_latency_ms >= MIN_LATENCY_MS,
+                format!("0 to disable, or at least {}", MIN_LATENCY_MS),
+            ),
+            (
+                "conversion.page_size",
+                conv.page_size as u64,
+                (1..=10).contains(&conv.page_size),
+                "1 to 10".to_string(),
+            ),
+        ];
+        if let Some((key, value, _, expected)) = checks.into_iter().find(|&(_, _, ok, _)| !ok) {
+            return Err(SettingsError::OutOfRange {
+                key,
+                value,
+                expected,
+            });
+        }
+        // NaN fails these too; it would make every learned score NaN
+        let learning = &self.learning;
+        let float_checks = [
+            (
+                "learning.half_life_days",
+                learning.half_life_days,
+                learning.half_life_days > 0.0,
+                "greater than 0",
+            ),
+            (
+                "learning.recency_weight",
+                learning.recency_weight,
+                learning.recency_weight.is_finite() && learning.recency_weight >= 0.0,
+                "a finite number, 0 or greater",
+            ),
+            (
+                "learning.frequency_weight",
+                learning.frequency_weight,
+                learning.frequency_weight.is_finite() && learning.frequency_weight >= 0.0,
+                "a finite number, 0 or greater",
+            ),
+        ];
+        if let Some((key, value, _, expected)) = float_checks.into_iter().find(|&(_, _, ok, _)| !ok)
+        {
+            return Err(SettingsError::FloatOutOfRange {
+                key,
+                value,
+                expected,
+            });
+        }
+        Ok(())
+    }
+
+    /// Override loaded settings from `KARUKAN_MODEL`, `KARUKAN_NUM_CANDIDATES`,
+    /// `KARUKAN_BEAM_WIDTH` and `KARUKAN_STRATEGY`, for deployments without a
+    /// config file. Values that do not parse or validate are logged and ignored.
+    pub fn apply_env_overrides(&mut self) {
+        self.env_override("KARUKAN_MODEL", |s, value| {
+            s.conversion.model = Some(value.to_string());
+            Ok(())
+        });
+        self.env_override("KARUKAN_NUM_CANDIDATES", |s, value| {
+            s.conversion.num_candidates = value.parse().map_err(|e| format!("{}", e))?;
+            Ok(())
+        });
+        self.env_override("KARUKAN_BEAM_WIDTH", |s, value| {
+            s.conversion.beam_width = value.parse().map_err(|e| format!("{}", e))?;
+            Ok(())
+        });
+        self.env_override("KARUKAN_STRATEGY", |s, value| {
+            let value = toml::Value::String(value.to_string());
+            check_variant::<StrategyMode>(Some(&value)).map_err(|e| e.to_string())?;
+            s.conversion.strategy = value.try_into().map_err(|e| format!("{}", e))?;
+            Ok(())
+        });
+    }
+
+    /// Apply one environment override if the variable is set and the result validates
+    fn env_override(
+        &mut self,
+        name: &str,
+        apply: impl FnOnce(&mut Settings, &str) -> Result<(), String>,
+    ) {
+        let Ok(raw) = std::env::var(name) else {
+            return;
+        };
+        let value = raw.trim();
+        if value.is_empty() {
+            warn!("Ignoring empty {}", name);
+            return;
+        }
+        let mut overridden = self.clone();
+        match apply(&mut overridden, value)
+            .and_then(|()| overridden.validate().map_err(|e| e.to_string()))
+        {
+            Ok(()) => {
+                info!("{}={} overrides the config file", name, value);
+                *self = overridden;
+            }
+            Err(e) => warn!("Ignoring {}={:?}: {}", name, value, e),
+        }
     }
 
     /// Save settings to the default configuration file
@@ -208,6 +719,20 @@ mod tests {
         assert_eq!(settings.conversion.num_candidates, 9);
         assert!(settings.conversion.use_context);
         assert_eq!(settings.conversion.max_context_length, 20);
+        assert!(settings.romaji.trailing_n_to_hiragana);
+        assert!(settings.romaji.katakana_long_vowel);
+        assert!(!settings.display.show_romaji);
+        assert!(!settings.display.aux_show_reading);
+        assert!(!settings.display.candidate_preview);
+        assert!(settings.conversion.loanword_katakana);
+        assert!(!settings.conversion.predictive);
+        assert_eq!(settings.conversion.utc_offset_minutes, JST_OFFSET_MINUTES);
+        assert_eq!(settings.conversion.cache_size, 256);
+        assert!(!settings.conversion.rerank_with_model);
+        assert_eq!(settings.conversion.page_size, 9);
+        assert_eq!(settings.keybindings.scheme, KeyBindingScheme::Default);
+        assert!(settings.keybindings.custom.is_empty());
+        assert_eq!(settings.learning.max_age_days, 0);
     }
 
     #[test]
@@ -287,6 +812,30 @@ num_candidates = 5
         assert_eq!(settings.conversion.strategy, StrategyMode::Adaptive);
     }
 
+    #[test]
+    fn test_keybindings_scheme_and_custom() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[keybindings]
+scheme = "emacs"
+
+[keybindings.custom]
+next_candidate = ["Ctrl+J"]
+"#
+        )
+        .unwrap();
+
+        let path = file.path().to_path_buf();
+        let settings = Settings::load_from(&path).unwrap();
+        assert_eq!(settings.keybindings.scheme, KeyBindingScheme::Emacs);
+        assert_eq!(
+            settings.keybindings.custom[&KeyAction::NextCandidate],
+            vec!["Ctrl+J".to_string()]
+        );
+    }
+
     #[test]
     fn test_strategy_light() {
         let mut file = NamedTempFile::new().unwrap();
@@ -320,4 +869,305 @@ strategy = "main"
         let settings = Settings::load_from(&path).unwrap();
         assert_eq!(settings.conversion.strategy, StrategyMode::Main);
     }
+
+    #[test]
+    fn test_strategy_rerank() {
+        let settings = validated("[conversion]\nstrategy = \"rerank\"\n").unwrap();
+        assert_eq!(settings.conversion.strategy, StrategyMode::Rerank);
+    }
+
+    #[test]
+    fn test_candidate_labels_partial_override() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[display.labels]
+model = "AI"
+learning = ""
+"#
+        )
+        .unwrap();
+
+        let path = file.path().to_path_buf();
+        let labels = Settings::load_from(&path).unwrap().display.labels;
+        assert_eq!(labels.model, "AI");
+        assert_eq!(labels.learning, "");
+        // Unset sources keep the default emoji label
+        assert_eq!(labels.dictionary, CandidateLabels::default().dictionary);
+    }
+
+    #[test]
+    fn test_romaji_trailing_n_disabled() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[romaji]
+trailing_n_to_hiragana = false
+"#
+        )
+        .unwrap();
+
+        let path = file.path().to_path_buf();
+        let settings = Settings::load_from(&path).unwrap();
+        assert!(!settings.romaji.trailing_n_to_hiragana);
+    }
+
+    #[test]
+    fn test_romaji_punctuation_width() {
+        assert_eq!(
+            Settings::default().romaji.punctuation_width,
+            PunctuationWidth::Full
+        );
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[romaji]
+punctuation_width = "half"
+"#
+        )
+        .unwrap();
+
+        let path = file.path().to_path_buf();
+        let settings = Settings::load_from(&path).unwrap();
+        assert_eq!(settings.romaji.punctuation_width, PunctuationWidth::Half);
+    }
+
+    #[test]
+    fn test_romaji_table() {
+        assert_eq!(Settings::default().romaji.table, RomajiTable::Hepburn);
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[romaji]
+table = "nihon"
+"#
+        )
+        .unwrap();
+
+        let path = file.path().to_path_buf();
+        let settings = Settings::load_from(&path).unwrap();
+        assert_eq!(settings.romaji.table, RomajiTable::Nihon);
+    }
+
+    #[test]
+    fn test_extra_dictionaries() {
+        let settings = Settings::default();
+        assert!(settings.conversion.dictionaries.is_empty());
+        assert_eq!(settings.conversion.dictionary_mode, DictionaryMode::Merge);
+
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            r#"
+[conversion]
+dictionaries = ["/tmp/a.tsv", "/tmp/b.krkn"]
+dictionary_mode = "layer"
+"#
+        )
+        .unwrap();
+
+        let path = file.path().to_path_buf();
+        let settings = Settings::load_from(&path).unwrap();
+        assert_eq!(
+            settings.conversion.dictionaries,
+            vec!["/tmp/a.tsv", "/tmp/b.krkn"]
+        );
+        assert_eq!(settings.conversion.dictionary_mode, DictionaryMode::Layer);
+    }
+
+    fn validated(content: &str) -> Result<Settings, SettingsError> {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{}", content).unwrap();
+        Settings::load_validated_from(file.path())
+    }
+
+    #[test]
+    fn test_validated_defaults() {
+        assert!(Settings::default().validate().is_ok());
+        assert!(validated("[conversion]\nnum_candidates = 5\n").is_ok());
+    }
+
+    #[test]
+    fn test_validated_zero_beam_width() {
+        let err = validated("[conversion]\nbeam_width = 0\n").unwrap_err();
+        assert!(matches!(
+            err,
+            SettingsError::OutOfRange {
+                key: "conversion.beam_width",
+                value: 0,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_validated_zero_num_candidates() {
+        let err = validated("[conversion]\nnum_candidates = 0\n").unwrap_err();
+        assert!(matches!(
+            err,
+            SettingsError::OutOfRange {
+                key: "conversion.num_candidates",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_validated_tiny_max_latency() {
+        let err = validated("[conversion]\nmax_latency_ms = 1\n").unwrap_err();
+        assert!(matches!(
+            err,
+            SettingsError::OutOfRange {
+                key: "conversion.max_latency_ms",
+                value: 1,
+                ..
+            }
+        ));
+        assert!(
+            err.to_string()
+                .contains(&format!("at least {}", MIN_LATENCY_MS))
+        );
+        // 0 disables the latency check
+        assert!(validated("[conversion]\nmax_latency_ms = 0\n").is_ok());
+    }
+
+    #[test]
+    fn test_validated_page_size_range() {
+        let err = validated("[conversion]\npage_size = 11\n").unwrap_err();
+        assert!(matches!(
+            err,
+            SettingsError::OutOfRange {
+                key: "conversion.page_size",
+                value: 11,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_validated_half_life_days() {
+        let settings = validated("[learning]\nhalf_life_days = 14.0\n").unwrap();
+        assert_eq!(settings.learning.score_params().half_life_days, 14.0);
+
+        for value in ["0.0", "-3.0", "nan"] {
+            let err = validated(&format!("[learning]\nhalf_life_days = {}\n", value)).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    SettingsError::FloatOutOfRange {
+                        key: "learning.half_life_days",
+                        ..
+                    }
+                ),
+                "{}: {:?}",
+                value,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_validated_recency_weight() {
+        let settings = validated("[learning]\nrecency_weight = 0.0\n").unwrap();
+        assert_eq!(settings.learning.score_params().recency_weight, 0.0);
+
+        for value in ["-1.0", "nan", "inf"] {
+            let err = validated(&format!("[learning]\nrecency_weight = {}\n", value)).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    SettingsError::FloatOutOfRange {
+                        key: "learning.recency_weight",
+                        ..
+                    }
+                ),
+                "{}: {:?}",
+                value,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_validated_frequency_weight() {
+        let settings = validated("[learning]\nfrequency_weight = 2.5\n").unwrap();
+        assert_eq!(settings.learning.score_params().frequency_weight, 2.5);
+
+        for value in ["-0.5", "nan", "-inf"] {
+            let err =
+                validated(&format!("[learning]\nfrequency_weight = {}\n", value)).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    SettingsError::FloatOutOfRange {
+                        key: "learning.frequency_weight",
+                        ..
+                    }
+                ),
+                "{}: {:?}",
+                value,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_validated_unknown_strategy() {
+        let err = validated("[conversion]\nstrategy = \"fast\"\n").unwrap_err();
+        match err {
+            SettingsError::UnknownValue { value, expected } => {
+                assert_eq!(value, "fast");
+                assert!(expected.contains("adaptive"), "{}", expected);
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validated_unknown_romaji_table() {
+        let err = validated("[romaji]\ntable = \"wapuro\"\n").unwrap_err();
+        match err {
+            SettingsError::UnknownValue { value, expected } => {
+                assert_eq!(value, "wapuro");
+                assert_eq!(expected, "one of `hepburn`, `kunrei`, `nihon`, `azik`");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validated_unknown_label_key() {
+        let err = validated("[display.labels]\nmodle = \"AI\"\n").unwrap_err();
+        assert!(matches!(err, SettingsError::UnknownKey { key } if key == "modle"));
+    }
+
+    #[test]
+    fn test_validated_unknown_key() {
+        let err = validated("[conversion]\nnum_candidate = 5\n").unwrap_err();
+        match err {
+            SettingsError::UnknownKey { key } => assert_eq!(key, "num_candidate"),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        let err = validated("[dispaly]\nshow_romaji = true\n").unwrap_err();
+        assert!(matches!(err, SettingsError::UnknownKey { key } if key == "dispaly"));
+    }
+
+    #[test]
+    fn test_validated_malformed_toml() {
+        let err = validated("[conversion\n").unwrap_err();
+        assert!(matches!(err, SettingsError::Toml(_)));
+    }
+
+    #[test]
+    fn test_validated_missing_file() {
+        let err =
+            Settings::load_validated_from(Path::new("/nonexistent/karukan.toml")).unwrap_err();
+        assert!(matches!(err, SettingsError::Read { .. }));
+    }
 }
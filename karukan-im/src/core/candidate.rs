@@ -2,6 +2,33 @@
 //!
 //! Handles the list of conversion candidates with pagination support.
 
+/// Source of a conversion candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateSource {
+    /// User dictionary lookup
+    UserDictionary,
+    /// Learning cache (user history)
+    Learning,
+    /// Phrase dictionary (reading → long phrase expansion)
+    Phrase,
+    /// Model inference result
+    Model,
+    /// System dictionary lookup
+    Dictionary,
+    /// Dictionary completion of a longer reading (predictive conversion)
+    Prediction,
+    /// Built-in symbol palette (keyword reading → symbols)
+    Symbol,
+    /// Date/time macro (きょう → today's date)
+    Date,
+    /// Numeric forms of a digit reading (1000 → 千, １０００, ...)
+    Number,
+    /// Katakana form of a loanword reading typed in hiragana mode
+    Loanword,
+    /// Hiragana/katakana fallback
+    Fallback,
+}
+
 /// A single conversion candidate
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Candidate {
@@ -13,6 +40,8 @@ pub struct Candidate {
     pub annotation: Option<String>,
     /// Unique index from the conversion engine
     pub index: usize,
+    /// Where the candidate came from (None for candidates not built by the engine)
+    pub source: Option<CandidateSource>,
 }
 
 impl Candidate {
@@ -22,6 +51,7 @@ impl Candidate {
             reading: None,
             annotation: None,
             index: 0,
+            source: None,
         }
     }
 
@@ -31,6 +61,7 @@ impl Candidate {
             reading: Some(reading.into()),
             annotation: None,
             index: 0,
+            source: None,
         }
     }
 
@@ -61,18 +92,29 @@ pub struct CandidateList {
     cursor: usize,
     /// Number of candidates per page
     page_size: usize,
+    /// Show all candidates at once (a scrollable window) instead of pages
+    expanded: bool,
 }
 
 impl CandidateList {
     /// Default page size for candidate display
     pub const DEFAULT_PAGE_SIZE: usize = 9;
 
+    /// Largest page size (selection keys 1–9 and 0)
+    pub const MAX_PAGE_SIZE: usize = 10;
+
     /// Create a new candidate list
     pub fn new(candidates: Vec<Candidate>) -> Self {
+        Self::with_page_size(candidates, Self::DEFAULT_PAGE_SIZE)
+    }
+
+    /// Create a new candidate list with a custom page size (clamped to 1..=MAX_PAGE_SIZE)
+    pub fn with_page_size(candidates: Vec<Candidate>, page_size: usize) -> Self {
         Self {
             candidates,
             cursor: 0,
-            page_size: Self::DEFAULT_PAGE_SIZE,
+            page_size: page_size.clamp(1, Self::MAX_PAGE_SIZE),
+            expanded: false,
         }
     }
 
@@ -125,33 +167,58 @@ impl CandidateList {
         self.page_size
     }
 
-    /// Get the current page number (0-indexed)
-    pub fn current_page(&self) -> usize {
-        if self.page_size == 0 {
-            0
+    /// Check whether all candidates are shown at once instead of paged
+    pub fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    /// Show all candidates at once (true) or one page at a time (false)
+    pub fn set_expanded(&mut self, expanded: bool) {
+        self.expanded = expanded;
+    }
+
+    /// Switch between the paged and the expanded (full list) view.
+    /// Returns whether the list is now expanded.
+    pub fn toggle_expanded(&mut self) -> bool {
+        self.expanded = !self.expanded;
+        self.expanded
+    }
+
+    /// Number of candidates shown at once: a page, or the whole list when expanded
+    fn view_size(&self) -> usize {
+        if self.expanded {
+            self.candidates.len().max(1)
         } else {
-            self.cursor / self.page_size
+            self.page_size
         }
     }
 
+    /// Get the current page number (0-indexed)
+    ///
+    /// Always 0 when expanded, since the whole list is a single page.
+    pub fn current_page(&self) -> usize {
+        self.cursor.checked_div(self.view_size()).unwrap_or(0)
+    }
+
     /// Get the total number of pages
     pub fn total_pages(&self) -> usize {
-        if self.page_size == 0 || self.candidates.is_empty() {
+        let view_size = self.view_size();
+        if view_size == 0 || self.candidates.is_empty() {
             0
         } else {
-            self.candidates.len().div_ceil(self.page_size)
+            self.candidates.len().div_ceil(view_size)
         }
     }
 
     /// Get the start index of the current page
     pub fn page_start(&self) -> usize {
-        self.current_page() * self.page_size
+        self.current_page() * self.view_size()
     }
 
-    /// Get the candidates for the current page
+    /// Get the candidates for the current page (all of them when expanded)
     pub fn page_candidates(&self) -> &[Candidate] {
         let start = self.page_start();
-        let end = (start + self.page_size).min(self.candidates.len());
+        let end = (start + self.view_size()).min(self.candidates.len());
         &self.candidates[start..end]
     }
 
@@ -199,10 +266,22 @@ impl CandidateList {
     }
 
     /// Move to the next page
+    ///
+    /// When expanded, scrolls the cursor by a page size instead (wrapping from
+    /// the last candidate to the first).
     pub fn next_page(&mut self) -> bool {
         if self.candidates.is_empty() {
             return false;
         }
+        if self.expanded {
+            let last = self.candidates.len() - 1;
+            self.cursor = if self.cursor == last {
+                0
+            } else {
+                (self.cursor + self.page_size).min(last)
+            };
+            return true;
+        }
 
         let next_page_start = self.page_start() + self.page_size;
         if next_page_start < self.candidates.len() {
@@ -216,10 +295,21 @@ impl CandidateList {
     }
 
     /// Move to the previous page
+    ///
+    /// When expanded, scrolls the cursor back by a page size instead (wrapping
+    /// from the first candidate to the last).
     pub fn prev_page(&mut self) -> bool {
         if self.candidates.is_empty() {
             return false;
         }
+        if self.expanded {
+            self.cursor = if self.cursor == 0 {
+                self.candidates.len() - 1
+            } else {
+                self.cursor.saturating_sub(self.page_size)
+            };
+            return true;
+        }
 
         let current_page = self.current_page();
         if current_page > 0 {
@@ -233,7 +323,18 @@ impl CandidateList {
         }
     }
 
-    /// Select a candidate by index within the current page (1-9)
+    /// Jump to the first candidate of the given page (0-indexed)
+    ///
+    /// Returns false (cursor unchanged) if the page is out of range.
+    pub fn goto_page(&mut self, page: usize) -> bool {
+        if page >= self.total_pages() {
+            return false;
+        }
+        self.cursor = page * self.page_size;
+        true
+    }
+
+    /// Select a candidate by index within the current page (1..=page_size)
     pub fn select_on_page(&mut self, page_index: usize) -> Option<&Candidate> {
         if page_index == 0 || page_index > self.page_size {
             return None;
@@ -258,6 +359,20 @@ impl CandidateList {
         }
     }
 
+    /// Remove the selected candidate, keeping the cursor at the same position
+    /// (or the new last candidate). Remaining candidates are re-indexed.
+    pub fn remove_selected(&mut self) -> Option<Candidate> {
+        if self.cursor >= self.candidates.len() {
+            return None;
+        }
+        let removed = self.candidates.remove(self.cursor);
+        for (i, c) in self.candidates.iter_mut().enumerate() {
+            c.index = i;
+        }
+        self.cursor = self.cursor.min(self.candidates.len().saturating_sub(1));
+        Some(removed)
+    }
+
     /// Reset cursor to beginning
     pub fn reset(&mut self) {
         self.cursor = 0;
@@ -280,6 +395,22 @@ impl Default for CandidateList {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_remove_selected() {
+        let mut candidates = CandidateList::from_strings(["今日", "京", "恭"]);
+        candidates.move_next();
+        assert_eq!(candidates.remove_selected().unwrap().text, "京");
+        assert_eq!(candidates.selected_text(), Some("恭"));
+        assert_eq!(candidates.selected().unwrap().index, 1);
+
+        // Removing the last candidate moves the cursor back
+        assert_eq!(candidates.remove_selected().unwrap().text, "恭");
+        assert_eq!(candidates.selected_text(), Some("今日"));
+        candidates.remove_selected();
+        assert!(candidates.is_empty());
+        assert!(candidates.remove_selected().is_none());
+    }
+
     #[test]
     fn test_candidate_list_basic() {
         let candidates = CandidateList::from_strings(["今日", "京", "恭"]);
@@ -343,4 +474,143 @@ mod tests {
         candidates.select_on_page(2);
         assert_eq!(candidates.selected_text(), Some("item11")); // 9 + 2 = 11
     }
+
+    #[test]
+    fn test_candidate_list_goto_page() {
+        let items: Vec<_> = (1..=20).map(|i| format!("item{}", i)).collect();
+        let mut candidates = CandidateList::from_strings(items);
+
+        assert!(candidates.goto_page(2));
+        assert_eq!(candidates.current_page(), 2);
+        assert_eq!(candidates.selected_text(), Some("item19"));
+
+        assert!(candidates.goto_page(0));
+        assert_eq!(candidates.selected_text(), Some("item1"));
+
+        // Out of range: cursor unchanged
+        candidates.goto_page(1);
+        assert!(!candidates.goto_page(3));
+        assert_eq!(candidates.current_page(), 1);
+        assert_eq!(candidates.selected_text(), Some("item10"));
+
+        assert!(!CandidateList::default().goto_page(0));
+    }
+
+    /// 23 candidates with the given page size
+    fn paged_list(page_size: usize) -> CandidateList {
+        let items = (1..=23)
+            .map(|i| Candidate::new(format!("item{}", i)).with_index(i - 1))
+            .collect();
+        CandidateList::with_page_size(items, page_size)
+    }
+
+    #[test]
+    fn test_page_size_5() {
+        let mut candidates = paged_list(5);
+        assert_eq!(candidates.page_size(), 5);
+        assert_eq!(candidates.total_pages(), 5);
+        assert_eq!(candidates.page_candidates().len(), 5);
+        assert!(candidates.select_on_page(6).is_none());
+        assert_eq!(candidates.select_on_page(5).unwrap().text, "item5");
+        assert_eq!(candidates.page_cursor(), 4);
+
+        // Moving past the page end lands on the next page
+        candidates.move_next();
+        assert_eq!(candidates.current_page(), 1);
+        assert_eq!(candidates.page_cursor(), 0);
+
+        // Last partial page: items 21-23
+        assert!(candidates.goto_page(4));
+        assert_eq!(candidates.page_start(), 20);
+        assert_eq!(candidates.page_candidates().len(), 3);
+        assert!(candidates.select_on_page(4).is_none());
+        assert_eq!(candidates.select_on_page(3).unwrap().text, "item23");
+        assert_eq!(candidates.page_cursor(), 2);
+        candidates.next_page();
+        assert_eq!(candidates.current_page(), 0);
+    }
+
+    #[test]
+    fn test_page_size_9() {
+        let mut candidates = paged_list(9);
+        assert_eq!(candidates.total_pages(), 3);
+
+        // Last partial page: items 19-23
+        candidates.prev_page();
+        assert_eq!(candidates.current_page(), 2);
+        assert_eq!(candidates.page_candidates().len(), 5);
+        assert_eq!(candidates.selected_text(), Some("item19"));
+        assert_eq!(candidates.select_on_page(5).unwrap().text, "item23");
+        assert!(candidates.select_on_page(6).is_none());
+
+        // Wrapping back from the first candidate lands on the last page
+        candidates.select(0);
+        candidates.move_prev();
+        assert_eq!(candidates.current_page(), 2);
+        assert_eq!(candidates.page_cursor(), 4);
+    }
+
+    #[test]
+    fn test_page_size_10() {
+        let mut candidates = paged_list(10);
+        assert_eq!(candidates.total_pages(), 3);
+        assert_eq!(candidates.page_candidates().len(), 10);
+        assert_eq!(candidates.select_on_page(10).unwrap().text, "item10");
+        assert_eq!(candidates.page_cursor(), 9);
+
+        candidates.next_page();
+        assert_eq!(candidates.page_start(), 10);
+        assert_eq!(candidates.select_on_page(10).unwrap().text, "item20");
+
+        // Last partial page: items 21-23
+        candidates.next_page();
+        assert_eq!(candidates.page_candidates().len(), 3);
+        assert!(candidates.select_on_page(10).is_none());
+        assert_eq!(candidates.selected_text(), Some("item21"));
+    }
+
+    #[test]
+    fn test_expanded_shows_full_list() {
+        let mut candidates = paged_list(9);
+        assert!(!candidates.is_expanded());
+        candidates.select(12);
+
+        assert!(candidates.toggle_expanded());
+        assert!(candidates.is_expanded());
+        assert_eq!(candidates.page_candidates().len(), 23);
+        assert_eq!(candidates.total_pages(), 1);
+        assert_eq!(candidates.current_page(), 0);
+        assert_eq!(candidates.page_cursor(), 12);
+
+        // Back to pages, keeping the selection
+        assert!(!candidates.toggle_expanded());
+        assert_eq!(candidates.page_candidates().len(), 9);
+        assert_eq!(candidates.current_page(), 1);
+        assert_eq!(candidates.selected_text(), Some("item13"));
+    }
+
+    #[test]
+    fn test_expanded_page_keys_scroll() {
+        let mut candidates = paged_list(9);
+        candidates.set_expanded(true);
+
+        candidates.next_page();
+        assert_eq!(candidates.cursor(), 9);
+        candidates.next_page();
+        candidates.next_page();
+        assert_eq!(candidates.cursor(), 22);
+        // Wrap from the last candidate
+        candidates.next_page();
+        assert_eq!(candidates.cursor(), 0);
+        candidates.prev_page();
+        assert_eq!(candidates.cursor(), 22);
+        candidates.prev_page();
+        assert_eq!(candidates.cursor(), 13);
+    }
+
+    #[test]
+    fn test_page_size_is_clamped() {
+        assert_eq!(paged_list(0).page_size(), 1);
+        assert_eq!(paged_list(20).page_size(), CandidateList::MAX_PAGE_SIZE);
+    }
 }
@@ -1,15 +1,35 @@
 //! Conversion state handling (candidates, segments, commit)
 
 use std::collections::HashSet;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
-use tracing::debug;
+use tracing::{Span, debug};
 
+use super::super::macros::expand_date_macros;
+use super::super::numbers::convert_number;
+use super::super::symbols::lookup_symbols;
 use super::*;
 
 /// Maximum number of learning candidates to show
 const MAX_LEARNING_CANDIDATES: usize = 3;
 
+/// Maximum number of dictionary or prediction candidates fetched per lookup;
+/// the page size only decides how they are paged
+const MAX_DICT_CANDIDATES: usize = 9;
+
+/// Maximum number of candidates scored by the model when reranking
+const MAX_RERANK_CANDIDATES: usize = 10;
+
+/// Upper bound on model candidates requested for explicit conversion
+/// (same as the server's clamp)
+const MAX_NUM_CANDIDATES: usize = 20;
+
+/// Whether a reading has nothing to convert: empty or only whitespace,
+/// including the full-width space U+3000 (Ctrl+Space)
+fn is_blank_reading(reading: &str) -> bool {
+    reading.chars().all(char::is_whitespace)
+}
+
 /// Helper for building a deduplicated list of conversion candidates.
 struct CandidateBuilder {
     candidates: Vec<AnnotatedCandidate>,
@@ -42,6 +62,19 @@ impl CandidateBuilder {
         }
     }
 
+    /// Push the reading as typed and its katakana form as fallbacks.
+    ///
+    /// Katakana in the reading is kept, so a reading with no hiragana is its
+    /// own katakana form and is offered once; a mixed reading gets one all-katakana form.
+    fn push_kana_fallbacks(&mut self, reading: &str) {
+        self.push_if_new(reading.to_string(), CandidateSource::Fallback, None);
+        self.push_if_new(
+            InputMethodEngine::hiragana_to_katakana(reading),
+            CandidateSource::Fallback,
+            None,
+        );
+    }
+
     fn is_empty(&self) -> bool {
         self.candidates.is_empty()
     }
@@ -52,10 +85,28 @@ impl CandidateBuilder {
 }
 
 impl InputMethodEngine {
+    /// Create the tracing span for a new conversion of `reading`.
+    ///
+    /// `strategy` and `model` are recorded by `run_kana_kanji_conversion` once known,
+    /// so all logs of one conversion (dictionaries, learning, inference) share a span.
+    fn conversion_span(&mut self, reading: &str) -> Span {
+        self.metrics.conversion_id += 1;
+        tracing::debug_span!(
+            "conversion",
+            conversion_id = self.metrics.conversion_id,
+            reading = reading,
+            strategy = tracing::field::Empty,
+            model = tracing::field::Empty,
+        )
+    }
+
     /// Run kana-kanji conversion for a reading via llama.cpp model.
     ///
-    /// Determines the conversion strategy (main model, light model, or parallel beam),
+    /// Determines the conversion strategy (main model, light model, parallel beam, or
+    /// light model draft reranked by the main model),
     /// dispatches to the appropriate model(s), measures latency, and records which model was used.
+    /// Results are cached per (reading, context, candidate count, model), so repeating
+    /// an identical conversion skips inference.
     fn run_kana_kanji_conversion(&mut self, reading: &str, num_candidates: usize) -> Vec<String> {
         let Some(converter) = self.converters.kanji.as_ref() else {
             return vec![];
@@ -63,14 +114,59 @@ impl InputMethodEngine {
         let katakana = karukan_engine::kana::hiragana_to_katakana(reading);
         let api_context = self.truncate_context_for_api();
         let main_model_name = converter.model_display_name().to_string();
+        let light_model_name = self
+            .converters
+            .light_kanji
+            .as_ref()
+            .map(|c| c.model_display_name().to_string());
 
         let strategy = self.determine_strategy(reading, num_candidates);
+        self.metrics.strategy = Some(strategy.clone());
+        Span::current().record("strategy", tracing::field::debug(&strategy));
         debug!(
             "convert: reading=\"{}\" api_context=\"{}\" candidates={} strategy={:?}",
             reading, api_context, num_candidates, strategy
         );
 
+        let model_name = match &strategy {
+            ConversionStrategy::ParallelBeam { .. } => {
+                format!(
+                    "{}+{}",
+                    main_model_name,
+                    light_model_name.unwrap_or_default()
+                )
+            }
+            ConversionStrategy::DraftThenRerank { .. } => {
+                format!(
+                    "{}>{}",
+                    light_model_name.unwrap_or_default(),
+                    main_model_name
+                )
+            }
+            ConversionStrategy::LightModelOnly => light_model_name.unwrap_or(main_model_name),
+            ConversionStrategy::MainModelOnly | ConversionStrategy::MainModelBeam { .. } => {
+                main_model_name
+            }
+        };
+
+        let cache_key = ConversionCacheKey {
+            katakana: katakana.clone(),
+            api_context: api_context.clone(),
+            num_candidates,
+            model_id: model_name.clone(),
+        };
+        if let Some(cached) = self.conversion_cache.get(&cache_key) {
+            debug!("convert: cache hit for \"{}\"", reading);
+            self.metrics.conversion_ms = 0;
+            self.metrics.model_name = model_name;
+            Span::current().record("model", self.metrics.model_name.as_str());
+            return cached;
+        }
+
         let start = Instant::now();
+        // Beam search stops at the latency cap with the candidates found so far
+        let deadline = (self.config.max_latency_ms > 0)
+            .then(|| start + Duration::from_millis(self.config.max_latency_ms));
 
         let candidates = match &strategy {
             ConversionStrategy::ParallelBeam { beam_width } => {
@@ -86,7 +182,7 @@ impl InputMethodEngine {
                     });
                     let h_beam = s.spawn(|| {
                         light_converter
-                            .convert(&katakana, &api_context, bw)
+                            .convert_with_deadline(&katakana, &api_context, bw, deadline)
                             .unwrap_or_default()
                     });
                     (
@@ -108,50 +204,65 @@ impl InputMethodEngine {
                 .convert(&katakana, &api_context, 1)
                 .unwrap_or_default(),
             ConversionStrategy::MainModelBeam { beam_width } => converter
-                .convert(&katakana, &api_context, *beam_width)
+                .convert_with_deadline(&katakana, &api_context, *beam_width, deadline)
                 .unwrap_or_default(),
+            ConversionStrategy::DraftThenRerank { beam_width } => {
+                let Some(light_converter) = self.converters.light_kanji.as_ref() else {
+                    return vec![];
+                };
+                let draft = light_converter
+                    .convert_with_deadline(&katakana, &api_context, *beam_width, deadline)
+                    .unwrap_or_default();
+                Self::rerank_draft(draft, |surfaces| {
+                    converter
+                        .score_candidates(reading, &api_context, surfaces)
+                        .inspect_err(|e| debug!("draft rerank failed: {}", e))
+                        .ok()
+                })
+            }
         };
 
         self.metrics.conversion_ms = start.elapsed().as_millis() as u64;
         self.update_adaptive_model_flag(&strategy);
 
-        self.metrics.model_name = match &strategy {
-            ConversionStrategy::ParallelBeam { .. } => {
-                let light_name = self
-                    .converters
-                    .light_kanji
-                    .as_ref()
-                    .map(|c| c.model_display_name().to_string())
-                    .unwrap_or_default();
-                format!("{}+{}", main_model_name, light_name)
-            }
-            ConversionStrategy::LightModelOnly => self
-                .converters
-                .light_kanji
-                .as_ref()
-                .map(|c| c.model_display_name().to_string())
-                .unwrap_or(main_model_name),
-            ConversionStrategy::MainModelOnly | ConversionStrategy::MainModelBeam { .. } => {
-                main_model_name
-            }
-        };
-
+        self.metrics.model_name = model_name;
+        Span::current().record("model", self.metrics.model_name.as_str());
+
+        // A search cut short by the deadline is not cached, so that it runs in
+        // full when there is time
+        let cut_short = deadline.is_some_and(|d| Instant::now() >= d);
+        if !candidates.is_empty() && !cut_short {
+            self.conversion_cache.insert(cache_key, candidates.clone());
+            debug!(
+                "convert: cached result ({} entries)",
+                self.conversion_cache.len()
+            );
+        }
         candidates
     }
 
     /// Run inference for auto-suggest and return candidates (raw strings).
     /// Initializes the kanji converter lazily. Falls back to the reading itself
     /// if no candidates are produced.
+    ///
+    /// Readings that look like loanwords get their katakana form as the top suggestion.
     pub(super) fn run_auto_suggest(&mut self, reading: &str, num_candidates: usize) -> Vec<String> {
+        let _span = self.conversion_span(reading).entered();
+
         // Ensure kanji converter is initialized
-        if self.converters.kanji.is_none()
+        let mut candidates = if self.converters.kanji.is_none()
             && let Err(e) = self.init_kanji_converter()
         {
             debug!("Failed to initialize kanji converter: {}", e);
-            return vec![reading.to_string()];
-        }
+            vec![]
+        } else {
+            self.run_kana_kanji_conversion(reading, num_candidates)
+        };
 
-        let candidates = self.run_kana_kanji_conversion(reading, num_candidates);
+        if let Some(katakana) = self.loanword_katakana(reading) {
+            candidates.retain(|c| *c != katakana);
+            candidates.insert(0, katakana);
+        }
 
         if candidates.is_empty() {
             vec![reading.to_string()]
@@ -160,15 +271,44 @@ impl InputMethodEngine {
         }
     }
 
+    /// Katakana form of `reading` if it looks like a loanword typed in hiragana
+    /// mode and `loanword_katakana` is enabled.
+    fn loanword_katakana(&self, reading: &str) -> Option<String> {
+        (self.config.loanword_katakana && karukan_engine::looks_like_loanword(reading))
+            .then(|| Self::hiragana_to_katakana(reading))
+    }
+
     /// Start conversion using the current live-conversion result + dictionary candidates.
     ///
     /// Called when DOWN/TAB is pressed during live conversion.  Instead of
     /// Start kanji conversion
     pub(super) fn start_conversion(&mut self) -> EngineResult {
+        self.convert_input(false)
+    }
+
+    /// Start kanji conversion from Space: with `candidate_preview`, only the
+    /// top candidate is shown (in the preedit) until Space is pressed again.
+    pub(super) fn start_conversion_from_space(&mut self) -> EngineResult {
+        self.convert_input(self.config.candidate_preview)
+    }
+
+    /// Convert the composing text, opening the candidate window unless `preview`
+    fn convert_input(&mut self, preview: bool) -> EngineResult {
         // Flush any remaining romaji into composed_hiragana
         self.flush_romaji_to_composed();
 
-        let reading = self.input_buf.text.clone();
+        // With the caret mid-buffer, convert only the text before it and keep
+        // the rest as composing input (caret at either end converts everything)
+        let caret = self.input_buf.cursor_pos;
+        let (reading, tail) = if caret > 0 && caret < self.input_buf.text.chars().count() {
+            let chars: Vec<char> = self.input_buf.text.chars().collect();
+            (
+                chars[..caret].iter().collect::<String>(),
+                chars[caret..].iter().collect::<String>(),
+            )
+        } else {
+            (self.input_buf.text.clone(), String::new())
+        };
 
         // Save auto-suggest/live conversion result before clearing state.
         // This ensures the candidate that was displayed during input is preserved
@@ -181,14 +321,31 @@ impl InputMethodEngine {
         if reading.is_empty() {
             return EngineResult::consumed();
         }
+        if is_blank_reading(&reading) {
+            return self.commit_blank_reading(reading, tail);
+        }
+
+        // With auto_segment, a reading the dictionaries split into several
+        // words starts as one segment per word
+        if self.config.auto_segment {
+            let readings = self.split_reading(&reading);
+            if readings.len() > 1 {
+                let segments = readings.iter().map(|r| self.convert_segment(r)).collect();
+                return self.enter_segmented_conversion_state(segments, tail, preview);
+            }
+        }
 
-        // Get candidates from kanji converter (use full num_candidates for explicit conversion)
-        let mut candidates = self.build_conversion_candidates(&reading, self.config.num_candidates);
+        // Get candidates from kanji converter (use full num_candidates for explicit conversion;
+        // the candidate window pages through all of them)
+        let num_candidates = self.conversion_num_candidates();
+        let mut candidates = self.build_conversion_candidates(&reading, num_candidates);
 
         // If the previous auto-suggest result is not in the new candidates, insert it at the top
         // so it doesn't disappear when the conversion strategy changes.
+        // It covers the whole input, so it doesn't apply to a partial conversion.
         let seen: HashSet<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
         if !prev_suggest_text.is_empty()
+            && tail.is_empty()
             && prev_suggest_text != reading
             && !seen.contains(prev_suggest_text.as_str())
         {
@@ -204,7 +361,7 @@ impl InputMethodEngine {
 
         if candidates.is_empty() {
             // No candidates, stay in hiragana mode
-            let preedit = Preedit::with_text_underlined(&reading);
+            let preedit = Preedit::with_text_underlined(&self.input_buf.text);
             self.state = InputState::Composing {
                 preedit: preedit.clone(),
                 romaji_buffer: String::new(),
@@ -212,68 +369,271 @@ impl InputMethodEngine {
             return EngineResult::consumed().with_action(EngineAction::UpdatePreedit(preedit));
         }
 
-        // Create candidate list with reading and source annotation
-        let candidate_list = CandidateList::new(
+        let candidate_list = self.make_candidate_list(&reading, candidates);
+        self.enter_conversion_state(&reading, candidate_list, tail, preview)
+    }
+
+    /// Commit whitespace as typed instead of converting it (the model would only
+    /// see a degenerate prompt); `tail` after the caret stays composing.
+    fn commit_blank_reading(&mut self, reading: String, tail: String) -> EngineResult {
+        if tail.is_empty() {
+            return self.commit_reading();
+        }
+        debug!("convert: committing blank reading {:?} as is", reading);
+        self.input_buf.clear();
+        self.input_buf.insert(&tail);
+        self.input_buf.cursor_pos = 0;
+        self.record_commit(&reading, &reading, false);
+        let preedit = self.set_composing_state();
+        EngineResult::consumed()
+            .with_action(EngineAction::Commit(reading))
+            .with_action(EngineAction::UpdatePreedit(preedit))
+            .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()))
+    }
+
+    /// Annotation of a candidate from `source` (None when its label is empty)
+    fn annotation(&self, source: &CandidateSource) -> Option<String> {
+        let label = source.label(&self.config.labels);
+        (!label.is_empty()).then(|| label.to_string())
+    }
+
+    /// Create a candidate list with reading and source annotation
+    fn make_candidate_list(
+        &self,
+        reading: &str,
+        candidates: Vec<AnnotatedCandidate>,
+    ) -> CandidateList {
+        CandidateList::with_page_size(
             candidates
                 .into_iter()
                 .enumerate()
                 .map(|(i, ac)| {
-                    let label = ac.source.label();
-                    let cand_reading = ac.reading.unwrap_or_else(|| reading.clone());
-                    let mut c = if label.is_empty() {
-                        Candidate::with_reading(&ac.text, &cand_reading)
-                    } else {
-                        Candidate {
-                            text: ac.text,
-                            reading: Some(cand_reading),
-                            annotation: Some(label.to_string()),
-                            index: 0,
-                        }
-                    };
-                    c.index = i;
-                    c
+                    let cand_reading = ac.reading.unwrap_or_else(|| reading.to_string());
+                    Candidate {
+                        annotation: self.annotation(&ac.source),
+                        text: ac.text,
+                        reading: Some(cand_reading),
+                        index: i,
+                        source: Some(ac.source),
+                    }
                 })
                 .collect(),
-        );
-        self.enter_conversion_state(&reading, candidate_list)
+            self.config.page_size,
+        )
+    }
+
+    /// Number of model candidates to request for explicit conversion,
+    /// `num_candidates` clamped to `1..=MAX_NUM_CANDIDATES`.
+    pub(super) fn conversion_num_candidates(&self) -> usize {
+        self.config.num_candidates.clamp(1, MAX_NUM_CANDIDATES)
+    }
+
+    /// Convert one segment's reading into a segment with its own candidate list.
+    fn convert_segment(&mut self, reading: &str) -> ConversionSegment {
+        let num_candidates = self.conversion_num_candidates();
+        let mut candidates = self.build_conversion_candidates(reading, num_candidates);
+        if candidates.is_empty() {
+            candidates.push(AnnotatedCandidate {
+                text: reading.to_string(),
+                source: CandidateSource::Fallback,
+                reading: None,
+            });
+        }
+        ConversionSegment {
+            reading: reading.to_string(),
+            candidates: self.make_candidate_list(reading, candidates),
+        }
+    }
+
+    /// Split `reading` into segments by dictionary longest match, for the
+    /// initial segmentation of `auto_segment`.
+    ///
+    /// At each position the longest reading found in the user, extra, or
+    /// system dictionary becomes a segment; characters no dictionary starts a
+    /// word at are grouped into one segment with their neighbours.
+    pub(super) fn split_reading(&self, reading: &str) -> Vec<String> {
+        let dicts: Vec<&Dictionary> = self
+            .dicts
+            .user
+            .as_ref()
+            .map(UserDictionary::dictionary)
+            .into_iter()
+            .chain(&self.dicts.extra)
+            .chain(&self.dicts.system)
+            .collect();
+
+        let mut segments = Vec::new();
+        let mut unknown = String::new();
+        let mut rest = reading;
+        while let Some(ch) = rest.chars().next() {
+            let longest = dicts
+                .iter()
+                .flat_map(|dict| dict.common_prefix_search(rest))
+                .map(|result| result.reading.len())
+                .max();
+            let len = match longest {
+                Some(len) => {
+                    if !unknown.is_empty() {
+                        segments.push(std::mem::take(&mut unknown));
+                    }
+                    segments.push(rest[..len].to_string());
+                    len
+                }
+                None => {
+                    unknown.push(ch);
+                    ch.len_utf8()
+                }
+            };
+            rest = &rest[len..];
+        }
+        if !unknown.is_empty() {
+            segments.push(unknown);
+        }
+        segments
     }
 
     /// Transition to Conversion state with the given reading and candidate list.
     ///
-    /// Sets up the preedit (highlighted selected text), updates the state, and
-    /// returns an EngineResult with preedit, candidates, and aux text actions.
-    fn enter_conversion_state(&mut self, reading: &str, candidates: CandidateList) -> EngineResult {
-        let selected_text = candidates.selected_text().unwrap_or(reading).to_string();
-
-        let preedit = Preedit::from_segments(
-            vec![PreeditSegment::highlighted(&selected_text)],
-            selected_text.chars().count(),
-        );
+    /// The whole reading starts as a single segment, followed by the
+    /// unconverted `tail`. Sets up the preedit (highlighted selected text),
+    /// updates the state, and returns an EngineResult with preedit,
+    /// candidates, and aux text actions. With `preview`, the candidate window
+    /// is hidden instead of shown.
+    fn enter_conversion_state(
+        &mut self,
+        reading: &str,
+        candidates: CandidateList,
+        tail: String,
+        preview: bool,
+    ) -> EngineResult {
+        let segments = vec![ConversionSegment {
+            reading: reading.to_string(),
+            candidates,
+        }];
+        self.enter_segmented_conversion_state(segments, tail, preview)
+    }
+
+    /// Transition to Conversion state with already converted `segments`, the
+    /// first one active, as [`Self::enter_conversion_state`] does for one.
+    fn enter_segmented_conversion_state(
+        &mut self,
+        segments: Vec<ConversionSegment>,
+        tail: String,
+        preview: bool,
+    ) -> EngineResult {
+        let reading = segments[0].reading.clone();
+        let candidates = segments[0].candidates.clone();
+        let preedit = Self::conversion_preedit(&segments, 0, &tail);
 
         self.state = InputState::Conversion {
             preedit: preedit.clone(),
-            candidates: candidates.clone(),
+            segments,
+            active: 0,
+            tail,
+            preview,
         };
 
+        let candidates_action = if preview {
+            EngineAction::HideCandidates
+        } else {
+            EngineAction::ShowCandidates(candidates.clone())
+        };
         EngineResult::consumed()
             .with_action(EngineAction::UpdatePreedit(preedit))
-            .with_action(EngineAction::ShowCandidates(candidates.clone()))
+            .with_action(candidates_action)
             .with_action(EngineAction::UpdateAuxText(
-                self.format_aux_conversion_with_page(reading, Some(&candidates)),
+                self.format_aux_conversion_with_page(&reading, Some(&candidates)),
             ))
     }
 
+    /// Build the conversion preedit from all segments.
+    ///
+    /// The active segment is highlighted and the others (and the unconverted
+    /// tail) are underlined; the caret sits at the end of the active segment.
+    /// The part of the active segment shared by all candidates on the page
+    /// (変換 of 変換/変換器/変換する) is shown in reverse video.
+    fn conversion_preedit(segments: &[ConversionSegment], active: usize, tail: &str) -> Preedit {
+        let caret = segments[..=active]
+            .iter()
+            .map(|s| s.selected_text().chars().count())
+            .sum();
+        let mut parts: Vec<PreeditSegment> = Vec::new();
+        for (i, s) in segments.iter().enumerate() {
+            let text = s.selected_text();
+            if i != active {
+                parts.push(PreeditSegment::new(text, AttributeType::Underline));
+                continue;
+            }
+            let prefix = Self::common_candidate_prefix(&s.candidates);
+            match text.strip_prefix(prefix.as_str()) {
+                Some(rest) if !prefix.is_empty() => {
+                    parts.push(PreeditSegment::new(prefix, AttributeType::Reverse));
+                    if !rest.is_empty() {
+                        parts.push(PreeditSegment::highlighted(rest));
+                    }
+                }
+                _ => parts.push(PreeditSegment::highlighted(text)),
+            }
+        }
+        if !tail.is_empty() {
+            parts.push(PreeditSegment::new(tail, AttributeType::Underline));
+        }
+        Preedit::from_segments(parts, caret)
+    }
+
+    /// Longest common prefix of the candidates on the current page.
+    ///
+    /// Hiragana/katakana fallbacks and loanword katakana are left out, since
+    /// they would never share a kanji prefix. Empty unless at least two
+    /// candidates remain.
+    pub(super) fn common_candidate_prefix(candidates: &CandidateList) -> String {
+        let texts: Vec<&str> = candidates
+            .page_candidates()
+            .iter()
+            .filter(|c| {
+                !matches!(
+                    c.source,
+                    Some(CandidateSource::Fallback | CandidateSource::Loanword)
+                )
+            })
+            .map(|c| c.text.as_str())
+            .collect();
+        let [first, rest @ ..] = texts.as_slice() else {
+            return String::new();
+        };
+        if rest.is_empty() {
+            return String::new();
+        }
+        let prefix_len = rest
+            .iter()
+            .map(|text| {
+                first
+                    .chars()
+                    .zip(text.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a.len_utf8())
+                    .sum()
+            })
+            .min()
+            .unwrap_or(0);
+        first[..prefix_len].to_string()
+    }
+
     /// Search user and system dictionaries for candidates matching a reading.
     ///
-    /// User dictionary results come first (higher priority), then system dictionary
-    /// results sorted by score. Duplicates are removed via HashSet.
-    fn search_dictionaries(&self, reading: &str, limit: usize) -> Vec<AnnotatedCandidate> {
+    /// User dictionary results come first (higher priority), then extra and system
+    /// dictionary results sorted by score. Duplicates are removed via HashSet.
+    pub(super) fn search_dictionaries(
+        &self,
+        reading: &str,
+        limit: usize,
+    ) -> Vec<AnnotatedCandidate> {
         let mut candidates = Vec::new();
         let mut seen = HashSet::new();
 
         // User dictionary (higher priority)
-        if let Some(dict) = &self.dicts.user
-            && let Some(result) = dict.exact_match_search(reading)
+        if let Some(user) = &self.dicts.user
+            && let Some(result) = user.dictionary().exact_match_search(reading)
         {
             for cand in result.candidates {
                 if candidates.len() >= limit {
@@ -289,10 +649,12 @@ impl InputMethodEngine {
             }
         }
 
-        // System dictionary (sorted by score)
-        if let Some(dict) = &self.dicts.system
-            && let Some(result) = dict.exact_match_search(reading)
-        {
+        // Configured extra dictionaries (in load order), then the system dictionary.
+        // Each dictionary's candidates are sorted by score.
+        for dict in self.dicts.extra.iter().chain(&self.dicts.system) {
+            let Some(result) = dict.exact_match_search(reading) else {
+                continue;
+            };
             let mut dict_candidates: Vec<_> = result.candidates.to_vec();
             dict_candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
             for cand in dict_candidates {
@@ -312,48 +674,153 @@ impl InputMethodEngine {
         candidates
     }
 
+    /// Search the phrase dictionary for expansions of a reading.
+    ///
+    /// Exact trigger matches come first with `reading: None`; phrases whose
+    /// trigger merely starts with `reading` follow with their full trigger
+    /// as the reading override.
+    pub(super) fn search_phrases(&self, reading: &str) -> Vec<AnnotatedCandidate> {
+        let Some(dict) = &self.dicts.phrase else {
+            return vec![];
+        };
+        let mut exact = Vec::new();
+        let mut predictive = Vec::new();
+        for result in dict.predictive_search(reading) {
+            let is_exact = result.reading == reading;
+            for cand in result.candidates {
+                let ac = AnnotatedCandidate {
+                    text: cand.surface.clone(),
+                    source: CandidateSource::Phrase,
+                    reading: (!is_exact).then(|| result.reading.to_string()),
+                };
+                if is_exact {
+                    exact.push(ac);
+                } else {
+                    predictive.push(ac);
+                }
+            }
+        }
+        exact.extend(predictive);
+        exact
+    }
+
+    /// Search the extra and system dictionaries for completions of a reading.
+    ///
+    /// Only entries whose reading is longer than `reading` are returned, shorter
+    /// completions first, each carrying its full reading as the override.
+    pub(super) fn search_predictions(
+        &self,
+        reading: &str,
+        limit: usize,
+    ) -> Vec<AnnotatedCandidate> {
+        let mut results: Vec<_> = self
+            .dicts
+            .extra
+            .iter()
+            .chain(&self.dicts.system)
+            .flat_map(|dict| dict.predictive_search(reading))
+            .filter(|result| result.reading != reading)
+            .collect();
+        // Stable sort keeps dictionary priority among readings of equal length
+        results.sort_by_key(|result| result.reading.chars().count());
+
+        let mut candidates = Vec::new();
+        let mut seen = HashSet::new();
+        for result in results {
+            let mut dict_candidates: Vec<_> = result.candidates.to_vec();
+            dict_candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+            for cand in dict_candidates {
+                if candidates.len() >= limit {
+                    return candidates;
+                }
+                if seen.insert(cand.surface.clone()) {
+                    candidates.push(AnnotatedCandidate {
+                        text: cand.surface,
+                        source: CandidateSource::Prediction,
+                        reading: Some(result.reading.to_string()),
+                    });
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Push the built-in candidates for `reading`, which need no model or
+    /// dictionary: symbols for a palette keyword (やじるし → arrows), date/time
+    /// macros (きょう → today's date) and numeric forms of an all-digit reading
+    /// (1000 → 千, １０００, 一〇〇〇).
+    fn push_builtin_candidates(&self, builder: &mut CandidateBuilder, reading: &str) {
+        for symbol in lookup_symbols(reading) {
+            builder.push_if_new(symbol.to_string(), CandidateSource::Symbol, None);
+        }
+        for text in expand_date_macros(reading, SystemTime::now(), self.config.utc_offset_minutes) {
+            builder.push_if_new(text, CandidateSource::Date, None);
+        }
+        for text in convert_number(reading) {
+            builder.push_if_new(text, CandidateSource::Number, None);
+        }
+    }
+
     /// Build conversion candidates for a reading from multiple sources.
     ///
     /// Combines learning cache, dictionaries, and model inference results
     /// with deduplication. Uses dynamic candidate count based on input token
     /// count for performance.
     ///
-    /// Priority: Learning → User Dictionary → Model → System Dictionary → Fallback
+    /// Priority: Learning → User Dictionary → Loanword → Phrase (exact) → Model
+    /// → System Dictionary → Built-ins → Phrase (predictive) → Prediction → Fallback
+    ///
+    /// With `rerank_with_model`, the top non-learning candidates are then
+    /// reordered by model NLL (see [`Self::rerank_by_nll`]).
     pub(super) fn build_conversion_candidates(
         &mut self,
         reading: &str,
         num_candidates: usize,
     ) -> Vec<AnnotatedCandidate> {
+        // Whitespace is offered as is, without inference
+        if is_blank_reading(reading) {
+            return (!reading.is_empty())
+                .then(|| AnnotatedCandidate {
+                    text: reading.to_string(),
+                    source: CandidateSource::Fallback,
+                    reading: None,
+                })
+                .into_iter()
+                .collect();
+        }
+
+        let _span = self.conversion_span(reading).entered();
+        let loanword = self.loanword_katakana(reading);
+
         // Ensure kanji converter is initialized
         if self.converters.kanji.is_none()
             && let Err(e) = self.init_kanji_converter()
         {
             debug!("Failed to initialize kanji converter: {}", e);
-            return vec![AnnotatedCandidate {
-                text: reading.to_string(),
-                source: CandidateSource::Fallback,
-                reading: None,
-            }];
+            // Built-in candidates work without a model
+            let mut builder = CandidateBuilder::new();
+            self.push_builtin_candidates(&mut builder, reading);
+            if let Some(katakana) = loanword {
+                builder.push_if_new(katakana, CandidateSource::Loanword, None);
+            }
+            builder.push_if_new(reading.to_string(), CandidateSource::Fallback, None);
+            return builder.into_candidates();
         }
 
         let candidates = self.run_kana_kanji_conversion(reading, num_candidates);
 
-        let hiragana = reading.to_string();
-        let katakana = Self::hiragana_to_katakana(reading);
-
-        // Priority: Learning → User Dictionary → Model → System Dictionary → Fallback
+        // Priority: Learning → User Dictionary → Loanword → Phrase (exact) → Model
+        // → System Dictionary → Built-ins → Phrase (predictive) → Prediction → Fallback
         let mut builder = CandidateBuilder::new();
 
         // 1. Learning cache candidates (highest priority)
         for c in self.lookup_learning_candidates(reading) {
-            // Force-insert learning candidates (always included even if duplicate text)
-            builder.seen.insert(c.text.clone());
-            builder.candidates.push(AnnotatedCandidate {
-                text: c.text,
-                source: CandidateSource::Learning,
+            builder.push_if_new(
+                c.text,
+                CandidateSource::Learning,
                 // Exact matches have reading == input reading; use None to avoid redundancy
-                reading: c.reading.filter(|r| r != reading),
-            });
+                c.reading.filter(|r| r != reading),
+            );
         }
 
         // 2. Dictionary candidates (user dict first, then system dict)
@@ -365,10 +832,25 @@ impl InputMethodEngine {
             }
         }
 
+        // Loanword typed in hiragana mode: offer the katakana form prominently
+        if let Some(katakana) = loanword {
+            builder.push_if_new(katakana, CandidateSource::Loanword, None);
+        }
+
+        // Phrases whose trigger matches the reading exactly are deliberate
+        // expansions, so they rank with the user dictionary
+        let (exact_phrases, predictive_phrases): (Vec<_>, Vec<_>) = self
+            .search_phrases(reading)
+            .into_iter()
+            .partition(|ac| ac.reading.is_none());
+        for ac in exact_phrases {
+            builder.push_annotated_if_new(ac);
+        }
+
         // 3. Model inference results
         if candidates.is_empty() {
             if builder.is_empty() {
-                builder.push_if_new(hiragana.clone(), CandidateSource::Fallback, None);
+                builder.push_if_new(reading.to_string(), CandidateSource::Fallback, None);
             }
         } else {
             for text in candidates {
@@ -383,11 +865,93 @@ impl InputMethodEngine {
             }
         }
 
+        // Symbols, date/time macros and numeric forms
+        self.push_builtin_candidates(&mut builder, reading);
+
+        // Phrases reached by a partial trigger stay below the word candidates
+        for ac in predictive_phrases {
+            builder.push_annotated_if_new(ac);
+        }
+
+        // Dictionary completions of the reading, when predictive conversion is on
+        if self.config.predictive {
+            for ac in self.search_predictions(reading, MAX_DICT_CANDIDATES) {
+                builder.push_annotated_if_new(ac);
+            }
+        }
+
         // 5. Append hiragana/katakana fallback if not already present
-        builder.push_if_new(hiragana, CandidateSource::Fallback, None);
-        builder.push_if_new(katakana, CandidateSource::Fallback, None);
+        builder.push_kana_fallbacks(reading);
 
-        builder.into_candidates()
+        let mut candidates = builder.into_candidates();
+        if self.config.rerank_with_model
+            && let Some(converter) = self.converters.kanji.as_ref()
+        {
+            let api_context = self.truncate_context_for_api();
+            Self::rerank_by_nll(&mut candidates, MAX_RERANK_CANDIDATES, |surfaces| {
+                converter
+                    .score_candidates(reading, &api_context, surfaces)
+                    .inspect_err(|e| debug!("NLL rerank failed: {}", e))
+                    .ok()
+            });
+        }
+        candidates
+    }
+
+    /// Reorder the candidates following the learning candidates by ascending NLL.
+    ///
+    /// Learning candidates keep their place at the top. At most `limit` of the
+    /// remaining candidates are scored (in one `score` call) and sorted among
+    /// themselves; the rest stay after them in their original order. Nothing
+    /// changes if scoring fails.
+    pub(super) fn rerank_by_nll(
+        candidates: &mut [AnnotatedCandidate],
+        limit: usize,
+        score: impl FnOnce(&[&str]) -> Option<Vec<f32>>,
+    ) {
+        let start = candidates
+            .iter()
+            .take_while(|c| c.source == CandidateSource::Learning)
+            .count();
+        let end = candidates.len().min(start + limit);
+        if end - start < 2 {
+            return;
+        }
+        let window = &mut candidates[start..end];
+        let surfaces: Vec<&str> = window.iter().map(|c| c.text.as_str()).collect();
+        let Some(scores) = score(&surfaces) else {
+            return;
+        };
+        if scores.len() != window.len() {
+            return;
+        }
+        let mut scored: Vec<(f32, AnnotatedCandidate)> =
+            scores.into_iter().zip(window.iter().cloned()).collect();
+        // Stable sort: equal scores keep their original order
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (slot, (_, candidate)) in window.iter_mut().zip(scored) {
+            *slot = candidate;
+        }
+    }
+
+    /// Order draft candidates by ascending main model NLL.
+    ///
+    /// Equal scores keep the draft order; the draft is returned unchanged when
+    /// scoring fails or returns the wrong number of scores.
+    pub(super) fn rerank_draft(
+        draft: Vec<String>,
+        score: impl FnOnce(&[&str]) -> Option<Vec<f32>>,
+    ) -> Vec<String> {
+        if draft.len() < 2 {
+            return draft;
+        }
+        let surfaces: Vec<&str> = draft.iter().map(String::as_str).collect();
+        let Some(scores) = score(&surfaces).filter(|s| s.len() == draft.len()) else {
+            return draft;
+        };
+        let mut scored: Vec<(f32, String)> = scores.into_iter().zip(draft).collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+        scored.into_iter().map(|(_, text)| text).collect()
     }
 
     /// Look up learning cache candidates for a reading (exact + prefix match, max 3).
@@ -399,10 +963,10 @@ impl InputMethodEngine {
         };
         let mut candidates: Vec<Candidate> = Vec::new();
         let mut seen = HashSet::new();
-        let label = CandidateSource::Learning.label().to_string();
+        let annotation = self.annotation(&CandidateSource::Learning);
 
-        // Exact match
-        for (surface, _score) in cache.lookup(reading) {
+        // Exact match, reranked by the previously learned surface
+        for (surface, _score) in cache.lookup_with_context(self.prev_surface.as_deref(), reading) {
             if candidates.len() >= MAX_LEARNING_CANDIDATES {
                 break;
             }
@@ -410,8 +974,9 @@ impl InputMethodEngine {
                 candidates.push(Candidate {
                     text: surface,
                     reading: Some(reading.to_string()),
-                    annotation: Some(label.clone()),
+                    annotation: annotation.clone(),
                     index: candidates.len(),
+                    source: Some(CandidateSource::Learning),
                 });
             }
         }
@@ -428,8 +993,9 @@ impl InputMethodEngine {
                 candidates.push(Candidate {
                     text: surface,
                     reading: Some(full_reading),
-                    annotation: Some(label.clone()),
+                    annotation: annotation.clone(),
                     index: candidates.len(),
+                    source: Some(CandidateSource::Learning),
                 });
             }
         }
@@ -437,18 +1003,40 @@ impl InputMethodEngine {
         candidates
     }
 
-    /// Look up dictionary candidates for a reading (1 page, for live conversion display)
+    /// Look up dictionary candidates for a reading (for live conversion display)
     ///
     /// Searches user dictionary first, then system dictionary.
     pub(super) fn lookup_dict_candidates(&self, reading: &str) -> Vec<Candidate> {
-        self.search_dictionaries(reading, CandidateList::DEFAULT_PAGE_SIZE)
+        self.search_dictionaries(reading, MAX_DICT_CANDIDATES)
             .into_iter()
             .enumerate()
             .map(|(i, ac)| Candidate {
                 text: ac.text,
                 reading: Some(reading.to_string()),
-                annotation: Some(ac.source.label().to_string()),
+                annotation: self.annotation(&ac.source),
+                index: i,
+                source: Some(ac.source),
+            })
+            .collect()
+    }
+
+    /// Look up dictionary completions of a composing reading.
+    ///
+    /// Empty unless predictive conversion is enabled. Each candidate carries
+    /// its full reading so that committing it is learned under that reading.
+    pub(super) fn lookup_predictive_candidates(&self, reading: &str) -> Vec<Candidate> {
+        if !self.config.predictive {
+            return vec![];
+        }
+        self.search_predictions(reading, MAX_DICT_CANDIDATES)
+            .into_iter()
+            .enumerate()
+            .map(|(i, ac)| Candidate {
+                text: ac.text,
+                reading: ac.reading,
+                annotation: self.annotation(&ac.source),
                 index: i,
+                source: Some(ac.source),
             })
             .collect()
     }
@@ -471,6 +1059,31 @@ impl InputMethodEngine {
 
     /// Process key in conversion state
     pub(super) fn process_key_conversion(&mut self, key: &KeyEvent) -> EngineResult {
+        // Previewing the top candidate: Space / Down open the candidate window on it
+        let preview = self.state.is_preview();
+        if preview
+            && (matches!(key.keysym, Keysym::SPACE | Keysym::DOWN | Keysym::TAB)
+                || self.config.keybindings.action_for(key) == Some(KeyAction::NextCandidate))
+        {
+            return self.update_conversion_preedit();
+        }
+
+        // Bound shortcuts (Ctrl+N / Ctrl+P candidate navigation, ... depending on the scheme)
+        if let Some(action) = self.config.keybindings.action_for(key) {
+            match action {
+                KeyAction::NextCandidate => return self.next_candidate(),
+                KeyAction::PrevCandidate => return self.prev_candidate(),
+                KeyAction::ExpandCandidates => {
+                    return self.navigate_candidate(CandidateList::toggle_expanded);
+                }
+                KeyAction::Commit => return self.commit_conversion(),
+                KeyAction::CommitReading => return self.commit_reading(),
+                KeyAction::Cancel => return self.cancel_conversion(),
+                KeyAction::Backspace => return self.backspace_conversion(),
+                _ => {}
+            }
+        }
+
         match key.keysym {
             Keysym::RETURN => self.commit_conversion(),
             Keysym::ESCAPE => self.cancel_conversion(),
@@ -479,20 +1092,25 @@ impl InputMethodEngine {
             Keysym::PAGE_DOWN => self.next_candidate_page(),
             Keysym::PAGE_UP => self.prev_candidate_page(),
             Keysym::BACKSPACE => self.backspace_conversion(),
+            Keysym::DELETE if key.modifiers.control_key => self.delete_selected_candidate(),
+            Keysym::LEFT if key.modifiers.shift_key => self.shrink_segment(),
+            Keysym::RIGHT if key.modifiers.shift_key => self.grow_segment(),
+            Keysym::LEFT => self.move_active_segment(false),
+            Keysym::RIGHT => self.move_active_segment(true),
             _ => {
-                // Ctrl+N / Ctrl+P: emacs-style candidate navigation
-                if key.modifiers.control_key && !key.modifiers.alt_key {
-                    match key.keysym {
-                        Keysym::KEY_N | Keysym::KEY_N_UPPER => return self.next_candidate(),
-                        Keysym::KEY_P | Keysym::KEY_P_UPPER => return self.prev_candidate(),
-                        _ => {}
-                    }
-                }
-
-                // Check for digit selection (1-9)
-                if let Some(digit) = key.keysym.digit_value() {
+                // Check for digit selection (1-9, and 0 for the 10th candidate),
+                // unless no candidates are shown yet
+                if let Some(digit) = key.keysym.digit_value()
+                    && !preview
+                {
                     return self.select_candidate_by_digit(digit);
                 }
+                if key.keysym == Keysym::KEY_0
+                    && !preview
+                    && self.config.page_size >= CandidateList::MAX_PAGE_SIZE
+                {
+                    return self.select_candidate_by_digit(CandidateList::MAX_PAGE_SIZE);
+                }
 
                 // Any printable character: commit current conversion and start new input
                 if let Some(ch) = key.to_char()
@@ -507,28 +1125,56 @@ impl InputMethodEngine {
         }
     }
 
-    /// Get selected text and reading from conversion state, or None if not in conversion
-    fn selected_conversion_info(&self) -> Option<(String, Option<String>)> {
-        match &self.state {
-            InputState::Conversion { candidates, .. } => {
-                let text = candidates.selected_text().unwrap_or("").to_string();
-                let reading = candidates.selected().and_then(|c| c.reading.clone());
-                Some((text, reading))
-            }
-            _ => None,
-        }
+    /// Get the text to commit (each segment's selection, concatenated) and the
+    /// (reading, surface) pairs to learn, or None if not in conversion
+    pub(super) fn conversion_selection(&self) -> Option<(String, Vec<(String, String)>)> {
+        let (segments, _) = self.state.segments()?;
+        let text = segments
+            .iter()
+            .map(|s| s.candidates.selected_text().unwrap_or(""))
+            .collect();
+        let learned = segments
+            .iter()
+            .filter_map(|s| {
+                let c = s.candidates.selected()?;
+                let reading = c.reading.clone()?;
+                // A date/time is only right at the moment it was converted
+                if c.source == Some(CandidateSource::Date) {
+                    return None;
+                }
+                Some((reading, c.text.clone()))
+            })
+            .collect();
+        Some((text, learned))
+    }
+
+    /// Reading covered by the conversion segments (without the unconverted tail)
+    fn conversion_reading(&self) -> String {
+        self.state
+            .segments()
+            .map(|(segments, _)| segments.iter().map(|s| s.reading.as_str()).collect())
+            .unwrap_or_default()
     }
 
-    /// Record a conversion selection in the learning cache.
+    /// Record a conversion selection in the learning cache, with the previously
+    /// learned surface as bigram context.
     pub(super) fn record_learning(&mut self, reading: &str, surface: &str) {
         if let Some(cache) = &mut self.learning {
-            cache.record(reading, surface);
+            cache.record_with_context(self.prev_surface.as_deref(), reading, surface);
+            self.prev_surface = Some(surface.to_string());
+        }
+    }
+
+    /// Record each segment's selection in the learning cache.
+    pub(super) fn record_conversion_learning(&mut self, learned: Vec<(String, String)>) {
+        for (reading, surface) in learned {
+            self.record_learning(&reading, &surface);
         }
     }
 
     /// Commit the current conversion
     fn commit_conversion(&mut self) -> EngineResult {
-        let Some((text, reading)) = self.selected_conversion_info() else {
+        let Some((text, learned)) = self.conversion_selection() else {
             return EngineResult::not_consumed();
         };
 
@@ -536,35 +1182,65 @@ impl InputMethodEngine {
             return EngineResult::consumed();
         }
 
-        if let Some(reading) = &reading {
-            self.record_learning(reading, &text);
-        }
+        self.record_conversion_learning(learned);
+        self.record_commit(&text, &self.conversion_reading(), true);
+
+        self.leave_conversion()
+            .with_action(EngineAction::Commit(text))
+    }
 
-        self.state = InputState::Empty;
-        self.input_buf.text.clear();
+    /// Leave conversion after its text has been committed.
+    ///
+    /// Returns to Empty, or to Composing with the unconverted tail (caret at
+    /// its start) when only the text before the caret was converted.
+    fn leave_conversion(&mut self) -> EngineResult {
+        let tail = match &mut self.state {
+            InputState::Conversion { tail, .. } => std::mem::take(tail),
+            _ => String::new(),
+        };
+        self.input_buf.clear();
 
+        if tail.is_empty() {
+            self.state = InputState::Empty;
+            return EngineResult::consumed()
+                .with_action(EngineAction::UpdatePreedit(Preedit::new()))
+                .with_action(EngineAction::HideCandidates)
+                .with_action(EngineAction::HideAuxText);
+        }
+
+        self.converters.romaji.reset();
+        self.input_buf.insert(&tail);
+        self.input_buf.cursor_pos = 0;
+        let preedit = self.set_composing_state();
         EngineResult::consumed()
-            .with_action(EngineAction::UpdatePreedit(Preedit::new()))
+            .with_action(EngineAction::UpdatePreedit(preedit))
             .with_action(EngineAction::HideCandidates)
-            .with_action(EngineAction::HideAuxText)
-            .with_action(EngineAction::Commit(text))
+            .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()))
     }
 
     /// Commit current conversion and then process a new character as fresh input
     fn commit_conversion_and_continue(&mut self, ch: char) -> EngineResult {
-        let Some((text, reading)) = self.selected_conversion_info() else {
+        let Some((text, learned)) = self.conversion_selection() else {
             return EngineResult::not_consumed();
         };
 
-        if let Some(reading) = &reading {
-            self.record_learning(reading, &text);
-        }
-
-        self.state = InputState::Empty;
-        self.input_buf.text.clear();
-
-        // Start new input with the character
-        let new_input_result = self.start_input(ch);
+        self.record_conversion_learning(learned);
+        self.record_commit(&text, &self.conversion_reading(), true);
+
+        let has_tail = self
+            .state
+            .conversion_tail()
+            .is_some_and(|tail| !tail.is_empty());
+        let new_input_result = if has_tail {
+            // Type the character into the restored tail, at the old caret position
+            self.leave_conversion();
+            self.input_char(ch)
+        } else {
+            self.state = InputState::Empty;
+            self.input_buf.text.clear();
+            // Start new input with the character
+            self.start_input(ch)
+        };
 
         // Combine: commit first, then new input actions
         let mut result = EngineResult::consumed()
@@ -580,6 +1256,10 @@ impl InputMethodEngine {
             return EngineResult::not_consumed();
         }
         let reading = self.input_buf.text.clone();
+        let tail_len = self
+            .state
+            .conversion_tail()
+            .map_or(0, |tail| tail.chars().count());
 
         if reading.is_empty() {
             self.state = InputState::Empty;
@@ -590,9 +1270,9 @@ impl InputMethodEngine {
                 .with_action(EngineAction::HideAuxText);
         }
 
-        // Set up composed_hiragana with the reading
+        // Set up composed_hiragana with the reading, caret back where conversion started
         self.input_buf.text = reading.clone();
-        self.input_buf.cursor_pos = self.input_buf.text.chars().count();
+        self.input_buf.cursor_pos = self.input_buf.text.chars().count() - tail_len;
 
         // Reset romaji converter and set output to reading
         self.converters.romaji.reset();
@@ -611,15 +1291,88 @@ impl InputMethodEngine {
 
     /// Navigate candidates with the given operation, then update preedit
     fn navigate_candidate(&mut self, op: impl FnOnce(&mut CandidateList) -> bool) -> EngineResult {
-        let (selected_text, candidates) = {
-            let Some(candidates) = self.state.candidates_mut() else {
-                return EngineResult::not_consumed();
-            };
-            op(candidates);
-            let text = candidates.selected_text().unwrap_or("").to_string();
-            (text, candidates.clone())
+        let Some(candidates) = self.state.candidates_mut() else {
+            return EngineResult::not_consumed();
+        };
+        op(candidates);
+        self.update_conversion_preedit()
+    }
+
+    /// Move the active segment left or right (stays put at either end)
+    fn move_active_segment(&mut self, forward: bool) -> EngineResult {
+        let InputState::Conversion {
+            segments, active, ..
+        } = &mut self.state
+        else {
+            return EngineResult::not_consumed();
         };
-        self.update_conversion_preedit(&selected_text, &candidates)
+        if forward && *active + 1 < segments.len() {
+            *active += 1;
+        } else if !forward && *active > 0 {
+            *active -= 1;
+        }
+        self.update_conversion_preedit()
+    }
+
+    /// Shrink the active segment by one character (Shift+Left).
+    ///
+    /// The released character moves to the front of the next segment (or a new
+    /// segment), and both segments are converted again.
+    fn shrink_segment(&mut self) -> EngineResult {
+        let Some((segments, active)) = self.state.segments() else {
+            return EngineResult::not_consumed();
+        };
+        let mut head: Vec<char> = segments[active].reading.chars().collect();
+        if head.len() < 2 {
+            return EngineResult::consumed();
+        }
+        let moved = head.pop().unwrap_or_default();
+        let mut tail = moved.to_string();
+        let merge_next = active + 1 < segments.len();
+        if merge_next {
+            tail.push_str(&segments[active + 1].reading);
+        }
+        let head: String = head.into_iter().collect();
+        self.resize_segments(active, merge_next, &head, &tail)
+    }
+
+    /// Grow the active segment by one character (Shift+Right).
+    ///
+    /// The first character of the next segment is taken over; the next segment
+    /// disappears once empty. The affected segments are converted again.
+    fn grow_segment(&mut self) -> EngineResult {
+        let Some((segments, active)) = self.state.segments() else {
+            return EngineResult::not_consumed();
+        };
+        let Some(next) = segments.get(active + 1) else {
+            return EngineResult::consumed();
+        };
+        let mut tail = next.reading.chars();
+        let mut head = segments[active].reading.clone();
+        head.extend(tail.next());
+        let tail: String = tail.collect();
+        self.resize_segments(active, true, &head, &tail)
+    }
+
+    /// Replace the active segment (and the next one if `replace_next`) with
+    /// freshly converted `head` and `tail` segments; an empty `tail` is dropped.
+    fn resize_segments(
+        &mut self,
+        active: usize,
+        replace_next: bool,
+        head: &str,
+        tail: &str,
+    ) -> EngineResult {
+        let mut replacement = vec![self.convert_segment(head)];
+        if !tail.is_empty() {
+            replacement.push(self.convert_segment(tail));
+        }
+        let InputState::Conversion { segments, .. } = &mut self.state else {
+            return EngineResult::not_consumed();
+        };
+        let end = if replace_next { active + 2 } else { active + 1 };
+        segments.splice(active..end, replacement);
+        self.update_conversion_preedit()
     }
 
     /// Select next candidate
@@ -633,18 +1386,31 @@ impl InputMethodEngine {
     }
 
     /// Go to next candidate page
-    fn next_candidate_page(&mut self) -> EngineResult {
+    pub fn next_candidate_page(&mut self) -> EngineResult {
         self.navigate_candidate(CandidateList::next_page)
     }
 
     /// Go to previous candidate page
-    fn prev_candidate_page(&mut self) -> EngineResult {
+    pub fn prev_candidate_page(&mut self) -> EngineResult {
         self.navigate_candidate(CandidateList::prev_page)
     }
 
-    /// Select candidate by digit (1-9)
+    /// Go to a candidate page (0-indexed), e.g. from a candidate window page button.
+    /// Not consumed if not converting or the page is out of range.
+    pub fn goto_candidate_page(&mut self, page: usize) -> EngineResult {
+        let in_range = self
+            .state
+            .candidates()
+            .is_some_and(|c| page < c.total_pages());
+        if !in_range {
+            return EngineResult::not_consumed();
+        }
+        self.navigate_candidate(|c| c.goto_page(page))
+    }
+
+    /// Select candidate by its 1-based position on the current page
     fn select_candidate_by_digit(&mut self, digit: usize) -> EngineResult {
-        let (selected_text, reading) = {
+        {
             let candidates = match self.state.candidates_mut() {
                 Some(c) => c,
                 None => return EngineResult::not_consumed(),
@@ -653,40 +1419,32 @@ impl InputMethodEngine {
             if candidates.select_on_page(digit).is_none() {
                 return EngineResult::consumed();
             }
-
-            let text = candidates.selected_text().unwrap_or("").to_string();
-            let reading = candidates.selected().and_then(|c| c.reading.clone());
-            (text, reading)
+        }
+        let Some((selected_text, learned)) = self.conversion_selection() else {
+            return EngineResult::not_consumed();
         };
 
         // Record learning before committing
-        if let Some(reading) = &reading {
-            self.record_learning(reading, &selected_text);
-        }
+        self.record_conversion_learning(learned);
+        self.record_commit(&selected_text, &self.conversion_reading(), true);
 
         // Commit immediately after digit selection
-
-        self.state = InputState::Empty;
-
-        EngineResult::consumed()
-            .with_action(EngineAction::UpdatePreedit(Preedit::new()))
-            .with_action(EngineAction::HideCandidates)
-            .with_action(EngineAction::HideAuxText)
+        self.leave_conversion()
             .with_action(EngineAction::Commit(selected_text))
     }
 
-    /// Update preedit after candidate selection change
-    fn update_conversion_preedit(
-        &mut self,
-        selected_text: &str,
-        candidates: &CandidateList,
-    ) -> EngineResult {
-        let mut preedit = Preedit::with_text(selected_text);
-        preedit.set_attributes(vec![PreeditAttribute::new(
-            0,
-            selected_text.chars().count(),
-            AttributeType::Highlight,
-        )]);
+    /// Update preedit after a candidate selection or segment change
+    pub(super) fn update_conversion_preedit(&mut self) -> EngineResult {
+        // Any change to the conversion opens the candidate window
+        if let InputState::Conversion { preview, .. } = &mut self.state {
+            *preview = false;
+        }
+        let Some((segments, active)) = self.state.segments() else {
+            return EngineResult::not_consumed();
+        };
+        let tail = self.state.conversion_tail().unwrap_or_default();
+        let preedit = Self::conversion_preedit(segments, active, tail);
+        let candidates = segments[active].candidates.clone();
 
         if let Some(p) = self.state.preedit_mut() {
             *p = preedit.clone();
@@ -701,10 +1459,76 @@ impl InputMethodEngine {
             .with_action(EngineAction::UpdatePreedit(preedit))
             .with_action(EngineAction::ShowCandidates(candidates.clone()))
             .with_action(EngineAction::UpdateAuxText(
-                self.format_aux_conversion_with_page(reading, Some(candidates)),
+                self.format_aux_conversion_with_page(reading, Some(&candidates)),
             ))
     }
 
+    /// Remove the highlighted candidate from the user dictionary and the
+    /// learning cache (Ctrl+Delete), and drop it from the candidate list
+    /// unless it is the last one.
+    ///
+    /// The user dictionary files are rewritten right away; the learning cache
+    /// is marked dirty and persisted by the next [`Self::save_learning`].
+    fn delete_selected_candidate(&mut self) -> EngineResult {
+        let Some((segments, active)) = self.state.segments() else {
+            return EngineResult::not_consumed();
+        };
+        let segment = &segments[active];
+        let Some(selected) = segment.candidates.selected() else {
+            return EngineResult::consumed();
+        };
+        let surface = selected.text.clone();
+        let reading = selected
+            .reading
+            .clone()
+            .unwrap_or_else(|| segment.reading.clone());
+
+        let removed = self
+            .dicts
+            .user
+            .as_mut()
+            .map(|user| user.remove(&reading, &surface));
+        let from_user = match removed {
+            Some(Ok(removed)) => removed,
+            Some(Err(e)) => {
+                debug!(
+                    "Failed to remove \"{}\" from the user dictionary: {}",
+                    surface, e
+                );
+                false
+            }
+            None => false,
+        };
+        if from_user {
+            self.remove_from_user_dict_files(&reading, &surface);
+        }
+        let from_learning = self
+            .learning
+            .as_mut()
+            .is_some_and(|cache| cache.forget(&reading, &surface));
+        debug!(
+            "delete candidate: reading=\"{}\" surface=\"{}\" user_dict={} learning={}",
+            reading, surface, from_user, from_learning
+        );
+
+        if let Some(candidates) = self.state.candidates_mut()
+            && candidates.len() > 1
+        {
+            candidates.remove_selected();
+        }
+        self.update_conversion_preedit()
+    }
+
+    /// Remove a user dictionary entry from the files it was loaded from, so
+    /// the removal survives a restart.
+    fn remove_from_user_dict_files(&self, reading: &str, surface: &str) {
+        for path in &self.dicts.user_files {
+            if let Err(e) = UserDictionary::remove_from_file(path, reading, surface) {
+                debug!("Failed to remove \"{}\" from {:?}: {}", surface, path, e);
+            }
+        }
+    }
+
     /// Handle backspace in conversion mode
     fn backspace_conversion(&mut self) -> EngineResult {
         // Return to hiragana mode with the reading
@@ -0,0 +1,73 @@
+//! ConversionCache: LRU cache of kana-kanji model results.
+//!
+//! Re-typing the same word re-runs inference with identical inputs, so the
+//! model output is cached per (reading, context, candidate count, model).
+//! The cache lives for the whole session and is not cleared by `reset()`.
+
+use std::collections::HashMap;
+
+/// Inputs that fully determine a model conversion result.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct ConversionCacheKey {
+    /// Reading in katakana (model input)
+    pub katakana: String,
+    /// Left context passed to the model
+    pub api_context: String,
+    /// Number of candidates requested
+    pub num_candidates: usize,
+    /// Display name of the model(s) used for the conversion
+    pub model_id: String,
+}
+
+/// Least-recently-used cache of conversion results.
+pub(super) struct ConversionCache {
+    /// Cached candidates and the tick of their last use
+    entries: HashMap<ConversionCacheKey, (Vec<String>, u64)>,
+    /// Maximum number of entries (0 = disabled)
+    capacity: usize,
+    /// Monotonic use counter for LRU ordering
+    tick: u64,
+}
+
+impl ConversionCache {
+    /// Create a cache holding at most `capacity` results (0 disables caching).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            tick: 0,
+        }
+    }
+
+    /// Look up a cached result, marking it as recently used.
+    pub fn get(&mut self, key: &ConversionCacheKey) -> Option<Vec<String>> {
+        self.tick += 1;
+        let (candidates, last_used) = self.entries.get_mut(key)?;
+        *last_used = self.tick;
+        Some(candidates.clone())
+    }
+
+    /// Store a result, evicting the least recently used entry when full.
+    pub fn insert(&mut self, key: ConversionCacheKey, candidates: Vec<String>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if !self.entries.contains_key(&key)
+            && self.entries.len() >= self.capacity
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(k, _)| k.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(key, (candidates, self.tick));
+    }
+
+    /// Number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
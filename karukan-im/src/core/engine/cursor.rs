@@ -1,16 +1,90 @@
 //! Cursor movement and character deletion
 
+use karukan_engine::BackspaceResult;
+
 use super::*;
 
+/// Character class for word-wise caret movement (Ctrl+Left/Right).
+///
+/// A word is a maximal run of one class, so the caret stops wherever the
+/// class changes: between scripts, and to or from punctuation. A prolonged
+/// sound mark (ー) belongs to the class of the character before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WordClass {
+    Hiragana,
+    Katakana,
+    Kanji,
+    /// Latin letters and digits (half- or full-width)
+    Latin,
+    /// Punctuation, symbols and spaces
+    Other,
+}
+
+impl WordClass {
+    fn of(c: char) -> Self {
+        match c {
+            '\u{3041}'..='\u{3096}' | 'ゝ' | 'ゞ' => WordClass::Hiragana,
+            '\u{30A1}'..='\u{30FA}' | 'ヽ' | 'ヾ' => WordClass::Katakana,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々' | '〆' => WordClass::Kanji,
+            'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '０'..='９' => WordClass::Latin,
+            c if c.is_ascii_alphanumeric() => WordClass::Latin,
+            _ => WordClass::Other,
+        }
+    }
+}
+
+/// Class of every character of `text`, with ー joining the class before it
+fn word_classes(text: &str) -> Vec<WordClass> {
+    let mut classes: Vec<WordClass> = Vec::new();
+    for c in text.chars() {
+        let class = match classes.last() {
+            Some(&prev) if c == 'ー' => prev,
+            _ => WordClass::of(c),
+        };
+        classes.push(class);
+    }
+    classes
+}
+
+/// Start of the word before `pos` (char index), or 0
+fn word_start_before(text: &str, pos: usize) -> usize {
+    let classes = word_classes(text);
+    let Some(&class) = pos.checked_sub(1).and_then(|i| classes.get(i)) else {
+        return 0;
+    };
+    classes[..pos]
+        .iter()
+        .rposition(|&c| c != class)
+        .map_or(0, |i| i + 1)
+}
+
+/// End of the word at `pos` (char index), or the text length
+fn word_end_after(text: &str, pos: usize) -> usize {
+    let classes = word_classes(text);
+    let Some(&class) = classes.get(pos) else {
+        return classes.len();
+    };
+    classes[pos..]
+        .iter()
+        .position(|&c| c != class)
+        .map_or(classes.len(), |i| pos + i)
+}
+
 impl InputMethodEngine {
-    /// Common helper for cursor movement: flush romaji, clear live conversion, set new position
-    fn move_caret(&mut self, new_pos: usize) -> EngineResult {
+    /// Move pending romaji into the composed text and start the converter afresh
+    fn flush_pending_romaji(&mut self) {
         if !self.converters.romaji.buffer().is_empty() {
             self.flush_romaji_to_composed();
             self.converters.romaji.reset();
         }
+    }
+
+    /// Common helper for cursor movement: flush romaji, clear live conversion, set new position
+    fn move_caret(&mut self, new_pos: usize) -> EngineResult {
+        self.flush_pending_romaji();
         self.live.text.clear();
         self.input_buf.cursor_pos = new_pos;
+        self.input_buf.romaji.clear();
         let preedit = self.set_composing_state();
         EngineResult::consumed()
             .with_action(EngineAction::UpdatePreedit(preedit))
@@ -20,22 +94,31 @@ impl InputMethodEngine {
 
     /// Handle backspace in composing mode
     pub(super) fn backspace_composing(&mut self) -> EngineResult {
-        // If romaji buffer is not empty, backspace from buffer (not from composed text)
-        if !self.converters.romaji.buffer().is_empty() {
-            self.converters.romaji.backspace();
-            if let Some(result) = self.try_reset_if_empty() {
-                return result;
-            }
+        match self.converters.romaji.backspace() {
+            // Pending romaji sits at the caret: drop its last letter only
+            BackspaceResult::RemovedBuffer(_) => {
+                self.input_buf.romaji.pop();
+                if let Some(result) = self.try_reset_if_empty() {
+                    return result;
+                }
 
-            let preedit = self.set_composing_state();
-            return EngineResult::consumed()
-                .with_action(EngineAction::UpdatePreedit(preedit))
-                .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()));
+                let preedit = self.set_composing_state();
+                return EngineResult::consumed()
+                    .with_action(EngineAction::UpdatePreedit(preedit))
+                    .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()));
+            }
+            // The converter's output only mirrors `input_buf.text` while the caret
+            // stays at the end (caret moves and alphabet input bypass it), so the
+            // composed text is always edited at the caret
+            BackspaceResult::RemovedOutput(_) | BackspaceResult::Empty => {}
         }
 
         // Remove character before cursor from composed_hiragana
         if self.input_buf.cursor_pos > 0 {
             self.input_buf.remove_char_before_cursor();
+            self.input_buf.romaji.clear();
+        } else if let Some(result) = self.try_reset_if_empty() {
+            return result;
         } else {
             // Nothing to delete
             return EngineResult::consumed();
@@ -61,17 +144,35 @@ impl InputMethodEngine {
         self.move_caret(new_pos)
     }
 
+    /// Move caret to the start of the word before it (Ctrl+Left)
+    pub(super) fn move_caret_word_left(&mut self) -> EngineResult {
+        self.flush_pending_romaji();
+        let new_pos = word_start_before(&self.input_buf.text, self.input_buf.cursor_pos);
+        self.move_caret(new_pos)
+    }
+
+    /// Move caret to the end of the word after it (Ctrl+Right)
+    pub(super) fn move_caret_word_right(&mut self) -> EngineResult {
+        self.flush_pending_romaji();
+        let new_pos = word_end_after(&self.input_buf.text, self.input_buf.cursor_pos);
+        self.move_caret(new_pos)
+    }
+
     /// Handle delete key in hiragana mode
     pub(super) fn delete_composing(&mut self) -> EngineResult {
-        // If romaji buffer is not empty, don't delete from composed (buffer is at cursor)
-        if !self.converters.romaji.buffer().is_empty() {
-            return EngineResult::consumed();
-        }
+        // Flush pending romaji first so the buffer and the composed text agree
+        // (the buffer sits at the caret, so the flushed kana end up before it)
+        let flushed = !self.converters.romaji.buffer().is_empty();
+        self.flush_pending_romaji();
 
-        // Delete character at cursor position
+        // Delete character at cursor position; at the end there is nothing to delete
         if self.input_buf.remove_char_at_cursor().is_none() {
+            if flushed {
+                return self.refresh_input_state();
+            }
             return EngineResult::consumed();
         }
+        self.input_buf.romaji.clear();
 
         if let Some(result) = self.try_reset_if_empty() {
             return result;
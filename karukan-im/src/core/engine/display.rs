@@ -7,57 +7,65 @@ impl InputMethodEngine {
     /// Format: composed[:cursor] + romaji_buffer + composed[cursor:]
     /// In katakana mode, the composed parts are converted to katakana.
     pub(super) fn build_input_display(&self) -> String {
-        let before: String = self
-            .input_buf
-            .text
-            .chars()
-            .take(self.input_buf.cursor_pos)
-            .collect();
-        let after: String = self
-            .input_buf
-            .text
-            .chars()
-            .skip(self.input_buf.cursor_pos)
-            .collect();
-        let buffer = self.converters.romaji.buffer();
+        let (before, buffer, after) = self.input_display_parts();
+        format!("{}{}{}", before, buffer, after)
+    }
 
-        let katakana = self.input_mode == InputMode::Katakana;
-        let display_before = if katakana {
-            Self::hiragana_to_katakana(&before)
-        } else {
-            before
-        };
-        let display_after = if katakana {
-            Self::hiragana_to_katakana(&after)
+    /// Display text split at the caret: (text before it, romaji buffer, text after it)
+    fn input_display_parts(&self) -> (String, &str, String) {
+        // Convert the whole text at once so long vowels across the cursor are
+        // detected; the katakana conversion keeps character offsets intact.
+        let text = if self.input_mode.is_katakana() {
+            self.to_katakana_output(&self.input_buf.text)
         } else {
-            after
+            self.input_buf.text.clone()
         };
-
-        format!("{}{}{}", display_before, buffer, display_after)
-    }
-
-    /// Get the caret position in the display text (in characters)
-    pub(super) fn display_caret_position(&self) -> usize {
-        self.input_buf.cursor_pos + self.converters.romaji.buffer().chars().count()
+        let before: String = text.chars().take(self.input_buf.cursor_pos).collect();
+        let after: String = text.chars().skip(self.input_buf.cursor_pos).collect();
+        (before, self.converters.romaji.buffer(), after)
     }
 
     /// Build a preedit for composing state.
     /// If live conversion text is present, shows live_text + romaji_buffer with caret at end.
     /// Otherwise shows the input buffer display with cursor-based caret.
+    /// Kana (or the live conversion) is underlined and the unconverted romaji buffer
+    /// highlighted, so the part that Enter would commit as is stands out.
+    /// With `show_romaji`, the typed romaji is appended as a highlighted segment.
     pub(super) fn build_composing_preedit(&self) -> Preedit {
-        let (display, caret) = if !self.live.text.is_empty() {
-            let buffer = self.converters.romaji.buffer();
-            let display = format!("{}{}", self.live.text, buffer);
-            let caret = display.chars().count();
-            (display, caret)
+        if let Some(form) = self.input_buf.function_key_form {
+            return Preedit::with_text_underlined(self.function_key_text(form));
+        }
+        let buffer = self.converters.romaji.buffer();
+        // The typed romaji already ends with the pending buffer, so the buffer
+        // segment is left out when the romaji is appended
+        let show_romaji = self.config.show_romaji
+            && self.input_mode != InputMode::Alphabet
+            && !self.input_buf.romaji.is_empty();
+        let shown_buffer = if show_romaji { "" } else { buffer };
+        let mut segments = if !self.live.text.is_empty() {
+            vec![
+                PreeditSegment::new(self.live.text.as_str(), AttributeType::Underline),
+                PreeditSegment::highlighted(shown_buffer),
+            ]
+        } else {
+            let (before, _, after) = self.input_display_parts();
+            vec![
+                PreeditSegment::new(before, AttributeType::Underline),
+                PreeditSegment::highlighted(shown_buffer),
+                PreeditSegment::new(after, AttributeType::Underline),
+            ]
+        };
+        let caret = if !self.live.text.is_empty() {
+            self.live.text.chars().count() + shown_buffer.chars().count()
         } else {
-            (self.build_input_display(), self.display_caret_position())
+            self.input_buf.cursor_pos + shown_buffer.chars().count()
         };
-        let len = display.chars().count();
-        let mut preedit = Preedit::with_text(&display);
-        preedit.set_caret(caret);
-        preedit.set_attributes(vec![PreeditAttribute::underline(0, len)]);
-        preedit
+        if show_romaji {
+            // Learner aid: typed romaji after the kana, styled apart from it
+            segments.push(PreeditSegment::highlighted(&self.input_buf.romaji));
+        }
+        segments.retain(|seg| !seg.text.is_empty());
+        Preedit::from_segments(segments, caret)
     }
 
     /// Get combined context display string (lctx: ... rctx: ...)
@@ -101,7 +109,9 @@ impl InputMethodEngine {
         let base = match self.input_mode {
             InputMode::Alphabet => "[A]",
             InputMode::Katakana => "[カ]",
+            InputMode::KatakanaLock => "[カ🔒]",
             InputMode::Hiragana => "[あ]",
+            InputMode::KanaDirect => "[かな]",
         };
         if self.live.enabled {
             format!("⚡{}", base)
@@ -173,7 +183,7 @@ impl InputMethodEngine {
             .filter(|a| !a.is_empty())
             .map(|a| format!(" | {}", a))
             .unwrap_or_default();
-        if ctx.is_empty() {
+        let aux = if ctx.is_empty() {
             format!(
                 "[変換]{} {} | {} {} | {}{}",
                 page_info, reading, timing, tokens, model, source_label
@@ -183,6 +193,28 @@ impl InputMethodEngine {
                 "[変換]{} {} | {} | {} {} | {}{}",
                 page_info, reading, ctx, timing, tokens, model, source_label
             )
+        };
+        if self.config.aux_show_reading {
+            format!("{} {}", self.aux_reading_prefix(candidates), aux)
+        } else {
+            aux
+        }
+    }
+
+    /// Reading of the selected candidate and its position, e.g. "きょう [1/3]",
+    /// put first in the aux text for screen readers.
+    ///
+    /// Falls back to the composed reading when the candidate carries none.
+    fn aux_reading_prefix(&self, candidates: Option<&CandidateList>) -> String {
+        let selected = candidates.and_then(|c| c.selected());
+        let reading = selected
+            .and_then(|c| c.reading.as_deref())
+            .unwrap_or(&self.input_buf.text);
+        match candidates {
+            Some(list) if selected.is_some() => {
+                format!("{} [{}/{}]", reading, list.cursor() + 1, list.len())
+            }
+            _ => reading.to_string(),
         }
     }
 
@@ -219,13 +251,53 @@ impl InputMethodEngine {
         karukan_engine::kana::hiragana_to_katakana(hiragana)
     }
 
+    /// Convert hiragana to katakana-mode output.
+    /// With `katakana_long_vowel`, a repeated vowel becomes ー (らあめん → ラーメン).
+    pub(super) fn to_katakana_output(&self, hiragana: &str) -> String {
+        if self.config.katakana_long_vowel {
+            Self::hiragana_to_katakana(&karukan_engine::vowel_repeat_to_long_vowel(hiragana))
+        } else {
+            Self::hiragana_to_katakana(hiragana)
+        }
+    }
+
+    /// The composing text in a form forced by F6–F10.
+    ///
+    /// Always derived from the reading, so pressing the same key again is a no-op.
+    /// The latin forms use the typed romaji, or the reading transliterated back
+    /// when the keystrokes are no longer known (e.g. after editing).
+    pub(super) fn function_key_text(&self, form: FunctionKeyForm) -> String {
+        let reading = &self.input_buf.text;
+        let romaji = || {
+            if self.input_buf.romaji.is_empty() {
+                karukan_engine::hiragana_to_romaji(&karukan_engine::katakana_to_hiragana(reading))
+            } else {
+                self.input_buf.romaji.clone()
+            }
+        };
+        match form {
+            FunctionKeyForm::Hiragana => karukan_engine::katakana_to_hiragana(reading),
+            FunctionKeyForm::Katakana => Self::hiragana_to_katakana(reading),
+            FunctionKeyForm::HalfwidthKatakana => {
+                karukan_engine::katakana_to_halfwidth(&Self::hiragana_to_katakana(reading))
+            }
+            FunctionKeyForm::FullwidthLatin => karukan_engine::ascii_to_fullwidth(&romaji()),
+            FunctionKeyForm::HalfwidthLatin => romaji(),
+        }
+    }
+
     /// Truncate context to safe size for API calls
+    /// With `normalize_context`, the context is NFKC-normalized first, since
+    /// normalization can change its length (e.g. ㌔ → キロ).
     pub(super) fn truncate_context_for_api(&self) -> String {
         match self
             .surrounding_context
             .as_ref()
             .and_then(|ctx| ctx.left.as_deref())
         {
+            Some(left) if self.config.normalize_context => {
+                self.truncate_context(&normalize_nfkc(left))
+            }
             Some(left) => self.truncate_context(left),
             None => String::new(),
         }
@@ -1,9 +1,10 @@
 //! Engine initialization (model loading, dictionary setup)
 
-use anyhow::Result;
-use tracing::debug;
+use anyhow::{Context, Result};
+use tracing::{debug, warn};
 
 use super::*;
+use crate::config::settings::DictionaryMode;
 
 /// Create a KanaKanjiConverter from a variant id, optionally setting thread count.
 fn create_converter(variant_id: &str, n_threads: u32) -> Result<KanaKanjiConverter> {
@@ -103,6 +104,97 @@ impl InputMethodEngine {
         }
     }
 
+    /// Layer user-defined romaji rules from a TSV file over the romaji table
+    pub fn load_custom_romaji_rules(&mut self, path: &std::path::Path) -> Result<()> {
+        self.converters.romaji.load_custom_rules(path)?;
+        debug!("Custom romaji rules loaded from {:?}", path);
+        Ok(())
+    }
+
+    /// Initialize the phrase dictionary for phrase expansion candidates
+    ///
+    /// Uses `phrase_dict_path` from settings if specified, otherwise defaults to
+    /// `data_dir/phrases.tsv`. The file is loaded with `Dictionary::load_auto()`
+    /// (Mozc TSV: `reading\tphrase`, or KRKN binary).
+    pub fn init_phrase_dictionary(&mut self, phrase_dict_path: Option<&str>) {
+        if self.dicts.phrase.is_some() {
+            return;
+        }
+
+        let path = if let Some(p) = phrase_dict_path {
+            std::path::PathBuf::from(p)
+        } else if let Some(path) = Settings::phrase_dict_file() {
+            path
+        } else {
+            debug!("Could not determine data directory for phrase dictionary");
+            return;
+        };
+
+        if !path.exists() {
+            debug!("Phrase dictionary not found at {:?}, skipping", path);
+            return;
+        }
+
+        match Dictionary::load_auto(&path) {
+            Ok(dict) => {
+                debug!("Phrase dictionary loaded from {:?}", path);
+                self.dicts.phrase = Some(dict);
+            }
+            Err(e) => {
+                debug!("Failed to load phrase dictionary from {:?}: {}", path, e);
+            }
+        }
+    }
+
+    /// Initialize extra dictionaries listed in `conversion.dictionaries`.
+    ///
+    /// Each file is loaded with `Dictionary::load_auto()` (KRKN binary, Sudachi
+    /// CSV, or Mozc TSV). A file that fails to load is reported and skipped
+    /// without aborting the others. With [`DictionaryMode::Merge`] the loaded
+    /// dictionaries are merged into one (earlier files win on duplicate
+    /// surfaces); with [`DictionaryMode::Layer`] they are kept separate and
+    /// searched in list order. If merging fails, every loaded file is reported
+    /// as failed, since none of them is installed.
+    pub fn init_extra_dictionaries(
+        &mut self,
+        paths: &[String],
+        mode: DictionaryMode,
+    ) -> Vec<DictionaryLoadStatus> {
+        let mut statuses = Vec::with_capacity(paths.len());
+        let mut dicts = Vec::new();
+        for path in paths {
+            let path = std::path::PathBuf::from(path);
+            let result = match Dictionary::load_auto(&path) {
+                Ok(dict) => {
+                    let count = dict.entry_count();
+                    debug!("Dictionary loaded from {:?} ({} readings)", path, count);
+                    dicts.push(dict);
+                    Ok(count)
+                }
+                Err(e) => {
+                    debug!("Failed to load dictionary from {:?}: {}", path, e);
+                    Err(e.to_string())
+                }
+            };
+            statuses.push(DictionaryLoadStatus { path, result });
+        }
+
+        match mode {
+            DictionaryMode::Layer => self.dicts.extra = dicts,
+            DictionaryMode::Merge => match Dictionary::merge(dicts) {
+                Ok(merged) => self.dicts.extra = merged.into_iter().collect(),
+                Err(e) => {
+                    warn!("Failed to merge dictionaries: {}", e);
+                    for status in statuses.iter_mut().filter(|s| s.result.is_ok()) {
+                        status.result = Err(format!("merge failed: {}", e));
+                    }
+                }
+            },
+        }
+
+        statuses
+    }
+
     /// Initialize the learning cache from disk.
     ///
     /// Loads `~/.local/share/karukan-im/learning.tsv` if it exists.
@@ -112,14 +204,15 @@ impl InputMethodEngine {
             return;
         }
 
+        let params = self.config.learning_score;
         let Some(path) = Settings::learning_file() else {
             debug!("Could not determine learning cache path");
-            self.learning = Some(LearningCache::new(max_entries));
+            self.learning = Some(LearningCache::with_params(max_entries, params));
             return;
         };
 
         if path.exists() {
-            match LearningCache::load(&path, max_entries) {
+            match LearningCache::load_with_params(&path, max_entries, params) {
                 Ok(cache) => {
                     debug!(
                         "Learning cache loaded from {:?} ({} entries)",
@@ -127,15 +220,16 @@ impl InputMethodEngine {
                         cache.entry_count()
                     );
                     self.learning = Some(cache);
+                    self.evict_stale_learning();
                 }
                 Err(e) => {
                     debug!("Failed to load learning cache from {:?}: {}", path, e);
-                    self.learning = Some(LearningCache::new(max_entries));
+                    self.learning = Some(LearningCache::with_params(max_entries, params));
                 }
             }
         } else {
             debug!("Learning cache not found at {:?}, starting empty", path);
-            self.learning = Some(LearningCache::new(max_entries));
+            self.learning = Some(LearningCache::with_params(max_entries, params));
         }
     }
 
@@ -164,30 +258,24 @@ impl InputMethodEngine {
             return;
         }
 
-        let Ok(entries) = std::fs::read_dir(&dir) else {
+        let Ok(paths) = user_dictionary_files(&dir) else {
             debug!("Failed to read user dictionary directory {:?}", dir);
             return;
         };
-        let mut paths: Vec<std::path::PathBuf> = entries
-            .filter_map(|e| e.ok())
-            .map(|e| e.path())
-            .filter(|p| p.is_file())
-            .collect();
 
         if paths.is_empty() {
             debug!("No files in user dictionary directory {:?}", dir);
             return;
         }
 
-        // Sort for deterministic load order (alphabetical)
-        paths.sort();
-
         let mut dicts = Vec::new();
+        let mut loaded = Vec::new();
         for path in &paths {
             match Dictionary::load_auto(path) {
                 Ok(dict) => {
                     debug!("User dictionary loaded from {:?}", path);
                     dicts.push(dict);
+                    loaded.push(path.clone());
                 }
                 Err(e) => {
                     debug!("Failed to load user dictionary from {:?}: {}", path, e);
@@ -199,14 +287,17 @@ impl InputMethodEngine {
             return;
         }
 
-        match Dictionary::merge(dicts) {
-            Ok(Some(merged)) => {
+        match Dictionary::merge(dicts)
+            .and_then(|d| d.map(UserDictionary::from_dictionary).transpose())
+        {
+            Ok(Some(user)) => {
                 debug!(
                     "User dictionaries merged successfully ({} files from {:?})",
                     paths.len(),
                     dir
                 );
-                self.dicts.user = Some(merged);
+                self.dicts.user = Some(user);
+                self.dicts.user_files = loaded;
             }
             Ok(None) => {}
             Err(e) => {
@@ -214,4 +305,55 @@ impl InputMethodEngine {
             }
         }
     }
+
+    /// Reload the user dictionary from `path`, replacing the current one.
+    ///
+    /// `path` is a dictionary file, or a directory whose files are all loaded
+    /// and merged as in [`init_user_dictionaries`](Self::init_user_dictionaries).
+    /// Safe mid-session: a conversion in progress keeps its candidate list, and
+    /// only new lookups see the reloaded data. If any file fails to load, the
+    /// previous dictionary is kept and the error is returned.
+    pub fn reload_user_dictionary(&mut self, path: &std::path::Path) -> Result<()> {
+        let load = |p: &std::path::Path| {
+            Dictionary::load_auto(p)
+                .with_context(|| format!("failed to load user dictionary {}", p.display()))
+        };
+        let files = if path.is_dir() {
+            user_dictionary_files(path)
+                .with_context(|| format!("failed to read {}", path.display()))?
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let dicts = files.iter().map(|p| load(p)).collect::<Result<Vec<_>>>()?;
+        let dict = Dictionary::merge(dicts)?;
+
+        debug!(
+            "User dictionary reloaded from {:?} ({} readings)",
+            path,
+            dict.as_ref().map_or(0, Dictionary::entry_count)
+        );
+        self.dicts.user = dict.map(UserDictionary::from_dictionary).transpose()?;
+        self.dicts.user_files = files;
+        Ok(())
+    }
+
+    /// Save the user dictionary (including runtime edits) to `path` as Mozc TSV.
+    pub fn save_user_dictionary(&self, path: &std::path::Path) -> Result<()> {
+        if let Some(user) = &self.dicts.user {
+            user.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Files in a user dictionary directory, sorted for a deterministic load
+/// order (earlier files have higher priority after merging).
+fn user_dictionary_files(dir: &std::path::Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+    Ok(paths)
 }
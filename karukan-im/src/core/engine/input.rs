@@ -1,8 +1,11 @@
 //! Composing input handling (Empty and Composing states)
 
+use std::time::Duration;
+
 use karukan_engine::ConversionEvent;
 
 use super::*;
+use crate::core::kana_layout;
 
 /// Append candidates to `target`, skipping duplicates and updating indices.
 fn append_candidates_dedup(target: &mut Vec<Candidate>, source: Vec<Candidate>) {
@@ -23,6 +26,20 @@ impl InputMethodEngine {
             return EngineResult::consumed().with_action(EngineAction::UpdatePreedit(preedit));
         }
 
+        // Katakana lock: no inference and no candidates, the preedit is what gets committed
+        if self.input_mode == InputMode::KatakanaLock {
+            self.live.text.clear();
+            let preedit = self.set_composing_state();
+            return EngineResult::consumed()
+                .with_action(EngineAction::UpdatePreedit(preedit))
+                .with_action(EngineAction::HideCandidates)
+                .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()));
+        }
+
+        if self.live_conversion_debounced() {
+            return self.extend_live_conversion();
+        }
+
         // Run auto-suggest (skip in alphabet mode — no hiragana to convert)
         let candidates =
             if self.input_mode != InputMode::Alphabet && !self.input_buf.text.is_empty() {
@@ -44,6 +61,10 @@ impl InputMethodEngine {
             let reading = self.input_buf.text.clone();
             let mut all_candidates = self.lookup_learning_candidates(&reading);
             append_candidates_dedup(&mut all_candidates, self.lookup_dict_candidates(&reading));
+            append_candidates_dedup(
+                &mut all_candidates,
+                self.lookup_predictive_candidates(&reading),
+            );
             if all_candidates.is_empty() {
                 return EngineResult::consumed()
                     .with_action(EngineAction::UpdatePreedit(preedit))
@@ -52,31 +73,19 @@ impl InputMethodEngine {
             }
             return EngineResult::consumed()
                 .with_action(EngineAction::UpdatePreedit(preedit))
-                .with_action(EngineAction::ShowCandidates(CandidateList::new(
+                .with_action(EngineAction::ShowCandidates(CandidateList::with_page_size(
                     all_candidates,
+                    self.config.page_size,
                 )))
                 .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()));
         };
 
         // Live conversion mode: show converted text in preedit
-        if self.live.enabled && self.input_mode != InputMode::Katakana {
+        if self.live.enabled && !self.input_mode.is_katakana() {
             self.live.text = candidates[0].clone();
-            let preedit = self.set_composing_state();
-            let mut result =
-                EngineResult::consumed().with_action(EngineAction::UpdatePreedit(preedit));
-
-            // Learning candidates first, then dictionary candidates
-            let mut all_candidates = self.lookup_learning_candidates(&reading);
-            append_candidates_dedup(&mut all_candidates, self.lookup_dict_candidates(&reading));
-            if all_candidates.is_empty() {
-                result = result.with_action(EngineAction::HideCandidates);
-            } else {
-                result = result.with_action(EngineAction::ShowCandidates(CandidateList::new(
-                    all_candidates,
-                )));
-            }
-            let aux = self.format_aux_suggest(&self.input_buf.text.clone());
-            return result.with_action(EngineAction::UpdateAuxText(aux));
+            self.live.reading = reading.clone();
+            self.live.typed.clear();
+            return self.live_conversion_result(&reading);
         }
 
         // Normal auto-suggest: show hiragana preedit + learning/model/dict candidates
@@ -92,19 +101,92 @@ impl InputMethodEngine {
         append_candidates_dedup(&mut all_candidates, model_candidates);
         // Then dictionary candidates
         append_candidates_dedup(&mut all_candidates, self.lookup_dict_candidates(&reading));
+        // Then predictions (completions of the reading)
+        append_candidates_dedup(
+            &mut all_candidates,
+            self.lookup_predictive_candidates(&reading),
+        );
         let aux = self.format_aux_suggest(&self.input_buf.text.clone());
         EngineResult::consumed()
             .with_action(EngineAction::UpdatePreedit(preedit))
-            .with_action(EngineAction::ShowCandidates(CandidateList::new(
+            .with_action(EngineAction::ShowCandidates(CandidateList::with_page_size(
                 all_candidates,
+                self.config.page_size,
             )))
             .with_action(EngineAction::UpdateAuxText(aux))
     }
 
+    /// Whether this key comes within `debounce_ms` of the previous key that
+    /// refreshed live conversion. Records the key's time for the next check.
+    fn live_conversion_debounced(&mut self) -> bool {
+        if !self.live.enabled
+            || self.config.debounce_ms == 0
+            || self.input_mode != InputMode::Hiragana
+            || self.input_buf.text.is_empty()
+        {
+            return false;
+        }
+        let now = (self.clock)();
+        let previous = self.metrics.last_live_key_at.replace(now);
+        previous.is_some_and(|at| {
+            now.saturating_duration_since(at) < Duration::from_millis(self.config.debounce_ms)
+        })
+    }
+
+    /// Live conversion without inference: keep the last result and append the
+    /// kana typed since. Any other edit (Backspace, caret moves) falls back to
+    /// the hiragana preedit until the next real conversion.
+    fn extend_live_conversion(&mut self) -> EngineResult {
+        let reading = self.input_buf.text.clone();
+        match reading.strip_prefix(self.live.reading.as_str()) {
+            Some(typed) if !self.live.text.is_empty() => {
+                let converted_len = self.live.text.len() - self.live.typed.len();
+                self.live.text.truncate(converted_len);
+                self.live.text.push_str(typed);
+                self.live.typed = typed.to_string();
+            }
+            _ => self.live.text.clear(),
+        }
+        self.live_conversion_result(&reading)
+    }
+
+    /// Whether committing the composing text teaches the learning cache that
+    /// it converts `reading`
+    ///
+    /// F6–F10 forms are not conversions, and live conversion text extended
+    /// with debounced kana was only partly converted.
+    pub(super) fn composing_commit_is_learnable(&self, reading: &str) -> bool {
+        self.input_buf.function_key_form.is_none()
+            && (self.live.text.is_empty() || self.live.reading == reading)
+    }
+
+    /// Live conversion preedit with the learning and dictionary candidates of `reading`
+    fn live_conversion_result(&mut self, reading: &str) -> EngineResult {
+        let preedit = self.set_composing_state();
+        let mut result = EngineResult::consumed().with_action(EngineAction::UpdatePreedit(preedit));
+
+        // Learning candidates first, then dictionary candidates
+        let mut all_candidates = self.lookup_learning_candidates(reading);
+        append_candidates_dedup(&mut all_candidates, self.lookup_dict_candidates(reading));
+        append_candidates_dedup(
+            &mut all_candidates,
+            self.lookup_predictive_candidates(reading),
+        );
+        if all_candidates.is_empty() {
+            result = result.with_action(EngineAction::HideCandidates);
+        } else {
+            result = result.with_action(EngineAction::ShowCandidates(
+                CandidateList::with_page_size(all_candidates, self.config.page_size),
+            ));
+        }
+        let aux = self.format_aux_suggest(&self.input_buf.text.clone());
+        result.with_action(EngineAction::UpdateAuxText(aux))
+    }
+
     /// Process key in empty state
     pub(super) fn process_key_empty(&mut self, key: &KeyEvent, shift_active: bool) -> EngineResult {
-        // Ctrl+Space: start input with full-width space
-        if key.modifiers.control_key && key.keysym == Keysym::SPACE {
+        // Ctrl+Space (by default): start input with full-width space
+        if self.config.keybindings.action_for(key) == Some(KeyAction::FullwidthSpace) {
             self.converters.romaji.reset();
             self.input_buf.clear();
             self.input_buf.insert("\u{3000}");
@@ -114,11 +196,22 @@ impl InputMethodEngine {
                 .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()));
         }
 
+        if self.input_mode == InputMode::KanaDirect {
+            return match self.kana_direct_char(key) {
+                Some(kana) => self.start_input(kana),
+                None => EngineResult::not_consumed(),
+            };
+        }
+
         // Only handle printable characters without modifiers (except shift)
         if let Some(ch) = key.to_char()
             && !key.modifiers.control_key
             && !key.modifiers.alt_key
         {
+            // Katakana lock stays on: Shift+letter is typed as romaji too
+            if self.input_mode == InputMode::KatakanaLock {
+                return self.start_input(ch.to_ascii_lowercase());
+            }
             // Detect Shift+letter: shift modifier with alphabetic, OR uppercase keysym.
             // fcitx5 may resolve Shift into the keysym (sending 'A' instead of 'a'+shift),
             // so we must also check for uppercase to handle both cases.
@@ -138,13 +231,22 @@ impl InputMethodEngine {
         EngineResult::not_consumed()
     }
 
+    /// Kana for `key` in kana direct mode (JIS kana layout), if it is a kana key
+    fn kana_direct_char(&self, key: &KeyEvent) -> Option<char> {
+        if key.modifiers.control_key || key.modifiers.alt_key {
+            return None;
+        }
+        kana_layout::jis_kana(key)
+    }
+
     /// Start input with a character (first character of a new input session).
-    /// In alphabet mode, inserts directly; otherwise goes through romaji conversion.
+    /// In alphabet and kana direct mode, inserts directly; otherwise goes
+    /// through romaji conversion.
     pub(super) fn start_input(&mut self, ch: char) -> EngineResult {
         self.converters.romaji.reset();
         self.input_buf.clear();
 
-        if self.input_mode == InputMode::Alphabet {
+        if matches!(self.input_mode, InputMode::Alphabet | InputMode::KanaDirect) {
             self.input_buf.insert(&ch.to_string());
         } else {
             let prev_output_len = 0;
@@ -166,6 +268,8 @@ impl InputMethodEngine {
                 return EngineResult::not_consumed();
             }
 
+            self.input_buf.romaji.push(ch);
+
             // Consume new converter output into composed_hiragana
             let new_output_len = self.converters.romaji.output().chars().count();
             if new_output_len > prev_output_len {
@@ -199,22 +303,52 @@ impl InputMethodEngine {
         key: &KeyEvent,
         shift_active: bool,
     ) -> EngineResult {
-        // Handle Ctrl+key shortcuts
-        if key.modifiers.control_key {
-            match key.keysym {
-                // Ctrl+Space: insert full-width space (U+3000)
-                Keysym::SPACE => return self.input_fullwidth_space(),
-                // Ctrl+K: enter katakana mode
-                Keysym::KEY_K | Keysym::KEY_K_UPPER => return self.enter_katakana_mode(),
-                // Ctrl+A: move to beginning (Emacs-style Home)
-                Keysym::KEY_A | Keysym::KEY_A_UPPER => return self.move_caret_home(),
-                // Ctrl+B: move left (Emacs-style Left)
-                Keysym::KEY_B | Keysym::KEY_B_UPPER => return self.move_caret_left(),
-                // Ctrl+E: move to end (Emacs-style End)
-                Keysym::KEY_E | Keysym::KEY_E_UPPER => return self.move_caret_end(),
-                // Ctrl+F: move right (Emacs-style Right)
-                Keysym::KEY_F | Keysym::KEY_F_UPPER => return self.move_caret_right(),
-                _ => {}
+        // F6–F10: show the text in a forced form
+        if let Some(form) = FunctionKeyForm::from_keysym(key.keysym) {
+            return self.apply_function_key_form(form);
+        }
+        // After F6–F10, Enter commits the forced form and a printable key
+        // commits it, then is processed as the first key of new input.
+        // Editing and caret keys return to the reading and act on it.
+        if self.input_buf.function_key_form.is_some() && key.keysym != Keysym::ESCAPE {
+            if key.keysym == Keysym::RETURN {
+                return self.commit_composing();
+            }
+            let printable =
+                key.to_char().is_some() && !key.modifiers.control_key && !key.modifiers.alt_key;
+            if printable {
+                let mut result = self.commit_composing();
+                let next = self.process_key_empty(key, shift_active);
+                result.consumed = next.consumed;
+                result.actions.extend(next.actions);
+                return result;
+            }
+            self.input_buf.function_key_form = None;
+        }
+
+        // Bound shortcuts (Ctrl+K katakana, Ctrl+A/E/B/F caret, Ctrl+Left/Right word, ...
+        // depending on the scheme)
+        if let Some(action) = self.config.keybindings.action_for(key) {
+            match action {
+                KeyAction::FullwidthSpace => return self.input_fullwidth_space(),
+                KeyAction::EnterKatakana => return self.enter_katakana_mode(),
+                KeyAction::CaretHome => return self.move_caret_home(),
+                KeyAction::CaretLeft => return self.move_caret_left(),
+                KeyAction::CaretEnd => return self.move_caret_end(),
+                KeyAction::CaretRight => return self.move_caret_right(),
+                KeyAction::CaretWordLeft => return self.move_caret_word_left(),
+                KeyAction::CaretWordRight => return self.move_caret_word_right(),
+                KeyAction::Commit => return self.commit_composing(),
+                KeyAction::CommitReading => return self.commit_reading(),
+                KeyAction::Cancel => return self.cancel_composing(),
+                KeyAction::Backspace => return self.backspace_composing(),
+                KeyAction::NextCandidate
+                | KeyAction::PrevCandidate
+                | KeyAction::ExpandCandidates
+                | KeyAction::ToggleLive
+                | KeyAction::ToggleKatakanaLock
+                | KeyAction::UndoCommit
+                | KeyAction::Reconvert => {}
             }
         }
 
@@ -224,28 +358,45 @@ impl InputMethodEngine {
             Keysym::BACKSPACE => self.backspace_composing(),
             Keysym::DELETE => self.delete_composing(),
             Keysym::SPACE if self.input_mode == InputMode::Alphabet => self.input_char(' '),
-            Keysym::SPACE | Keysym::DOWN | Keysym::TAB => self.start_conversion(),
+            // Alphabet mode: Tab is for the application (indentation), not conversion.
+            // The text is committed first so it lands before the Tab in the document.
+            Keysym::TAB if self.input_mode == InputMode::Alphabet => {
+                let mut result = self.commit_composing();
+                result.consumed = false;
+                result
+            }
+            // Katakana lock never converts: the katakana is committed as is
+            Keysym::SPACE | Keysym::DOWN | Keysym::TAB
+                if self.input_mode == InputMode::KatakanaLock =>
+            {
+                self.commit_composing()
+            }
+            Keysym::SPACE => self.start_conversion_from_space(),
+            Keysym::DOWN | Keysym::TAB => self.start_conversion(),
             Keysym::LEFT => self.move_caret_left(),
             Keysym::RIGHT => self.move_caret_right(),
             Keysym::HOME => self.move_caret_home(),
             Keysym::END => self.move_caret_end(),
+            _ if self.input_mode == InputMode::KanaDirect => match self.kana_direct_char(key) {
+                Some(kana) => self.input_char(kana),
+                None => EngineResult::not_consumed(),
+            },
             _ => {
                 if let Some(ch) = key.to_char()
                     && !key.modifiers.control_key
                     && !key.modifiers.alt_key
                 {
+                    if self.input_mode == InputMode::KatakanaLock {
+                        return self.input_char(ch.to_ascii_lowercase());
+                    }
                     // Detect Shift+letter: shift modifier with alphabetic, OR uppercase keysym.
                     // fcitx5 may resolve Shift into the keysym (sending 'A' instead of 'a'+shift).
                     let is_shift_alpha =
                         ch.is_ascii_uppercase() || (shift_active && ch.is_ascii_alphabetic());
 
                     if is_shift_alpha && self.input_mode != InputMode::Alphabet {
-                        // Bake katakana before switching so preedit doesn't revert
-                        if self.input_mode == InputMode::Katakana {
-                            self.bake_katakana();
-                        }
+                        self.settle_before_mode_change(InputMode::Alphabet);
                         self.input_mode = InputMode::Alphabet;
-                        self.flush_romaji_to_composed();
                         self.live.text.clear();
                     }
                     let ch = if self.input_mode == InputMode::Alphabet && is_shift_alpha {
@@ -262,12 +413,30 @@ impl InputMethodEngine {
 
     /// Input a character during composing.
     /// In alphabet mode, inserts directly; otherwise goes through romaji conversion.
+    /// In kana direct mode the kana is inserted, and ゛/゜ combine with the
+    /// kana before the caret when it has a voiced form.
     pub(super) fn input_char(&mut self, ch: char) -> EngineResult {
         if self.input_mode == InputMode::Alphabet {
             self.input_buf.insert(&ch.to_string());
             return self.refresh_input_state();
         }
+        if self.input_mode == InputMode::KanaDirect {
+            let combined = self
+                .input_buf
+                .cursor_pos
+                .checked_sub(1)
+                .and_then(|prev_pos| self.input_buf.text.chars().nth(prev_pos))
+                .and_then(|prev| kana_layout::combine_mark(prev, ch));
+            if let Some(combined) = combined {
+                self.input_buf.remove_char_before_cursor();
+                self.input_buf.insert(&combined.to_string());
+            } else {
+                self.input_buf.insert(&ch.to_string());
+            }
+            return self.refresh_input_state();
+        }
 
+        self.input_buf.romaji.push(ch);
         let prev_output_len = self.converters.romaji.output().chars().count();
         let event = self.converters.romaji.push(ch);
         let curr_output_len = self.converters.romaji.output().chars().count();
@@ -327,9 +496,11 @@ impl InputMethodEngine {
         self.flush_romaji_to_composed();
 
         let reading = self.input_buf.text.clone();
-        let text = if self.input_mode == InputMode::Katakana {
+        let text = if let Some(form) = self.input_buf.function_key_form {
+            self.function_key_text(form)
+        } else if self.input_mode.is_katakana() {
             // Katakana mode always commits katakana, ignoring live conversion
-            Self::hiragana_to_katakana(&reading)
+            self.to_katakana_output(&reading)
         } else if !self.live.text.is_empty() {
             // Live conversion active: commit converted text
             self.live.text.clone()
@@ -345,7 +516,14 @@ impl InputMethodEngine {
         }
 
         // Record live conversion result in learning cache
-        self.record_learning(&reading, &text);
+        if self.composing_commit_is_learnable(&reading) {
+            self.record_learning(&reading, &text);
+        }
+        // Live conversion results go back to Conversion on undo
+        let reconvert = self.input_buf.function_key_form.is_none()
+            && !self.input_mode.is_katakana()
+            && !self.live.text.is_empty();
+        self.record_commit(&text, &reading, reconvert);
 
         self.converters.romaji.reset();
         self.input_buf.clear();
@@ -358,13 +536,51 @@ impl InputMethodEngine {
             .with_action(EngineAction::HideAuxText)
     }
 
+    /// Commit the whole reading as hiragana (Ctrl+Enter), from Composing or
+    /// Conversion.
+    ///
+    /// Live conversion, katakana mode and the selected candidates are ignored,
+    /// and nothing is recorded in the learning cache.
+    pub(super) fn commit_reading(&mut self) -> EngineResult {
+        self.flush_romaji_to_composed();
+
+        let reading = std::mem::take(&mut self.input_buf.text);
+        self.converters.romaji.reset();
+        self.input_buf.clear();
+        self.live.text.clear();
+        self.state = InputState::Empty;
+
+        let result = EngineResult::consumed()
+            .with_action(EngineAction::UpdatePreedit(Preedit::new()))
+            .with_action(EngineAction::HideCandidates)
+            .with_action(EngineAction::HideAuxText);
+        if reading.is_empty() {
+            return result;
+        }
+        self.record_commit(&reading, &reading, false);
+        result.with_action(EngineAction::Commit(reading))
+    }
+
+    /// Show the composing text in the form selected by F6–F10 (no Conversion state)
+    fn apply_function_key_form(&mut self, form: FunctionKeyForm) -> EngineResult {
+        self.flush_romaji_to_composed();
+        self.live.text.clear();
+        self.input_buf.function_key_form = Some(form);
+        let preedit = self.set_composing_state();
+        EngineResult::consumed()
+            .with_action(EngineAction::UpdatePreedit(preedit))
+            .with_action(EngineAction::HideCandidates)
+    }
+
     /// Cancel the current input
     /// In live conversion mode: first Escape clears live conversion and shows hiragana,
     /// second Escape cancels input entirely.
     pub(super) fn cancel_composing(&mut self) -> EngineResult {
-        // If live conversion is active, first Escape returns to hiragana display
-        if !self.live.text.is_empty() {
+        // If live conversion or an F6–F10 form is active, first Escape returns
+        // to the normal display
+        if !self.live.text.is_empty() || self.input_buf.function_key_form.is_some() {
             self.live.text.clear();
+            self.input_buf.function_key_form = None;
             let preedit = self.set_composing_state();
             return EngineResult::consumed()
                 .with_action(EngineAction::UpdatePreedit(preedit))
@@ -1,7 +1,11 @@
 //! InputBuffer: composed hiragana text with cursor.
 //!
 //! This struct bundles `text` and `cursor_pos`
-//! which are always operated on together.
+//! which are always operated on together, plus the romaji keystrokes
+//! typed for the composition (shown by `display.show_romaji`) and the
+//! F6–F10 form currently applied to it.
+
+use super::FunctionKeyForm;
 
 /// Composed input buffer with cursor.
 pub(super) struct InputBuffer {
@@ -9,6 +13,11 @@ pub(super) struct InputBuffer {
     pub text: String,
     /// Cursor position (in characters, not bytes)
     pub cursor_pos: usize,
+    /// Romaji keystrokes typed since the composition started or the composed
+    /// text was last edited (display only, never committed)
+    pub romaji: String,
+    /// Form forced with F6–F10 (display/commit only; `text` stays the reading)
+    pub function_key_form: Option<FunctionKeyForm>,
 }
 
 impl InputBuffer {
@@ -17,13 +26,17 @@ impl InputBuffer {
         Self {
             text: String::new(),
             cursor_pos: 0,
+            romaji: String::new(),
+            function_key_form: None,
         }
     }
 
-    /// Clear the buffer (text, cursor).
+    /// Clear the buffer (text, cursor, romaji, function key form).
     pub fn clear(&mut self) {
         self.text.clear();
         self.cursor_pos = 0;
+        self.romaji.clear();
+        self.function_key_form = None;
     }
 
     /// Insert text at the current cursor position.
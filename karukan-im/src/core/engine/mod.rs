@@ -4,53 +4,54 @@
 //! the romaji converter, kanji converter, and manages the IME state.
 
 mod conversion;
+mod conversion_cache;
 mod cursor;
 mod display;
 mod init;
 mod input;
 mod input_buffer;
 mod mode;
+mod reconvert;
 mod strategy;
 mod types;
+mod undo;
 
 pub use types::*;
 
+use conversion_cache::{ConversionCache, ConversionCacheKey};
 use input_buffer::InputBuffer;
 
 #[cfg(test)]
 mod tests;
 
-use karukan_engine::{Dictionary, KanaKanjiConverter, LearningCache, RomajiConverter};
+use std::time::Instant;
+
+use karukan_engine::{
+    Dictionary, KanaKanjiConverter, LearningCache, RomajiConverter, UserDictionary, normalize_nfkc,
+};
 use tracing::{debug, trace};
 
-use super::candidate::{Candidate, CandidateList};
+use super::candidate::{Candidate, CandidateList, CandidateSource};
+use super::keybindings::KeyAction;
 use super::keycode::{KeyEvent, Keysym};
-use super::preedit::{AttributeType, Preedit, PreeditAttribute, PreeditSegment};
-use super::state::InputState;
-use crate::config::settings::Settings;
-
-/// Source of a conversion candidate
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum CandidateSource {
-    /// User dictionary lookup
-    UserDictionary,
-    /// Learning cache (user history)
-    Learning,
-    /// Model inference result
-    Model,
-    /// System dictionary lookup
-    Dictionary,
-    /// Hiragana/katakana fallback
-    Fallback,
-}
+use super::preedit::{AttributeType, Preedit, PreeditSegment};
+use super::state::{ConversionSegment, InputState};
+use crate::config::settings::{CandidateLabels, Settings};
 
 impl CandidateSource {
-    fn label(&self) -> &'static str {
+    /// Configured annotation label (empty for no annotation)
+    fn label<'a>(&self, labels: &'a CandidateLabels) -> &'a str {
         match self {
-            CandidateSource::UserDictionary => "\u{1F464} \u{30E6}\u{30FC}\u{30B6}\u{30FC}", // 👤 ユーザー
-            CandidateSource::Learning => "\u{1F4DD} \u{5B66}\u{7FD2}", // 📝 学習
-            CandidateSource::Model => "\u{1F916} AI",                  // 🤖 AI
-            CandidateSource::Dictionary => "\u{1F4DA} \u{8F9E}\u{66F8}", // 📚 辞書
+            CandidateSource::UserDictionary => &labels.user_dictionary,
+            CandidateSource::Learning => &labels.learning,
+            CandidateSource::Phrase => &labels.phrase,
+            CandidateSource::Model => &labels.model,
+            CandidateSource::Dictionary => &labels.dictionary,
+            CandidateSource::Prediction => &labels.prediction,
+            CandidateSource::Symbol => &labels.symbol,
+            CandidateSource::Date => &labels.date,
+            CandidateSource::Number => &labels.number,
+            CandidateSource::Loanword => &labels.loanword,
             CandidateSource::Fallback => "",
         }
     }
@@ -106,35 +107,55 @@ pub struct InputMethodEngine {
     dicts: Dictionaries,
     /// Learning cache (user conversion history)
     learning: Option<LearningCache>,
+    /// Last learned surface, used as bigram context for the learning cache
+    prev_surface: Option<String>,
+    /// Session-level cache of model conversion results (kept across `reset()`)
+    conversion_cache: ConversionCache,
+    /// Commit made by the previous key, undoable with Ctrl+Z
+    last_commit: Option<CommitRecord>,
+    /// Clock used for the live conversion debounce (replaced in tests)
+    clock: fn() -> Instant,
 }
 
 impl InputMethodEngine {
     /// Create a new IME engine
     pub fn new() -> Self {
+        Self::with_config(EngineConfig::default())
+    }
+
+    /// Create with configuration
+    pub fn with_config(config: EngineConfig) -> Self {
         Self {
             state: InputState::Empty,
             converters: Converters {
-                romaji: RomajiConverter::new(),
+                romaji: RomajiConverter::with_config(config.romaji.clone()),
                 kanji: None,
                 light_kanji: None,
             },
             surrounding_context: None,
-            config: EngineConfig::default(),
+            conversion_cache: ConversionCache::new(config.conversion_cache_size),
+            config,
             metrics: ConversionMetrics::default(),
             input_mode: InputMode::Hiragana,
             input_buf: InputBuffer::new(),
             live: LiveConversion::default(),
             dicts: Dictionaries::default(),
             learning: None,
+            prev_surface: None,
+            last_commit: None,
+            clock: Instant::now,
         }
     }
 
-    /// Create with configuration
-    pub fn with_config(config: EngineConfig) -> Self {
-        Self {
-            config,
-            ..Self::new()
-        }
+    /// Get the current configuration
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// Replace the configuration at runtime. The romaji converter and the
+    /// conversion cache are built at construction and keep their current setup.
+    pub fn update_config(&mut self, config: EngineConfig) {
+        self.config = config;
     }
 
     /// Get last conversion time in milliseconds (inference only)
@@ -147,6 +168,16 @@ impl InputMethodEngine {
         self.metrics.process_key_ms
     }
 
+    /// Get the current input mode
+    pub(crate) fn input_mode(&self) -> InputMode {
+        self.input_mode
+    }
+
+    /// Get the strategy of the last model conversion (None until a model has run)
+    pub(crate) fn last_strategy(&self) -> Option<&ConversionStrategy> {
+        self.metrics.strategy.as_ref()
+    }
+
     /// Get the model name being used
     pub fn model_name(&self) -> String {
         let main = self
@@ -186,13 +217,29 @@ impl InputMethodEngine {
     /// It is set once at activate() time and should persist through
     /// the session. fcitx5 may send reset events between activate
     /// and the first keyEvent, which would wipe the context.
+    /// Use [`reset_with_reason`](Self::reset_with_reason) for real focus
+    /// or cursor changes.
     pub fn reset(&mut self) {
+        self.reset_with_reason(ResetReason::Spurious);
+    }
+
+    /// Reset the engine state, clearing the surrounding context unless the
+    /// reset is spurious.
+    pub fn reset_with_reason(&mut self, reason: ResetReason) {
+        if reason.clears_context() {
+            self.surrounding_context = None;
+        }
         self.state = InputState::Empty;
         self.converters.romaji.reset();
-        self.input_mode = InputMode::Hiragana;
+        // The katakana lock is a user preference that outlives focus changes
+        if self.input_mode != InputMode::KatakanaLock {
+            self.input_mode = InputMode::Hiragana;
+        }
         self.input_buf.clear();
         self.live.text.clear();
         self.metrics = ConversionMetrics::default();
+        self.prev_surface = None;
+        self.last_commit = None;
     }
 
     /// If the display is empty, reset to Empty state and return the result.
@@ -228,7 +275,7 @@ impl InputMethodEngine {
     /// Called when leaving Katakana mode so the preedit doesn't revert.
     fn bake_katakana(&mut self) {
         if !self.input_buf.text.is_empty() {
-            self.input_buf.text = karukan_engine::kana::hiragana_to_katakana(&self.input_buf.text);
+            self.input_buf.text = self.to_katakana_output(&self.input_buf.text);
         }
     }
 
@@ -271,6 +318,9 @@ impl InputMethodEngine {
             .split_once('\n')
             .map_or(right_context, |(before, _)| before);
 
+        // Kept as the application has it: reconversion deletes characters of
+        // it, so only the copy sent to the model is normalized
+
         if left_context.is_empty() && right_context.is_empty() {
             self.surrounding_context = None;
             return;
@@ -314,14 +364,17 @@ impl InputMethodEngine {
             return None;
         }
         // Only consume the key when actually switching; otherwise pass through
-        // so the system can properly track modifier state.
-        if key.is_press && self.input_mode != InputMode::Hiragana {
-            // Bake katakana before switching so preedit doesn't revert
-            if self.input_mode == InputMode::Katakana {
-                self.bake_katakana();
-            }
+        // so the system can properly track modifier state. The katakana lock is
+        // only turned off by its own binding, and kana direct input is left
+        // only through `set_input_mode`.
+        if key.is_press
+            && !matches!(
+                self.input_mode,
+                InputMode::Hiragana | InputMode::KatakanaLock | InputMode::KanaDirect
+            )
+        {
+            self.settle_before_mode_change(InputMode::Hiragana);
             self.input_mode = InputMode::Hiragana;
-            self.flush_romaji_to_composed();
             let aux = self.format_aux_composing();
             if matches!(self.state, InputState::Composing { .. }) {
                 let preedit = self.set_composing_state();
@@ -338,6 +391,14 @@ impl InputMethodEngine {
 
     /// Process a key event
     pub fn process_key(&mut self, key: &KeyEvent) -> EngineResult {
+        let _span = tracing::debug_span!(
+            "process_key",
+            keysym = format_args!("0x{:04x}", key.keysym.0),
+            press = key.is_press,
+            mode = ?self.input_mode,
+        )
+        .entered();
+
         // Log modifier key events for debugging key mapping issues
         if key.keysym.is_modifier() {
             debug!(
@@ -361,14 +422,38 @@ impl InputMethodEngine {
             return EngineResult::not_consumed();
         }
 
-        // Ctrl+Shift+L: toggle live conversion (works in all states)
-        if key.modifiers.control_key
-            && key.modifiers.shift_key
-            && (key.keysym == Keysym::KEY_L || key.keysym == Keysym::KEY_L_UPPER)
-        {
+        // Ctrl+Shift+L (by default): toggle live conversion (works in all states)
+        if self.config.keybindings.action_for(key) == Some(KeyAction::ToggleLive) {
             return self.toggle_live_conversion();
         }
 
+        // Ctrl+Shift+K (by default): toggle the katakana lock (not while converting)
+        if !matches!(self.state, InputState::Conversion { .. })
+            && self.config.keybindings.action_for(key) == Some(KeyAction::ToggleKatakanaLock)
+        {
+            return self.toggle_katakana_lock();
+        }
+
+        // Only the commit made by the previous key can be undone: any other key
+        // may have moved the application's cursor
+        let last_commit = self.last_commit.take();
+        if matches!(self.state, InputState::Empty)
+            && self.config.keybindings.action_for(key) == Some(KeyAction::UndoCommit)
+            && let Some(record) = last_commit
+        {
+            return self.undo_commit(record);
+        }
+
+        // Reconvert (unbound by default): reconvert the word left of the cursor.
+        // Without such a word, the application handles the key.
+        if matches!(self.state, InputState::Empty)
+            && self.config.keybindings.action_for(key) == Some(KeyAction::Reconvert)
+        {
+            return self
+                .reconvert_left_word()
+                .unwrap_or_else(EngineResult::not_consumed);
+        }
+
         // Reset adaptive model flag when starting a new word (first key in Empty state)
         if matches!(self.state, InputState::Empty) {
             self.metrics.adaptive_use_light_model = false;
@@ -402,13 +487,17 @@ impl InputMethodEngine {
                 // Flush romaji buffer into composed_hiragana
                 self.flush_romaji_to_composed();
                 let reading = self.input_buf.text.clone();
-                let text = if !self.live.text.is_empty() {
+                let text = if let Some(form) = self.input_buf.function_key_form {
+                    self.function_key_text(form)
+                } else if !self.live.text.is_empty() {
                     self.live.text.clone()
                 } else {
                     reading.clone()
                 };
                 // Record live conversion result in learning cache
-                self.record_learning(&reading, &text);
+                if self.composing_commit_is_learnable(&reading) {
+                    self.record_learning(&reading, &text);
+                }
                 self.converters.romaji.reset();
                 self.input_buf.clear();
                 self.live.text.clear();
@@ -416,13 +505,12 @@ impl InputMethodEngine {
                 self.surrounding_context = None;
                 text
             }
-            InputState::Conversion { candidates, .. } => {
-                let text = candidates.selected_text().unwrap_or("").to_string();
-                let reading = candidates.selected().and_then(|c| c.reading.clone());
+            InputState::Conversion { .. } => {
+                let (mut text, learned) = self.conversion_selection().unwrap_or_default();
+                // The unconverted tail is committed as typed
+                text.push_str(self.state.conversion_tail().unwrap_or_default());
                 // Record conversion result in learning cache
-                if let Some(reading) = &reading {
-                    self.record_learning(reading, &text);
-                }
+                self.record_conversion_learning(learned);
                 self.input_buf.clear();
                 self.state = InputState::Empty;
                 self.surrounding_context = None;
@@ -431,8 +519,79 @@ impl InputMethodEngine {
         }
     }
 
+    /// Type `romaji`, convert it and accept the top candidate of every segment,
+    /// without driving key events. Returns the converted text.
+    ///
+    /// Pending input is discarded first and the engine is left in Empty.
+    /// Input always goes through romaji conversion, whatever the input mode.
+    /// Unlike a commit, nothing is learned and the surrounding context, the
+    /// bigram context and the undoable commit are kept.
+    pub fn convert_sentence(&mut self, romaji: &str) -> String {
+        // reset() drops a non-locked katakana mode and the commit history, so
+        // take them first
+        let mode = self.input_mode;
+        let prev_surface = self.prev_surface.take();
+        let last_commit = self.last_commit.take();
+        self.reset();
+        self.input_mode = InputMode::Hiragana;
+
+        let mut converted = String::new();
+        for ch in romaji.chars() {
+            let result = if matches!(self.state, InputState::Empty) {
+                self.start_input(ch)
+            } else {
+                self.input_char(ch)
+            };
+            // Standalone punctuation is committed as it is typed
+            for action in result.actions {
+                if let EngineAction::Commit(text) = action {
+                    converted.push_str(&text);
+                }
+            }
+        }
+        if matches!(self.state, InputState::Composing { .. }) {
+            self.start_conversion();
+        }
+        // Read the selection directly: commit() would record it for learning
+        match self.conversion_selection() {
+            Some((text, _)) => {
+                converted.push_str(&text);
+                // The unconverted tail is taken as typed
+                converted.push_str(self.state.conversion_tail().unwrap_or_default());
+            }
+            None => {
+                self.flush_romaji_to_composed();
+                converted.push_str(&self.input_buf.text);
+            }
+        }
+
+        self.reset();
+        self.input_mode = mode;
+        self.prev_surface = prev_surface;
+        self.last_commit = last_commit;
+        converted
+    }
+
+    /// Forget learning entries older than `learning_max_age_days`, if set.
+    fn evict_stale_learning(&mut self) {
+        let max_age_days = self.config.learning_max_age_days;
+        if max_age_days == 0 {
+            return;
+        }
+        if let Some(cache) = &mut self.learning {
+            let removed = cache.evict_older_than(max_age_days);
+            if removed > 0 {
+                debug!(
+                    "Evicted {} learning entries older than {} days",
+                    removed, max_age_days
+                );
+            }
+        }
+    }
+
     /// Save the learning cache to disk if it has unsaved changes.
     pub fn save_learning(&mut self) {
+        self.evict_stale_learning();
         if let Some(cache) = &mut self.learning
             && cache.is_dirty()
             && let Some(path) = Settings::learning_file()
@@ -5,21 +5,32 @@ use tracing::debug;
 use super::*;
 
 impl InputMethodEngine {
+    /// Settle the composing text before switching to `new_mode`.
+    ///
+    /// Pending romaji is flushed in the mode it was typed in: buffered
+    /// consonants stay latin ("ky") and a lone "n" becomes ん. Leaving
+    /// katakana then bakes the text, flushed ン included, so it doesn't revert.
+    pub(super) fn settle_before_mode_change(&mut self, new_mode: InputMode) {
+        self.flush_romaji_to_composed();
+        if self.input_mode.is_katakana() && !new_mode.is_katakana() {
+            self.bake_katakana();
+        }
+    }
+
     /// Enter katakana mode (Ctrl+k)
     /// One-way switch to Katakana; use Right Super to return to Hiragana.
     pub(super) fn enter_katakana_mode(&mut self) -> EngineResult {
-        // Already in katakana mode: nothing to do
-        if self.input_mode == InputMode::Katakana {
+        // Already in katakana mode (or the lock): nothing to do
+        if self.input_mode.is_katakana() {
             return EngineResult::consumed();
         }
 
+        self.settle_before_mode_change(InputMode::Katakana);
         self.input_mode = InputMode::Katakana;
         // Clear live conversion text so katakana mode takes priority on commit
         self.live.text.clear();
 
-        let romaji_buffer = self.converters.romaji.buffer().to_string();
-
-        if self.input_buf.text.is_empty() && romaji_buffer.is_empty() {
+        if self.input_buf.text.is_empty() {
             return EngineResult::consumed();
         }
 
@@ -33,6 +44,65 @@ impl InputMethodEngine {
             .with_action(EngineAction::UpdateAuxText(aux))
     }
 
+    /// Turn the katakana lock on or off (Ctrl+Shift+K by default).
+    ///
+    /// Turning it off keeps the composing text as katakana and returns to hiragana.
+    pub(super) fn toggle_katakana_lock(&mut self) -> EngineResult {
+        if self.input_mode == InputMode::KatakanaLock {
+            self.settle_before_mode_change(InputMode::Hiragana);
+            self.input_mode = InputMode::Hiragana;
+        } else {
+            // Typed latin stays latin; only the kana that follows becomes katakana
+            self.settle_before_mode_change(InputMode::KatakanaLock);
+            self.input_mode = InputMode::KatakanaLock;
+            self.live.text.clear();
+        }
+        debug!("Katakana lock: {:?}", self.input_mode);
+
+        let aux = self.format_aux_composing();
+        if matches!(self.state, InputState::Composing { .. }) {
+            let preedit = self.set_composing_state();
+            return EngineResult::consumed()
+                .with_action(EngineAction::UpdatePreedit(preedit))
+                .with_action(EngineAction::HideCandidates)
+                .with_action(EngineAction::UpdateAuxText(aux));
+        }
+        EngineResult::consumed().with_action(EngineAction::UpdateAuxText(aux))
+    }
+
+    /// Switch to `mode` directly, e.g. from a UI menu.
+    ///
+    /// Like the mode toggle keys, pending romaji is flushed and leaving
+    /// katakana bakes the composing text (see [`Self::settle_before_mode_change`]).
+    /// While converting, only the mode for the next input changes.
+    pub(crate) fn set_input_mode(&mut self, mode: InputMode) -> EngineResult {
+        if mode == self.input_mode {
+            return EngineResult::consumed();
+        }
+        if matches!(self.state, InputState::Conversion { .. }) {
+            self.input_mode = mode;
+            return EngineResult::consumed();
+        }
+
+        self.settle_before_mode_change(mode);
+        if mode.is_katakana() {
+            // Katakana takes priority over the live conversion text on commit
+            self.live.text.clear();
+        }
+        self.input_mode = mode;
+        debug!("Input mode set: {:?}", mode);
+
+        let aux = self.format_aux_composing();
+        if matches!(self.state, InputState::Composing { .. }) {
+            let preedit = self.set_composing_state();
+            return EngineResult::consumed()
+                .with_action(EngineAction::UpdatePreedit(preedit))
+                .with_action(EngineAction::HideCandidates)
+                .with_action(EngineAction::UpdateAuxText(aux));
+        }
+        EngineResult::consumed().with_action(EngineAction::UpdateAuxText(aux))
+    }
+
     /// Toggle live conversion mode via Ctrl+Shift+L
     pub(super) fn toggle_live_conversion(&mut self) -> EngineResult {
         self.live.enabled = !self.live.enabled;
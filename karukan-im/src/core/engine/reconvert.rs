@@ -0,0 +1,117 @@
+//! Reconversion of the word left of the application's cursor (the `reconvert`
+//! action, bound via `keybindings`; unbound by default)
+
+use karukan_engine::katakana_to_hiragana;
+use tracing::debug;
+
+use super::*;
+
+/// Script of a character, used to find where the trailing word starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Hiragana,
+    Katakana,
+    Kanji,
+    /// Latin, digits, punctuation, spaces: never part of a reconvertible word
+    Other,
+}
+
+impl Script {
+    fn of(c: char) -> Self {
+        match c {
+            '\u{3041}'..='\u{3096}' | 'ゝ' | 'ゞ' => Script::Hiragana,
+            '\u{30A1}'..='\u{30FA}' | 'ヽ' | 'ヾ' => Script::Katakana,
+            // Half-width katakana, including its prolonged sound and voicing marks
+            '\u{FF66}'..='\u{FF9F}' => Script::Katakana,
+            '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々' | '〆' => Script::Kanji,
+            _ => Script::Other,
+        }
+    }
+}
+
+/// The word at the end of `left`: the trailing run of one script (hiragana,
+/// katakana or kanji). A prolonged sound mark (ー) joins the run before it.
+/// Returns None when `left` ends with anything else (latin, punctuation, ...).
+fn trailing_word(left: &str) -> Option<&str> {
+    let script = left
+        .chars()
+        .rev()
+        .find(|&c| c != 'ー')
+        .map(Script::of)
+        .filter(|&s| s != Script::Other)?;
+    let start = left
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| c == 'ー' || Script::of(c) == script)
+        .last()
+        .map(|(i, _)| i)?;
+    Some(&left[start..])
+}
+
+/// Most trailing kanji tried when reconverting: longer runs are cut to their
+/// last characters, bounding the dictionary scans
+const MAX_KANJI_RUN: usize = 8;
+
+impl InputMethodEngine {
+    /// The part of `word` to reconvert and its reading: kana is read as is,
+    /// kanji is looked up by surface in the dictionaries (user first, then
+    /// extra and system). A kanji run without a dictionary reading is
+    /// shortened from the left until one is found.
+    fn infer_reading<'a>(&self, word: &'a str) -> Option<(&'a str, String)> {
+        match word.chars().find(|&c| c != 'ー').map(Script::of)? {
+            Script::Hiragana => Some((word, word.to_string())),
+            Script::Katakana => Some((word, katakana_to_hiragana(&normalize_nfkc(word)))),
+            Script::Kanji => {
+                let start = word
+                    .char_indices()
+                    .rev()
+                    .nth(MAX_KANJI_RUN - 1)
+                    .map_or(0, |(i, _)| i);
+                // Longest first; each dictionary is scanned once for all of them
+                let suffixes: Vec<&str> = word[start..]
+                    .char_indices()
+                    .map(|(i, _)| &word[start + i..])
+                    .collect();
+                let user = self.dicts.user.as_ref().map(|u| u.dictionary());
+                let found: Vec<Vec<Option<String>>> = user
+                    .into_iter()
+                    .chain(&self.dicts.extra)
+                    .chain(&self.dicts.system)
+                    .map(|dict| dict.readings_by_surface(&suffixes))
+                    .collect();
+                suffixes.iter().enumerate().find_map(|(i, &suffix)| {
+                    let reading = found.iter().find_map(|readings| readings[i].clone())?;
+                    Some((suffix, reading))
+                })
+            }
+            Script::Other => None,
+        }
+    }
+
+    /// Delete the word left of the application's cursor and convert its
+    /// reading again (`Reconvert`, unbound by default).
+    ///
+    /// Returns None (key not handled) when there is no such word.
+    pub(super) fn reconvert_left_word(&mut self) -> Option<EngineResult> {
+        let left = self.surrounding_context.as_ref()?.left.clone()?;
+        let (word, reading) = self.infer_reading(trailing_word(&left)?)?;
+        debug!("reconvert: word=\"{}\" reading=\"{}\"", word, reading);
+
+        // The model context is what stays in front of the deleted word
+        let rest = &left[..left.len() - word.len()];
+        if let Some(ctx) = &mut self.surrounding_context {
+            ctx.left = (!rest.is_empty()).then(|| rest.to_string());
+        }
+
+        let mut result = self.convert_reading_again(&reading, word);
+        // The deletion must reach the application before the new preedit
+        result.actions.insert(
+            0,
+            EngineAction::DeleteSurrounding {
+                before: word.chars().count() as u32,
+                after: 0,
+            },
+        );
+        Some(result)
+    }
+}
@@ -43,6 +43,19 @@ pub(super) fn determine_conversion_strategy(
             // Main mode: always use main model greedy only
             ConversionStrategy::MainModelOnly
         }
+        StrategyMode::Rerank => {
+            // Rerank mode: the light model drafts, the main model only scores.
+            // Auto-suggest stays on the fast light model path.
+            if !has_light_model {
+                ConversionStrategy::MainModelOnly
+            } else if num_candidates == 1 {
+                ConversionStrategy::LightModelOnly
+            } else {
+                ConversionStrategy::DraftThenRerank {
+                    beam_width: num_candidates.min(config.beam_width),
+                }
+            }
+        }
     }
 }
 
@@ -59,8 +72,10 @@ fn determine_adaptive_strategy(
     }
 
     if num_candidates == 1 {
-        // Auto-suggest: adapt based on measured latency
-        if adaptive_use_light_model {
+        // Auto-suggest: short input always takes the fast light model path (the
+        // main model is left for Space conversion); longer input adapts based on
+        // measured latency
+        if adaptive_use_light_model || reading_tokens <= config.short_input_threshold {
             ConversionStrategy::LightModelOnly
         } else {
             ConversionStrategy::MainModelOnly
@@ -86,8 +101,9 @@ impl InputMethodEngine {
     /// Determine the conversion strategy based on input token counts, adaptive latency
     /// flag, and configuration.
     ///
-    /// Counts tokens using the main model's tokenizer and delegates to
-    /// `determine_conversion_strategy` for the actual decision logic.
+    /// Counts tokens using the light model's tokenizer (the main model's when
+    /// there is no light model) and delegates to `determine_conversion_strategy`
+    /// for the actual decision logic.
     pub(super) fn determine_strategy(
         &self,
         reading: &str,
@@ -96,10 +112,16 @@ impl InputMethodEngine {
         let has_light_model = self.converters.light_kanji.is_some();
         let katakana = karukan_engine::kana::hiragana_to_katakana(reading);
 
-        // Count tokens using main model's tokenizer
-        let Some(converter) = &self.converters.kanji else {
+        let Some(main_converter) = &self.converters.kanji else {
             return ConversionStrategy::MainModelOnly;
         };
+        // The threshold decides whether the light model path is fast enough,
+        // so count tokens the way the light model sees the reading
+        let converter = self
+            .converters
+            .light_kanji
+            .as_ref()
+            .unwrap_or(main_converter);
 
         let reading_tokens = match converter.count_input_tokens(&katakana) {
             Ok(n) => n,
@@ -136,7 +158,9 @@ impl InputMethodEngine {
                 self.metrics.adaptive_use_light_model =
                     self.metrics.conversion_ms > self.config.max_latency_ms;
             }
-            ConversionStrategy::LightModelOnly | ConversionStrategy::MainModelBeam { .. } => {
+            ConversionStrategy::LightModelOnly
+            | ConversionStrategy::MainModelBeam { .. }
+            | ConversionStrategy::DraftThenRerank { .. } => {
                 // Don't update — light model latency doesn't reflect main model speed
             }
         }
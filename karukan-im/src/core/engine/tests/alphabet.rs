@@ -248,6 +248,48 @@ fn test_alphabet_mode_persists_across_commit() {
     assert_eq!(engine.preedit().unwrap().text(), "y");
 }
 
+#[test]
+fn test_alphabet_mode_tab_commits_then_passes_through() {
+    let mut engine = InputMethodEngine::new();
+
+    engine.process_key(&press_shift('F'));
+    engine.process_key(&press('o'));
+    engine.process_key(&press('o'));
+
+    let result = engine.process_key(&press_key(Keysym::TAB));
+    assert!(!result.consumed);
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(text) if text == "Foo"))
+    );
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert!(engine.input_mode == InputMode::Alphabet);
+
+    // Nothing to commit: Tab simply passes through
+    let result = engine.process_key(&press_key(Keysym::TAB));
+    assert!(!result.consumed);
+    assert!(
+        !result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(_)))
+    );
+}
+
+#[test]
+fn test_hiragana_mode_tab_starts_conversion() {
+    let mut engine = InputMethodEngine::new();
+
+    engine.process_key(&press('k'));
+    engine.process_key(&press('a'));
+
+    let result = engine.process_key(&press_key(Keysym::TAB));
+    assert!(result.consumed);
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+}
+
 #[test]
 fn test_alphabet_mode_cancel_clears_flags() {
     let mut engine = InputMethodEngine::new();
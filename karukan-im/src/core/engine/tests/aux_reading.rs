@@ -0,0 +1,63 @@
+use super::*;
+
+/// Enter conversion for `romaji` and return the aux text formatted for the
+/// active segment with `aux_show_reading` off and on, plus the candidate count.
+fn aux_off_and_on(romaji: &str) -> (String, String, usize) {
+    let mut engine = InputMethodEngine::new();
+    for ch in romaji.chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+
+    let reading = engine.input_buf.text.clone();
+    let candidates = engine.state().candidates().cloned();
+    let off = engine.format_aux_conversion_with_page(&reading, candidates.as_ref());
+    engine.config.aux_show_reading = true;
+    let on = engine.format_aux_conversion_with_page(&reading, candidates.as_ref());
+    let total = candidates.map_or(0, |c| c.len());
+    (off, on, total)
+}
+
+#[test]
+fn test_aux_show_reading_disabled_keeps_format() {
+    let mut engine = InputMethodEngine::new();
+    engine.metrics.conversion_ms = 12;
+    engine.metrics.process_key_ms = 34;
+    engine.metrics.model_name = "light".to_string();
+    let mut today = Candidate::with_reading("今日", "きょう");
+    today.annotation = Some("辞書".to_string());
+    let candidates = CandidateList::new(vec![today, Candidate::new("京")]);
+
+    let aux = engine.format_aux_conversion_with_page("きょう", Some(&candidates));
+    assert_eq!(aux, "[変換] きょう | 12ms/34ms  | light | 辞書");
+}
+
+#[test]
+fn test_aux_show_reading_prefixes_reading_and_position() {
+    let (off, on, total) = aux_off_and_on("kyou");
+    assert_eq!(on, format!("きょう [1/{}] {}", total, off));
+}
+
+#[test]
+fn test_aux_show_reading_uses_candidate_reading() {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        aux_show_reading: true,
+        ..EngineConfig::default()
+    });
+    engine.input_buf.text = "きょう".to_string();
+    let candidates = CandidateList::new(vec![
+        Candidate::with_reading("今日", "きょう"),
+        Candidate::new("京"),
+    ]);
+
+    let aux = engine.format_aux_conversion_with_page("きょう", Some(&candidates));
+    assert!(aux.starts_with("きょう [1/2] [変換]"), "{aux}");
+
+    // A candidate without a reading falls back to the composed reading
+    engine.input_buf.text = "けふ".to_string();
+    let mut candidates = candidates;
+    candidates.move_next();
+    let aux = engine.format_aux_conversion_with_page("きょう", Some(&candidates));
+    assert!(aux.starts_with("けふ [2/2] [変換]"), "{aux}");
+}
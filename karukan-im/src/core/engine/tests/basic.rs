@@ -97,3 +97,129 @@ fn test_truncate_context() {
     let jp = engine.truncate_context("今日はとても良い天気");
     assert_eq!(jp.chars().count(), 5); // Last 5 chars
 }
+
+#[test]
+fn test_commit_trailing_n_converts_to_hiragana() {
+    let mut engine = InputMethodEngine::new();
+
+    for ch in "karukan".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "かるかn");
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    let has_commit = result
+        .actions
+        .iter()
+        .any(|a| matches!(a, EngineAction::Commit(text) if text == "かるかん"));
+    assert!(has_commit);
+}
+
+#[test]
+fn test_commit_trailing_n_raw_when_disabled() {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        romaji: karukan_engine::RomajiConfig {
+            trailing_n_to_hiragana: false,
+            ..karukan_engine::RomajiConfig::default()
+        },
+        ..EngineConfig::default()
+    });
+
+    for ch in "karukan".chars() {
+        engine.process_key(&press(ch));
+    }
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    let has_commit = result
+        .actions
+        .iter()
+        .any(|a| matches!(a, EngineAction::Commit(text) if text == "かるかn"));
+    assert!(has_commit);
+}
+
+#[test]
+fn test_half_width_punctuation_commit() {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        romaji: karukan_engine::RomajiConfig {
+            punctuation_width: karukan_engine::PunctuationWidth::Half,
+            ..karukan_engine::RomajiConfig::default()
+        },
+        ..EngineConfig::default()
+    });
+
+    for ch in "nani?".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "なに?");
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("なに?"));
+}
+
+#[test]
+fn test_custom_romaji_rules() {
+    use std::io::Write;
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all("ka\tカ\n@@\t＠\n".as_bytes()).unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    engine.load_custom_romaji_rules(file.path()).unwrap();
+
+    for ch in "ka@@".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "カ＠");
+}
+
+#[test]
+fn test_composing_preedit_styles_romaji_buffer_apart() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "kannjik".chars() {
+        engine.process_key(&press(ch));
+    }
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), "かんじk");
+    assert_eq!(preedit.caret(), 4);
+    assert_eq!(
+        preedit.attributes(),
+        &[
+            PreeditAttribute::new(0, 3, AttributeType::Underline),
+            PreeditAttribute::new(3, 4, AttributeType::Highlight),
+        ]
+    );
+
+    // Buffer in the middle: kana on both sides stay underlined
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    engine.process_key(&press_key(Keysym::LEFT));
+    engine.process_key(&press('k'));
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), "かんkじ");
+    assert_eq!(preedit.caret(), 3);
+    assert_eq!(
+        preedit.attributes(),
+        &[
+            PreeditAttribute::new(0, 2, AttributeType::Underline),
+            PreeditAttribute::new(2, 3, AttributeType::Highlight),
+            PreeditAttribute::new(3, 4, AttributeType::Underline),
+        ]
+    );
+}
+
+#[test]
+fn test_live_conversion_preedit_styles_romaji_buffer_apart() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "kannjik".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.live.text = "漢字".to_string();
+    let preedit = engine.build_composing_preedit();
+    assert_eq!(preedit.text(), "漢字k");
+    assert_eq!(preedit.caret(), 3);
+    assert_eq!(
+        preedit.attributes(),
+        &[
+            PreeditAttribute::new(0, 2, AttributeType::Underline),
+            PreeditAttribute::new(2, 3, AttributeType::Highlight),
+        ]
+    );
+}
@@ -0,0 +1,65 @@
+//! Converting readings that are only whitespace (no inference runs)
+
+use super::*;
+
+#[test]
+fn test_lone_fullwidth_space_is_committed() {
+    let mut engine = make_mock_engine();
+    engine.process_key(&press_ctrl(Keysym::SPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "\u{3000}");
+
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(committed_text(&result), Some("\u{3000}"));
+    assert!(matches!(engine.state(), InputState::Empty));
+    // No model conversion ran
+    assert_eq!(engine.metrics.strategy, None);
+}
+
+#[test]
+fn test_mixed_spaces_are_committed() {
+    let mut engine = make_mock_engine();
+    engine.input_buf.insert("\u{3000} \u{3000}\t");
+    engine.set_composing_state();
+
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(committed_text(&result), Some("\u{3000} \u{3000}\t"));
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert_eq!(engine.metrics.strategy, None);
+}
+
+#[test]
+fn test_blank_reading_before_caret_keeps_tail() {
+    let mut engine = make_mock_engine();
+    engine.process_key(&press_ctrl(Keysym::SPACE));
+    for ch in "kyou".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::LEFT));
+    engine.process_key(&press_key(Keysym::LEFT));
+    engine.process_key(&press_key(Keysym::LEFT));
+    assert_eq!(engine.input_buf.cursor_pos, 1);
+    // Forget the auto-suggest inference while typing
+    engine.metrics.strategy = None;
+
+    // Only the space before the caret would be converted: it is committed as is
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(committed_text(&result), Some("\u{3000}"));
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+    assert_eq!(engine.preedit().unwrap().text(), "きょう");
+    assert_eq!(engine.metrics.strategy, None);
+
+    // The rest converts normally
+    engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "今日");
+}
+
+#[test]
+fn test_blank_reading_candidates_skip_inference() {
+    let mut engine = make_mock_engine();
+    let candidates = engine.build_conversion_candidates("\u{3000} ", 9);
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].text, "\u{3000} ");
+    assert_eq!(candidates[0].source, CandidateSource::Fallback);
+    assert!(engine.build_conversion_candidates("", 9).is_empty());
+    assert_eq!(engine.metrics.strategy, None);
+}
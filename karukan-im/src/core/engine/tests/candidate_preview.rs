@@ -0,0 +1,111 @@
+use super::*;
+
+fn make_preview_engine() -> InputMethodEngine {
+    InputMethodEngine::with_config(EngineConfig {
+        candidate_preview: true,
+        ..EngineConfig::default()
+    })
+}
+
+fn shows_candidates(result: &EngineResult) -> bool {
+    result
+        .actions
+        .iter()
+        .any(|a| matches!(a, EngineAction::ShowCandidates(_)))
+}
+
+#[test]
+fn test_first_space_previews_top_candidate() {
+    let mut engine = make_preview_engine();
+    let result = type_and_convert(&mut engine, "yajirusi");
+
+    assert!(!shows_candidates(&result));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::HideCandidates))
+    );
+    assert!(engine.state().is_preview());
+    let top = engine.state().candidates().unwrap().candidates()[0]
+        .text
+        .clone();
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), top);
+    assert_eq!(preedit.attributes()[0].attr_type, AttributeType::Highlight);
+}
+
+#[test]
+fn test_second_space_opens_candidate_list() {
+    let mut engine = make_preview_engine();
+    type_and_convert(&mut engine, "yajirusi");
+
+    // The window opens on the previewed candidate
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+    assert!(shows_candidates(&result));
+    assert!(!engine.state().is_preview());
+    assert_eq!(engine.state().candidates().unwrap().cursor(), 0);
+
+    // From then on Space moves through the candidates
+    engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(engine.state().candidates().unwrap().cursor(), 1);
+}
+
+#[test]
+fn test_down_opens_candidate_list_from_preview() {
+    let mut engine = make_preview_engine();
+    type_and_convert(&mut engine, "yajirusi");
+
+    let result = engine.process_key(&press_key(Keysym::DOWN));
+    assert!(shows_candidates(&result));
+    assert!(!engine.state().is_preview());
+    assert_eq!(engine.state().candidates().unwrap().cursor(), 0);
+}
+
+#[test]
+fn test_escape_from_preview_returns_to_hiragana() {
+    let mut engine = make_preview_engine();
+    type_and_convert(&mut engine, "yajirusi");
+
+    engine.process_key(&press_key(Keysym::ESCAPE));
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+    assert_eq!(engine.preedit().unwrap().text(), "やじるし");
+}
+
+#[test]
+fn test_digit_in_preview_is_typed() {
+    let mut engine = make_preview_engine();
+    type_and_convert(&mut engine, "yajirusi");
+    let top = engine.state().candidates().unwrap().candidates()[0]
+        .text
+        .clone();
+
+    // No candidate is selected by number while the list is hidden
+    let result = engine.process_key(&press('2'));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(t) if *t == top))
+    );
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+}
+
+#[test]
+fn test_down_from_composing_skips_preview() {
+    let mut engine = make_preview_engine();
+    for ch in "yajirusi".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::DOWN));
+    assert!(shows_candidates(&result));
+    assert!(!engine.state().is_preview());
+}
+
+#[test]
+fn test_preview_disabled_by_default() {
+    let mut engine = InputMethodEngine::new();
+    let result = type_and_convert(&mut engine, "yajirusi");
+    assert!(shows_candidates(&result));
+    assert!(!engine.state().is_preview());
+}
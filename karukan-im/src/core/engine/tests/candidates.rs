@@ -97,3 +97,644 @@ fn test_empty_live_text_not_added_to_candidates() {
         );
     }
 }
+
+// --- Phrase dictionary tests ---
+
+/// Engine with a phrase dictionary (and no system dictionary)
+fn make_phrase_engine() -> InputMethodEngine {
+    let mut engine = engine_with_dict(
+        "おせわ\tいつもお世話になっております\n\
+         おせわに\tお世話になりました\n\
+         よろ\tよろしくお願いいたします\n",
+        EngineConfig::default(),
+    );
+    engine.dicts.phrase = engine.dicts.system.take();
+    engine
+}
+
+#[test]
+fn test_phrase_expansion_for_trigger_reading() {
+    let engine = make_phrase_engine();
+
+    let phrases = engine.search_phrases("おせわ");
+    assert_eq!(phrases[0].text, "いつもお世話になっております");
+    assert_eq!(phrases[0].source, CandidateSource::Phrase);
+    assert_eq!(phrases[0].reading, None);
+
+    // Longer trigger is offered predictively, after the exact match
+    assert_eq!(phrases[1].text, "お世話になりました");
+    assert_eq!(phrases[1].reading.as_deref(), Some("おせわに"));
+    assert_eq!(phrases.len(), 2);
+}
+
+#[test]
+fn test_phrase_expansion_reaches_candidate_list() {
+    let mut engine = make_phrase_engine();
+
+    type_and_convert(&mut engine, "osewa");
+    let texts = candidate_texts(&mut engine);
+    let exact = texts
+        .iter()
+        .position(|t| t == "いつもお世話になっております");
+    let predictive = texts.iter().position(|t| t == "お世話になりました");
+    assert!(exact.is_some(), "{texts:?}");
+    assert!(predictive.is_some(), "{texts:?}");
+    // The partial-trigger phrase stays below the exact one
+    assert!(exact < predictive, "{texts:?}");
+}
+
+#[test]
+fn test_phrase_no_match() {
+    let engine = make_phrase_engine();
+    assert!(engine.search_phrases("かきく").is_empty());
+    assert!(InputMethodEngine::new().search_phrases("おせわ").is_empty());
+}
+
+// --- Predictive conversion tests ---
+
+fn make_predictive_engine(predictive: bool) -> InputMethodEngine {
+    engine_with_dict(
+        "とう\t塔\t名詞\t\n\
+         とうきょう\t東京\t名詞\t\n\
+         とうきょうと\t東京都\t名詞\t\n",
+        EngineConfig {
+            predictive,
+            ..EngineConfig::default()
+        },
+    )
+}
+
+/// Dictionary candidates of "とう" with the given labels
+fn dictionary_candidates_with_labels(labels: CandidateLabels) -> Vec<Candidate> {
+    let mut engine = make_predictive_engine(true);
+    engine.config.labels = labels;
+    let mut result = EngineResult::consumed();
+    for ch in "tou".chars() {
+        result = engine.process_key(&press(ch));
+    }
+    shown_candidates(&result)
+        .expect("candidates should be shown")
+        .candidates()
+        .to_vec()
+}
+
+#[test]
+fn test_custom_candidate_labels() {
+    let candidates = dictionary_candidates_with_labels(CandidateLabels {
+        dictionary: "[dict]".to_string(),
+        prediction: "[pred]".to_string(),
+        ..CandidateLabels::default()
+    });
+    let annotations: Vec<_> = candidates.iter().map(|c| c.annotation.as_deref()).collect();
+    assert_eq!(
+        annotations,
+        [Some("[dict]"), Some("[pred]"), Some("[pred]")]
+    );
+}
+
+#[test]
+fn test_empty_candidate_labels_hide_annotations() {
+    let candidates = dictionary_candidates_with_labels(CandidateLabels {
+        dictionary: String::new(),
+        prediction: String::new(),
+        ..CandidateLabels::default()
+    });
+    assert!(!candidates.is_empty());
+    assert!(candidates.iter().all(|c| c.annotation.is_none()));
+    // The reading is still kept for learning
+    assert_eq!(candidates[1].reading.as_deref(), Some("とうきょう"));
+}
+
+/// Candidates shown by the last ShowCandidates action of a result
+fn shown_candidates(result: &EngineResult) -> Option<CandidateList> {
+    result.actions.iter().rev().find_map(|a| match a {
+        EngineAction::ShowCandidates(list) => Some(list.clone()),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_predictive_candidates_while_composing() {
+    let mut engine = make_predictive_engine(true);
+
+    let mut result = EngineResult::consumed();
+    for ch in "tou".chars() {
+        result = engine.process_key(&press(ch));
+    }
+    let list = shown_candidates(&result).expect("candidates should be shown");
+    let texts: Vec<_> = list.candidates().iter().map(|c| c.text.as_str()).collect();
+    // Exact dictionary match first, then completions (shorter readings first)
+    assert_eq!(texts, vec!["塔", "東京", "東京都"]);
+
+    let tokyo = &list.candidates()[1];
+    assert_eq!(tokyo.reading.as_deref(), Some("とうきょう"));
+    assert_eq!(
+        tokyo.annotation.as_deref(),
+        Some(CandidateSource::Prediction.label(&CandidateLabels::default()))
+    );
+    assert_eq!(list.candidates()[0].reading.as_deref(), Some("とう"));
+}
+
+#[test]
+fn test_predictive_disabled_shows_no_completions() {
+    let mut engine = make_predictive_engine(false);
+
+    let mut result = EngineResult::consumed();
+    for ch in "tou".chars() {
+        result = engine.process_key(&press(ch));
+    }
+    let list = shown_candidates(&result).expect("candidates should be shown");
+    let texts: Vec<_> = list.candidates().iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(texts, vec!["塔"]);
+    assert_eq!(engine.search_predictions("とう", 9).len(), 2);
+}
+
+#[test]
+fn test_predictive_candidate_learned_under_full_reading() {
+    let mut engine = make_predictive_engine(true);
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+
+    let mut result = EngineResult::consumed();
+    for ch in "tou".chars() {
+        result = engine.process_key(&press(ch));
+    }
+    let list = shown_candidates(&result).unwrap();
+
+    // Convert and pick the prediction from the shown list
+    engine.process_key(&press_key(Keysym::SPACE));
+    *engine.state.candidates_mut().unwrap() = list;
+    engine.state.candidates_mut().unwrap().select(1);
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(t) if t == "東京"))
+    );
+
+    let cache = engine.learning.as_ref().unwrap();
+    assert!(cache.lookup("とう").is_empty());
+    assert_eq!(cache.lookup("とうきょう")[0].0, "東京");
+
+    // The learned completion is offered again from the learning cache
+    let candidates = engine.lookup_learning_candidates("とう");
+    assert_eq!(candidates[0].text, "東京");
+    assert_eq!(candidates[0].reading.as_deref(), Some("とうきょう"));
+}
+
+// --- NLL rerank tests ---
+
+fn annotated(text: &str, source: CandidateSource) -> AnnotatedCandidate {
+    AnnotatedCandidate {
+        text: text.to_string(),
+        source,
+        reading: None,
+    }
+}
+
+fn texts(candidates: &[AnnotatedCandidate]) -> Vec<&str> {
+    candidates.iter().map(|c| c.text.as_str()).collect()
+}
+
+#[test]
+fn test_rerank_by_nll_orders_by_ascending_nll() {
+    let mut candidates = vec![
+        annotated("感じ", CandidateSource::Learning),
+        annotated("幹事", CandidateSource::Dictionary),
+        annotated("漢字", CandidateSource::Model),
+        annotated("監事", CandidateSource::Dictionary),
+        annotated("かんじ", CandidateSource::Fallback),
+    ];
+    let mock_nll = |surface: &str| match surface {
+        "漢字" => 0.5,
+        "監事" => 2.0,
+        "幹事" => 3.0,
+        _ => 9.0,
+    };
+
+    let mut scored = Vec::new();
+    InputMethodEngine::rerank_by_nll(&mut candidates, 10, |surfaces| {
+        scored = surfaces.iter().map(|s| s.to_string()).collect();
+        Some(surfaces.iter().map(|s| mock_nll(s)).collect())
+    });
+
+    // Learning candidates are neither scored nor moved
+    assert_eq!(scored, vec!["幹事", "漢字", "監事", "かんじ"]);
+    assert_eq!(
+        texts(&candidates),
+        vec!["感じ", "漢字", "監事", "幹事", "かんじ"]
+    );
+}
+
+#[test]
+fn test_rerank_with_model_scores_after_context() {
+    let backend = MockBackend::new().with_scores(&[("京", 1.0), ("今日", 2.0)]);
+    let scored = Arc::clone(&backend.scored);
+    let mut engine = make_mock_engine();
+    engine.converters.kanji = Some(KanaKanjiConverter::from_backend(backend));
+    engine.config.rerank_with_model = true;
+    engine.set_surrounding_context("東の", "");
+
+    let candidates = engine.build_conversion_candidates("きょう", 9);
+    assert_eq!(candidates[0].text, "京");
+    let scored = scored.lock().unwrap();
+    assert_eq!(scored[0].katakana, "キョウ");
+    assert_eq!(scored[0].context, "東の");
+}
+
+#[test]
+fn test_rerank_by_nll_caps_scored_candidates() {
+    let mut candidates = vec![
+        annotated("一", CandidateSource::Dictionary),
+        annotated("二", CandidateSource::Dictionary),
+        annotated("三", CandidateSource::Dictionary),
+    ];
+    InputMethodEngine::rerank_by_nll(&mut candidates, 2, |surfaces| {
+        assert_eq!(surfaces.len(), 2);
+        Some(vec![1.0, 0.0])
+    });
+    assert_eq!(texts(&candidates), vec!["二", "一", "三"]);
+}
+
+#[test]
+fn test_rerank_by_nll_keeps_order_when_scoring_fails() {
+    let mut candidates = vec![
+        annotated("一", CandidateSource::Dictionary),
+        annotated("二", CandidateSource::Dictionary),
+    ];
+    InputMethodEngine::rerank_by_nll(&mut candidates, 10, |_| None);
+    assert_eq!(texts(&candidates), vec!["一", "二"]);
+}
+
+// --- Page size tests ---
+
+fn make_paged_engine(page_size: usize) -> InputMethodEngine {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        page_size,
+        ..EngineConfig::default()
+    });
+    for ch in "kann".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    // Without a model only the fallback is produced; fill the list the engine built
+    let kanji = [
+        "感", "缶", "巻", "間", "完", "館", "環", "刊", "官", "冠", "寛", "勘",
+    ];
+    let items = kanji
+        .iter()
+        .enumerate()
+        .map(|(i, k)| Candidate::with_reading(*k, "かん").with_index(i))
+        .collect();
+    engine.state.candidates_mut().unwrap().update(items);
+    engine
+}
+
+#[test]
+fn test_small_page_size_keeps_all_dictionary_candidates() {
+    let kanji = ["感", "缶", "巻", "間", "完", "館"];
+    let tsv: String = kanji
+        .iter()
+        .map(|k| format!("かん\t{k}\t名詞\t\n"))
+        .collect();
+    let mut engine = engine_with_dict(
+        &tsv,
+        EngineConfig {
+            page_size: 3,
+            ..EngineConfig::default()
+        },
+    );
+    type_and_convert(&mut engine, "kann");
+
+    // The page size only pages the list; every dictionary entry is offered
+    let texts = candidate_texts(&mut engine);
+    for k in kanji {
+        assert!(texts.iter().any(|t| t == k), "{k} missing from {texts:?}");
+    }
+    assert_eq!(engine.state().candidates().unwrap().page_size(), 3);
+}
+
+#[test]
+fn test_page_size_5_digit_selection() {
+    let mut engine = make_paged_engine(5);
+    let list = engine.state().candidates().unwrap().clone();
+    assert_eq!(list.page_size(), 5);
+    assert_eq!(list.page_candidates().len(), 5);
+
+    // Digits beyond the page are swallowed without committing
+    let result = engine.process_key(&press('6'));
+    assert!(result.consumed);
+    assert_eq!(committed_text(&result), None);
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+
+    // Digits on the second page select relative to that page
+    engine.process_key(&press_key(Keysym::PAGE_DOWN));
+    let expected = list.candidates()[6].text.clone();
+    let result = engine.process_key(&press('2'));
+    assert_eq!(committed_text(&result), Some(expected.as_str()));
+}
+
+#[test]
+fn test_page_size_9_zero_key_is_not_selection() {
+    let mut engine = make_paged_engine(9);
+    let list = engine.state().candidates().unwrap().clone();
+    assert_eq!(list.page_candidates().len(), 9);
+
+    // '0' commits the current candidate and starts new input with "0"
+    let result = engine.process_key(&press('0'));
+    assert_eq!(committed_text(&result), list.selected_text());
+    assert!(!matches!(engine.state(), InputState::Conversion { .. }));
+}
+
+#[test]
+fn test_page_size_10_zero_key_selects_tenth() {
+    let mut engine = make_paged_engine(10);
+    let list = engine.state().candidates().unwrap().clone();
+    assert_eq!(list.page_candidates().len(), 10);
+
+    let expected = list.candidates()[9].text.clone();
+    let result = engine.process_key(&press('0'));
+    assert_eq!(committed_text(&result), Some(expected.as_str()));
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_page_size_10_zero_key_on_last_partial_page() {
+    let mut engine = make_paged_engine(10);
+    let list = engine.state().candidates().unwrap().clone();
+    assert!(list.len() > 10 && list.len() < 20);
+
+    // The last page has fewer than 10 candidates: '0' selects nothing
+    engine.process_key(&press_key(Keysym::PAGE_DOWN));
+    let result = engine.process_key(&press('0'));
+    assert!(result.consumed);
+    assert_eq!(committed_text(&result), None);
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+}
+
+#[test]
+fn test_symbol_keyword_offers_arrows_before_fallback() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "yajirusi".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+
+    let list = shown_candidates(&result).unwrap();
+    let texts: Vec<&str> = list.candidates().iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(&texts[..4], ["→", "←", "↑", "↓"]);
+    let fallback = texts.iter().position(|t| *t == "やじるし").unwrap();
+    assert!(fallback > texts.iter().position(|t| *t == "↓").unwrap());
+
+    let arrow = &list.candidates()[0];
+    assert!(arrow.annotation.as_deref().unwrap().contains("記号"));
+    assert_eq!(arrow.reading.as_deref(), Some("やじるし"));
+}
+
+#[test]
+fn test_non_keyword_reading_has_no_symbols() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "yajiri".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+
+    let list = shown_candidates(&result).unwrap();
+    assert!(
+        list.candidates()
+            .iter()
+            .all(|c| c.annotation.as_deref().is_none_or(|a| !a.contains("記号")))
+    );
+}
+
+#[test]
+fn test_date_macro_ranks_above_hiragana_fallback() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "kyou".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+
+    let list = shown_candidates(&result).unwrap();
+    let is_date = |c: &Candidate| c.annotation.as_deref().is_some_and(|a| a.contains("日付"));
+    let first_date = list.candidates().iter().position(is_date).unwrap();
+    let fallback = list
+        .candidates()
+        .iter()
+        .position(|c| c.text == "きょう")
+        .unwrap();
+    assert!(first_date < fallback);
+    assert!(list.candidates()[first_date].text.ends_with('日'));
+}
+
+#[test]
+fn test_date_macro_is_not_learned() {
+    let mut engine = InputMethodEngine::new();
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    for ch in "kyou".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let candidates = engine.state.candidates_mut().unwrap();
+    let date = candidates
+        .candidates()
+        .iter()
+        .position(|c| c.annotation.as_deref().is_some_and(|a| a.contains("日付")))
+        .unwrap();
+    candidates.select(date);
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    assert!(
+        engine
+            .learning
+            .as_ref()
+            .unwrap()
+            .lookup("きょう")
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_date_macro_committed_a_day_later_is_not_learned() {
+    let mut engine = InputMethodEngine::new();
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    for ch in "kyou".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let candidates = engine.state.candidates_mut().unwrap();
+    let date = candidates
+        .candidates()
+        .iter()
+        .position(|c| c.source == Some(CandidateSource::Date))
+        .unwrap();
+    candidates.select(date);
+    // Shifting the offset a day ahead makes "today" the next day, as if the
+    // clock had passed midnight before the commit
+    engine.config.utc_offset_minutes += 24 * 60;
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    assert!(
+        engine
+            .learning
+            .as_ref()
+            .unwrap()
+            .lookup("きょう")
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_numeric_reading_offers_number_forms() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "1000".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+
+    let list = shown_candidates(&result).unwrap();
+    let texts: Vec<&str> = list.candidates().iter().map(|c| c.text.as_str()).collect();
+    for form in ["千", "1000", "１０００", "一〇〇〇"] {
+        assert!(texts.contains(&form), "{form} missing from {texts:?}");
+    }
+    let kanji = &list.candidates()[texts.iter().position(|t| *t == "千").unwrap()];
+    assert!(kanji.annotation.as_deref().unwrap().contains("数字"));
+}
+
+fn assert_unique_texts(candidates: &[AnnotatedCandidate]) {
+    let texts: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+    let unique: std::collections::HashSet<&str> = texts.iter().copied().collect();
+    assert_eq!(
+        unique.len(),
+        texts.len(),
+        "duplicate candidates in {texts:?}"
+    );
+}
+
+#[test]
+fn test_katakana_reading_fallback_is_offered_once() {
+    let mut engine = InputMethodEngine::new();
+    let candidates = engine.build_conversion_candidates("ラーメン", 5);
+
+    assert_unique_texts(&candidates);
+    assert!(candidates.iter().any(|c| c.text == "ラーメン"));
+}
+
+#[test]
+fn test_mixed_reading_fallbacks_are_unique() {
+    let mut engine = InputMethodEngine::new();
+    let candidates = engine.build_conversion_candidates("ぱそこんカバー", 5);
+
+    assert_unique_texts(&candidates);
+    assert!(candidates.iter().any(|c| c.text == "ぱそこんカバー"));
+}
+
+#[test]
+fn test_learned_candidate_duplicating_model_and_dictionary_is_shown_once() {
+    let mut engine = make_mock_engine();
+    let mut user = UserDictionary::new();
+    user.add("きょう", "京").unwrap();
+    engine.dicts.user = Some(user);
+    let mut cache = LearningCache::new(100);
+    cache.record("きょう", "京");
+    cache.record("きょう", "今日");
+    engine.learning = Some(cache);
+
+    // The mock model also returns 今日 and 京
+    let candidates = engine.build_conversion_candidates("きょう", 9);
+    assert_unique_texts(&candidates);
+    let learned: Vec<&str> = candidates
+        .iter()
+        .filter(|c| c.source == CandidateSource::Learning)
+        .map(|c| c.text.as_str())
+        .collect();
+    assert_eq!(learned.len(), 2);
+    assert!(learned.contains(&"京") && learned.contains(&"今日"));
+}
+
+#[test]
+fn test_num_candidates_is_clamped() {
+    let mut engine = InputMethodEngine::new();
+    engine.config.num_candidates = 100;
+    // Capped like the server's beam size
+    assert_eq!(engine.conversion_num_candidates(), 20);
+    engine.config.num_candidates = 0;
+    assert_eq!(engine.conversion_num_candidates(), 1);
+}
+
+/// More model candidates than `num_candidates` allows
+const MANY_SURFACES: [&str; 25] = [
+    "候補01", "候補02", "候補03", "候補04", "候補05", "候補06", "候補07", "候補08", "候補09",
+    "候補10", "候補11", "候補12", "候補13", "候補14", "候補15", "候補16", "候補17", "候補18",
+    "候補19", "候補20", "候補21", "候補22", "候補23", "候補24", "候補25",
+];
+
+#[test]
+fn test_large_num_candidates_pages_through_all() {
+    let backend = MockBackend::new().with_candidates(&MANY_SURFACES);
+    let calls = Arc::clone(&backend.calls);
+    let mut engine = make_mock_engine();
+    engine.converters.kanji = Some(KanaKanjiConverter::from_backend(backend));
+    engine.config.num_candidates = 100;
+    engine.config.beam_width = 100;
+    engine.config.page_size = 3;
+
+    let result = type_and_convert(&mut engine, "kyou");
+    // Clamped to 20 before the beam search
+    assert!(calls.lock().unwrap().iter().any(|c| c.n == 20));
+    assert!(calls.lock().unwrap().iter().all(|c| c.n <= 20));
+    let list = shown_candidates(&result).unwrap();
+    assert_eq!(list.page_candidates().len(), 3);
+
+    // PageDown walks every page and reaches all 20 model candidates
+    let mut seen: Vec<String> = list
+        .page_candidates()
+        .iter()
+        .map(|c| c.text.clone())
+        .collect();
+    for page in 1..list.total_pages() {
+        engine.process_key(&press_key(Keysym::PAGE_DOWN));
+        let current = engine.state().candidates().unwrap();
+        assert_eq!(current.current_page(), page);
+        seen.extend(current.page_candidates().iter().map(|c| c.text.clone()));
+    }
+    for surface in &MANY_SURFACES[..20] {
+        assert!(seen.iter().any(|t| t == surface), "{surface} not shown");
+    }
+    assert!(!seen.iter().any(|t| t == MANY_SURFACES[20]));
+}
+
+#[test]
+fn test_expand_candidates_toggle() {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        page_size: 3,
+        ..EngineConfig::default()
+    });
+    for ch in "yajirusi".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+    let paged = shown_candidates(&result).unwrap();
+    assert!(!paged.is_expanded());
+    assert_eq!(paged.page_candidates().len(), 3);
+    let total = paged.len();
+    assert!(total > 3);
+
+    // Ctrl+Tab shows every candidate at once, keeping the selection
+    engine.process_key(&press_key(Keysym::SPACE));
+    let result = engine.process_key(&press_ctrl(Keysym::TAB));
+    assert!(result.consumed);
+    let expanded = shown_candidates(&result).unwrap();
+    assert!(expanded.is_expanded());
+    assert_eq!(expanded.page_candidates().len(), total);
+    assert_eq!(expanded.total_pages(), 1);
+    assert_eq!(expanded.page_cursor(), 1);
+
+    // And back to pages
+    let result = engine.process_key(&press_ctrl(Keysym::TAB));
+    let paged = shown_candidates(&result).unwrap();
+    assert!(!paged.is_expanded());
+    assert_eq!(paged.page_candidates().len(), 3);
+    assert_eq!(paged.selected_text(), expanded.selected_text());
+}
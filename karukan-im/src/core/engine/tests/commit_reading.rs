@@ -0,0 +1,66 @@
+use super::*;
+
+fn make_learning_engine() -> InputMethodEngine {
+    let mut engine = make_live_conversion_engine();
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    engine
+}
+
+#[test]
+fn test_commit_reading_from_composing() {
+    let mut engine = make_learning_engine();
+    for ch in "kan".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.live.text = "感".to_string();
+
+    // The pending "n" is flushed and live conversion is ignored
+    let result = engine.process_key(&press_ctrl(Keysym::RETURN));
+    assert!(result.consumed);
+    assert_eq!(committed_text(&result), Some("かん"));
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert!(engine.learning.as_ref().unwrap().lookup("かん").is_empty());
+}
+
+#[test]
+fn test_commit_reading_from_conversion() {
+    let mut engine = make_learning_engine();
+    for ch in "kann".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let items = ["感", "缶"]
+        .iter()
+        .enumerate()
+        .map(|(i, k)| Candidate::with_reading(*k, "かん").with_index(i))
+        .collect();
+    engine.state.candidates_mut().unwrap().update(items);
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    let result = engine.process_key(&press_ctrl(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("かん"));
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::HideCandidates))
+    );
+    assert!(engine.learning.as_ref().unwrap().lookup("かん").is_empty());
+}
+
+#[test]
+fn test_commit_reading_includes_conversion_tail() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "kanji".chars() {
+        engine.process_key(&press(ch));
+    }
+    // Convert only "かん", keeping "じ" as the unconverted tail
+    engine.process_key(&press_key(Keysym::LEFT));
+    engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(engine.state().conversion_tail(), Some("じ"));
+
+    let result = engine.process_key(&press_ctrl(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("かんじ"));
+    assert!(matches!(engine.state(), InputState::Empty));
+}
@@ -69,3 +69,70 @@ fn test_alphabet_mode_space_inserts_literal_space() {
     engine.process_key(&press('k'));
     assert_eq!(engine.preedit().unwrap().text(), "New york");
 }
+
+/// Japanese text only: kana, kanji and the prolonged sound mark
+fn is_japanese(text: &str) -> bool {
+    text.chars().all(|c| {
+        matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}' | 'ー' | '\u{4E00}'..='\u{9FFF}' | '々')
+    })
+}
+
+#[test]
+fn test_convert_sentence() {
+    let mut engine = InputMethodEngine::new();
+
+    for romaji in ["konnnichiha", "toukyou"] {
+        let text = engine.convert_sentence(romaji);
+        assert!(!text.is_empty(), "{romaji} converted to nothing");
+        assert!(is_japanese(&text), "{romaji} → {text:?}");
+        assert!(matches!(engine.state(), InputState::Empty));
+        assert!(engine.preedit().is_none());
+    }
+}
+
+#[test]
+fn test_convert_sentence_discards_pending_input() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press('a'));
+    engine.process_key(&press('k'));
+
+    let text = engine.convert_sentence("ne");
+    assert!(is_japanese(&text), "{text:?}");
+    assert!(!text.contains('あ'), "{text:?}");
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_convert_sentence_does_not_learn() {
+    let mut engine = InputMethodEngine::new();
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    engine.set_surrounding_context("前の文", "");
+
+    let text = engine.convert_sentence("sakura");
+    assert!(is_japanese(&text), "{text:?}");
+    let cache = engine.learning.as_ref().unwrap();
+    assert_eq!(cache.entry_count(), 0);
+    assert_eq!(cache.bigram_count(), 0);
+    assert!(!cache.is_dirty());
+    // The context survives, as it would not after a commit
+    assert_eq!(engine.truncate_context_for_api(), "前の文");
+}
+
+#[test]
+fn test_convert_sentence_ignores_alphabet_mode() {
+    let mut engine = InputMethodEngine::new();
+    engine.input_mode = InputMode::Alphabet;
+
+    let text = engine.convert_sentence("sakura");
+    assert!(is_japanese(&text), "{text:?}");
+}
+
+#[test]
+fn test_convert_sentence_keeps_katakana_mode() {
+    let mut engine = InputMethodEngine::new();
+    engine.input_mode = InputMode::Katakana;
+
+    let text = engine.convert_sentence("sakura");
+    assert!(is_japanese(&text), "{text:?}");
+    assert_eq!(engine.input_mode, InputMode::Katakana);
+}
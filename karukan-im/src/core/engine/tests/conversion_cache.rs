@@ -0,0 +1,133 @@
+use super::*;
+
+fn key(
+    katakana: &str,
+    api_context: &str,
+    num_candidates: usize,
+    model_id: &str,
+) -> ConversionCacheKey {
+    ConversionCacheKey {
+        katakana: katakana.to_string(),
+        api_context: api_context.to_string(),
+        num_candidates,
+        model_id: model_id.to_string(),
+    }
+}
+
+/// Engine converting with a [`MockBackend`] whose requests are logged in the
+/// returned list, caching up to `cache_size` results
+fn make_counting_engine(cache_size: usize) -> (InputMethodEngine, Arc<Mutex<Vec<MockCall>>>) {
+    let mut engine = make_mock_engine();
+    engine.conversion_cache = ConversionCache::new(cache_size);
+    let backend = MockBackend::new();
+    let calls = Arc::clone(&backend.calls);
+    engine.converters.kanji = Some(KanaKanjiConverter::from_backend(backend));
+    (engine, calls)
+}
+
+/// Number of Space (beam search) conversions of `katakana` the model ran
+fn beam_calls(calls: &Mutex<Vec<MockCall>>, katakana: &str) -> usize {
+    calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|c| c.katakana == katakana && c.n > 1)
+        .count()
+}
+
+/// Convert the reading again: back to composing, then Space
+fn reconvert(engine: &mut InputMethodEngine) {
+    engine.process_key(&press_key(Keysym::ESCAPE));
+    engine.process_key(&press_key(Keysym::SPACE));
+}
+
+#[test]
+fn test_second_identical_conversion_skips_model() {
+    let (mut engine, calls) = make_counting_engine(256);
+    type_and_convert(&mut engine, "kyou");
+    let first = candidate_texts(&mut engine);
+    reconvert(&mut engine);
+
+    assert_eq!(candidate_texts(&mut engine), first);
+    assert_eq!(beam_calls(&calls, "キョウ"), 1);
+    assert_eq!(engine.metrics.conversion_ms, 0);
+}
+
+#[test]
+fn test_cache_key_distinguishes_context() {
+    let (mut engine, calls) = make_counting_engine(256);
+    type_and_convert(&mut engine, "kyou");
+    engine.set_surrounding_context("明日と", "");
+    reconvert(&mut engine);
+
+    assert_eq!(beam_calls(&calls, "キョウ"), 2);
+    let contexts: Vec<String> = calls
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|c| c.n > 1)
+        .map(|c| c.context.clone())
+        .collect();
+    assert_eq!(contexts, ["", "明日と"]);
+}
+
+#[test]
+fn test_cache_key_distinguishes_readings() {
+    let (mut engine, calls) = make_counting_engine(256);
+    type_and_convert(&mut engine, "kyou");
+    engine.process_key(&press_key(Keysym::ESCAPE));
+    engine.process_key(&press_key(Keysym::ESCAPE));
+    type_and_convert(&mut engine, "toukyou");
+
+    assert_eq!(beam_calls(&calls, "キョウ"), 1);
+    assert_eq!(beam_calls(&calls, "トウキョウ"), 1);
+}
+
+#[test]
+fn test_cache_evicts_least_recently_used() {
+    let mut cache = ConversionCache::new(2);
+    cache.insert(key("ア", "", 1, "m"), vec!["亜".into()]);
+    cache.insert(key("イ", "", 1, "m"), vec!["伊".into()]);
+    // Touch ア so that イ becomes the oldest
+    assert!(cache.get(&key("ア", "", 1, "m")).is_some());
+    cache.insert(key("ウ", "", 1, "m"), vec!["宇".into()]);
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&key("イ", "", 1, "m")).is_none());
+    assert_eq!(
+        cache.get(&key("ア", "", 1, "m")),
+        Some(vec!["亜".to_string()])
+    );
+    assert!(cache.get(&key("ウ", "", 1, "m")).is_some());
+}
+
+#[test]
+fn test_cache_disabled_with_zero_capacity() {
+    let (mut engine, calls) = make_counting_engine(0);
+    type_and_convert(&mut engine, "kyou");
+    reconvert(&mut engine);
+
+    assert_eq!(beam_calls(&calls, "キョウ"), 2);
+    assert_eq!(engine.conversion_cache.len(), 0);
+}
+
+#[test]
+fn test_reset_keeps_conversion_cache() {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        conversion_cache_size: 1,
+        ..EngineConfig::default()
+    });
+    engine
+        .conversion_cache
+        .insert(key("カンジ", "", 1, "main"), vec!["漢字".into()]);
+
+    engine.process_key(&press('a'));
+    engine.reset();
+    assert_eq!(engine.conversion_cache.len(), 1);
+
+    // Capacity comes from EngineConfig
+    engine
+        .conversion_cache
+        .insert(key("カンゼン", "", 1, "main"), vec!["完全".into()]);
+    assert_eq!(engine.conversion_cache.len(), 1);
+}
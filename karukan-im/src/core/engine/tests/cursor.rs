@@ -206,6 +206,60 @@ fn test_cursor_backspace_in_middle() {
     assert_eq!(engine.preedit().unwrap().caret(), 0);
 }
 
+#[test]
+fn test_backspace_through_romaji_buffer() {
+    let mut engine = InputMethodEngine::new();
+
+    // "a" converts, "ky" stays buffered (waiting for e.g. "a" → きゃ)
+    for ch in "aky".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "あky");
+    assert_eq!(engine.preedit().unwrap().caret(), 3);
+    assert_eq!(engine.converters.romaji.buffer(), "ky");
+
+    // Backspace removes the buffered letters first, one at a time
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "あk");
+    assert_eq!(engine.preedit().unwrap().caret(), 2);
+    assert_eq!(engine.converters.romaji.buffer(), "k");
+
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "あ");
+    assert_eq!(engine.preedit().unwrap().caret(), 1);
+    assert_eq!(engine.converters.romaji.buffer(), "");
+    assert_eq!(engine.input_buf.text, "あ");
+
+    // Then the composed text; the buffer can be refilled afterwards
+    engine.process_key(&press('k'));
+    engine.process_key(&press('y'));
+    engine.process_key(&press('a'));
+    assert_eq!(engine.preedit().unwrap().text(), "あきゃ");
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "あき");
+    assert_eq!(engine.preedit().unwrap().caret(), 2);
+    assert_eq!(engine.converters.romaji.output(), "あき");
+
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert!(engine.preedit().is_none());
+}
+
+#[test]
+fn test_backspace_after_caret_move_edits_at_caret() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "aiu".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::LEFT));
+
+    // The converter's last output is "う", but the caret is before it
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "あう");
+    assert_eq!(engine.preedit().unwrap().caret(), 1);
+}
+
 #[test]
 fn test_cursor_delete_key() {
     let mut engine = InputMethodEngine::new();
@@ -225,6 +279,55 @@ fn test_cursor_delete_key() {
     assert_eq!(engine.preedit().unwrap().caret(), 0);
 }
 
+#[test]
+fn test_cursor_delete_at_start_middle_end() {
+    let type_aiu = || {
+        let mut engine = InputMethodEngine::new();
+        for ch in "aiu".chars() {
+            engine.process_key(&press(ch));
+        }
+        engine
+    };
+
+    // Start: removes the first character, caret stays at 0
+    let mut engine = type_aiu();
+    engine.process_key(&press_key(Keysym::HOME));
+    assert!(engine.process_key(&press_key(Keysym::DELETE)).consumed);
+    assert_eq!(engine.preedit().unwrap().text(), "いう");
+    assert_eq!(engine.preedit().unwrap().caret(), 0);
+
+    // Middle: removes the character after the caret, not the one before it
+    let mut engine = type_aiu();
+    engine.process_key(&press_key(Keysym::LEFT));
+    engine.process_key(&press_key(Keysym::LEFT));
+    assert!(engine.process_key(&press_key(Keysym::DELETE)).consumed);
+    assert_eq!(engine.preedit().unwrap().text(), "あう");
+    assert_eq!(engine.preedit().unwrap().caret(), 1);
+
+    // End: nothing to delete, but the key is still consumed
+    let mut engine = type_aiu();
+    assert!(engine.process_key(&press_key(Keysym::DELETE)).consumed);
+    assert_eq!(engine.preedit().unwrap().text(), "あいう");
+    assert_eq!(engine.preedit().unwrap().caret(), 3);
+}
+
+#[test]
+fn test_cursor_delete_flushes_pending_romaji() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "aiu".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::HOME));
+    engine.process_key(&press('n'));
+    assert_eq!(engine.converters.romaji.buffer(), "n");
+
+    // The pending "n" is flushed as ん before the caret, then "あ" is deleted
+    engine.process_key(&press_key(Keysym::DELETE));
+    assert_eq!(engine.preedit().unwrap().text(), "んいう");
+    assert_eq!(engine.preedit().unwrap().caret(), 1);
+    assert_eq!(engine.converters.romaji.buffer(), "");
+}
+
 #[test]
 fn test_cursor_home_end() {
     let mut engine = InputMethodEngine::new();
@@ -363,3 +466,166 @@ fn test_cursor_composed_hiragana_tracking() {
     assert_eq!(engine.input_buf.text, "");
     assert_eq!(engine.input_buf.cursor_pos, 0);
 }
+
+// --- Cursor-aware Conversion Tests ---
+
+/// Type "aiueo" (あいうえお) and move the caret left `left` times
+fn type_aiueo_caret_left(left: usize) -> InputMethodEngine {
+    let mut engine = InputMethodEngine::new();
+    for ch in "aiueo".chars() {
+        engine.process_key(&press(ch));
+    }
+    for _ in 0..left {
+        engine.process_key(&press_key(Keysym::LEFT));
+    }
+    engine
+}
+
+fn converted_readings(engine: &InputMethodEngine) -> Vec<String> {
+    let (segments, _) = engine.state().segments().unwrap();
+    segments.iter().map(|s| s.reading.clone()).collect()
+}
+
+#[test]
+fn test_convert_with_caret_at_end_converts_everything() {
+    let mut engine = type_aiueo_caret_left(0);
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    assert_eq!(converted_readings(&engine), vec!["あいうえお"]);
+    assert_eq!(engine.state().conversion_tail(), Some(""));
+}
+
+#[test]
+fn test_convert_with_caret_at_start_converts_everything() {
+    let mut engine = type_aiueo_caret_left(0);
+    engine.process_key(&press_key(Keysym::HOME));
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    assert_eq!(converted_readings(&engine), vec!["あいうえお"]);
+    assert_eq!(engine.state().conversion_tail(), Some(""));
+}
+
+#[test]
+fn test_convert_with_caret_in_middle_converts_left_part() {
+    let mut engine = type_aiueo_caret_left(2);
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    assert_eq!(converted_readings(&engine), vec!["あいう"]);
+    assert_eq!(engine.state().conversion_tail(), Some("えお"));
+    // The tail stays visible after the converted part, caret between them
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), "あいうえお");
+    assert_eq!(preedit.caret(), 3);
+
+    // Committing leaves the tail as composing input, caret at its start
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(t) if t == "あいう"))
+    );
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+    assert_eq!(engine.preedit().unwrap().text(), "えお");
+    assert_eq!(engine.input_buf.cursor_pos, 0);
+
+    // The next conversion covers the remaining text
+    engine.process_key(&press_key(Keysym::SPACE));
+    assert_eq!(converted_readings(&engine), vec!["えお"]);
+    assert_eq!(engine.state().conversion_tail(), Some(""));
+}
+
+#[test]
+fn test_cancel_partial_conversion_restores_caret() {
+    let mut engine = type_aiueo_caret_left(2);
+    engine.process_key(&press_key(Keysym::SPACE));
+    engine.process_key(&press_key(Keysym::ESCAPE));
+
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+    assert_eq!(engine.preedit().unwrap().text(), "あいうえお");
+    assert_eq!(engine.input_buf.cursor_pos, 3);
+}
+
+#[test]
+fn test_typing_during_partial_conversion_inserts_before_tail() {
+    let mut engine = type_aiueo_caret_left(2);
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    let result = engine.process_key(&press('k'));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(t) if t == "あいう"))
+    );
+    engine.process_key(&press('a'));
+    assert_eq!(engine.preedit().unwrap().text(), "かえお");
+}
+
+#[test]
+fn test_commit_partial_conversion_includes_tail() {
+    let mut engine = type_aiueo_caret_left(2);
+    engine.process_key(&press_key(Keysym::SPACE));
+
+    assert_eq!(engine.commit(), "あいうえお");
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_cursor_word_movement_mixed_scripts() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "kannji".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_shift('A'));
+    engine.process_key(&press('b'));
+    engine.process_key(&press('c'));
+    engine.process_key(&press_key(Keysym::ALT_R));
+    for ch in "hiragana".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "かんじAbcひらがな");
+    assert_eq!(engine.preedit().unwrap().caret(), 10);
+
+    // Ctrl+Left stops at the start of each script run, then stays at 0
+    for expected in [6, 3, 0, 0] {
+        let result = engine.process_key(&press_ctrl(Keysym::LEFT));
+        assert!(result.consumed);
+        assert_eq!(engine.preedit().unwrap().caret(), expected);
+    }
+    // Ctrl+Right stops at the end of each run, then stays at the end
+    for expected in [3, 6, 10, 10] {
+        engine.process_key(&press_ctrl(Keysym::RIGHT));
+        assert_eq!(engine.preedit().unwrap().caret(), expected);
+    }
+}
+
+#[test]
+fn test_cursor_word_movement_punctuation_and_long_vowel() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "ra-menn,taberu".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "らーめん、たべる");
+
+    // ー stays with the run before it; the comma is a word of its own
+    for expected in [5, 4, 0] {
+        engine.process_key(&press_ctrl(Keysym::LEFT));
+        assert_eq!(engine.preedit().unwrap().caret(), expected);
+    }
+}
+
+#[test]
+fn test_cursor_word_movement_flushes_romaji() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "aiuk".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.converters.romaji.buffer(), "k");
+
+    // The pending "k" becomes a latin word of its own
+    engine.process_key(&press_ctrl(Keysym::LEFT));
+    assert_eq!(engine.preedit().unwrap().text(), "あいうk");
+    assert_eq!(engine.preedit().unwrap().caret(), 3);
+    assert_eq!(engine.converters.romaji.buffer(), "");
+}
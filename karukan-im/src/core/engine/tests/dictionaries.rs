@@ -0,0 +1,359 @@
+use super::*;
+use crate::config::settings::DictionaryMode;
+use std::io::Write;
+use tempfile::TempDir;
+
+/// Write a Mozc TSV, a Sudachi CSV, and a KRKN binary dictionary into `dir`.
+fn write_mixed_dictionaries(dir: &TempDir) -> Vec<String> {
+    let tsv = dir.path().join("domain.tsv");
+    let mut f = std::fs::File::create(&tsv).unwrap();
+    writeln!(f, "かるかん\t軽羹\t名詞\t").unwrap();
+    writeln!(f, "きょう\t侠\t名詞\t").unwrap();
+
+    let csv = dir.path().join("sudachi.csv");
+    let mut f = std::fs::File::create(&csv).unwrap();
+    writeln!(f, "c0,c1,c2,3000,東京,c5,c6,c7,c8,c9,c10,トウキョウ").unwrap();
+    writeln!(f, "c0,c1,c2,5000,今日,c5,c6,c7,c8,c9,c10,キョウ").unwrap();
+
+    let src = dir.path().join("src.tsv");
+    let mut f = std::fs::File::create(&src).unwrap();
+    writeln!(f, "じしょ\t辞書\t名詞\t").unwrap();
+    let bin = dir.path().join("binary.krkn");
+    Dictionary::load_auto(&src).unwrap().save(&bin).unwrap();
+
+    [tsv, csv, bin]
+        .iter()
+        .map(|p| p.to_str().unwrap().to_string())
+        .collect()
+}
+
+fn surfaces(engine: &InputMethodEngine, reading: &str) -> Vec<String> {
+    engine
+        .search_dictionaries(reading, usize::MAX)
+        .into_iter()
+        .map(|ac| ac.text)
+        .collect()
+}
+
+#[test]
+fn test_extra_dictionaries_mixed_formats_merge() {
+    let dir = TempDir::new().unwrap();
+    let paths = write_mixed_dictionaries(&dir);
+
+    let mut engine = InputMethodEngine::new();
+    let statuses = engine.init_extra_dictionaries(&paths, DictionaryMode::Merge);
+    assert!(statuses.iter().all(|s| s.result.is_ok()));
+    assert_eq!(engine.dicts.extra.len(), 1);
+
+    assert_eq!(surfaces(&engine, "かるかん"), vec!["軽羹"]);
+    assert_eq!(surfaces(&engine, "とうきょう"), vec!["東京"]);
+    assert_eq!(surfaces(&engine, "じしょ"), vec!["辞書"]);
+    // Earlier file has priority for the shared reading
+    assert_eq!(surfaces(&engine, "きょう"), vec!["侠", "今日"]);
+}
+
+#[test]
+fn test_extra_dictionaries_layer() {
+    let dir = TempDir::new().unwrap();
+    let paths = write_mixed_dictionaries(&dir);
+
+    let mut engine = InputMethodEngine::new();
+    engine.init_extra_dictionaries(&paths, DictionaryMode::Layer);
+    assert_eq!(engine.dicts.extra.len(), 3);
+
+    assert_eq!(surfaces(&engine, "きょう"), vec!["侠", "今日"]);
+    assert_eq!(surfaces(&engine, "じしょ"), vec!["辞書"]);
+}
+
+#[test]
+fn test_extra_dictionaries_bad_file_does_not_abort() {
+    let dir = TempDir::new().unwrap();
+    let mut paths = write_mixed_dictionaries(&dir);
+    paths.insert(
+        1,
+        dir.path().join("missing.tsv").to_str().unwrap().to_string(),
+    );
+
+    let mut engine = InputMethodEngine::new();
+    let statuses = engine.init_extra_dictionaries(&paths, DictionaryMode::Merge);
+    assert_eq!(statuses.len(), 4);
+    assert!(statuses[1].result.is_err());
+    assert_eq!(statuses[0].result, Ok(2));
+
+    // Files after the bad one are still loaded
+    assert_eq!(surfaces(&engine, "とうきょう"), vec!["東京"]);
+    assert_eq!(surfaces(&engine, "じしょ"), vec!["辞書"]);
+}
+
+/// Write one single-entry dictionary per surface, all for the reading かんじ
+fn write_ranked_dictionaries(dir: &TempDir, surfaces: &[&str]) -> Vec<String> {
+    surfaces
+        .iter()
+        .map(|surface| {
+            let path = dir.path().join(format!("{}.tsv", surface));
+            std::fs::write(&path, format!("かんじ\t{}\t名詞\t\n", surface)).unwrap();
+            path.to_str().unwrap().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn test_extra_dictionaries_order_between_user_and_system() {
+    let dir = TempDir::new().unwrap();
+    let system = write_ranked_dictionaries(&dir, &["漢字"]);
+    let paths = write_ranked_dictionaries(&dir, &["幹事", "感じ", "監事"]);
+
+    for mode in [DictionaryMode::Merge, DictionaryMode::Layer] {
+        let mut engine = InputMethodEngine::new();
+        let mut user = UserDictionary::new();
+        user.add("かんじ", "莞爾").unwrap();
+        engine.dicts.user = Some(user);
+        engine.dicts.system =
+            Some(Dictionary::load_auto(std::path::Path::new(&system[0])).unwrap());
+        engine.init_extra_dictionaries(&paths, mode);
+
+        // User dictionary, then the configured list in order, then the system dictionary
+        assert_eq!(
+            surfaces(&engine, "かんじ"),
+            vec!["莞爾", "幹事", "感じ", "監事", "漢字"],
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn test_extra_dictionaries_order_follows_config() {
+    let dir = TempDir::new().unwrap();
+    let mut paths = write_ranked_dictionaries(&dir, &["幹事", "感じ", "監事"]);
+    paths.reverse();
+
+    let mut engine = InputMethodEngine::new();
+    engine.init_extra_dictionaries(&paths, DictionaryMode::Merge);
+    assert_eq!(surfaces(&engine, "かんじ"), vec!["監事", "感じ", "幹事"]);
+}
+
+#[test]
+fn test_reload_user_dictionary_picks_up_new_entries() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("user.tsv");
+    std::fs::write(&path, "かるかん\t軽羹\t名詞\t\n").unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    engine.reload_user_dictionary(&path).unwrap();
+    assert_eq!(surfaces(&engine, "かるかん"), vec!["軽羹"]);
+    assert!(surfaces(&engine, "ねこ").is_empty());
+
+    std::fs::write(&path, "かるかん\t軽羹\t名詞\t\nねこ\t猫\t名詞\t\n").unwrap();
+    engine.reload_user_dictionary(&path).unwrap();
+    assert_eq!(surfaces(&engine, "ねこ"), vec!["猫"]);
+    assert_eq!(surfaces(&engine, "かるかん"), vec!["軽羹"]);
+}
+
+#[test]
+fn test_reload_user_dictionary_directory() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("a.tsv"), "ねこ\t猫\t名詞\t\n").unwrap();
+    std::fs::write(dir.path().join("b.tsv"), "ねこ\tネコ\t名詞\t\n").unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    engine.reload_user_dictionary(dir.path()).unwrap();
+    assert_eq!(surfaces(&engine, "ねこ"), vec!["猫", "ネコ"]);
+}
+
+#[test]
+fn test_reload_user_dictionary_failure_keeps_previous() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("user.tsv");
+    std::fs::write(&path, "かるかん\t軽羹\t名詞\t\n").unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    engine.reload_user_dictionary(&path).unwrap();
+
+    // A truncated KRKN binary fails to parse
+    let broken = dir.path().join("broken.krkn");
+    std::fs::write(&broken, b"KRKN\x02").unwrap();
+    assert!(engine.reload_user_dictionary(&broken).is_err());
+    assert!(
+        engine
+            .reload_user_dictionary(&dir.path().join("missing.tsv"))
+            .is_err()
+    );
+    assert_eq!(surfaces(&engine, "かるかん"), vec!["軽羹"]);
+}
+
+#[test]
+fn test_reload_user_dictionary_during_conversion() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("user.tsv");
+    std::fs::write(&path, "ねこ\t猫\t名詞\t\n").unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    for ch in "neko".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let before: Vec<String> = engine
+        .state()
+        .candidates()
+        .unwrap()
+        .candidates()
+        .iter()
+        .map(|c| c.text.clone())
+        .collect();
+
+    // The current candidate list is kept; only new lookups see the reload
+    engine.reload_user_dictionary(&path).unwrap();
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    let after: Vec<String> = engine
+        .state()
+        .candidates()
+        .unwrap()
+        .candidates()
+        .iter()
+        .map(|c| c.text.clone())
+        .collect();
+    assert_eq!(before, after);
+    assert_eq!(surfaces(&engine, "ねこ"), vec!["猫"]);
+}
+
+#[test]
+fn test_ctrl_delete_removes_candidate_from_user_dict_and_learning() {
+    let mut engine = InputMethodEngine::new();
+    let mut user = UserDictionary::new();
+    user.add("ねこ", "猫").unwrap();
+    user.add("ねこ", "ネコ").unwrap();
+    engine.dicts.user = Some(user);
+    let mut cache = LearningCache::new(100);
+    cache.record("ねこ", "猫");
+    engine.learning = Some(cache);
+
+    for ch in "neko".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    *engine.state.candidates_mut().unwrap() =
+        CandidateList::from_strings_with_reading(["猫", "ネコ", "ねこ"], "ねこ");
+
+    let result = engine.process_key(&press_ctrl(Keysym::DELETE));
+    assert!(result.consumed);
+    assert_eq!(surfaces(&engine, "ねこ"), vec!["ネコ"]);
+    assert!(engine.learning.as_ref().unwrap().lookup("ねこ").is_empty());
+
+    // The removed candidate is gone from the list; the next one is highlighted
+    let candidates = engine.state().candidates().unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates.selected_text(), Some("ネコ"));
+    assert_eq!(engine.preedit().unwrap().text(), "ネコ");
+}
+
+#[test]
+fn test_ctrl_delete_survives_reload() {
+    let dir = TempDir::new().unwrap();
+    let dict_dir = dir.path().join("user_dicts");
+    std::fs::create_dir(&dict_dir).unwrap();
+    std::fs::write(
+        dict_dir.join("user.tsv"),
+        "# pets\nねこ\t猫\t名詞\t\nねこ\tネコ\t名詞\t\n",
+    )
+    .unwrap();
+    let learning_path = dir.path().join("learning.tsv");
+
+    let mut engine = InputMethodEngine::new();
+    engine.reload_user_dictionary(&dict_dir).unwrap();
+    let mut cache = LearningCache::new(100);
+    cache.record("ねこ", "猫");
+    engine.learning = Some(cache);
+
+    for ch in "neko".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    *engine.state.candidates_mut().unwrap() =
+        CandidateList::from_strings_with_reading(["猫", "ネコ", "ねこ"], "ねこ");
+    engine.process_key(&press_ctrl(Keysym::DELETE));
+    engine
+        .learning
+        .as_mut()
+        .unwrap()
+        .save(&learning_path)
+        .unwrap();
+
+    // A fresh engine loading the same files no longer offers the removed entry
+    let mut engine = InputMethodEngine::new();
+    engine.reload_user_dictionary(&dict_dir).unwrap();
+    assert_eq!(surfaces(&engine, "ねこ"), vec!["ネコ"]);
+    let cache = LearningCache::load(&learning_path, 100).unwrap();
+    assert!(cache.lookup("ねこ").is_empty());
+    let text = std::fs::read_to_string(dict_dir.join("user.tsv")).unwrap();
+    assert!(text.starts_with("# pets\n"));
+}
+
+#[test]
+fn test_ctrl_delete_keeps_last_candidate() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "neko".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let count = engine.state().candidates().unwrap().len();
+    assert_eq!(count, 1);
+
+    let result = engine.process_key(&press_ctrl(Keysym::DELETE));
+    assert!(result.consumed);
+    assert_eq!(engine.state().candidates().unwrap().len(), 1);
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+}
+
+#[test]
+fn test_save_user_dictionary_keeps_edits() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("user.tsv");
+    std::fs::write(&path, "ねこ\t猫\t名詞\t\nいぬ\t犬\t名詞\t\n").unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    engine.reload_user_dictionary(&path).unwrap();
+    engine
+        .dicts
+        .user
+        .as_mut()
+        .unwrap()
+        .remove("いぬ", "犬")
+        .unwrap();
+    engine.save_user_dictionary(&path).unwrap();
+
+    engine.reload_user_dictionary(&path).unwrap();
+    assert_eq!(surfaces(&engine, "ねこ"), vec!["猫"]);
+    assert!(surfaces(&engine, "いぬ").is_empty());
+}
+
+#[test]
+fn test_stale_learning_entries_evicted_by_max_age() {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("learning.tsv");
+    let mut f = std::fs::File::create(&path).unwrap();
+    writeln!(f, "ねこ\t猫\t3\t{}", now - 86400).unwrap();
+    writeln!(f, "ねこ\t寝子\t1\t{}", now - 90 * 86400).unwrap();
+    writeln!(f, "ごしょく\t誤植\t1\t{}", now - 400 * 86400).unwrap();
+
+    let mut engine = InputMethodEngine::new();
+    engine.learning = Some(LearningCache::load(&path, 100).unwrap());
+    // Disabled by default
+    engine.evict_stale_learning();
+    assert_eq!(engine.learning.as_ref().unwrap().entry_count(), 3);
+
+    engine.update_config(EngineConfig {
+        learning_max_age_days: 30,
+        ..engine.config().clone()
+    });
+    engine.evict_stale_learning();
+    let cache = engine.learning.as_ref().unwrap();
+    assert_eq!(cache.entry_count(), 1);
+    assert_eq!(cache.lookup("ねこ")[0].0, "猫");
+    assert!(cache.lookup("ごしょく").is_empty());
+    assert!(cache.is_dirty());
+}
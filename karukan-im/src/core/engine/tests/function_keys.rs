@@ -0,0 +1,136 @@
+use super::*;
+
+// --- F6–F10 forced conversion ---
+
+fn composing_engine(input: &str) -> InputMethodEngine {
+    let mut engine = InputMethodEngine::new();
+    for ch in input.chars() {
+        engine.process_key(&press(ch));
+    }
+    engine
+}
+
+#[test]
+fn test_function_keys_convert_preedit() {
+    let cases = [
+        (Keysym::F6, "らーめん"),
+        (Keysym::F7, "ラーメン"),
+        (Keysym::F8, "ﾗｰﾒﾝ"),
+        (Keysym::F9, "ｒａ－ｍｅｎｎ"),
+        (Keysym::F10, "ra-menn"),
+    ];
+    for (keysym, expected) in cases {
+        let mut engine = composing_engine("ra-menn");
+        let result = engine.process_key(&press_key(keysym));
+        assert!(result.consumed);
+        assert_eq!(engine.preedit().unwrap().text(), expected);
+        // Stays in composing state, no candidates
+        assert!(matches!(engine.state(), InputState::Composing { .. }));
+        assert!(committed_text(&result).is_none());
+    }
+}
+
+#[test]
+fn test_function_key_flushes_pending_romaji() {
+    // Trailing "n" is still in the romaji buffer when F7 is pressed
+    let mut engine = composing_engine("ra-men");
+    engine.process_key(&press_key(Keysym::F7));
+    assert_eq!(engine.preedit().unwrap().text(), "ラーメン");
+}
+
+#[test]
+fn test_function_key_repeat_is_idempotent() {
+    let mut engine = composing_engine("kana");
+    engine.process_key(&press_key(Keysym::F8));
+    engine.process_key(&press_key(Keysym::F8));
+    assert_eq!(engine.preedit().unwrap().text(), "ｶﾅ");
+
+    // Switching between forms always starts from the reading
+    engine.process_key(&press_key(Keysym::F7));
+    engine.process_key(&press_key(Keysym::F6));
+    assert_eq!(engine.preedit().unwrap().text(), "かな");
+}
+
+#[test]
+fn test_function_key_enter_commits_form() {
+    let mut engine = composing_engine("kana");
+    engine.process_key(&press_key(Keysym::F7));
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("カナ"));
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_function_key_then_char_commits_and_starts_new_input() {
+    let mut engine = composing_engine("kana");
+    engine.process_key(&press_key(Keysym::F10));
+    let result = engine.process_key(&press('a'));
+    assert!(result.consumed);
+    assert_eq!(committed_text(&result), Some("kana"));
+    assert_eq!(engine.preedit().unwrap().text(), "あ");
+}
+
+#[test]
+fn test_function_key_escape_reverts_to_reading() {
+    let mut engine = composing_engine("kana");
+    engine.process_key(&press_key(Keysym::F7));
+    engine.process_key(&press_key(Keysym::ESCAPE));
+    assert_eq!(engine.preedit().unwrap().text(), "かな");
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("かな"));
+}
+
+#[test]
+fn test_function_key_latin_after_edit_uses_reading() {
+    let mut engine = composing_engine("kanaa");
+    // Backspace over a kana drops the recorded keystrokes
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    engine.process_key(&press_key(Keysym::F10));
+    assert_eq!(engine.preedit().unwrap().text(), "kana");
+}
+
+#[test]
+fn test_function_key_ignored_when_empty() {
+    let mut engine = InputMethodEngine::new();
+    let result = engine.process_key(&press_key(Keysym::F7));
+    assert!(!result.consumed);
+}
+
+#[test]
+fn test_function_key_then_backspace_edits_reading() {
+    let mut engine = composing_engine("kana");
+    engine.process_key(&press_key(Keysym::F7));
+    let result = engine.process_key(&press_key(Keysym::BACKSPACE));
+    // Nothing is committed, so no text in the application is deleted
+    assert!(result.consumed);
+    assert!(committed_text(&result).is_none());
+    assert_eq!(engine.preedit().unwrap().text(), "か");
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+}
+
+#[test]
+fn test_function_key_then_arrow_moves_caret_in_reading() {
+    let mut engine = composing_engine("kana");
+    engine.process_key(&press_key(Keysym::F8));
+    let result = engine.process_key(&press_key(Keysym::LEFT));
+    assert!(committed_text(&result).is_none());
+    assert_eq!(engine.preedit().unwrap().text(), "かな");
+    assert_eq!(engine.preedit().unwrap().caret(), 1);
+}
+
+#[test]
+fn test_function_key_commit_is_not_learned() {
+    let mut engine = composing_engine("kana");
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    engine.process_key(&press_key(Keysym::F7));
+    engine.process_key(&press_key(Keysym::RETURN));
+    assert!(engine.learning.as_ref().unwrap().lookup("かな").is_empty());
+
+    let mut engine = composing_engine("kana");
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    engine.process_key(&press_key(Keysym::F10));
+    assert_eq!(engine.commit(), "kana");
+    assert!(engine.learning.as_ref().unwrap().lookup("かな").is_empty());
+}
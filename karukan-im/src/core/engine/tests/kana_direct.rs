@@ -0,0 +1,99 @@
+use super::*;
+
+fn make_kana_engine() -> InputMethodEngine {
+    let mut engine = InputMethodEngine::new();
+    engine.set_input_mode(InputMode::KanaDirect);
+    engine
+}
+
+/// Type keys of the `jp` layout; an uppercase letter is sent as Shift+letter
+fn type_keys(engine: &mut InputMethodEngine, keys: &str) {
+    for ch in keys.chars() {
+        let key = if ch.is_ascii_uppercase() {
+            press_shift(ch.to_ascii_lowercase())
+        } else {
+            press(ch)
+        };
+        assert!(engine.process_key(&key).consumed, "key: {}", ch);
+    }
+}
+
+#[test]
+fn test_kana_direct_types_kana() {
+    let mut engine = make_kana_engine();
+    // に ほ ん ご: i - y b@
+    type_keys(&mut engine, "i-yb@");
+    assert_eq!(engine.preedit().unwrap().text(), "にほんご");
+    assert!(engine.input_buf.romaji.is_empty());
+}
+
+#[test]
+fn test_kana_direct_dakuten_and_small_kana() {
+    let mut engine = make_kana_engine();
+    // が っ こ う: t@ Z b 4
+    type_keys(&mut engine, "t@Zb4");
+    assert_eq!(engine.preedit().unwrap().text(), "がっこう");
+
+    // ぱ ん: f[ y
+    let mut engine = make_kana_engine();
+    type_keys(&mut engine, "f[y");
+    assert_eq!(engine.preedit().unwrap().text(), "ぱん");
+
+    // Small ゃ via Shift+7 (')
+    let mut engine = make_kana_engine();
+    type_keys(&mut engine, "g'");
+    assert_eq!(engine.preedit().unwrap().text(), "きゃ");
+}
+
+#[test]
+fn test_kana_direct_mark_without_voiced_form() {
+    let mut engine = make_kana_engine();
+    // A mark after a kana without a voiced form (or at the start) stays as is
+    type_keys(&mut engine, "@3@");
+    assert_eq!(engine.preedit().unwrap().text(), "゛あ゛");
+}
+
+#[test]
+fn test_kana_direct_mark_combines_before_caret() {
+    let mut engine = make_kana_engine();
+    type_keys(&mut engine, "ts");
+    assert_eq!(engine.preedit().unwrap().text(), "かと");
+    engine.process_key(&press_key(Keysym::LEFT));
+    type_keys(&mut engine, "@");
+    assert_eq!(engine.preedit().unwrap().text(), "がと");
+}
+
+#[test]
+fn test_kana_direct_mark_at_caret_start_is_inserted() {
+    let mut engine = make_kana_engine();
+    type_keys(&mut engine, "ts");
+    engine.process_key(&press_key(Keysym::HOME));
+    type_keys(&mut engine, "@");
+    assert_eq!(engine.preedit().unwrap().text(), "゛かと");
+}
+
+#[test]
+fn test_kana_direct_commit_and_convert() {
+    let mut engine = make_kana_engine();
+    type_keys(&mut engine, "gs");
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("きと"));
+    assert_eq!(engine.input_mode, InputMode::KanaDirect);
+
+    // Space converts the typed reading like romaji input
+    type_keys(&mut engine, "gs");
+    engine.process_key(&press_key(Keysym::SPACE));
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    assert_eq!(engine.input_buf.text, "きと");
+}
+
+#[test]
+fn test_kana_direct_keeps_mode_on_toggle_key() {
+    let mut engine = make_kana_engine();
+    let result = engine.process_key(&press_key(Keysym::SUPER_R));
+    assert!(!result.consumed);
+    assert_eq!(engine.input_mode, InputMode::KanaDirect);
+
+    // Keys without kana pass through in the empty state
+    assert!(!engine.process_key(&press_key(Keysym::SPACE)).consumed);
+}
@@ -236,3 +236,236 @@ fn test_ctrl_k_is_one_way_to_katakana() {
     engine.process_key(&press('u'));
     assert_eq!(engine.preedit().unwrap().text(), "アイう");
 }
+
+// --- Loanword readings typed in hiragana mode ---
+
+#[test]
+fn test_loanword_reading_suggests_katakana() {
+    let mut engine = InputMethodEngine::new();
+
+    for ch in "ra-menn".chars() {
+        engine.process_key(&press(ch));
+    }
+    // Preedit stays hiragana (not katakana mode)...
+    assert_eq!(engine.preedit().unwrap().text(), "らーめん");
+    assert!(engine.input_mode == InputMode::Hiragana);
+
+    // ...but the conversion candidates lead with the katakana form
+    engine.process_key(&press_key(Keysym::SPACE));
+    let candidates = engine.state().candidates().unwrap();
+    let top = &candidates.candidates()[0];
+    assert_eq!(top.text, "ラーメン");
+    assert_eq!(top.source, Some(CandidateSource::Loanword));
+    assert_eq!(
+        top.annotation.as_deref(),
+        Some(CandidateLabels::default().loanword.as_str())
+    );
+    assert!(candidates.candidates().iter().any(|c| c.text == "らーめん"));
+}
+
+#[test]
+fn test_loanword_live_conversion_shows_katakana() {
+    let mut engine = make_live_conversion_engine();
+
+    for ch in "fairu".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "ファイル");
+}
+
+#[test]
+fn test_native_reading_not_promoted_to_katakana() {
+    let mut engine = InputMethodEngine::new();
+
+    for ch in "sakura".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let candidates = engine.state().candidates().unwrap();
+    assert_eq!(candidates.candidates()[0].text, "さくら");
+}
+
+#[test]
+fn test_loanword_katakana_disabled() {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        loanword_katakana: false,
+        ..EngineConfig::default()
+    });
+
+    for ch in "ra-menn".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let candidates = engine.state().candidates().unwrap();
+    assert_eq!(candidates.candidates()[0].text, "らーめん");
+}
+
+// --- Long vowels: ー vs. repeated vowels ---
+
+/// Type `input` in the given mode and return (preedit, committed text).
+fn type_and_commit(config: EngineConfig, mode: InputMode, input: &str) -> (String, String) {
+    let mut engine = InputMethodEngine::with_config(config);
+    engine.input_mode = mode;
+    for ch in input.chars() {
+        engine.process_key(&press(ch));
+    }
+    let preedit = engine.preedit().unwrap().text().to_string();
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    let commit = committed_text(&result).unwrap().to_string();
+    (preedit, commit)
+}
+
+#[test]
+fn test_long_vowel_katakana_mode_converges() {
+    for input in ["ra-menn", "raamenn"] {
+        let (preedit, commit) =
+            type_and_commit(EngineConfig::default(), InputMode::Katakana, input);
+        assert_eq!(preedit, "ラーメン", "input: {}", input);
+        assert_eq!(commit, "ラーメン", "input: {}", input);
+    }
+    // No vowel repetition, no long vowel
+    let (_, commit) = type_and_commit(EngineConfig::default(), InputMode::Katakana, "ramenn");
+    assert_eq!(commit, "ラメン");
+}
+
+#[test]
+fn test_long_vowel_hiragana_mode_keeps_spellings_distinct() {
+    let cases = [
+        ("ra-menn", "らーめん"),
+        ("raamenn", "らあめん"),
+        ("ramenn", "らめん"),
+    ];
+    for (input, expected) in cases {
+        let (preedit, commit) =
+            type_and_commit(EngineConfig::default(), InputMode::Hiragana, input);
+        assert_eq!(preedit, expected);
+        assert_eq!(commit, expected);
+    }
+}
+
+#[test]
+fn test_long_vowel_after_n() {
+    let (_, commit) = type_and_commit(EngineConfig::default(), InputMode::Hiragana, "nn-");
+    assert_eq!(commit, "んー");
+    let (_, commit) = type_and_commit(EngineConfig::default(), InputMode::Katakana, "nn-");
+    assert_eq!(commit, "ンー");
+    // ん has no vowel, so a following vowel stays as is
+    let (_, commit) = type_and_commit(EngineConfig::default(), InputMode::Katakana, "nna");
+    assert_eq!(commit, "ンア");
+}
+
+#[test]
+fn test_long_vowel_katakana_disabled() {
+    let config = EngineConfig {
+        katakana_long_vowel: false,
+        ..EngineConfig::default()
+    };
+    let (preedit, commit) = type_and_commit(config, InputMode::Katakana, "raamenn");
+    assert_eq!(preedit, "ラアメン");
+    assert_eq!(commit, "ラアメン");
+}
+
+#[test]
+fn test_long_vowel_pending_n_flushed_on_commit() {
+    // The trailing "n" is still romaji when Enter flushes it
+    let (_, commit) = type_and_commit(EngineConfig::default(), InputMode::Katakana, "raamen");
+    assert_eq!(commit, "ラーメン");
+
+    let config = EngineConfig {
+        katakana_long_vowel: false,
+        ..EngineConfig::default()
+    };
+    let (_, commit) = type_and_commit(config, InputMode::Katakana, "raamen");
+    assert_eq!(commit, "ラアメン");
+}
+
+#[test]
+fn test_long_vowel_baked_when_leaving_katakana() {
+    for (long_vowel, expected) in [(true, "ラーメン"), (false, "ラアメン")] {
+        let mut engine = InputMethodEngine::with_config(EngineConfig {
+            katakana_long_vowel: long_vowel,
+            ..EngineConfig::default()
+        });
+        for ch in "raamenn".chars() {
+            engine.process_key(&press(ch));
+        }
+        engine.process_key(&press_ctrl(Keysym::KEY_K));
+        assert_eq!(engine.preedit().unwrap().text(), expected);
+
+        // Back to hiragana: the katakana form (with ー) is kept as typed text
+        engine.process_key(&press_key(Keysym::SUPER_R));
+        assert_eq!(engine.input_mode, InputMode::Hiragana);
+        assert_eq!(engine.input_buf.text, expected);
+    }
+}
+
+// --- Katakana Lock Tests ---
+
+#[test]
+fn test_katakana_lock_space_commits_without_conversion() {
+    let mut engine = InputMethodEngine::new();
+    let result = engine.process_key(&press_ctrl_shift(Keysym::KEY_K_UPPER));
+    assert!(result.consumed);
+    assert_eq!(engine.input_mode, InputMode::KatakanaLock);
+    assert!(aux_text(&result).unwrap().starts_with("[カ🔒]"));
+
+    for ch in "tesuto".chars() {
+        engine.process_key(&press(ch));
+        assert!(!matches!(engine.state(), InputState::Conversion { .. }));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "テスト");
+
+    let result = engine.process_key(&press_key(Keysym::SPACE));
+    assert!(result.consumed);
+    assert_eq!(committed_text(&result), Some("テスト"));
+    assert!(matches!(engine.state(), InputState::Empty));
+
+    // The lock stays on for the next word
+    assert_eq!(engine.input_mode, InputMode::KatakanaLock);
+    for ch in "ka".chars() {
+        engine.process_key(&press(ch));
+    }
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("カ"));
+}
+
+#[test]
+fn test_katakana_lock_shift_letter_stays_in_lock() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press_ctrl_shift(Keysym::KEY_K_UPPER));
+
+    engine.process_key(&press_shift('A'));
+    engine.process_key(&press_shift('I'));
+    assert_eq!(engine.input_mode, InputMode::KatakanaLock);
+    assert_eq!(engine.preedit().unwrap().text(), "アイ");
+}
+
+#[test]
+fn test_katakana_lock_survives_mode_toggle_key_and_reset() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press_ctrl_shift(Keysym::KEY_K_UPPER));
+
+    let result = engine.process_key(&press_key(Keysym::ALT_R));
+    assert!(!result.consumed);
+    assert_eq!(engine.input_mode, InputMode::KatakanaLock);
+
+    engine.reset();
+    assert_eq!(engine.input_mode, InputMode::KatakanaLock);
+}
+
+#[test]
+fn test_katakana_lock_toggle_off_keeps_composed_katakana() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press_ctrl_shift(Keysym::KEY_K_UPPER));
+    for ch in "aiu".chars() {
+        engine.process_key(&press(ch));
+    }
+
+    let result = engine.process_key(&press_ctrl_shift(Keysym::KEY_K_UPPER));
+    assert!(result.consumed);
+    assert_eq!(engine.input_mode, InputMode::Hiragana);
+    assert_eq!(engine.preedit().unwrap().text(), "アイウ");
+
+    engine.process_key(&press('e'));
+    assert_eq!(engine.preedit().unwrap().text(), "アイウえ");
+}
@@ -0,0 +1,131 @@
+use super::*;
+use crate::config::settings::KeyBindingScheme;
+use crate::core::keybindings::{KeyAction, KeyBindings, KeyCombo};
+
+fn make_engine(keybindings: KeyBindings) -> InputMethodEngine {
+    InputMethodEngine::with_config(EngineConfig {
+        keybindings,
+        ..EngineConfig::default()
+    })
+}
+
+/// Enter conversion of "かん" with three candidates
+fn start_conversion_with_candidates(engine: &mut InputMethodEngine) {
+    for ch in "kann".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let items = ["感", "缶", "巻"]
+        .iter()
+        .enumerate()
+        .map(|(i, k)| Candidate::with_reading(*k, "かん").with_index(i))
+        .collect();
+    engine.state.candidates_mut().unwrap().update(items);
+}
+
+fn cursor(engine: &InputMethodEngine) -> usize {
+    engine.state().candidates().unwrap().cursor()
+}
+
+#[test]
+fn test_remapped_next_candidate_follows_custom_key() {
+    let mut bindings = KeyBindings::default();
+    bindings.bind(
+        KeyAction::NextCandidate,
+        vec![KeyCombo::ctrl(Keysym(0x6a))], // Ctrl+J
+    );
+    let mut engine = make_engine(bindings);
+    start_conversion_with_candidates(&mut engine);
+    assert_eq!(cursor(&engine), 0);
+
+    let result = engine.process_key(&press_ctrl(Keysym(0x6a)));
+    assert!(result.consumed);
+    assert_eq!(cursor(&engine), 1);
+
+    // Ctrl+N is no longer bound and leaves the selection alone
+    let result = engine.process_key(&press_ctrl(Keysym::KEY_N));
+    assert!(!result.consumed);
+    assert_eq!(cursor(&engine), 1);
+
+    // Ctrl+P keeps its default binding; Space/Down still navigate
+    engine.process_key(&press_ctrl(Keysym::KEY_P));
+    assert_eq!(cursor(&engine), 0);
+    engine.process_key(&press_key(Keysym::DOWN));
+    assert_eq!(cursor(&engine), 1);
+}
+
+#[test]
+fn test_remapped_next_candidate_to_plain_key() {
+    let mut bindings = KeyBindings::default();
+    bindings.bind(
+        KeyAction::NextCandidate,
+        vec![KeyCombo::parse("j").unwrap()],
+    );
+    let mut engine = make_engine(bindings);
+    start_conversion_with_candidates(&mut engine);
+
+    // "j" navigates instead of committing and starting new input
+    engine.process_key(&press('j'));
+    engine.process_key(&press('j'));
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    assert_eq!(cursor(&engine), 2);
+}
+
+#[test]
+fn test_default_scheme_ctrl_n_navigates() {
+    let mut engine = make_engine(KeyBindings::default());
+    start_conversion_with_candidates(&mut engine);
+    engine.process_key(&press_ctrl(Keysym::KEY_N_UPPER));
+    assert_eq!(cursor(&engine), 1);
+}
+
+#[test]
+fn test_emacs_scheme_ctrl_g_cancels() {
+    let mut engine = make_engine(KeyBindings::new(KeyBindingScheme::Emacs));
+    engine.process_key(&press('a'));
+    engine.process_key(&press_ctrl(Keysym(0x67))); // Ctrl+G
+    assert!(matches!(engine.state(), InputState::Empty));
+
+    // The default scheme leaves Ctrl+G unbound
+    let mut engine = make_engine(KeyBindings::default());
+    engine.process_key(&press('a'));
+    engine.process_key(&press_ctrl(Keysym(0x67)));
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+}
+
+#[test]
+fn test_atok_scheme_ctrl_i_enters_katakana() {
+    let mut engine = make_engine(KeyBindings::new(KeyBindingScheme::Atok));
+    engine.process_key(&press('a'));
+
+    engine.process_key(&press_ctrl(Keysym::KEY_K));
+    assert_eq!(engine.input_mode, InputMode::Hiragana);
+
+    engine.process_key(&press_ctrl(Keysym(0x69))); // Ctrl+I
+    assert_eq!(engine.input_mode, InputMode::Katakana);
+}
+
+#[test]
+fn test_remapped_word_motion() {
+    let mut bindings = KeyBindings::default();
+    bindings.bind(
+        KeyAction::CaretWordLeft,
+        vec![KeyCombo::ctrl(Keysym(0x79))], // Ctrl+Y
+    );
+    let mut engine = make_engine(bindings);
+    for ch in "kannji,kana".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "かんじ、かな");
+    assert_eq!(engine.preedit().unwrap().caret(), 6);
+
+    // Ctrl+Left is no longer word motion: it moves by one character
+    engine.process_key(&press_ctrl(Keysym::LEFT));
+    assert_eq!(engine.preedit().unwrap().caret(), 5);
+
+    for expected in [4, 3, 0] {
+        let result = engine.process_key(&press_ctrl(Keysym(0x79)));
+        assert!(result.consumed);
+        assert_eq!(engine.preedit().unwrap().caret(), expected);
+    }
+}
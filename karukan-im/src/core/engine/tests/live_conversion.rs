@@ -248,3 +248,127 @@ fn test_ctrl_shift_l_shows_aux_text() {
     );
     assert!(has_aux);
 }
+
+// --- Debounce tests ---
+
+thread_local! {
+    /// Time returned by `fake_now` (each test runs on its own thread)
+    static FAKE_NOW: std::cell::Cell<Option<std::time::Instant>> = const { std::cell::Cell::new(None) };
+}
+
+fn fake_now() -> std::time::Instant {
+    FAKE_NOW.with(|now| now.get().expect("fake clock not set"))
+}
+
+/// Advance the fake clock by `ms` milliseconds (starting it if needed)
+fn advance_ms(ms: u64) {
+    FAKE_NOW.with(|now| {
+        let base = now.get().unwrap_or_else(std::time::Instant::now);
+        now.set(Some(base + std::time::Duration::from_millis(ms)));
+    });
+}
+
+fn make_debounced_engine(debounce_ms: u64) -> InputMethodEngine {
+    let mut engine = InputMethodEngine::with_config(EngineConfig {
+        debounce_ms,
+        ..EngineConfig::default()
+    });
+    engine.live.enabled = true;
+    engine.clock = fake_now;
+    advance_ms(0);
+    engine
+}
+
+/// Type `romaji`, advancing the fake clock by `gap_ms` before each key
+fn type_with_gap(engine: &mut InputMethodEngine, romaji: &str, gap_ms: u64) {
+    for ch in romaji.chars() {
+        advance_ms(gap_ms);
+        engine.process_key(&press(ch));
+    }
+}
+
+#[test]
+fn test_debounce_disabled_converts_every_key() {
+    let mut engine = make_debounced_engine(0);
+    type_with_gap(&mut engine, "aiu", 1);
+    // Every key after the one starting the input converts
+    assert_eq!(engine.metrics.conversion_id, 2);
+}
+
+#[test]
+fn test_debounce_skips_inference_for_rapid_keys() {
+    let mut engine = make_debounced_engine(100);
+    type_with_gap(&mut engine, "aiu", 10);
+    // Only the first composing key converted; the rapid ones still update the preedit
+    assert_eq!(engine.metrics.conversion_id, 1);
+    assert_eq!(engine.preedit().unwrap().text(), "あいう");
+
+    // The first key after the quiet period converts again
+    type_with_gap(&mut engine, "e", 150);
+    assert_eq!(engine.metrics.conversion_id, 2);
+    assert_eq!(engine.preedit().unwrap().text(), "あいうえ");
+}
+
+#[test]
+fn test_debounce_keeps_live_text_and_appends_typed_kana() {
+    let mut engine = make_debounced_engine(100);
+    type_with_gap(&mut engine, "toukyou", 500);
+    // Simulate the model result of the last real conversion
+    engine.live.text = "東京".to_string();
+    engine.live.reading = "とうきょう".to_string();
+
+    type_with_gap(&mut engine, "to", 10);
+    assert_eq!(engine.preedit().unwrap().text(), "東京と");
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(t) if t == "東京と"))
+    );
+}
+
+#[test]
+fn test_debounced_live_text_is_not_learned() {
+    let mut engine = make_debounced_engine(100);
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+    type_with_gap(&mut engine, "toukyou", 500);
+    engine.live.text = "東京".to_string();
+    engine.live.reading = "とうきょう".to_string();
+
+    type_with_gap(&mut engine, "to", 10);
+    assert_eq!(engine.preedit().unwrap().text(), "東京と");
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    // "と" was never converted, so 東京と is not a conversion of とうきょうと
+    let cache = engine.learning.as_ref().unwrap();
+    assert!(cache.lookup("とうきょうと").is_empty());
+}
+
+#[test]
+fn test_debounced_keys_extend_from_last_conversion() {
+    let mut engine = make_debounced_engine(100);
+    type_with_gap(&mut engine, "toukyou", 500);
+    engine.live.text = "東京".to_string();
+    engine.live.reading = "とうきょう".to_string();
+
+    type_with_gap(&mut engine, "toto", 10);
+    assert_eq!(engine.preedit().unwrap().text(), "東京とと");
+    // The converted reading stays the one the model saw
+    assert_eq!(engine.live.reading, "とうきょう");
+    assert_eq!(engine.live.typed, "とと");
+}
+
+#[test]
+fn test_debounced_backspace_falls_back_to_hiragana() {
+    let mut engine = make_debounced_engine(100);
+    type_with_gap(&mut engine, "toukyou", 500);
+    engine.live.text = "東京".to_string();
+    engine.live.reading = "とうきょう".to_string();
+
+    advance_ms(10);
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert!(engine.live.text.is_empty());
+    assert_eq!(engine.preedit().unwrap().text(), "とうきょ");
+}
@@ -0,0 +1,63 @@
+//! Conversion flows driven by the deterministic `MockBackend`
+
+use super::*;
+
+#[test]
+fn test_space_shows_model_candidates() {
+    let mut engine = make_mock_engine();
+    type_and_convert(&mut engine, "kyou");
+
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    assert_eq!(engine.preedit().unwrap().text(), "今日");
+    assert_eq!(engine.metrics.model_name, "mock");
+    let texts = candidate_texts(&mut engine);
+    assert_eq!(texts[..2], ["今日", "京"]);
+    // Kana fallbacks are still offered after the model candidates
+    assert!(texts.iter().any(|t| t == "きょう"));
+    assert!(texts.iter().any(|t| t == "キョウ"));
+}
+
+#[test]
+fn test_enter_commits_top_candidate() {
+    let mut engine = make_mock_engine();
+    type_and_convert(&mut engine, "toukyou");
+    assert_eq!(engine.preedit().unwrap().text(), "東京");
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("東京"));
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_digit_selects_and_commits() {
+    let mut engine = make_mock_engine();
+    type_and_convert(&mut engine, "kyou");
+
+    let result = engine.process_key(&press('2'));
+    assert_eq!(committed_text(&result), Some("京"));
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_unknown_reading_is_echoed() {
+    let mut engine = make_mock_engine();
+    type_and_convert(&mut engine, "neko");
+    assert_eq!(candidate_texts(&mut engine)[0], "ネコ");
+}
+
+#[test]
+fn test_selection_is_learned() {
+    let mut engine = make_mock_engine();
+    engine.learning = Some(karukan_engine::LearningCache::new(100));
+
+    type_and_convert(&mut engine, "kyou");
+    engine.process_key(&press('2'));
+    let cache = engine.learning.as_ref().unwrap();
+    assert_eq!(cache.lookup("きょう")[0].0, "京");
+
+    // The learned candidate now comes first
+    type_and_convert(&mut engine, "kyou");
+    assert_eq!(engine.preedit().unwrap().text(), "京");
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("京"));
+}
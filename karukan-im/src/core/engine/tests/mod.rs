@@ -2,18 +2,36 @@
 
 use super::*;
 use crate::core::keycode::KeyModifiers;
+use crate::core::preedit::PreeditAttribute;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 mod alphabet;
+mod aux_reading;
 mod basic;
+mod blank_reading;
+mod candidate_preview;
 mod candidates;
+mod commit_reading;
 mod conversion;
+mod conversion_cache;
 mod cursor;
+mod dictionaries;
+mod function_keys;
+mod kana_direct;
 mod katakana;
+mod keybindings;
 mod live_conversion;
+mod mock_backend;
 mod mode_toggle;
 mod passthrough;
+mod reconvert;
+mod segments;
+mod show_romaji;
+mod spans;
 mod strategy;
 mod surrounding;
+mod undo;
 
 fn press(ch: char) -> KeyEvent {
     KeyEvent::press(Keysym(ch as u32))
@@ -47,6 +65,198 @@ fn press_ctrl_shift(keysym: Keysym) -> KeyEvent {
     )
 }
 
+/// Text of the first Commit action of a result
+fn committed_text(result: &EngineResult) -> Option<&str> {
+    result.actions.iter().find_map(|a| match a {
+        EngineAction::Commit(text) => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+/// Text of the first UpdateAuxText action of a result
+fn aux_text(result: &EngineResult) -> Option<&str> {
+    result.actions.iter().find_map(|a| match a {
+        EngineAction::UpdateAuxText(text) => Some(text.as_str()),
+        _ => None,
+    })
+}
+
+/// Type `romaji` and press Space to convert it
+fn type_and_convert(engine: &mut InputMethodEngine, romaji: &str) -> EngineResult {
+    for ch in romaji.chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE))
+}
+
+/// Texts of the candidates in the conversion candidate list
+fn candidate_texts(engine: &mut InputMethodEngine) -> Vec<String> {
+    engine
+        .state
+        .candidates_mut()
+        .unwrap()
+        .candidates()
+        .iter()
+        .map(|c| c.text.clone())
+        .collect()
+}
+
+/// Engine with `config` whose system dictionary is loaded from `tsv`
+/// (Mozc TSV lines, e.g. `"はし\t橋\t名詞\t\n"`)
+fn engine_with_dict(tsv: &str, config: EngineConfig) -> InputMethodEngine {
+    use std::io::Write;
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(tsv.as_bytes()).unwrap();
+    file.flush().unwrap();
+
+    let mut engine = InputMethodEngine::with_config(config);
+    engine.dicts.system = Some(karukan_engine::Dictionary::load_auto(file.path()).unwrap());
+    engine
+}
+
+/// A `convert` or `score_candidates` request seen by a [`MockBackend`]
+#[derive(Debug, Clone, PartialEq)]
+struct MockCall {
+    katakana: String,
+    context: String,
+    /// Candidates requested, or surfaces scored
+    n: usize,
+}
+
+/// Deterministic conversion backend, so conversion can be tested without a model
+///
+/// きょう → 今日/京 and とうきょう → 東京; any other reading is echoed back.
+/// [`Self::with_candidates`] returns fixed candidates for every reading instead,
+/// [`Self::with_scores`] makes it score surfaces for reranking, and
+/// [`Self::with_step`] makes candidates slow to find, like beam search. Requests
+/// are logged in `calls` and `scored`, which stay readable after the backend
+/// is moved into a converter.
+#[derive(Default)]
+struct MockBackend {
+    name: &'static str,
+    candidates: Option<Vec<&'static str>>,
+    /// NLL per surface (lower is better); scoring is unsupported when empty
+    scores: Vec<(&'static str, f32)>,
+    /// Time to find each candidate; the search stops at the deadline
+    step: Option<Duration>,
+    calls: Arc<Mutex<Vec<MockCall>>>,
+    scored: Arc<Mutex<Vec<MockCall>>>,
+}
+
+impl MockBackend {
+    fn new() -> Self {
+        Self {
+            name: "mock",
+            ..Self::default()
+        }
+    }
+
+    fn named(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    fn with_candidates(mut self, candidates: &[&'static str]) -> Self {
+        self.candidates = Some(candidates.to_vec());
+        self
+    }
+
+    fn with_scores(mut self, scores: &[(&'static str, f32)]) -> Self {
+        self.scores = scores.to_vec();
+        self
+    }
+
+    fn with_step(mut self, step: Duration) -> Self {
+        self.step = Some(step);
+        self
+    }
+}
+
+impl karukan_engine::ConversionBackend for MockBackend {
+    fn convert(
+        &self,
+        katakana: &str,
+        context: &str,
+        n: usize,
+    ) -> karukan_engine::kanji::error::Result<Vec<String>> {
+        self.calls.lock().unwrap().push(MockCall {
+            katakana: katakana.to_string(),
+            context: context.to_string(),
+            n,
+        });
+        let candidates: &[&str] = match (&self.candidates, katakana) {
+            (Some(candidates), _) => candidates,
+            (None, "キョウ") => &["今日", "京"],
+            (None, "トウキョウ") => &["東京"],
+            (None, _) => &[katakana],
+        };
+        Ok(candidates.iter().take(n).map(|c| c.to_string()).collect())
+    }
+
+    fn convert_with_deadline(
+        &self,
+        katakana: &str,
+        context: &str,
+        n: usize,
+        deadline: Option<Instant>,
+    ) -> karukan_engine::kanji::error::Result<Vec<String>> {
+        let mut candidates = self.convert(katakana, context, n)?;
+        if let Some(step) = self.step {
+            let mut found = 0;
+            while found < candidates.len() {
+                std::thread::sleep(step);
+                found += 1;
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    break;
+                }
+            }
+            candidates.truncate(found);
+        }
+        Ok(candidates)
+    }
+
+    fn model_display_name(&self) -> &str {
+        self.name
+    }
+
+    fn score_candidates(
+        &self,
+        katakana: &str,
+        context: &str,
+        surfaces: &[&str],
+    ) -> karukan_engine::kanji::error::Result<Vec<f32>> {
+        if self.scores.is_empty() {
+            return Err(karukan_engine::kanji::KanjiError::Unsupported(
+                "candidate scoring",
+            ));
+        }
+        self.scored.lock().unwrap().push(MockCall {
+            katakana: katakana.to_string(),
+            context: context.to_string(),
+            n: surfaces.len(),
+        });
+        Ok(surfaces
+            .iter()
+            .map(|surface| {
+                self.scores
+                    .iter()
+                    .find(|(s, _)| s == surface)
+                    .map_or(f32::MAX, |(_, score)| *score)
+            })
+            .collect())
+    }
+}
+
+/// Engine converting with [`MockBackend`]
+///
+/// The light strategy runs beam search on Space, so all mock candidates are used.
+fn make_mock_engine() -> InputMethodEngine {
+    let mut engine = InputMethodEngine::new();
+    engine.config.strategy = crate::config::settings::StrategyMode::Light;
+    engine.converters.kanji = Some(KanaKanjiConverter::from_backend(MockBackend::new()));
+    engine
+}
+
 fn make_live_conversion_engine() -> InputMethodEngine {
     let mut engine = InputMethodEngine::new();
     engine.live.enabled = true;
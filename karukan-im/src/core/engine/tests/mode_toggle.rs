@@ -84,3 +84,66 @@ fn test_meta_r_also_switches_alphabet_to_hiragana() {
     assert!(result.consumed);
     assert!(engine.input_mode != InputMode::Alphabet);
 }
+
+// --- Pending romaji across mode switches ---
+//
+// Every switch flushes the pending romaji in the mode it was typed in:
+// consonants stay latin, a lone "n" becomes ん (ン in katakana).
+
+/// Type "ka" + `pending`, switch with `switch`, and return the composed text
+/// and the romaji buffer left behind
+fn switch_with_pending(pending: &str, switch: fn(&mut InputMethodEngine)) -> (String, String) {
+    let mut engine = InputMethodEngine::new();
+    for ch in "ka".chars().chain(pending.chars()) {
+        engine.process_key(&press(ch));
+    }
+    switch(&mut engine);
+    (
+        engine.input_buf.text.clone(),
+        engine.converters.romaji.buffer().to_string(),
+    )
+}
+
+#[test]
+fn test_enter_katakana_flushes_pending_romaji() {
+    let ctrl_k = |e: &mut InputMethodEngine| {
+        e.process_key(&press_ctrl(Keysym::KEY_K));
+    };
+    for (pending, expected) in [("k", "かk"), ("ky", "かky"), ("n", "かん")] {
+        let (text, buffer) = switch_with_pending(pending, ctrl_k);
+        assert_eq!(text, expected, "pending: {}", pending);
+        assert_eq!(buffer, "", "pending: {}", pending);
+    }
+}
+
+#[test]
+fn test_leaving_katakana_flushes_before_baking() {
+    let katakana_then_toggle = |e: &mut InputMethodEngine| {
+        e.input_mode = InputMode::Katakana;
+        e.process_key(&press_key(Keysym::SUPER_R));
+    };
+    for (pending, expected) in [("k", "カk"), ("ky", "カky"), ("n", "カン")] {
+        let (text, buffer) = switch_with_pending(pending, katakana_then_toggle);
+        assert_eq!(text, expected, "pending: {}", pending);
+        assert_eq!(buffer, "", "pending: {}", pending);
+    }
+
+    let katakana_then_alphabet = |e: &mut InputMethodEngine| {
+        e.input_mode = InputMode::Katakana;
+        e.set_input_mode(InputMode::Alphabet);
+    };
+    let (text, _) = switch_with_pending("n", katakana_then_alphabet);
+    assert_eq!(text, "カン");
+}
+
+#[test]
+fn test_shift_letter_flushes_pending_romaji() {
+    let shift_l = |e: &mut InputMethodEngine| {
+        e.process_key(&press_shift('L'));
+    };
+    for (pending, expected) in [("k", "かkL"), ("ky", "かkyL"), ("n", "かんL")] {
+        let (text, buffer) = switch_with_pending(pending, shift_l);
+        assert_eq!(text, expected, "pending: {}", pending);
+        assert_eq!(buffer, "", "pending: {}", pending);
+    }
+}
@@ -0,0 +1,193 @@
+use super::*;
+use crate::core::keybindings::KeyCombo;
+
+/// Bind Reconvert to Ctrl+Backspace (unbound by default)
+fn bind_reconvert(mut engine: InputMethodEngine) -> InputMethodEngine {
+    engine.config.keybindings.bind(
+        KeyAction::Reconvert,
+        vec![KeyCombo::ctrl(Keysym::BACKSPACE)],
+    );
+    engine
+}
+
+/// Engine with Reconvert on Ctrl+Backspace
+fn make_reconvert_engine() -> InputMethodEngine {
+    bind_reconvert(InputMethodEngine::new())
+}
+
+/// Engine in Empty state with `left` as the text before the application's cursor
+fn make_engine_with_left(left: &str) -> InputMethodEngine {
+    let mut engine = make_reconvert_engine();
+    engine.set_surrounding_context(left, "");
+    engine
+}
+
+/// [`make_engine_with_left`] with a system dictionary reading both 橋 and 箸 as はし
+fn make_dict_engine(left: &str) -> InputMethodEngine {
+    let mut engine = bind_reconvert(engine_with_dict(
+        "はし\t橋\t名詞\t\nはし\t箸\t名詞\t\n",
+        EngineConfig::default(),
+    ));
+    engine.set_surrounding_context(left, "");
+    engine
+}
+
+fn reconvert(engine: &mut InputMethodEngine) -> EngineResult {
+    engine.process_key(&press_ctrl(Keysym::BACKSPACE))
+}
+
+fn converted_reading(engine: &InputMethodEngine) -> String {
+    let (segments, _) = engine.state().segments().unwrap();
+    segments.iter().map(|s| s.reading.as_str()).collect()
+}
+
+fn left_context(engine: &InputMethodEngine) -> Option<&str> {
+    engine
+        .surrounding_context
+        .as_ref()
+        .and_then(|ctx| ctx.left.as_deref())
+}
+
+#[test]
+fn test_reconvert_katakana_word() {
+    let mut engine = make_engine_with_left("今日はハシ");
+
+    let result = reconvert(&mut engine);
+    assert!(result.consumed);
+    assert!(matches!(
+        result.actions.first(),
+        Some(EngineAction::DeleteSurrounding {
+            before: 2,
+            after: 0
+        })
+    ));
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    assert_eq!(converted_reading(&engine), "はし");
+    // The deleted word no longer counts as context
+    assert_eq!(left_context(&engine), Some("今日は"));
+}
+
+#[test]
+fn test_reconvert_halfwidth_katakana_deletes_raw_length() {
+    // NFKC would read ﾊﾞｼ as the two characters バシ; the application has three
+    let mut engine = make_engine_with_left("今日はﾊﾞｼ");
+
+    let result = reconvert(&mut engine);
+    assert!(result.consumed);
+    assert!(matches!(
+        result.actions.first(),
+        Some(EngineAction::DeleteSurrounding {
+            before: 3,
+            after: 0
+        })
+    ));
+    assert_eq!(converted_reading(&engine), "ばし");
+    assert_eq!(left_context(&engine), Some("今日は"));
+}
+
+#[test]
+fn test_reconvert_hiragana_word_stops_at_script_change() {
+    let mut engine = make_engine_with_left("橋をわたる");
+
+    reconvert(&mut engine);
+    assert_eq!(converted_reading(&engine), "をわたる");
+    assert_eq!(left_context(&engine), Some("橋"));
+}
+
+#[test]
+fn test_reconvert_keeps_prolonged_sound_mark() {
+    let mut engine = make_engine_with_left("熱いコーヒー");
+
+    reconvert(&mut engine);
+    assert_eq!(converted_reading(&engine), "こーひー");
+    assert_eq!(left_context(&engine), Some("熱い"));
+}
+
+#[test]
+fn test_reconvert_kanji_word_from_dictionary() {
+    let mut engine = make_dict_engine("木の橋");
+
+    let result = reconvert(&mut engine);
+    assert!(matches!(
+        result.actions.first(),
+        Some(EngineAction::DeleteSurrounding {
+            before: 1,
+            after: 0
+        })
+    ));
+    assert_eq!(converted_reading(&engine), "はし");
+    assert_eq!(left_context(&engine), Some("木の"));
+}
+
+#[test]
+fn test_reconvert_kanji_run_shortened_to_known_word() {
+    let mut engine = make_dict_engine("石橋");
+
+    let result = reconvert(&mut engine);
+    assert!(matches!(
+        result.actions.first(),
+        Some(EngineAction::DeleteSurrounding { before: 1, .. })
+    ));
+    assert_eq!(converted_reading(&engine), "はし");
+    assert_eq!(left_context(&engine), Some("石"));
+}
+
+#[test]
+fn test_reconvert_unknown_kanji_passes_through() {
+    let mut engine = make_engine_with_left("石橋");
+
+    let result = reconvert(&mut engine);
+    assert!(!result.consumed);
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_reconvert_after_punctuation_passes_through() {
+    for left in ["ハシ。", "ハシ ", "hashi"] {
+        let mut engine = make_engine_with_left(left);
+        let result = reconvert(&mut engine);
+        assert!(!result.consumed, "{left}");
+        assert!(matches!(engine.state(), InputState::Empty), "{left}");
+    }
+}
+
+#[test]
+fn test_reconvert_without_surrounding_text_passes_through() {
+    let mut engine = make_reconvert_engine();
+    let result = reconvert(&mut engine);
+    assert!(!result.consumed);
+    assert!(result.actions.is_empty());
+}
+
+#[test]
+fn test_ctrl_backspace_while_composing_is_not_reconvert() {
+    let mut engine = make_engine_with_left("ハシ");
+    engine.process_key(&press('a'));
+
+    reconvert(&mut engine);
+    assert!(matches!(
+        engine.state(),
+        InputState::Empty | InputState::Composing { .. }
+    ));
+    assert_eq!(left_context(&engine), Some("ハシ"));
+}
+
+#[test]
+fn test_reconvert_is_unbound_by_default() {
+    let mut engine = InputMethodEngine::new();
+    engine.set_surrounding_context("ハシ", "");
+
+    let result = reconvert(&mut engine);
+    assert!(!result.consumed);
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_reconvert_long_kanji_run_is_capped() {
+    // Only the last MAX_KANJI_RUN characters are looked up
+    let mut engine = make_dict_engine("一二三四五六七八九橋");
+
+    reconvert(&mut engine);
+    assert_eq!(converted_reading(&engine), "はし");
+    assert_eq!(left_context(&engine), Some("一二三四五六七八九"));
+}
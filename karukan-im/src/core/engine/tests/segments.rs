@@ -0,0 +1,252 @@
+use super::*;
+use karukan_engine::LearningCache;
+
+fn press_shift_key(keysym: Keysym) -> KeyEvent {
+    KeyEvent::new(keysym, KeyModifiers::new().with_shift(true), true)
+}
+
+fn convert(engine: &mut InputMethodEngine, romaji: &str) {
+    for ch in romaji.chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+}
+
+fn segment_readings(engine: &InputMethodEngine) -> Vec<String> {
+    let (segments, _) = engine.state().segments().unwrap();
+    segments.iter().map(|s| s.reading.clone()).collect()
+}
+
+#[test]
+fn test_conversion_starts_with_single_segment() {
+    let mut engine = InputMethodEngine::new();
+    convert(&mut engine, "kanji");
+
+    assert_eq!(segment_readings(&engine), vec!["かんじ"]);
+    assert_eq!(engine.state().segments().unwrap().1, 0);
+}
+
+/// Mock engine splitting readings with a user dictionary of きょう, いい, and てんき
+fn make_auto_segment_engine() -> InputMethodEngine {
+    let mut engine = make_mock_engine();
+    engine.config.auto_segment = true;
+    let mut user = UserDictionary::new();
+    user.add("きょう", "今日").unwrap();
+    user.add("いい", "良い").unwrap();
+    user.add("てんき", "天気").unwrap();
+    engine.dicts.user = Some(user);
+    engine
+}
+
+#[test]
+fn test_split_reading_by_longest_match() {
+    let engine = make_auto_segment_engine();
+    assert_eq!(
+        engine.split_reading("きょうはいいてんきですね"),
+        vec!["きょう", "は", "いい", "てんき", "ですね"]
+    );
+    // No word starts anywhere: one segment
+    assert_eq!(engine.split_reading("ですね"), vec!["ですね"]);
+}
+
+#[test]
+fn test_auto_segment_converts_each_word() {
+    let mut engine = make_auto_segment_engine();
+    convert(&mut engine, "kyouhaiitenkidesune");
+
+    assert_eq!(
+        segment_readings(&engine),
+        vec!["きょう", "は", "いい", "てんき", "ですね"]
+    );
+    let (segments, active) = engine.state().segments().unwrap();
+    assert_eq!(active, 0);
+    assert_eq!(segments[0].selected_text(), "今日");
+    assert_eq!(segments[3].selected_text(), "天気");
+
+    // Resizing still works on the split segments
+    engine.process_key(&press_shift_key(Keysym::RIGHT));
+    assert_eq!(segment_readings(&engine)[..2], ["きょうは", "いい"]);
+}
+
+#[test]
+fn test_auto_segment_off_keeps_single_segment() {
+    let mut engine = make_auto_segment_engine();
+    engine.config.auto_segment = false;
+    convert(&mut engine, "kyouhaiitenkidesune");
+    assert_eq!(segment_readings(&engine), vec!["きょうはいいてんきですね"]);
+}
+
+#[test]
+fn test_shift_left_shrinks_active_segment() {
+    let mut engine = InputMethodEngine::new();
+    convert(&mut engine, "kanji");
+    let before = engine.state().candidates().unwrap().clone();
+
+    let result = engine.process_key(&press_shift_key(Keysym::LEFT));
+    assert!(result.consumed);
+    assert_eq!(segment_readings(&engine), vec!["かん", "じ"]);
+
+    // The active segment was converted again with the shorter reading
+    let after = engine.state().candidates().unwrap();
+    assert_ne!(before.selected_text(), after.selected_text());
+    assert_eq!(after.selected().unwrap().reading.as_deref(), Some("かん"));
+
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), "かんじ");
+    assert_eq!(preedit.caret(), 2);
+    assert_eq!(
+        preedit.attributes(),
+        &[
+            PreeditAttribute::new(0, 2, AttributeType::Highlight),
+            PreeditAttribute::new(2, 3, AttributeType::Underline),
+        ]
+    );
+}
+
+#[test]
+fn test_shift_right_grows_active_segment() {
+    let mut engine = InputMethodEngine::new();
+    convert(&mut engine, "kanji");
+    engine.process_key(&press_shift_key(Keysym::LEFT));
+    engine.process_key(&press_shift_key(Keysym::LEFT));
+    assert_eq!(segment_readings(&engine), vec!["か", "んじ"]);
+
+    engine.process_key(&press_shift_key(Keysym::RIGHT));
+    assert_eq!(segment_readings(&engine), vec!["かん", "じ"]);
+    assert_eq!(
+        engine.state().candidates().unwrap().selected_text(),
+        Some("かん")
+    );
+
+    // Growing over the whole next segment removes it
+    engine.process_key(&press_shift_key(Keysym::RIGHT));
+    assert_eq!(segment_readings(&engine), vec!["かんじ"]);
+}
+
+#[test]
+fn test_shift_left_keeps_single_character_segment() {
+    let mut engine = InputMethodEngine::new();
+    convert(&mut engine, "ka");
+
+    let result = engine.process_key(&press_shift_key(Keysym::LEFT));
+    assert!(result.consumed);
+    assert_eq!(segment_readings(&engine), vec!["か"]);
+}
+
+#[test]
+fn test_left_right_move_active_segment() {
+    let mut engine = InputMethodEngine::new();
+    convert(&mut engine, "kanji");
+    engine.process_key(&press_shift_key(Keysym::LEFT));
+
+    engine.process_key(&press_key(Keysym::RIGHT));
+    assert_eq!(engine.state().segments().unwrap().1, 1);
+    assert_eq!(
+        engine.state().candidates().unwrap().selected_text(),
+        Some("じ")
+    );
+    assert_eq!(engine.preedit().unwrap().caret(), 3);
+
+    // Already at the last segment
+    engine.process_key(&press_key(Keysym::RIGHT));
+    assert_eq!(engine.state().segments().unwrap().1, 1);
+
+    engine.process_key(&press_key(Keysym::LEFT));
+    assert_eq!(engine.state().segments().unwrap().1, 0);
+}
+
+#[test]
+fn test_commit_concatenates_segments_and_learns_each() {
+    let mut engine = InputMethodEngine::new();
+    engine.learning = Some(LearningCache::new(100));
+    convert(&mut engine, "kanji");
+    engine.process_key(&press_shift_key(Keysym::LEFT));
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("かんじ"));
+    assert!(engine.state().is_empty());
+
+    let cache = engine.learning.as_ref().unwrap();
+    assert_eq!(cache.lookup("かん")[0].0, "かん");
+    assert_eq!(cache.lookup("じ")[0].0, "じ");
+    assert!(cache.lookup("かんじ").is_empty());
+}
+
+#[test]
+fn test_segments_learned_as_bigrams() {
+    let mut engine = InputMethodEngine::new();
+    engine.learning = Some(LearningCache::new(100));
+    convert(&mut engine, "kanji");
+    engine.process_key(&press_shift_key(Keysym::LEFT));
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    // The second segment was learned in the context of the first
+    let cache = engine.learning.as_ref().unwrap();
+    assert_eq!(cache.bigram_count(), 1);
+    let with_context = cache.lookup_with_context(Some("かん"), "じ");
+    assert!(with_context[0].1 > cache.lookup("じ")[0].1);
+}
+
+/// Candidates for "へんかん" sharing the 変換 prefix, plus the hiragana fallback
+fn henkan_candidates() -> Vec<Candidate> {
+    let mut items: Vec<Candidate> = ["変換", "変換器", "変換する", "へんかん"]
+        .iter()
+        .enumerate()
+        .map(|(i, k)| Candidate::with_reading(*k, "へんかん").with_index(i))
+        .collect();
+    items[3].source = Some(CandidateSource::Fallback);
+    items
+}
+
+#[test]
+fn test_common_candidate_prefix() {
+    let list = CandidateList::new(henkan_candidates());
+    assert_eq!(InputMethodEngine::common_candidate_prefix(&list), "変換");
+
+    // Nothing shared
+    let list = CandidateList::from_strings(["感じ", "漢字", "幹事"]);
+    assert_eq!(InputMethodEngine::common_candidate_prefix(&list), "");
+
+    // A single candidate (besides the fallback) has nothing to compare with
+    let mut items = henkan_candidates();
+    items.drain(1..3);
+    let list = CandidateList::new(items);
+    assert_eq!(InputMethodEngine::common_candidate_prefix(&list), "");
+
+    // Only the current page counts
+    let list = CandidateList::with_page_size(henkan_candidates(), 2);
+    assert_eq!(InputMethodEngine::common_candidate_prefix(&list), "変換");
+}
+
+#[test]
+fn test_common_prefix_shown_in_reverse() {
+    let mut engine = InputMethodEngine::new();
+    convert(&mut engine, "henkann");
+    engine
+        .state
+        .candidates_mut()
+        .unwrap()
+        .update(henkan_candidates());
+
+    // 変換器: the shared 変換 in reverse, the rest highlighted
+    engine.process_key(&press_key(Keysym::SPACE));
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), "変換器");
+    assert_eq!(
+        preedit.attributes(),
+        &[
+            PreeditAttribute::new(0, 2, AttributeType::Reverse),
+            PreeditAttribute::new(2, 3, AttributeType::Highlight),
+        ]
+    );
+
+    // The fallback doesn't start with the prefix and stays highlighted as a whole
+    engine.process_key(&press_key(Keysym::UP));
+    engine.process_key(&press_key(Keysym::UP));
+    let preedit = engine.preedit().unwrap();
+    assert_eq!(preedit.text(), "へんかん");
+    assert_eq!(
+        preedit.attributes(),
+        &[PreeditAttribute::new(0, 4, AttributeType::Highlight)]
+    );
+}
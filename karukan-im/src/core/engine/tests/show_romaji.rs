@@ -0,0 +1,56 @@
+use super::*;
+
+fn make_show_romaji_engine() -> InputMethodEngine {
+    InputMethodEngine::with_config(EngineConfig {
+        show_romaji: true,
+        ..EngineConfig::default()
+    })
+}
+
+#[test]
+fn test_show_romaji_appends_typed_romaji() {
+    let mut engine = make_show_romaji_engine();
+
+    for ch in "kyouk".chars() {
+        engine.process_key(&press(ch));
+    }
+    let preedit = engine.preedit().unwrap();
+    // The pending "k" is shown once, as part of the typed romaji
+    assert_eq!(preedit.text(), "きょうkyouk");
+    // Caret stays at the end of the kana part
+    assert_eq!(preedit.caret(), 3);
+    assert_eq!(
+        preedit.attributes(),
+        &[
+            PreeditAttribute::new(0, 3, AttributeType::Underline),
+            PreeditAttribute::new(3, 8, AttributeType::Highlight),
+        ]
+    );
+
+    // Backspace over the pending buffer removes it from the romaji too
+    engine.process_key(&press_key(Keysym::BACKSPACE));
+    assert_eq!(engine.preedit().unwrap().text(), "きょうkyou");
+}
+
+#[test]
+fn test_show_romaji_commit_strips_romaji() {
+    let mut engine = make_show_romaji_engine();
+
+    for ch in "kana".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "かなkana");
+
+    let result = engine.process_key(&press_key(Keysym::RETURN));
+    assert_eq!(committed_text(&result), Some("かな"));
+}
+
+#[test]
+fn test_show_romaji_disabled_by_default() {
+    let mut engine = InputMethodEngine::new();
+
+    for ch in "kana".chars() {
+        engine.process_key(&press(ch));
+    }
+    assert_eq!(engine.preedit().unwrap().text(), "かな");
+}
@@ -0,0 +1,91 @@
+use super::*;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+// --- Tracing span instrumentation ---
+
+/// Collects formatted tracing output in memory.
+#[derive(Clone, Default)]
+struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CaptureWriter {
+    type Writer = CaptureWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Run `f` with a debug-level subscriber and return everything it logged.
+fn capture_logs(f: impl FnOnce()) -> String {
+    let writer = CaptureWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(FmtSpan::NEW)
+        .with_ansi(false)
+        .with_writer(writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+    let bytes = writer.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
+/// Conversion ids of all conversion spans created, in order.
+fn conversion_ids(logs: &str) -> Vec<u64> {
+    logs.lines()
+        .filter(|line| line.ends_with(": new"))
+        .filter_map(|line| line.split("conversion{conversion_id=").nth(1))
+        .map(|rest| rest.split(' ').next().unwrap().parse().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_conversion_span_fields() {
+    let mut engine = InputMethodEngine::new();
+    let logs = capture_logs(|| {
+        engine.process_key(&press('a'));
+        engine.process_key(&press('i'));
+        engine.process_key(&press_key(Keysym::SPACE));
+    });
+
+    assert!(logs.contains("process_key{keysym=0x0061 press=true mode=Hiragana}"));
+    // The Space conversion span is nested in the key event that triggered it
+    let space_conversion = logs
+        .lines()
+        .find(|line| {
+            line.contains("process_key{keysym=0x0020 press=true mode=Hiragana}:conversion{")
+        })
+        .unwrap_or_else(|| panic!("no conversion span for Space in logs:\n{}", logs));
+    assert!(space_conversion.contains("reading=\"あい\""));
+}
+
+#[test]
+fn test_conversion_id_increments() {
+    let mut engine = InputMethodEngine::new();
+    let logs = capture_logs(|| {
+        for _ in 0..2 {
+            engine.process_key(&press('a'));
+            engine.process_key(&press_key(Keysym::SPACE));
+            engine.process_key(&press_key(Keysym::ESCAPE));
+            engine.process_key(&press_key(Keysym::ESCAPE));
+        }
+    });
+
+    let ids = conversion_ids(&logs);
+    assert!(ids.len() >= 2);
+    assert!(ids.windows(2).all(|w| w[1] == w[0] + 1), "ids: {:?}", ids);
+    assert_eq!(ids[0], 1);
+}
@@ -1,5 +1,6 @@
 use super::super::strategy::determine_conversion_strategy;
 use super::*;
+use crate::config::settings::StrategyMode;
 
 // --- ConversionStrategy tests ---
 
@@ -41,11 +42,51 @@ fn strategy_no_light_model_returns_main_model_only() {
 // --- Auto-suggest (num_candidates == 1) ---
 
 #[test]
-fn strategy_auto_suggest_adaptive_false_returns_main_model() {
+fn strategy_auto_suggest_adaptive_false_long_reading_returns_main_model() {
     let config = default_strategy_config();
-    // adaptive=false → MainModelOnly
+    // adaptive=false, reading above threshold → MainModelOnly
     assert_eq!(
-        determine_conversion_strategy(5, 1, true, false, &config),
+        determine_conversion_strategy(15, 1, true, false, &config),
+        ConversionStrategy::MainModelOnly,
+    );
+}
+
+#[test]
+fn strategy_auto_suggest_short_reading_returns_light_model() {
+    let config = default_strategy_config();
+    // 1-token reading (e.g. a single kana) → LightModelOnly fast path
+    assert_eq!(
+        determine_conversion_strategy(1, 1, true, false, &config),
+        ConversionStrategy::LightModelOnly,
+    );
+    // Explicit conversion of the same reading still uses the full beam
+    assert_eq!(
+        determine_conversion_strategy(1, 9, true, false, &config),
+        ConversionStrategy::ParallelBeam { beam_width: 3 },
+    );
+}
+
+#[test]
+fn strategy_auto_suggest_reading_boundary_at_threshold() {
+    let config = default_strategy_config();
+    // reading_tokens == threshold → LightModelOnly (<=)
+    assert_eq!(
+        determine_conversion_strategy(10, 1, true, false, &config),
+        ConversionStrategy::LightModelOnly,
+    );
+    // reading_tokens == threshold + 1 → MainModelOnly
+    assert_eq!(
+        determine_conversion_strategy(11, 1, true, false, &config),
+        ConversionStrategy::MainModelOnly,
+    );
+}
+
+#[test]
+fn strategy_auto_suggest_short_reading_without_light_model() {
+    let config = default_strategy_config();
+    // No light model to fall back to → MainModelOnly even for short input
+    assert_eq!(
+        determine_conversion_strategy(1, 1, false, false, &config),
         ConversionStrategy::MainModelOnly,
     );
 }
@@ -139,6 +180,96 @@ fn strategy_beam_width_capped_by_beam_width() {
     );
 }
 
+// --- Rerank mode ---
+
+#[test]
+fn strategy_rerank_mode() {
+    let config = EngineConfig {
+        strategy: StrategyMode::Rerank,
+        ..default_strategy_config()
+    };
+    // Space conversion: light model draft reranked by the main model
+    assert_eq!(
+        determine_conversion_strategy(5, 9, true, false, &config),
+        ConversionStrategy::DraftThenRerank { beam_width: 3 },
+    );
+    // Long readings too (the main model only scores, it does not generate)
+    assert_eq!(
+        determine_conversion_strategy(50, 2, true, false, &config),
+        ConversionStrategy::DraftThenRerank { beam_width: 2 },
+    );
+    // Auto-suggest stays on the light model
+    assert_eq!(
+        determine_conversion_strategy(5, 1, true, false, &config),
+        ConversionStrategy::LightModelOnly,
+    );
+    // Nothing to draft with
+    assert_eq!(
+        determine_conversion_strategy(5, 9, false, false, &config),
+        ConversionStrategy::MainModelOnly,
+    );
+}
+
+#[test]
+fn test_draft_then_rerank_orders_by_main_model() {
+    // The light model drafts 感じ, 漢字, 幹事; the main model scores
+    // 漢字 < 幹事 < 感じ (lower NLL is better)
+    let draft = MockBackend::new()
+        .named("draft")
+        .with_candidates(&["感じ", "漢字", "幹事"]);
+    let main = MockBackend::new().named("main").with_scores(&[
+        ("漢字", 1.0),
+        ("幹事", 2.0),
+        ("感じ", 3.0),
+    ]);
+    let main_calls = Arc::clone(&main.calls);
+    let scored = Arc::clone(&main.scored);
+
+    let mut engine = InputMethodEngine::new();
+    engine.config.strategy = StrategyMode::Rerank;
+    engine.converters.kanji = Some(KanaKanjiConverter::from_backend(main));
+    engine.converters.light_kanji = Some(KanaKanjiConverter::from_backend(draft));
+    engine.set_surrounding_context("日本語の", "");
+    type_and_convert(&mut engine, "kanji");
+
+    assert_eq!(
+        engine.metrics.strategy,
+        Some(ConversionStrategy::DraftThenRerank { beam_width: 3 })
+    );
+    assert_eq!(engine.metrics.model_name, "draft>main");
+    assert_eq!(candidate_texts(&mut engine)[..3], ["漢字", "幹事", "感じ"]);
+    // The main model only scored the draft
+    assert!(main_calls.lock().unwrap().is_empty());
+    // after the same context the draft was converted with
+    assert_eq!(
+        scored.lock().unwrap()[0],
+        MockCall {
+            katakana: "カンジ".to_string(),
+            context: "日本語の".to_string(),
+            n: 3,
+        }
+    );
+}
+
+#[test]
+fn test_rerank_draft_keeps_order_without_scores() {
+    let draft = || vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    assert_eq!(
+        InputMethodEngine::rerank_draft(draft(), |_| None),
+        ["a", "b", "c"]
+    );
+    // Wrong number of scores
+    assert_eq!(
+        InputMethodEngine::rerank_draft(draft(), |_| Some(vec![0.0])),
+        ["a", "b", "c"]
+    );
+    // Ties keep the draft order
+    assert_eq!(
+        InputMethodEngine::rerank_draft(draft(), |_| Some(vec![2.0, 1.0, 1.0])),
+        ["b", "c", "a"]
+    );
+}
+
 // --- Adaptive latency-based model switching ---
 
 #[test]
@@ -227,3 +358,37 @@ fn test_config_default_max_latency_ms() {
     let config = EngineConfig::default();
     assert_eq!(config.max_latency_ms, 100);
 }
+
+#[test]
+fn test_max_latency_caps_beam_search() {
+    let make_engine = |max_latency_ms| {
+        let mut engine = make_mock_engine();
+        engine.config.max_latency_ms = max_latency_ms;
+        let backend = MockBackend::new()
+            .with_candidates(&["感じ", "漢字", "幹事"])
+            .with_step(Duration::from_millis(5));
+        engine.converters.kanji = Some(KanaKanjiConverter::from_backend(backend));
+        engine
+    };
+    let model_candidates = |engine: &mut InputMethodEngine| {
+        candidate_texts(engine)
+            .into_iter()
+            .filter(|t| ["感じ", "漢字", "幹事"].contains(&t.as_str()))
+            .count()
+    };
+
+    // No cap: the whole beam
+    let mut engine = make_engine(0);
+    type_and_convert(&mut engine, "kanji");
+    assert_eq!(
+        engine.metrics.strategy,
+        Some(ConversionStrategy::MainModelBeam { beam_width: 3 })
+    );
+    assert_eq!(model_candidates(&mut engine), 3);
+
+    // The first candidate already takes longer than the cap
+    let mut engine = make_engine(1);
+    type_and_convert(&mut engine, "kanji");
+    assert_eq!(model_candidates(&mut engine), 1);
+    assert_eq!(engine.preedit().unwrap().text(), "感じ");
+}
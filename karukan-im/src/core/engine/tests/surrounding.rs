@@ -118,6 +118,26 @@ fn test_reset_clears_all_state() {
     assert!(matches!(engine.state(), InputState::Empty));
 }
 
+#[test]
+fn test_reset_with_reason_context() {
+    let mut engine = InputMethodEngine::new();
+    engine.set_surrounding_context("文脈テキスト", "右側");
+
+    engine.process_key(&press('a'));
+    engine.reset_with_reason(ResetReason::Spurious);
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert!(engine.surrounding_context.is_some());
+
+    engine.process_key(&press('a'));
+    engine.reset_with_reason(ResetReason::FocusOut);
+    assert!(matches!(engine.state(), InputState::Empty));
+    assert!(engine.surrounding_context.is_none());
+
+    engine.set_surrounding_context("文脈テキスト", "");
+    engine.reset_with_reason(ResetReason::CursorMoved);
+    assert!(engine.surrounding_context.is_none());
+}
+
 // --- Candidate Merge Tests ---
 
 #[test]
@@ -241,6 +261,42 @@ fn test_set_surrounding_context_truncation() {
     assert!(right.contains("右側が")); // first part
 }
 
+#[test]
+fn test_set_surrounding_context_normalizes_fullwidth() {
+    let mut engine = InputMethodEngine::new();
+    engine.config.max_api_context_len = 50;
+
+    engine.set_surrounding_context("ＡＢＣは１２３円", "ｘｙｚ！");
+
+    // The stored context is the application's text; the model sees it normalized
+    let ctx = engine.surrounding_context.as_ref().unwrap();
+    assert_eq!(ctx.left.as_deref(), Some("ＡＢＣは１２３円"));
+    assert_eq!(ctx.right.as_deref(), Some("ｘｙｚ！"));
+    assert_eq!(engine.truncate_context_for_api(), "ABCは123円");
+}
+
+#[test]
+fn test_set_surrounding_context_truncates_after_normalizing() {
+    let mut engine = InputMethodEngine::new();
+    engine.config.max_api_context_len = 5;
+
+    // ㌔ expands to キロ, so the cap applies to the normalized text
+    engine.set_surrounding_context("前の文\n重さ１０㌔", "㍍先\n次の行");
+
+    assert_eq!(engine.truncate_context_for_api(), "さ10キロ");
+}
+
+#[test]
+fn test_set_surrounding_context_normalization_disabled() {
+    let mut engine = InputMethodEngine::new();
+    engine.config.max_api_context_len = 50;
+    engine.config.normalize_context = false;
+
+    engine.set_surrounding_context("ＡＢＣ１２３", "");
+
+    assert_eq!(engine.truncate_context_for_api(), "ＡＢＣ１２３");
+}
+
 #[test]
 fn test_display_context_lctx_rctx_format() {
     let mut engine = InputMethodEngine::new();
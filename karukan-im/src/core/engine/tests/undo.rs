@@ -0,0 +1,116 @@
+use super::*;
+
+const KEY_Z: Keysym = Keysym(0x7a);
+
+/// Number of characters deleted by the first DeleteSurrounding action
+fn deleted_before(result: &EngineResult) -> Option<u32> {
+    result.actions.iter().find_map(|a| match a {
+        EngineAction::DeleteSurrounding { before, after: 0 } => Some(*before),
+        _ => None,
+    })
+}
+
+fn converted_reading(engine: &InputMethodEngine) -> String {
+    let (segments, _) = engine.state().segments().unwrap();
+    segments.iter().map(|s| s.reading.as_str()).collect()
+}
+
+#[test]
+fn test_undo_after_digit_selection_commit() {
+    let mut engine = InputMethodEngine::new();
+    for ch in "kann".chars() {
+        engine.process_key(&press(ch));
+    }
+    engine.process_key(&press_key(Keysym::SPACE));
+    let items = ["感", "缶"]
+        .iter()
+        .enumerate()
+        .map(|(i, k)| Candidate::with_reading(*k, "かん").with_index(i))
+        .collect();
+    engine.state.candidates_mut().unwrap().update(items);
+
+    let result = engine.process_key(&press('2'));
+    assert!(
+        result
+            .actions
+            .iter()
+            .any(|a| matches!(a, EngineAction::Commit(t) if t == "缶"))
+    );
+    assert!(matches!(engine.state(), InputState::Empty));
+
+    let result = engine.process_key(&press_ctrl(KEY_Z));
+    assert!(result.consumed);
+    // The deletion comes before the restored conversion
+    assert!(matches!(
+        result.actions.first(),
+        Some(EngineAction::DeleteSurrounding {
+            before: 1,
+            after: 0
+        })
+    ));
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    assert_eq!(converted_reading(&engine), "かん");
+}
+
+#[test]
+fn test_undo_after_live_conversion_commit() {
+    let mut engine = make_live_conversion_engine();
+    engine.process_key(&press('a'));
+    engine.process_key(&press('i'));
+    engine.live.text = "愛".to_string();
+    engine.process_key(&press_key(Keysym::RETURN));
+    assert!(matches!(engine.state(), InputState::Empty));
+
+    let result = engine.process_key(&press_ctrl(KEY_Z));
+    assert_eq!(deleted_before(&result), Some(1));
+    assert!(matches!(engine.state(), InputState::Conversion { .. }));
+    assert_eq!(converted_reading(&engine), "あい");
+}
+
+#[test]
+fn test_undo_after_hiragana_commit_restores_composing() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press('a'));
+    engine.process_key(&press('i'));
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    let result = engine.process_key(&press_ctrl(KEY_Z));
+    assert_eq!(deleted_before(&result), Some(2));
+    assert!(matches!(engine.state(), InputState::Composing { .. }));
+    assert_eq!(engine.preedit().unwrap().text(), "あい");
+}
+
+#[test]
+fn test_undo_only_right_after_commit() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press('a'));
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    // A key passed through to the application may move its cursor
+    let result = engine.process_key(&press_key(Keysym::LEFT));
+    assert!(!result.consumed);
+
+    let result = engine.process_key(&press_ctrl(KEY_Z));
+    assert!(!result.consumed);
+    assert_eq!(deleted_before(&result), None);
+    assert!(matches!(engine.state(), InputState::Empty));
+}
+
+#[test]
+fn test_undo_survives_modifier_press() {
+    let mut engine = InputMethodEngine::new();
+    engine.process_key(&press('a'));
+    engine.process_key(&press_key(Keysym::RETURN));
+
+    // Pressing Ctrl on its way to Ctrl+Z doesn't drop the undo buffer
+    engine.process_key(&press_key(Keysym::CONTROL_L));
+    let result = engine.process_key(&press_ctrl(KEY_Z));
+    assert_eq!(deleted_before(&result), Some(1));
+}
+
+#[test]
+fn test_undo_without_commit_passes_through() {
+    let mut engine = InputMethodEngine::new();
+    let result = engine.process_key(&press_ctrl(KEY_Z));
+    assert!(!result.consumed);
+}
@@ -1,10 +1,19 @@
 //! Type definitions for the IME engine
 
-use karukan_engine::{Dictionary, KanaKanjiConverter, RomajiConverter};
+use std::path::PathBuf;
+use std::time::Instant;
 
-use crate::config::settings::StrategyMode;
+use karukan_engine::{
+    Dictionary, KanaKanjiConverter, LearningScoreParams, RomajiConfig, RomajiConverter,
+    UserDictionary,
+};
+
+use crate::config::settings::{CandidateLabels, StrategyMode};
 
 use super::super::candidate::CandidateList;
+use super::super::keybindings::KeyBindings;
+use super::super::keycode::Keysym;
+use super::super::macros::JST_OFFSET_MINUTES;
 use super::super::preedit::Preedit;
 
 /// Action to be performed by the framework/UI layer
@@ -12,12 +21,19 @@ use super::super::preedit::Preedit;
 pub enum EngineAction {
     /// Update the preedit display
     UpdatePreedit(Preedit),
-    /// Show the candidate window with candidates
+    /// Show the candidate window with candidates (a page, or the whole list
+    /// when expanded)
     ShowCandidates(CandidateList),
     /// Hide the candidate window
     HideCandidates,
     /// Commit text to the application
     Commit(String),
+    /// Delete already-committed text around the application's cursor:
+    /// `before` characters to the left and `after` characters to the right.
+    ///
+    /// Ordering contract: the deletion is applied before any `Commit` in the
+    /// same result, so a commit never lands inside the text being removed.
+    DeleteSurrounding { before: u32, after: u32 },
     /// Update auxiliary text (e.g., reading hint, mode indicator)
     UpdateAuxText(String),
     /// Hide auxiliary text
@@ -66,21 +82,65 @@ pub(in crate::core) struct SurroundingContext {
 /// Configuration for the IME engine
 #[derive(Debug, Clone)]
 pub struct EngineConfig {
-    /// Number of conversion candidates for explicit conversion (Space key)
+    /// Number of model candidates for explicit conversion (Space key), capped
+    /// at 20; the candidate window pages them by `page_size`
     pub num_candidates: usize,
     /// Maximum context length to display
     pub display_context_len: usize,
     /// Maximum context length for API calls (to avoid overflow)
     pub max_api_context_len: usize,
+    /// NFKC-normalize surrounding text (full-width latin → ASCII, ...) before
+    /// it is sent to the model as context
+    pub normalize_context: bool,
     /// Token count threshold for beam search (at or below → beam, above → greedy)
     pub short_input_threshold: usize,
     /// Beam width for short input
     pub beam_width: usize,
     /// Maximum acceptable latency in milliseconds for auto-suggest (0 = disabled)
     /// When a main model conversion exceeds this, the engine adaptively switches to light_model
+    /// Beam search also stops at this limit with the candidates found so far
     pub max_latency_ms: u64,
     /// Conversion strategy mode (adaptive, light, main)
     pub strategy: StrategyMode,
+    /// Romaji → hiragana conversion options
+    pub romaji: RomajiConfig,
+    /// Show the typed romaji after the kana in the composing preedit
+    pub show_romaji: bool,
+    /// Start the conversion aux text with the selected candidate's reading
+    /// and position (for screen readers)
+    pub aux_show_reading: bool,
+    /// First Space only previews the top candidate in the preedit; the
+    /// candidate window opens on the second Space (or Down)
+    pub candidate_preview: bool,
+    /// Promote the katakana form of loanword-like readings typed in hiragana mode
+    pub loanword_katakana: bool,
+    /// Show dictionary completions of the composing reading as prediction candidates
+    pub predictive: bool,
+    /// In katakana mode, write a vowel repeating the previous kana's vowel as ー
+    pub katakana_long_vowel: bool,
+    /// Maximum number of model conversion results kept in the session cache (0 = disabled)
+    pub conversion_cache_size: usize,
+    /// Reorder non-learning conversion candidates by model NLL on explicit conversion
+    pub rerank_with_model: bool,
+    /// Start explicit conversion with the reading split into dictionary
+    /// words (longest match), one segment each
+    pub auto_segment: bool,
+    /// Number of candidates per page (digit keys 1–9, and 0 when 10)
+    pub page_size: usize,
+    /// Skip live conversion inference for keys typed within this many
+    /// milliseconds of the previous one (0 = convert on every key)
+    pub debounce_ms: u64,
+    /// Offset of the local time from UTC in minutes, for the date/time macros
+    pub utc_offset_minutes: i32,
+    /// Shortcut keys for logical actions (next candidate, caret moves, ...)
+    pub keybindings: KeyBindings,
+    /// Candidate annotation label per source
+    pub labels: CandidateLabels,
+    /// Forget learning entries not selected for this many days on load and
+    /// save (0 = disabled)
+    pub learning_max_age_days: u64,
+    /// Weights used to order learned candidates
+    pub learning_score: LearningScoreParams,
 }
 
 impl Default for EngineConfig {
@@ -89,10 +149,28 @@ impl Default for EngineConfig {
             num_candidates: 3, // Space conversion: beam search with 3 candidates
             display_context_len: 10,
             max_api_context_len: 10,
+            normalize_context: true,
             short_input_threshold: 10,
             beam_width: 3,
             max_latency_ms: 100,
             strategy: StrategyMode::default(),
+            romaji: RomajiConfig::default(),
+            show_romaji: false,
+            aux_show_reading: false,
+            candidate_preview: false,
+            loanword_katakana: true,
+            predictive: false,
+            katakana_long_vowel: true,
+            conversion_cache_size: 256,
+            rerank_with_model: false,
+            auto_segment: false,
+            page_size: CandidateList::DEFAULT_PAGE_SIZE,
+            debounce_ms: 0,
+            utc_offset_minutes: JST_OFFSET_MINUTES,
+            keybindings: KeyBindings::default(),
+            labels: CandidateLabels::default(),
+            learning_max_age_days: 0,
+            learning_score: LearningScoreParams::default(),
         }
     }
 }
@@ -114,8 +192,61 @@ pub(crate) enum InputMode {
     Hiragana,
     /// Katakana mode — preedit displays katakana instead of hiragana
     Katakana,
+    /// Katakana lock — like `Katakana`, but Space commits instead of converting,
+    /// no model inference runs, and the mode stays on until toggled off
+    KatakanaLock,
     /// Alphabet (direct input) mode — characters bypass romaji conversion
     Alphabet,
+    /// Kana direct input — keys of a JIS kana keyboard are typed as kana
+    /// (see [`kana_layout`](crate::core::kana_layout)), bypassing romaji conversion
+    KanaDirect,
+}
+
+impl InputMode {
+    /// Whether the composing text is displayed and committed as katakana
+    pub(crate) fn is_katakana(self) -> bool {
+        matches!(self, InputMode::Katakana | InputMode::KatakanaLock)
+    }
+}
+
+/// Forced form of the composing text selected with F6–F10
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FunctionKeyForm {
+    /// F6: hiragana
+    Hiragana,
+    /// F7: full-width katakana
+    Katakana,
+    /// F8: half-width katakana
+    HalfwidthKatakana,
+    /// F9: full-width latin (typed romaji)
+    FullwidthLatin,
+    /// F10: half-width latin (typed romaji)
+    HalfwidthLatin,
+}
+
+impl FunctionKeyForm {
+    /// Form selected by a function key, if any
+    pub fn from_keysym(keysym: Keysym) -> Option<Self> {
+        match keysym {
+            Keysym::F6 => Some(Self::Hiragana),
+            Keysym::F7 => Some(Self::Katakana),
+            Keysym::F8 => Some(Self::HalfwidthKatakana),
+            Keysym::F9 => Some(Self::FullwidthLatin),
+            Keysym::F10 => Some(Self::HalfwidthLatin),
+            _ => None,
+        }
+    }
+}
+
+/// Last commit, kept so that it can be taken back with Ctrl+Z
+#[derive(Debug, Clone)]
+pub(in crate::core) struct CommitRecord {
+    /// Committed text
+    pub text: String,
+    /// Reading the text was typed as
+    pub reading: String,
+    /// Restore into Conversion (true) or Composing (false) on undo
+    pub reconvert: bool,
 }
 
 /// Live conversion state: enabled flag and current converted text
@@ -125,6 +256,10 @@ pub(in crate::core) struct LiveConversion {
     pub enabled: bool,
     /// Converted text (non-empty when live conversion produced a result)
     pub text: String,
+    /// Reading the last real conversion covered
+    pub reading: String,
+    /// Kana typed after `reading` while debounced, appended to `text` unconverted
+    pub typed: String,
 }
 
 /// Dictionary store: system, user, and future cache dictionaries
@@ -132,13 +267,47 @@ pub(in crate::core) struct LiveConversion {
 pub(in crate::core) struct Dictionaries {
     /// System dictionary for yada double-array trie lookup
     pub system: Option<Dictionary>,
-    /// User dictionary (merged from user_dict_paths)
-    pub user: Option<Dictionary>,
+    /// User dictionary (merged from user_dict_paths, editable at runtime)
+    pub user: Option<UserDictionary>,
+    /// Files the user dictionary was loaded from; removals are written back to them
+    pub user_files: Vec<PathBuf>,
+    /// Phrase dictionary (short reading → long phrase, e.g. greetings/signatures)
+    pub phrase: Option<Dictionary>,
+    /// Extra dictionaries from `conversion.dictionaries` (one merged, or one per file when layered)
+    pub extra: Vec<Dictionary>,
+}
+
+/// Per-file result of loading a configured dictionary at startup
+#[derive(Debug)]
+pub struct DictionaryLoadStatus {
+    /// Path of the dictionary file
+    pub path: PathBuf,
+    /// Number of readings loaded, or the load error message
+    pub result: Result<usize, String>,
+}
+
+/// Why the engine is being reset, deciding whether the surrounding context survives
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetReason {
+    /// Reset without a real change (fcitx5 may send these between activate
+    /// and the first key); the context is kept
+    Spurious,
+    /// The input context lost focus; the context belongs to another field
+    FocusOut,
+    /// The application moved the cursor; the context is stale
+    CursorMoved,
+}
+
+impl ResetReason {
+    /// Whether the surrounding context should be cleared
+    pub(super) fn clears_context(self) -> bool {
+        !matches!(self, ResetReason::Spurious)
+    }
 }
 
 /// Conversion model dispatch strategy based on input length
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub(in crate::core) enum ConversionStrategy {
+pub(crate) enum ConversionStrategy {
     /// Short input: main model greedy + light model beam search (parallel)
     ParallelBeam { beam_width: usize },
     /// Long input: light model greedy only (skip slow main model)
@@ -147,6 +316,8 @@ pub(in crate::core) enum ConversionStrategy {
     MainModelOnly,
     /// Main model beam search (used in Light strategy mode where light model occupies main slot)
     MainModelBeam { beam_width: usize },
+    /// Light model beam search as a draft, reordered by main model NLL (Rerank strategy mode)
+    DraftThenRerank { beam_width: usize },
 }
 
 /// Timing and adaptive model selection metrics for conversion
@@ -161,4 +332,10 @@ pub(in crate::core) struct ConversionMetrics {
     /// Adaptive flag: set when the main model exceeded max_latency_ms
     /// Reset when a new word begins (Empty state)
     pub adaptive_use_light_model: bool,
+    /// Id of the last conversion (increments per conversion; tags its tracing span)
+    pub conversion_id: u64,
+    /// Time of the previous key that refreshed live conversion (for the debounce)
+    pub last_live_key_at: Option<Instant>,
+    /// Strategy of the last model conversion (None until a model has run)
+    pub strategy: Option<ConversionStrategy>,
 }
@@ -0,0 +1,61 @@
+//! Undo of the last commit (Ctrl+Z)
+
+use tracing::debug;
+
+use super::*;
+
+impl InputMethodEngine {
+    /// Remember a commit so that the next key can take it back
+    pub(super) fn record_commit(&mut self, text: &str, reading: &str, reconvert: bool) {
+        self.last_commit = Some(CommitRecord {
+            text: text.to_string(),
+            reading: reading.to_string(),
+            reconvert,
+        });
+    }
+
+    /// Delete the committed text from the application and bring its reading
+    /// back: into Conversion (with the committed candidate selected when still
+    /// offered) for converted text, otherwise into Composing.
+    pub(super) fn undo_commit(&mut self, record: CommitRecord) -> EngineResult {
+        debug!(
+            "undo commit: text=\"{}\" reading=\"{}\"",
+            record.text, record.reading
+        );
+        let delete = EngineAction::DeleteSurrounding {
+            before: record.text.chars().count() as u32,
+            after: 0,
+        };
+
+        let mut result = if record.reconvert {
+            self.convert_reading_again(&record.reading, &record.text)
+        } else {
+            self.converters.romaji.reset();
+            self.input_buf.clear();
+            self.input_buf.insert(&record.reading);
+            let preedit = self.set_composing_state();
+            EngineResult::consumed()
+                .with_action(EngineAction::UpdatePreedit(preedit))
+                .with_action(EngineAction::UpdateAuxText(self.format_aux_composing()))
+        };
+        // The deletion must reach the application before the restored preedit
+        result.actions.insert(0, delete);
+        result
+    }
+
+    /// Enter Conversion for `reading`, selecting `text` when it is offered
+    pub(super) fn convert_reading_again(&mut self, reading: &str, text: &str) -> EngineResult {
+        self.converters.romaji.reset();
+        self.input_buf.clear();
+        self.input_buf.insert(reading);
+
+        let mut result = self.start_conversion();
+        if let Some(candidates) = self.state.candidates_mut()
+            && let Some(index) = candidates.candidates().iter().position(|c| c.text == text)
+        {
+            candidates.select(index);
+            result = self.update_conversion_preedit();
+        }
+        result
+    }
+}
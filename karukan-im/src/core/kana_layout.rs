@@ -0,0 +1,248 @@
+//! JIS kana keyboard layout for direct kana input
+//!
+//! Used by `InputMode::KanaDirect`: each key on a kana-labeled (JIS 106/109)
+//! keyboard is mapped straight to its kana, without romaji. Keys are matched
+//! by the keysym they send, so both setups work:
+//!
+//! - the `jp` layout, where kana keys send ASCII (q → た, 3 → あ, Shift+3 → ぁ)
+//! - the `jp(kana)` layout, where they send X11 kana keysyms
+//!
+//! With the plain `jp` (106) layout the ¥ and ろ keys both send `\`, which is
+//! taken as ろ; ー is then typed with Shift+¥.
+
+use super::keycode::KeyEvent;
+
+/// Output of the dakuten (゛) key
+pub const DAKUTEN: char = '゛';
+/// Output of the handakuten (゜) key
+pub const HANDAKUTEN: char = '゜';
+
+/// ASCII keysym → kana on the `jp` layout, unshifted and shifted.
+///
+/// Shifted keys without their own kana (Shift+1, Shift+; ...) give the
+/// unshifted kana, like a kana keyboard's unshifted legend.
+const JIS_KANA: &[(char, char)] = &[
+    // Number row
+    ('1', 'ぬ'),
+    ('2', 'ふ'),
+    ('3', 'あ'),
+    ('4', 'う'),
+    ('5', 'え'),
+    ('6', 'お'),
+    ('7', 'や'),
+    ('8', 'ゆ'),
+    ('9', 'よ'),
+    ('0', 'わ'),
+    ('-', 'ほ'),
+    ('^', 'へ'),
+    ('\u{a5}', 'ー'),
+    ('!', 'ぬ'),
+    ('"', 'ふ'),
+    ('#', 'ぁ'),
+    ('$', 'ぅ'),
+    ('%', 'ぇ'),
+    ('&', 'ぉ'),
+    ('\'', 'ゃ'),
+    ('(', 'ゅ'),
+    (')', 'ょ'),
+    ('~', 'を'),
+    ('=', 'ほ'),
+    ('|', 'ー'),
+    // Top row
+    ('q', 'た'),
+    ('w', 'て'),
+    ('e', 'い'),
+    ('r', 'す'),
+    ('t', 'か'),
+    ('y', 'ん'),
+    ('u', 'な'),
+    ('i', 'に'),
+    ('o', 'ら'),
+    ('p', 'せ'),
+    ('@', DAKUTEN),
+    ('[', HANDAKUTEN),
+    ('E', 'ぃ'),
+    ('`', DAKUTEN),
+    ('{', '「'),
+    // Home row
+    ('a', 'ち'),
+    ('s', 'と'),
+    ('d', 'し'),
+    ('f', 'は'),
+    ('g', 'き'),
+    ('h', 'く'),
+    ('j', 'ま'),
+    ('k', 'の'),
+    ('l', 'り'),
+    (';', 'れ'),
+    (':', 'け'),
+    (']', 'む'),
+    ('+', 'れ'),
+    ('*', 'け'),
+    ('}', '」'),
+    // Bottom row
+    ('z', 'つ'),
+    ('x', 'さ'),
+    ('c', 'そ'),
+    ('v', 'ひ'),
+    ('b', 'こ'),
+    ('n', 'み'),
+    ('m', 'も'),
+    (',', 'ね'),
+    ('.', 'る'),
+    ('/', 'め'),
+    ('\\', 'ろ'),
+    ('Z', 'っ'),
+    ('<', '、'),
+    ('>', '。'),
+    ('?', '・'),
+    ('_', 'ろ'),
+];
+
+/// X11 kana keysyms (kana_fullstop .. semivoicedsound) follow JIS X 0201,
+/// i.e. the half-width katakana block starting at U+FF61
+const X11_KANA_KEYSYMS: std::ops::RangeInclusive<u32> = 0x04a1..=0x04df;
+
+/// Voiced forms: (base, with dakuten)
+const DAKUTEN_PAIRS: &[(char, char)] = &[
+    ('か', 'が'),
+    ('き', 'ぎ'),
+    ('く', 'ぐ'),
+    ('け', 'げ'),
+    ('こ', 'ご'),
+    ('さ', 'ざ'),
+    ('し', 'じ'),
+    ('す', 'ず'),
+    ('せ', 'ぜ'),
+    ('そ', 'ぞ'),
+    ('た', 'だ'),
+    ('ち', 'ぢ'),
+    ('つ', 'づ'),
+    ('て', 'で'),
+    ('と', 'ど'),
+    ('は', 'ば'),
+    ('ひ', 'び'),
+    ('ふ', 'ぶ'),
+    ('へ', 'べ'),
+    ('ほ', 'ぼ'),
+    ('う', 'ゔ'),
+];
+
+/// Semi-voiced forms: (base, with handakuten)
+const HANDAKUTEN_PAIRS: &[(char, char)] = &[
+    ('は', 'ぱ'),
+    ('ひ', 'ぴ'),
+    ('ふ', 'ぷ'),
+    ('へ', 'ぺ'),
+    ('ほ', 'ぽ'),
+];
+
+/// Kana for `key` on a JIS kana keyboard, or `None` for keys without kana.
+///
+/// Shift is usually already applied to the keysym (Shift+3 sends `#`); for
+/// letters it may also arrive as a modifier on the lowercase keysym.
+pub fn jis_kana(key: &KeyEvent) -> Option<char> {
+    let code = key.keysym.0;
+    if X11_KANA_KEYSYMS.contains(&code) {
+        let halfwidth = char::from_u32(0xff61 + (code - 0x04a1))?;
+        let katakana = karukan_engine::kana::halfwidth_to_katakana(&halfwidth.to_string());
+        return karukan_engine::kana::katakana_to_hiragana(&katakana)
+            .chars()
+            .next();
+    }
+
+    let ch = char::from_u32(code)?;
+    let ch = if key.modifiers.shift_key && ch.is_ascii_lowercase() {
+        ch.to_ascii_uppercase()
+    } else {
+        ch
+    };
+    // Shift+0 sends a plain 0 on some layouts (OADG 109A)
+    if ch == '0' && key.modifiers.shift_key {
+        return Some('を');
+    }
+    lookup(ch).or_else(|| {
+        // Shifted letters without a small kana fall back to the unshifted one
+        ch.is_ascii_uppercase()
+            .then(|| lookup(ch.to_ascii_lowercase()))
+            .flatten()
+    })
+}
+
+fn lookup(ch: char) -> Option<char> {
+    JIS_KANA
+        .iter()
+        .find(|&&(k, _)| k == ch)
+        .map(|&(_, kana)| kana)
+}
+
+/// Combine a dakuten/handakuten `mark` with the preceding `kana`
+/// (か + ゛ → が, は + ゜ → ぱ), or `None` if it has no such form.
+pub fn combine_mark(kana: char, mark: char) -> Option<char> {
+    let pairs = match mark {
+        DAKUTEN => DAKUTEN_PAIRS,
+        HANDAKUTEN => HANDAKUTEN_PAIRS,
+        _ => return None,
+    };
+    pairs
+        .iter()
+        .find(|&&(base, _)| base == kana)
+        .map(|&(_, combined)| combined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::keycode::{KeyModifiers, Keysym};
+
+    fn key(code: u32, shift: bool) -> KeyEvent {
+        let modifiers = KeyModifiers {
+            shift_key: shift,
+            ..KeyModifiers::default()
+        };
+        KeyEvent::new(Keysym(code), modifiers, true)
+    }
+
+    #[test]
+    fn test_ascii_keysyms() {
+        assert_eq!(jis_kana(&key('q' as u32, false)), Some('た'));
+        assert_eq!(jis_kana(&key('3' as u32, false)), Some('あ'));
+        assert_eq!(jis_kana(&key('\\' as u32, false)), Some('ろ'));
+        assert_eq!(jis_kana(&key(0xa5, false)), Some('ー'));
+        assert_eq!(jis_kana(&key(' ' as u32, false)), None);
+    }
+
+    #[test]
+    fn test_shifted_keys() {
+        // Shift already applied to the keysym
+        assert_eq!(jis_kana(&key('#' as u32, true)), Some('ぁ'));
+        assert_eq!(jis_kana(&key('Z' as u32, true)), Some('っ'));
+        // Shift as a modifier on the lowercase keysym
+        assert_eq!(jis_kana(&key('z' as u32, true)), Some('っ'));
+        assert_eq!(jis_kana(&key('e' as u32, true)), Some('ぃ'));
+        // No small form: the unshifted kana
+        assert_eq!(jis_kana(&key('Q' as u32, true)), Some('た'));
+        assert_eq!(jis_kana(&key('0' as u32, true)), Some('を'));
+        assert_eq!(jis_kana(&key('~' as u32, true)), Some('を'));
+    }
+
+    #[test]
+    fn test_x11_kana_keysyms() {
+        // kana_A, kana_tsu, prolongedsound, voicedsound
+        assert_eq!(jis_kana(&key(0x04b1, false)), Some('あ'));
+        assert_eq!(jis_kana(&key(0x04af, false)), Some('っ'));
+        assert_eq!(jis_kana(&key(0x04b0, false)), Some('ー'));
+        assert_eq!(jis_kana(&key(0x04de, false)), Some(DAKUTEN));
+        assert_eq!(jis_kana(&key(0x04df, false)), Some(HANDAKUTEN));
+    }
+
+    #[test]
+    fn test_combine_mark() {
+        assert_eq!(combine_mark('か', DAKUTEN), Some('が'));
+        assert_eq!(combine_mark('う', DAKUTEN), Some('ゔ'));
+        assert_eq!(combine_mark('は', HANDAKUTEN), Some('ぱ'));
+        assert_eq!(combine_mark('あ', DAKUTEN), None);
+        assert_eq!(combine_mark('か', HANDAKUTEN), None);
+        assert_eq!(combine_mark('か', 'あ'), None);
+    }
+}
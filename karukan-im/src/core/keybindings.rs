@@ -0,0 +1,400 @@
+//! Key bindings: logical IME actions mapped to key combinations
+//!
+//! Shortcuts such as Ctrl+N (next candidate) or Ctrl+K (katakana) differ between
+//! IMEs. A [`KeyBindingScheme`] selects a preset, and individual actions can be
+//! rebound from the `[keybindings.custom]` config table.
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::settings::{KeyBindingScheme, KeyBindingSettings};
+
+use super::keycode::{KeyEvent, Keysym};
+
+/// Logical action that can be bound to a key combination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    /// Select the next conversion candidate
+    NextCandidate,
+    /// Select the previous conversion candidate
+    PrevCandidate,
+    /// Switch the candidate window between pages and the full (scrollable) list
+    ExpandCandidates,
+    /// Move the composing caret to the beginning
+    CaretHome,
+    /// Move the composing caret to the end
+    CaretEnd,
+    /// Move the composing caret one character left
+    CaretLeft,
+    /// Move the composing caret one character right
+    CaretRight,
+    /// Move the composing caret to the start of the previous word (script run)
+    CaretWordLeft,
+    /// Move the composing caret to the end of the next word (script run)
+    CaretWordRight,
+    /// Switch the composing text to katakana mode
+    EnterKatakana,
+    /// Turn the persistent katakana lock (no conversion) on or off
+    ToggleKatakanaLock,
+    /// Toggle live conversion (works in all states)
+    ToggleLive,
+    /// Insert a full-width space (U+3000)
+    FullwidthSpace,
+    /// Commit the composing text or the current conversion
+    Commit,
+    /// Commit the hiragana reading as is, ignoring conversion (not learned)
+    CommitReading,
+    /// Cancel composing or conversion
+    Cancel,
+    /// Delete the character before the caret (Backspace)
+    Backspace,
+    /// Take back the text committed by the previous key (Empty state only)
+    UndoCommit,
+    /// Convert the word left of the application's cursor again (Empty state only).
+    /// Unbound by default, since Ctrl+Backspace usually deletes a word.
+    Reconvert,
+}
+
+/// A keysym with the modifiers that must be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub keysym: Keysym,
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyCombo {
+    /// Key without modifiers
+    pub const fn new(keysym: Keysym) -> Self {
+        Self {
+            keysym,
+            control: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    /// Ctrl + key
+    pub const fn ctrl(keysym: Keysym) -> Self {
+        Self {
+            control: true,
+            ..Self::new(keysym)
+        }
+    }
+
+    /// Ctrl + character key (e.g. `ctrl_char('n')` for Ctrl+N)
+    const fn ctrl_char(ch: char) -> Self {
+        Self::ctrl(Keysym(ch as u32))
+    }
+
+    /// Add Shift to the combination
+    pub const fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    /// Parse a combination such as `Ctrl+N`, `Ctrl+Shift+L`, `Alt+j` or `Ctrl+space`.
+    ///
+    /// Modifier and key names are case-insensitive. Returns None for unknown names.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parts.pop()?;
+        let mut combo = Self::new(parse_keysym(key)?);
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => combo.control = true,
+                "shift" => combo.shift = true,
+                "alt" => combo.alt = true,
+                _ => return None,
+            }
+        }
+        Some(combo)
+    }
+
+    /// Check whether a key event triggers this combination.
+    ///
+    /// Letters match regardless of case, since fcitx5 may resolve Shift into the
+    /// keysym. Shift is ignored for Ctrl/Alt combinations that don't require it
+    /// (Ctrl+Shift+N still counts as Ctrl+N), matching the engine's historic behavior.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let shift_ok = if self.shift {
+            key.modifiers.shift_key
+        } else {
+            self.control || self.alt || !key.modifiers.shift_key
+        };
+        key.modifiers.control_key == self.control
+            && key.modifiers.alt_key == self.alt
+            && shift_ok
+            && fold_case(key.keysym) == fold_case(self.keysym)
+    }
+}
+
+/// Lowercase an ASCII uppercase letter keysym
+fn fold_case(keysym: Keysym) -> Keysym {
+    if (0x41..=0x5a).contains(&keysym.0) {
+        Keysym(keysym.0 + 0x20)
+    } else {
+        keysym
+    }
+}
+
+/// Parse a key name (single printable character or a named key)
+fn parse_keysym(name: &str) -> Option<Keysym> {
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.clone().next()) {
+        let keysym = Keysym(ch as u32);
+        return keysym.is_printable().then_some(keysym);
+    }
+    let keysym = match name.to_ascii_lowercase().as_str() {
+        "space" => Keysym::SPACE,
+        "tab" => Keysym::TAB,
+        "return" | "enter" => Keysym::RETURN,
+        "escape" | "esc" => Keysym::ESCAPE,
+        "backspace" => Keysym::BACKSPACE,
+        "delete" => Keysym::DELETE,
+        "home" => Keysym::HOME,
+        "end" => Keysym::END,
+        "left" => Keysym::LEFT,
+        "right" => Keysym::RIGHT,
+        "up" => Keysym::UP,
+        "down" => Keysym::DOWN,
+        "pageup" => Keysym::PAGE_UP,
+        "pagedown" => Keysym::PAGE_DOWN,
+        other => {
+            let n: u32 = other.strip_prefix('f')?.parse().ok()?;
+            if !(1..=12).contains(&n) {
+                return None;
+            }
+            Keysym(Keysym::F1.0 + n - 1)
+        }
+    };
+    Some(keysym)
+}
+
+/// Key combinations bound to each logical action
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyCombo, KeyAction)>,
+}
+
+impl KeyBindings {
+    /// Bindings of a preset scheme
+    pub fn new(scheme: KeyBindingScheme) -> Self {
+        use KeyAction::*;
+
+        // Shared by all schemes
+        let mut bindings = vec![
+            (KeyCombo::ctrl_char('l').with_shift(), ToggleLive),
+            (KeyCombo::ctrl(Keysym::SPACE), FullwidthSpace),
+            (KeyCombo::ctrl(Keysym::RETURN), CommitReading),
+            (KeyCombo::ctrl(Keysym::TAB), ExpandCandidates),
+            (KeyCombo::ctrl_char('z'), UndoCommit),
+            (KeyCombo::ctrl_char('k').with_shift(), ToggleKatakanaLock),
+            (KeyCombo::ctrl(Keysym::LEFT), CaretWordLeft),
+            (KeyCombo::ctrl(Keysym::RIGHT), CaretWordRight),
+        ];
+        let emacs_motion = [
+            (KeyCombo::ctrl_char('n'), NextCandidate),
+            (KeyCombo::ctrl_char('p'), PrevCandidate),
+            (KeyCombo::ctrl_char('a'), CaretHome),
+            (KeyCombo::ctrl_char('e'), CaretEnd),
+            (KeyCombo::ctrl_char('b'), CaretLeft),
+            (KeyCombo::ctrl_char('f'), CaretRight),
+        ];
+        match scheme {
+            KeyBindingScheme::Default => {
+                bindings.extend(emacs_motion);
+                bindings.push((KeyCombo::ctrl_char('k'), EnterKatakana));
+            }
+            KeyBindingScheme::Emacs => {
+                bindings.extend(emacs_motion);
+                bindings.extend([
+                    (KeyCombo::ctrl_char('k'), EnterKatakana),
+                    (KeyCombo::ctrl_char('m'), Commit),
+                    (KeyCombo::ctrl_char('j'), Commit),
+                    (KeyCombo::ctrl_char('g'), Cancel),
+                    (KeyCombo::ctrl_char('h'), Backspace),
+                ]);
+            }
+            // ATOK-style: WordStar diamond (Ctrl+E/X/S/D), Ctrl+I for katakana
+            KeyBindingScheme::Atok => bindings.extend([
+                (KeyCombo::ctrl_char('x'), NextCandidate),
+                (KeyCombo::ctrl_char('e'), PrevCandidate),
+                (KeyCombo::ctrl_char('a'), CaretHome),
+                (KeyCombo::ctrl_char('f'), CaretEnd),
+                (KeyCombo::ctrl_char('s'), CaretLeft),
+                (KeyCombo::ctrl_char('d'), CaretRight),
+                (KeyCombo::ctrl_char('i'), EnterKatakana),
+                (KeyCombo::ctrl_char('m'), Commit),
+                (KeyCombo::ctrl_char('g'), Cancel),
+                (KeyCombo::ctrl_char('h'), Backspace),
+            ]),
+            // Kotoeri (macOS): emacs motion, Ctrl+K katakana, Ctrl+H backspace
+            KeyBindingScheme::Kotoeri => {
+                bindings.extend(emacs_motion);
+                bindings.extend([
+                    (KeyCombo::ctrl_char('k'), EnterKatakana),
+                    (KeyCombo::ctrl_char('m'), Commit),
+                    (KeyCombo::ctrl_char('h'), Backspace),
+                ]);
+            }
+        }
+        Self { bindings }
+    }
+
+    /// Bindings from settings: the chosen scheme with custom per-action overrides.
+    ///
+    /// Unparseable key names are logged and skipped.
+    pub fn from_settings(settings: &KeyBindingSettings) -> Self {
+        let mut bindings = Self::new(settings.scheme);
+        for (&action, keys) in &settings.custom {
+            let combos = keys
+                .iter()
+                .filter_map(|k| {
+                    let combo = KeyCombo::parse(k);
+                    if combo.is_none() {
+                        warn!("Ignoring unknown key {:?} for {:?}", k, action);
+                    }
+                    combo
+                })
+                .collect();
+            bindings.bind(action, combos);
+        }
+        bindings
+    }
+
+    /// Replace the combinations bound to an action (an empty list unbinds it)
+    pub fn bind(&mut self, action: KeyAction, combos: Vec<KeyCombo>) {
+        self.bindings.retain(|(_, a)| *a != action);
+        self.bindings
+            .extend(combos.into_iter().map(|combo| (combo, action)));
+    }
+
+    /// Action triggered by a key event, if any.
+    ///
+    /// A combination requiring Shift wins over one that merely tolerates it.
+    pub fn action_for(&self, key: &KeyEvent) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .filter(|(combo, _)| combo.matches(key))
+            .max_by_key(|(combo, _)| combo.shift)
+            .map(|&(_, action)| action)
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new(KeyBindingScheme::Default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::keycode::KeyModifiers;
+
+    fn ctrl(ch: char) -> KeyEvent {
+        KeyEvent::new(
+            Keysym(ch as u32),
+            KeyModifiers::new().with_control(true),
+            true,
+        )
+    }
+
+    #[test]
+    fn test_parse_combo() {
+        assert_eq!(KeyCombo::parse("Ctrl+N"), Some(KeyCombo::ctrl_char('N')));
+        assert_eq!(
+            KeyCombo::parse("ctrl+shift+l"),
+            Some(KeyCombo::ctrl_char('l').with_shift())
+        );
+        assert_eq!(
+            KeyCombo::parse("Ctrl+Space"),
+            Some(KeyCombo::ctrl(Keysym::SPACE))
+        );
+        assert_eq!(KeyCombo::parse("F7"), Some(KeyCombo::new(Keysym::F7)));
+        assert_eq!(KeyCombo::parse("j"), Some(KeyCombo::new(Keysym(0x6a))));
+        assert_eq!(KeyCombo::parse("Hyper+j"), None);
+        assert_eq!(KeyCombo::parse("Ctrl+Nope"), None);
+        assert_eq!(KeyCombo::parse("F13"), None);
+    }
+
+    #[test]
+    fn test_match_ignores_letter_case() {
+        let combo = KeyCombo::ctrl_char('n');
+        assert!(combo.matches(&ctrl('n')));
+        assert!(combo.matches(&ctrl('N')));
+        assert!(!combo.matches(&KeyEvent::press(Keysym(0x6e))));
+    }
+
+    #[test]
+    fn test_shifted_binding_wins() {
+        let bindings = KeyBindings::new(KeyBindingScheme::Atok);
+        let ctrl_shift_l = KeyEvent::new(
+            Keysym(0x4c),
+            KeyModifiers::new().with_control(true).with_shift(true),
+            true,
+        );
+        assert_eq!(
+            bindings.action_for(&ctrl_shift_l),
+            Some(KeyAction::ToggleLive)
+        );
+    }
+
+    #[test]
+    fn test_schemes() {
+        let default = KeyBindings::default();
+        assert_eq!(
+            default.action_for(&ctrl('n')),
+            Some(KeyAction::NextCandidate)
+        );
+        assert_eq!(default.action_for(&ctrl('g')), None);
+
+        let emacs = KeyBindings::new(KeyBindingScheme::Emacs);
+        assert_eq!(emacs.action_for(&ctrl('g')), Some(KeyAction::Cancel));
+        assert_eq!(emacs.action_for(&ctrl('h')), Some(KeyAction::Backspace));
+
+        let atok = KeyBindings::new(KeyBindingScheme::Atok);
+        assert_eq!(atok.action_for(&ctrl('x')), Some(KeyAction::NextCandidate));
+        assert_eq!(atok.action_for(&ctrl('i')), Some(KeyAction::EnterKatakana));
+        assert_eq!(atok.action_for(&ctrl('k')), None);
+
+        let kotoeri = KeyBindings::new(KeyBindingScheme::Kotoeri);
+        assert_eq!(
+            kotoeri.action_for(&ctrl('k')),
+            Some(KeyAction::EnterKatakana)
+        );
+        assert_eq!(kotoeri.action_for(&ctrl('h')), Some(KeyAction::Backspace));
+
+        // Word motion is shared by every scheme
+        let ctrl_left = KeyEvent::new(Keysym::LEFT, KeyModifiers::new().with_control(true), true);
+        for bindings in [default, emacs, atok, kotoeri] {
+            assert_eq!(
+                bindings.action_for(&ctrl_left),
+                Some(KeyAction::CaretWordLeft)
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_overrides_scheme() {
+        let mut settings = KeyBindingSettings::default();
+        settings.custom.insert(
+            KeyAction::NextCandidate,
+            vec!["Ctrl+J".to_string(), "bogus+key".to_string()],
+        );
+        let bindings = KeyBindings::from_settings(&settings);
+        assert_eq!(
+            bindings.action_for(&ctrl('j')),
+            Some(KeyAction::NextCandidate)
+        );
+        // The override replaces the scheme's Ctrl+N
+        assert_eq!(bindings.action_for(&ctrl('n')), None);
+        assert_eq!(
+            bindings.action_for(&ctrl('p')),
+            Some(KeyAction::PrevCandidate)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Date/time macro candidates
+//!
+//! Converting "きょう" offers today's date and "いま"/"じこく" the current
+//! time, formatted the way they are usually written in Japanese. The local
+//! time is UTC plus a configured offset, Japan Standard Time by default.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Offset of Japan Standard Time from UTC in minutes (no daylight saving time)
+pub const JST_OFFSET_MINUTES: i32 = 9 * 60;
+
+/// Weekday names, starting from Sunday
+const WEEKDAYS: [&str; 7] = ["日", "月", "火", "水", "木", "金", "土"];
+
+/// First day of the Reiwa era (2019-05-01)
+const REIWA_START: (i64, u32, u32) = (2019, 5, 1);
+
+/// Local calendar date and time of day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LocalDateTime {
+    year: i64,
+    month: u32,
+    day: u32,
+    /// 0 = Sunday
+    weekday: usize,
+    hour: u32,
+    minute: u32,
+}
+
+impl LocalDateTime {
+    fn from_system_time(now: SystemTime, utc_offset_minutes: i32) -> Option<Self> {
+        let secs = now.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64
+            + i64::from(utc_offset_minutes) * 60;
+        let days = secs.div_euclid(86_400);
+        let time = secs.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        Some(Self {
+            year,
+            month,
+            day,
+            // 1970-01-01 was a Thursday
+            weekday: (days + 4).rem_euclid(7) as usize,
+            hour: (time / 3600) as u32,
+            minute: (time % 3600 / 60) as u32,
+        })
+    }
+
+    /// Japanese era form of the date (令和6年1月2日), for dates in the Reiwa era
+    fn reiwa_date(&self) -> Option<String> {
+        if (self.year, self.month, self.day) < REIWA_START {
+            return None;
+        }
+        let era_year = self.year - REIWA_START.0 + 1;
+        let era_year = if era_year == 1 {
+            "元".to_string()
+        } else {
+            era_year.to_string()
+        };
+        Some(format!("令和{}年{}月{}日", era_year, self.month, self.day))
+    }
+}
+
+/// Gregorian (year, month, day) of a day count since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Candidates for a date/time keyword reading at `now`, or an empty list.
+/// The local time is `utc_offset_minutes` ahead of UTC.
+///
+/// - "きょう": 2024年1月2日, 2024年1月2日(火), 2024/01/02, 2024-01-02, 令和6年1月2日
+/// - "いま", "じこく": 03:04, 3時4分
+pub fn expand_date_macros(reading: &str, now: SystemTime, utc_offset_minutes: i32) -> Vec<String> {
+    if !matches!(reading, "きょう" | "いま" | "じこく") {
+        return Vec::new();
+    }
+    let Some(t) = LocalDateTime::from_system_time(now, utc_offset_minutes) else {
+        return Vec::new();
+    };
+    match reading {
+        "きょう" => {
+            let mut dates = vec![
+                format!("{}年{}月{}日", t.year, t.month, t.day),
+                format!(
+                    "{}年{}月{}日({})",
+                    t.year, t.month, t.day, WEEKDAYS[t.weekday]
+                ),
+                format!("{}/{:02}/{:02}", t.year, t.month, t.day),
+                format!("{}-{:02}-{:02}", t.year, t.month, t.day),
+            ];
+            dates.extend(t.reiwa_date());
+            dates
+        }
+        _ => vec![
+            format!("{:02}:{:02}", t.hour, t.minute),
+            format!("{}時{}分", t.hour, t.minute),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    /// 2024-01-02 03:04:05 JST (a Tuesday)
+    fn pinned() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_704_132_245)
+    }
+
+    #[test]
+    fn test_today_formats() {
+        assert_eq!(
+            expand_date_macros("きょう", pinned(), JST_OFFSET_MINUTES),
+            [
+                "2024年1月2日",
+                "2024年1月2日(火)",
+                "2024/01/02",
+                "2024-01-02",
+                "令和6年1月2日",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_formats() {
+        assert_eq!(
+            expand_date_macros("いま", pinned(), JST_OFFSET_MINUTES),
+            ["03:04", "3時4分"]
+        );
+        assert_eq!(
+            expand_date_macros("じこく", pinned(), JST_OFFSET_MINUTES),
+            ["03:04", "3時4分"]
+        );
+    }
+
+    #[test]
+    fn test_date_uses_japan_time() {
+        // 2023-12-31 15:00:00 UTC is already 2024-01-01 in Japan
+        let now = UNIX_EPOCH + Duration::from_secs(1_704_034_800);
+        assert_eq!(
+            expand_date_macros("きょう", now, JST_OFFSET_MINUTES)[0],
+            "2024年1月1日"
+        );
+        assert_eq!(
+            expand_date_macros("いま", now, JST_OFFSET_MINUTES)[0],
+            "00:00"
+        );
+    }
+
+    #[test]
+    fn test_date_uses_configured_offset() {
+        // 2023-12-31 15:00:00 UTC
+        let now = UNIX_EPOCH + Duration::from_secs(1_704_034_800);
+        assert_eq!(expand_date_macros("きょう", now, 0)[0], "2023年12月31日");
+        assert_eq!(expand_date_macros("いま", now, 0)[0], "15:00");
+        // UTC-5 (New York in winter) and UTC+5:30 (India)
+        assert_eq!(expand_date_macros("いま", now, -5 * 60)[0], "10:00");
+        assert_eq!(expand_date_macros("いま", now, 5 * 60 + 30)[0], "20:30");
+    }
+
+    #[test]
+    fn test_reiwa_first_year_and_before() {
+        // 2019-05-01 00:00 JST
+        let reiwa = UNIX_EPOCH + Duration::from_secs(1_556_636_400);
+        assert!(
+            expand_date_macros("きょう", reiwa, JST_OFFSET_MINUTES)
+                .contains(&"令和元年5月1日".to_string())
+        );
+        // 2019-04-30 23:59 JST (Heisei): no Reiwa form
+        let heisei = reiwa - Duration::from_secs(60);
+        let dates = expand_date_macros("きょう", heisei, JST_OFFSET_MINUTES);
+        assert_eq!(dates[0], "2019年4月30日");
+        assert!(dates.iter().all(|d| !d.starts_with("令和")));
+    }
+
+    #[test]
+    fn test_leap_day() {
+        // 2024-02-29 12:00 JST (a Thursday)
+        let now = UNIX_EPOCH + Duration::from_secs(1_709_175_600);
+        assert_eq!(
+            expand_date_macros("きょう", now, JST_OFFSET_MINUTES)[1],
+            "2024年2月29日(木)"
+        );
+    }
+
+    #[test]
+    fn test_other_readings_have_no_macros() {
+        assert!(expand_date_macros("あした", pinned(), JST_OFFSET_MINUTES).is_empty());
+        assert!(expand_date_macros("きょうと", pinned(), JST_OFFSET_MINUTES).is_empty());
+    }
+}
@@ -4,6 +4,11 @@
 
 pub mod candidate;
 pub mod engine;
+pub mod kana_layout;
+pub mod keybindings;
 pub mod keycode;
+pub mod macros;
+pub mod numbers;
 pub mod preedit;
 pub mod state;
+pub mod symbols;
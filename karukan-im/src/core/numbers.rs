@@ -0,0 +1,179 @@
+//! Numeric conversion candidates
+//!
+//! Converting a reading made only of digits ("1000" or "１０００") offers the
+//! number as 漢数字 with units (千), half-/full-width arabic numerals
+//! (1000, １０００), digit-by-digit 漢数字 (一〇〇〇) and a comma-grouped form
+//! (1,000).
+
+/// 漢数字 for 0–9
+const KANJI_DIGITS: [char; 10] = ['〇', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// Units within a group of four digits: ones, 十, 百, 千
+const SMALL_UNITS: [&str; 4] = ["", "十", "百", "千"];
+
+/// Units of each four-digit group: ones, 万, 億, 兆, 京
+const LARGE_UNITS: [&str; 5] = ["", "万", "億", "兆", "京"];
+
+/// Digits of a numeric reading (half- or full-width), or None if the reading
+/// has anything else in it.
+fn parse_digits(reading: &str) -> Option<Vec<u8>> {
+    if reading.is_empty() {
+        return None;
+    }
+    reading
+        .chars()
+        .map(|c| match c {
+            '0'..='9' => Some(c as u8 - b'0'),
+            '０'..='９' => Some((c as u32 - '０' as u32) as u8),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 漢数字 with units (10500 → 一万五百). 一 is omitted before 十 and 百, and
+/// before 千 in the lowest group (1000 → 千, 10000000 → 一千万).
+///
+/// Returns None for numbers of 10^20 or more, which have no unit beyond 京.
+fn to_kanji_with_units(digits: &[u8]) -> Option<String> {
+    let digits = match digits.iter().position(|&d| d != 0) {
+        Some(start) => &digits[start..],
+        None => return Some(KANJI_DIGITS[0].to_string()),
+    };
+    let groups = digits.len().div_ceil(4);
+    if groups > LARGE_UNITS.len() {
+        return None;
+    }
+
+    let mut out = String::new();
+    // Split from the least significant digit into groups of four
+    let lead = digits.len() - (groups - 1) * 4;
+    let chunks = std::iter::once(&digits[..lead]).chain(digits[lead..].chunks(4));
+    for (group, chunk) in (0..groups).rev().zip(chunks) {
+        if chunk.iter().all(|&d| d == 0) {
+            continue;
+        }
+        for (i, &d) in chunk.iter().enumerate() {
+            let place = chunk.len() - 1 - i;
+            if d == 0 {
+                continue;
+            }
+            let omit_one = d == 1 && (place == 1 || place == 2 || (place == 3 && group == 0));
+            if !omit_one {
+                out.push(KANJI_DIGITS[d as usize]);
+            }
+            out.push_str(SMALL_UNITS[place]);
+        }
+        out.push_str(LARGE_UNITS[group]);
+    }
+    Some(out)
+}
+
+/// Arabic numerals with a comma every three digits (10500 → 10,500)
+fn with_commas(ascii: &str) -> String {
+    let len = ascii.len();
+    let mut out = String::with_capacity(len + len / 3);
+    for (i, c) in ascii.chars().enumerate() {
+        if i > 0 && (len - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Candidates for an all-digit reading, or an empty list for any other reading.
+pub fn convert_number(reading: &str) -> Vec<String> {
+    let Some(digits) = parse_digits(reading) else {
+        return Vec::new();
+    };
+    let ascii: String = digits.iter().map(|&d| char::from(b'0' + d)).collect();
+    let fullwidth: String = digits
+        .iter()
+        .filter_map(|&d| char::from_u32('０' as u32 + u32::from(d)))
+        .collect();
+    let positional: String = digits.iter().map(|&d| KANJI_DIGITS[d as usize]).collect();
+
+    let mut forms = Vec::new();
+    forms.extend(to_kanji_with_units(&digits));
+    forms.push(ascii.clone());
+    forms.push(fullwidth);
+    forms.push(positional);
+    if ascii.len() > 3 && !ascii.starts_with('0') {
+        forms.push(with_commas(&ascii));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    forms.retain(|f| seen.insert(f.clone()));
+    forms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero() {
+        assert_eq!(convert_number("0"), ["〇", "0", "０"]);
+    }
+
+    #[test]
+    fn test_twenty_one() {
+        assert_eq!(convert_number("21"), ["二十一", "21", "２１", "二一"]);
+    }
+
+    #[test]
+    fn test_thousand() {
+        assert_eq!(
+            convert_number("1000"),
+            ["千", "1000", "１０００", "一〇〇〇", "1,000"]
+        );
+    }
+
+    #[test]
+    fn test_ten_thousand_five_hundred() {
+        assert_eq!(
+            convert_number("10500"),
+            ["一万五百", "10500", "１０５００", "一〇五〇〇", "10,500"]
+        );
+    }
+
+    #[test]
+    fn test_hundred_million() {
+        assert_eq!(convert_number("100000000")[0], "一億");
+        assert_eq!(convert_number("100000000")[4], "100,000,000");
+    }
+
+    #[test]
+    fn test_large_units() {
+        assert_eq!(convert_number("10000000")[0], "一千万");
+        assert_eq!(convert_number("1111")[0], "千百十一");
+        assert_eq!(convert_number("20000300000001")[0], "二十兆三億一");
+        assert_eq!(convert_number("10000000000000000")[0], "一京");
+        assert_eq!(
+            convert_number("99999999999999999999")[0],
+            "九千九百九十九京九千九百九十九兆九千九百九十九億九千九百九十九万九千九百九十九"
+        );
+        // Beyond 京: no unit form, the other forms remain
+        assert_eq!(
+            convert_number("100000000000000000000")[0],
+            "100000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_fullwidth_reading() {
+        assert_eq!(convert_number("１０００")[..2], ["千", "1000"]);
+    }
+
+    #[test]
+    fn test_leading_zeros_keep_digits() {
+        assert_eq!(convert_number("007"), ["七", "007", "００７", "〇〇七"]);
+    }
+
+    #[test]
+    fn test_non_numeric_reading() {
+        assert!(convert_number("").is_empty());
+        assert!(convert_number("20せいき").is_empty());
+        assert!(convert_number("1.5").is_empty());
+    }
+}
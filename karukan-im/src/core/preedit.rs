@@ -12,7 +12,8 @@ pub enum AttributeType {
     UnderlineDouble,
     /// Highlight for text being converted
     Highlight,
-    /// Reverse video for selected candidate
+    /// Reverse video for the part of the selected candidate shared by all
+    /// candidates on the page
     Reverse,
 }
 
@@ -24,11 +24,35 @@ pub enum InputState {
     Conversion {
         /// The preedit string showing conversion result
         preedit: Preedit,
-        /// List of conversion candidates
-        candidates: CandidateList,
+        /// Segments (bunsetsu) of the reading, in order (never empty)
+        segments: Vec<ConversionSegment>,
+        /// Index of the active segment, whose candidates are shown
+        active: usize,
+        /// Composing text after the caret, left unconverted and restored to
+        /// Composing once the conversion is committed (empty if none)
+        tail: String,
+        /// The top candidate is only previewed in the preedit; the candidate
+        /// window opens on the next Space / Down (`candidate_preview`)
+        preview: bool,
     },
 }
 
+/// A segment (bunsetsu) of the reading in conversion mode
+#[derive(Debug, Clone)]
+pub struct ConversionSegment {
+    /// Reading (hiragana) covered by this segment
+    pub reading: String,
+    /// Conversion candidates for this segment
+    pub candidates: CandidateList,
+}
+
+impl ConversionSegment {
+    /// Text of the selected candidate, or the reading if there is none
+    pub fn selected_text(&self) -> &str {
+        self.candidates.selected_text().unwrap_or(&self.reading)
+    }
+}
+
 impl InputState {
     /// Check if the engine is in the Empty (idle) state
     pub fn is_empty(&self) -> bool {
@@ -53,18 +77,45 @@ impl InputState {
         }
     }
 
-    /// Get candidates in conversion state
+    /// Get the active segment's candidates in conversion state
     pub fn candidates(&self) -> Option<&CandidateList> {
         match self {
-            Self::Conversion { candidates, .. } => Some(candidates),
+            Self::Conversion {
+                segments, active, ..
+            } => segments.get(*active).map(|s| &s.candidates),
             _ => None,
         }
     }
 
-    /// Get mutable reference to candidates
+    /// Get mutable reference to the active segment's candidates
     pub fn candidates_mut(&mut self) -> Option<&mut CandidateList> {
         match self {
-            Self::Conversion { candidates, .. } => Some(candidates),
+            Self::Conversion {
+                segments, active, ..
+            } => segments.get_mut(*active).map(|s| &mut s.candidates),
+            _ => None,
+        }
+    }
+
+    /// Get the unconverted text after the converted part in conversion state
+    pub fn conversion_tail(&self) -> Option<&str> {
+        match self {
+            Self::Conversion { tail, .. } => Some(tail),
+            _ => None,
+        }
+    }
+
+    /// Check if a conversion is only previewed, without the candidate window
+    pub fn is_preview(&self) -> bool {
+        matches!(self, Self::Conversion { preview: true, .. })
+    }
+
+    /// Get the conversion segments and the active segment index
+    pub fn segments(&self) -> Option<(&[ConversionSegment], usize)> {
+        match self {
+            Self::Conversion {
+                segments, active, ..
+            } => Some((segments, *active)),
             _ => None,
         }
     }
@@ -0,0 +1,92 @@
+//! Built-in symbol palette
+//!
+//! Typing a keyword reading such as "やじるし" or "かお" and converting offers
+//! the associated symbols (arrows, brackets, math signs, kaomoji) as candidates.
+
+/// Keyword reading → symbols offered for it, in candidate order.
+///
+/// Kept as a plain table so that entries from a user TSV can be layered on
+/// top later without changing the lookup interface.
+const SYMBOL_TABLE: &[(&str, &[&str])] = &[
+    (
+        "やじるし",
+        &["→", "←", "↑", "↓", "⇒", "⇔", "↔", "↗", "↘", "↙", "↖"],
+    ),
+    (
+        "かっこ",
+        &[
+            "「」", "『』", "（）", "【】", "［］", "｛｝", "〈〉", "《》", "〔〕",
+        ],
+    ),
+    ("まる", &["○", "●", "◎", "◯"]),
+    ("しかく", &["□", "■", "◇", "◆"]),
+    ("さんかく", &["△", "▲", "▽", "▼"]),
+    ("ほし", &["☆", "★", "※"]),
+    (
+        "すうがく",
+        &[
+            "＋", "－", "×", "÷", "＝", "≠", "≒", "＜", "＞", "≦", "≧", "±", "∞", "√", "∑", "∫",
+        ],
+    ),
+    ("かける", &["×"]),
+    ("わる", &["÷"]),
+    ("ぷらすまいなす", &["±"]),
+    ("むげんだい", &["∞"]),
+    ("ゆうびん", &["〒"]),
+    (
+        "かお",
+        &[
+            "(^_^)",
+            "(＾▽＾)",
+            "(´・ω・`)",
+            "(*´ω｀*)",
+            "(;_;)",
+            "(T_T)",
+            "m(_ _)m",
+            "(・∀・)",
+        ],
+    ),
+    ("えがお", &["(^_^)", "(＾▽＾)", "(*´ω｀*)"]),
+    ("なみだ", &["(;_;)", "(T_T)", "(´；ω；`)"]),
+    ("ごめん", &["m(_ _)m", "(>_<)"]),
+];
+
+/// Symbols for a keyword reading (exact match), or an empty slice.
+pub fn lookup_symbols(reading: &str) -> &'static [&'static str] {
+    SYMBOL_TABLE
+        .iter()
+        .find(|(keyword, _)| *keyword == reading)
+        .map_or(&[], |(_, symbols)| symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_arrows() {
+        let symbols = lookup_symbols("やじるし");
+        assert_eq!(&symbols[..4], ["→", "←", "↑", "↓"]);
+    }
+
+    #[test]
+    fn test_lookup_kaomoji() {
+        assert!(lookup_symbols("かお").contains(&"(^_^)"));
+    }
+
+    #[test]
+    fn test_lookup_requires_exact_reading() {
+        assert!(lookup_symbols("やじる").is_empty());
+        assert!(lookup_symbols("やじるしを").is_empty());
+        assert!(lookup_symbols("").is_empty());
+    }
+
+    #[test]
+    fn test_keywords_are_unique_and_nonempty() {
+        let mut seen = std::collections::HashSet::new();
+        for (keyword, symbols) in SYMBOL_TABLE {
+            assert!(seen.insert(*keyword), "duplicate keyword {keyword}");
+            assert!(!symbols.is_empty(), "no symbols for {keyword}");
+        }
+    }
+}
@@ -2,9 +2,10 @@
 
 use std::ffi::{c_char, c_int, c_uint};
 
+use crate::core::engine::{EngineResult, InputMethodEngine, ResetReason};
 use crate::core::keycode::{KeyEvent, KeyModifiers, Keysym};
 
-use super::{KarukanEngine, ffi_mut};
+use super::{KarukanEngine, ffi_mut, mode_from_code, reset_reason_from_code};
 
 /// Process a key event
 /// Returns 1 if the key was consumed, 0 if not
@@ -30,11 +31,79 @@ pub extern "C" fn karukan_engine_process_key(
     if result.consumed { 1 } else { 0 }
 }
 
+/// Apply a candidate page operation and cache the resulting actions.
+/// Returns 1 if the page changed, 0 otherwise.
+fn navigate_page(
+    engine: &mut KarukanEngine,
+    op: impl FnOnce(&mut InputMethodEngine) -> EngineResult,
+) -> c_int {
+    engine.clear_flags();
+    let result = op(&mut engine.engine);
+    engine.apply_actions(result.actions);
+    if result.consumed { 1 } else { 0 }
+}
+
+/// Go to the next candidate page (wraps to the first page)
+/// Returns 1 if the page changed, 0 if not converting
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_next_page(engine: *mut KarukanEngine) -> c_int {
+    let engine = ffi_mut!(engine, 0);
+    navigate_page(engine, |e| e.next_candidate_page())
+}
+
+/// Go to the previous candidate page (wraps to the last page)
+/// Returns 1 if the page changed, 0 if not converting
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_prev_page(engine: *mut KarukanEngine) -> c_int {
+    let engine = ffi_mut!(engine, 0);
+    navigate_page(engine, |e| e.prev_candidate_page())
+}
+
+/// Go to a candidate page (0-indexed)
+/// Returns 1 if the page changed, 0 if not converting or the page is out of range
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_goto_page(engine: *mut KarukanEngine, page: c_uint) -> c_int {
+    let engine = ffi_mut!(engine, 0);
+    navigate_page(engine, |e| e.goto_candidate_page(page as usize))
+}
+
+/// Set the input mode (`KARUKAN_MODE_*`), e.g. from a UI menu
+/// Returns 1 if the mode was set, 0 for an unknown mode
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_set_input_mode(engine: *mut KarukanEngine, mode: u8) -> c_int {
+    let engine = ffi_mut!(engine, 0);
+    let Some(mode) = mode_from_code(mode) else {
+        return 0;
+    };
+    engine.clear_flags();
+    let result = engine.engine.set_input_mode(mode);
+    engine.apply_actions(result.actions);
+    1
+}
+
 /// Reset the engine state
 #[unsafe(no_mangle)]
 pub extern "C" fn karukan_engine_reset(engine: *mut KarukanEngine) {
     let engine = ffi_mut!(engine);
-    engine.engine.reset();
+    reset_engine(engine, ResetReason::Spurious);
+}
+
+/// Reset the engine state for a `KARUKAN_RESET_*` reason; focus-out and
+/// cursor-move resets also clear the surrounding context
+/// Returns 1 if the engine was reset, 0 for an unknown reason
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_reset_with_reason(engine: *mut KarukanEngine, reason: u8) -> c_int {
+    let engine = ffi_mut!(engine, 0);
+    let Some(reason) = reset_reason_from_code(reason) else {
+        return 0;
+    };
+    reset_engine(engine, reason);
+    1
+}
+
+/// Reset the engine and clear the cached FFI output.
+fn reset_engine(engine: &mut KarukanEngine, reason: ResetReason) {
+    engine.engine.reset_with_reason(reason);
     engine.preedit = super::PreeditCache::default();
     engine.candidates = super::CandidateCache::default();
     engine.commit = super::CommitCache::default();
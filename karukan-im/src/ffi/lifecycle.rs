@@ -1,11 +1,14 @@
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
-use std::ffi::c_int;
+use std::ffi::{CStr, c_char, c_int, c_void};
+use std::path::{Path, PathBuf};
+
+use crate::config::settings::Settings;
 
 use crate::config::settings::StrategyMode;
 use crate::core::engine::resolve_variant_id;
 
-use super::{KarukanEngine, ffi_mut, init_logging};
+use super::{CommitCallback, KarukanCommitCallback, KarukanEngine, ffi_mut, init_logging};
 
 /// Create a new Karukan engine instance
 /// Returns a pointer to the engine, or null on failure
@@ -37,6 +40,20 @@ pub extern "C" fn karukan_engine_init(engine: *mut KarukanEngine) -> c_int {
 
     engine.engine.init_user_dictionaries();
 
+    for status in engine.engine.init_extra_dictionaries(
+        &settings.conversion.dictionaries,
+        settings.conversion.dictionary_mode,
+    ) {
+        match status.result {
+            Ok(count) => tracing::info!("Dictionary {:?}: {} readings", status.path, count),
+            Err(e) => tracing::warn!("Dictionary {:?} skipped: {}", status.path, e),
+        }
+    }
+
+    engine
+        .engine
+        .init_phrase_dictionary(settings.conversion.phrase_dict_path.as_deref());
+
     engine
         .engine
         .init_learning_cache(settings.learning.enabled, settings.learning.max_entries);
@@ -84,8 +101,8 @@ pub extern "C" fn karukan_engine_init(engine: *mut KarukanEngine) -> c_int {
             }
             tracing::info!("Main model loaded: {}", engine.engine.model_name());
         }
-        StrategyMode::Adaptive => {
-            // Adaptive mode: load both main and light models
+        StrategyMode::Adaptive | StrategyMode::Rerank => {
+            // Adaptive and rerank modes: load both main and light models
             let main_variant = match resolve_variant_id(settings.conversion.model.as_deref()) {
                 Ok(id) => id,
                 Err(e) => {
@@ -131,6 +148,96 @@ pub extern "C" fn karukan_engine_init(engine: *mut KarukanEngine) -> c_int {
     0
 }
 
+/// Reload the user dictionary without restarting the engine
+/// `path` is a dictionary file or directory; null reloads the default user dictionary directory.
+/// Returns 0 on success, -1 on failure (the previous dictionary is kept)
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_reload_user_dict(
+    engine: *mut KarukanEngine,
+    path: *const c_char,
+) -> c_int {
+    let engine = ffi_mut!(engine, -1);
+    let path = if path.is_null() {
+        match Settings::user_dict_dir() {
+            Some(dir) => dir,
+            None => {
+                tracing::warn!("reload_user_dict: could not determine user dictionary directory");
+                return -1;
+            }
+        }
+    } else {
+        // SAFETY: path pointer is non-null (checked above) and expected to be a valid C string
+        match unsafe { CStr::from_ptr(path) }.to_str() {
+            Ok(s) => PathBuf::from(s),
+            Err(e) => {
+                tracing::warn!("reload_user_dict: invalid UTF-8 path: {}", e);
+                return -1;
+            }
+        }
+    };
+
+    match engine.engine.reload_user_dictionary(&path) {
+        Ok(()) => 0,
+        Err(e) => {
+            tracing::warn!("Failed to reload user dictionary: {:#}", e);
+            -1
+        }
+    }
+}
+
+/// Re-read the settings file and apply the runtime-changeable settings
+/// (candidate counts, page size, strategy, display, key bindings) without
+/// reloading models or the learning cache. A null path reads the default
+/// config file. Returns 0 on success, -1 on failure (the previous settings are kept).
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_reload_settings(
+    engine: *mut KarukanEngine,
+    path: *const c_char,
+) -> c_int {
+    let engine = ffi_mut!(engine, -1);
+    let loaded = if path.is_null() {
+        Settings::load_validated()
+    } else {
+        // SAFETY: path pointer is non-null (checked above) and expected to be a valid C string
+        match unsafe { CStr::from_ptr(path) }.to_str() {
+            Ok(s) => Settings::load_validated_from(Path::new(s)),
+            Err(e) => {
+                tracing::warn!("reload_settings: invalid UTF-8 path: {}", e);
+                return -1;
+            }
+        }
+    };
+
+    match loaded {
+        Ok(mut settings) => {
+            settings.apply_env_overrides();
+            engine.reload_settings(settings);
+            0
+        }
+        Err(e) => {
+            tracing::warn!("Failed to reload settings: {:#}", e);
+            -1
+        }
+    }
+}
+
+/// Register a callback invoked for each commit, or unregister it with a null
+/// `callback`.
+///
+/// The callback runs during the call that produced the commit (e.g.
+/// `karukan_engine_process_key`) with a pointer valid only for its duration,
+/// and must not call back into the engine. While a callback is registered,
+/// commits are not reported through `karukan_engine_has_commit`.
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_set_commit_callback(
+    engine: *mut KarukanEngine,
+    callback: Option<KarukanCommitCallback>,
+    user_data: *mut c_void,
+) {
+    let engine = ffi_mut!(engine);
+    engine.commit_callback = callback.map(|func| CommitCallback { func, user_data });
+}
+
 /// Destroy a Karukan engine instance
 #[unsafe(no_mangle)]
 pub extern "C" fn karukan_engine_free(engine: *mut KarukanEngine) {
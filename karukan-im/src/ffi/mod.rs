@@ -3,7 +3,7 @@
 //! This module provides C-compatible functions that can be called from
 //! the fcitx5 C++ addon wrapper.
 
-use std::ffi::CString;
+use std::ffi::{CString, c_char, c_void};
 use std::sync::Once;
 
 mod input;
@@ -44,8 +44,16 @@ macro_rules! ffi_mut {
 pub(crate) use ffi_mut;
 pub(crate) use ffi_ref;
 
+use karukan_engine::RomajiConfig;
+
 use crate::config::Settings;
-use crate::core::engine::{EngineAction, EngineConfig, InputMethodEngine};
+use crate::config::settings::StrategyMode;
+use crate::core::candidate::CandidateSource;
+use crate::core::engine::{
+    ConversionStrategy, EngineAction, EngineConfig, InputMethodEngine, InputMode, ResetReason,
+};
+use crate::core::keybindings::KeyBindings;
+use crate::core::preedit::AttributeType;
 
 static INIT_LOGGING: Once = Once::new();
 
@@ -66,20 +74,90 @@ fn init_logging() {
 struct PreeditCache {
     text: CString,
     caret_bytes: u32,
+    /// Styled ranges of the text, in bytes
+    attributes: Vec<PreeditSpan>,
     dirty: bool,
 }
 
+/// A styled byte range of the preedit text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PreeditSpan {
+    start: u32,
+    end: u32,
+    /// `KARUKAN_PREEDIT_*` style code
+    attr: u32,
+}
+
+/// Underlined text (composing text, unselected conversion segments)
+pub const KARUKAN_PREEDIT_UNDERLINE: u32 = 0;
+/// Double underline
+pub const KARUKAN_PREEDIT_UNDERLINE_DOUBLE: u32 = 1;
+/// Highlighted text (selected conversion segment, pending romaji)
+pub const KARUKAN_PREEDIT_HIGHLIGHT: u32 = 2;
+/// Reverse video
+pub const KARUKAN_PREEDIT_REVERSE: u32 = 3;
+
+/// `KARUKAN_PREEDIT_*` code of a preedit attribute type
+fn attribute_code(attr_type: AttributeType) -> u32 {
+    match attr_type {
+        AttributeType::Underline => KARUKAN_PREEDIT_UNDERLINE,
+        AttributeType::UnderlineDouble => KARUKAN_PREEDIT_UNDERLINE_DOUBLE,
+        AttributeType::Highlight => KARUKAN_PREEDIT_HIGHLIGHT,
+        AttributeType::Reverse => KARUKAN_PREEDIT_REVERSE,
+    }
+}
+
+/// Byte offset of a character offset in `text` (clamped to the text length)
+fn byte_offset(text: &str, chars: usize) -> u32 {
+    text.char_indices()
+        .nth(chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len()) as u32
+}
+
+/// `text` as a C string, with interior NUL bytes dropped
+fn c_string_lossy(text: &str) -> CString {
+    CString::new(text.replace('\0', "")).unwrap_or_default()
+}
+
 /// Cached candidate list for FFI consumption.
-#[derive(Default)]
 struct CandidateCache {
     texts: Vec<CString>,
     annotations: Vec<CString>,
+    /// `KARUKAN_SOURCE_*` code per candidate
+    sources: Vec<u8>,
     count: usize,
     cursor: usize,
+    /// Current page (0-indexed) and total page count
+    page: usize,
+    total_pages: usize,
+    /// Number of candidates per page
+    page_size: usize,
+    /// False when the whole list is cached, for a scrollable window
+    paged: bool,
     dirty: bool,
     hide: bool,
 }
 
+impl Default for CandidateCache {
+    fn default() -> Self {
+        Self {
+            texts: Vec::new(),
+            annotations: Vec::new(),
+            sources: Vec::new(),
+            count: 0,
+            cursor: 0,
+            page: 0,
+            total_pages: 0,
+            page_size: 0,
+            // Candidates are paged until Ctrl+Tab expands them
+            paged: true,
+            dirty: false,
+            hide: false,
+        }
+    }
+}
+
 /// Cached commit text for FFI consumption.
 #[derive(Default)]
 struct CommitCache {
@@ -87,6 +165,26 @@ struct CommitCache {
     dirty: bool,
 }
 
+/// Called with the committed text (valid only during the call) and the
+/// registered user data.
+pub type KarukanCommitCallback = extern "C" fn(text: *const c_char, user_data: *mut c_void);
+
+/// Commit callback registered with `karukan_set_commit_callback`.
+struct CommitCallback {
+    func: KarukanCommitCallback,
+    user_data: *mut c_void,
+}
+
+/// Pending deletion of committed text for FFI consumption.
+#[derive(Default)]
+struct DeleteSurroundingCache {
+    /// Characters to delete before the cursor
+    before: u32,
+    /// Characters to delete after the cursor
+    after: u32,
+    dirty: bool,
+}
+
 /// Cached aux text for FFI consumption.
 #[derive(Default)]
 struct AuxCache {
@@ -94,6 +192,240 @@ struct AuxCache {
     dirty: bool,
 }
 
+/// Conversion metrics for a status display, filled by `karukan_get_last_metrics`.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct KarukanMetrics {
+    /// Last conversion time in milliseconds (inference only)
+    pub conversion_ms: u64,
+    /// Last process_key time in milliseconds (input to result, end-to-end)
+    pub process_key_ms: u64,
+    /// Number of candidates in the candidate window (0 when hidden)
+    pub candidate_count: u32,
+    /// Strategy of the last model conversion (`KARUKAN_STRATEGY_*`)
+    pub strategy: u32,
+}
+
+/// No model conversion has run yet
+pub const KARUKAN_STRATEGY_NONE: u32 = 0;
+/// Main model greedy only
+pub const KARUKAN_STRATEGY_MAIN_MODEL_ONLY: u32 = 1;
+/// Light model greedy only
+pub const KARUKAN_STRATEGY_LIGHT_MODEL_ONLY: u32 = 2;
+/// Main model greedy + light model beam search in parallel
+pub const KARUKAN_STRATEGY_PARALLEL_BEAM: u32 = 3;
+/// Main model beam search
+pub const KARUKAN_STRATEGY_MAIN_MODEL_BEAM: u32 = 4;
+/// Light model beam search reranked by the main model
+pub const KARUKAN_STRATEGY_DRAFT_THEN_RERANK: u32 = 5;
+
+/// `KARUKAN_STRATEGY_*` code of a conversion strategy
+fn strategy_code(strategy: Option<&ConversionStrategy>) -> u32 {
+    match strategy {
+        None => KARUKAN_STRATEGY_NONE,
+        Some(ConversionStrategy::MainModelOnly) => KARUKAN_STRATEGY_MAIN_MODEL_ONLY,
+        Some(ConversionStrategy::LightModelOnly) => KARUKAN_STRATEGY_LIGHT_MODEL_ONLY,
+        Some(ConversionStrategy::ParallelBeam { .. }) => KARUKAN_STRATEGY_PARALLEL_BEAM,
+        Some(ConversionStrategy::MainModelBeam { .. }) => KARUKAN_STRATEGY_MAIN_MODEL_BEAM,
+        Some(ConversionStrategy::DraftThenRerank { .. }) => KARUKAN_STRATEGY_DRAFT_THEN_RERANK,
+    }
+}
+
+/// Candidate not built by the conversion engine
+pub const KARUKAN_SOURCE_UNKNOWN: u8 = 0;
+/// User dictionary
+pub const KARUKAN_SOURCE_USER_DICTIONARY: u8 = 1;
+/// Learning cache
+pub const KARUKAN_SOURCE_LEARNING: u8 = 2;
+/// Phrase dictionary
+pub const KARUKAN_SOURCE_PHRASE: u8 = 3;
+/// Model inference
+pub const KARUKAN_SOURCE_MODEL: u8 = 4;
+/// System dictionary
+pub const KARUKAN_SOURCE_DICTIONARY: u8 = 5;
+/// Dictionary completion of a longer reading
+pub const KARUKAN_SOURCE_PREDICTION: u8 = 6;
+/// Symbol palette
+pub const KARUKAN_SOURCE_SYMBOL: u8 = 7;
+/// Date/time macro
+pub const KARUKAN_SOURCE_DATE: u8 = 8;
+/// Numeric form of a digit reading
+pub const KARUKAN_SOURCE_NUMBER: u8 = 9;
+/// Hiragana/katakana fallback
+pub const KARUKAN_SOURCE_FALLBACK: u8 = 10;
+/// Katakana form of a loanword reading
+pub const KARUKAN_SOURCE_LOANWORD: u8 = 11;
+
+/// `KARUKAN_SOURCE_*` code of a candidate source
+fn source_code(source: Option<CandidateSource>) -> u8 {
+    match source {
+        None => KARUKAN_SOURCE_UNKNOWN,
+        Some(CandidateSource::UserDictionary) => KARUKAN_SOURCE_USER_DICTIONARY,
+        Some(CandidateSource::Learning) => KARUKAN_SOURCE_LEARNING,
+        Some(CandidateSource::Phrase) => KARUKAN_SOURCE_PHRASE,
+        Some(CandidateSource::Model) => KARUKAN_SOURCE_MODEL,
+        Some(CandidateSource::Dictionary) => KARUKAN_SOURCE_DICTIONARY,
+        Some(CandidateSource::Prediction) => KARUKAN_SOURCE_PREDICTION,
+        Some(CandidateSource::Symbol) => KARUKAN_SOURCE_SYMBOL,
+        Some(CandidateSource::Date) => KARUKAN_SOURCE_DATE,
+        Some(CandidateSource::Number) => KARUKAN_SOURCE_NUMBER,
+        Some(CandidateSource::Fallback) => KARUKAN_SOURCE_FALLBACK,
+        Some(CandidateSource::Loanword) => KARUKAN_SOURCE_LOANWORD,
+    }
+}
+
+/// Hiragana input (default)
+pub const KARUKAN_MODE_HIRAGANA: u8 = 0;
+/// Katakana input
+pub const KARUKAN_MODE_KATAKANA: u8 = 1;
+/// Katakana lock (Space commits, no conversion)
+pub const KARUKAN_MODE_KATAKANA_LOCK: u8 = 2;
+/// Alphabet (direct) input
+pub const KARUKAN_MODE_ALPHABET: u8 = 3;
+/// Kana typed directly on a JIS kana keyboard
+pub const KARUKAN_MODE_KANA_DIRECT: u8 = 4;
+
+/// `KARUKAN_MODE_*` code of an input mode
+fn mode_code(mode: InputMode) -> u8 {
+    match mode {
+        InputMode::Hiragana => KARUKAN_MODE_HIRAGANA,
+        InputMode::Katakana => KARUKAN_MODE_KATAKANA,
+        InputMode::KatakanaLock => KARUKAN_MODE_KATAKANA_LOCK,
+        InputMode::Alphabet => KARUKAN_MODE_ALPHABET,
+        InputMode::KanaDirect => KARUKAN_MODE_KANA_DIRECT,
+    }
+}
+
+/// Input mode of a `KARUKAN_MODE_*` code (None if unknown)
+fn mode_from_code(code: u8) -> Option<InputMode> {
+    match code {
+        KARUKAN_MODE_HIRAGANA => Some(InputMode::Hiragana),
+        KARUKAN_MODE_KATAKANA => Some(InputMode::Katakana),
+        KARUKAN_MODE_KATAKANA_LOCK => Some(InputMode::KatakanaLock),
+        KARUKAN_MODE_ALPHABET => Some(InputMode::Alphabet),
+        KARUKAN_MODE_KANA_DIRECT => Some(InputMode::KanaDirect),
+        _ => None,
+    }
+}
+
+/// Reset without a real change; the surrounding context is kept
+pub const KARUKAN_RESET_SPURIOUS: u8 = 0;
+/// The input context lost focus
+pub const KARUKAN_RESET_FOCUS_OUT: u8 = 1;
+/// The application moved the cursor
+pub const KARUKAN_RESET_CURSOR_MOVED: u8 = 2;
+
+/// Reset reason of a `KARUKAN_RESET_*` code (None if unknown)
+fn reset_reason_from_code(code: u8) -> Option<ResetReason> {
+    match code {
+        KARUKAN_RESET_SPURIOUS => Some(ResetReason::Spurious),
+        KARUKAN_RESET_FOCUS_OUT => Some(ResetReason::FocusOut),
+        KARUKAN_RESET_CURSOR_MOVED => Some(ResetReason::CursorMoved),
+        _ => None,
+    }
+}
+
+/// Engine configuration from settings
+fn engine_config(settings: &Settings) -> EngineConfig {
+    EngineConfig {
+        num_candidates: settings.conversion.num_candidates,
+        display_context_len: 10,
+        max_api_context_len: if settings.conversion.use_context {
+            settings.conversion.max_context_length
+        } else {
+            0
+        },
+        normalize_context: settings.conversion.normalize_context,
+        short_input_threshold: settings.conversion.short_input_threshold,
+        beam_width: settings.conversion.beam_width,
+        max_latency_ms: settings.conversion.max_latency_ms,
+        strategy: settings.conversion.strategy,
+        romaji: RomajiConfig {
+            trailing_n_to_hiragana: settings.romaji.trailing_n_to_hiragana,
+            punctuation_width: settings.romaji.punctuation_width,
+            table: settings.romaji.table,
+        },
+        show_romaji: settings.display.show_romaji,
+        aux_show_reading: settings.display.aux_show_reading,
+        candidate_preview: settings.display.candidate_preview,
+        loanword_katakana: settings.conversion.loanword_katakana,
+        predictive: settings.conversion.predictive,
+        katakana_long_vowel: settings.romaji.katakana_long_vowel,
+        conversion_cache_size: settings.conversion.cache_size,
+        rerank_with_model: settings.conversion.rerank_with_model,
+        auto_segment: settings.conversion.auto_segment,
+        page_size: settings.conversion.page_size,
+        debounce_ms: settings.conversion.debounce_ms,
+        utc_offset_minutes: settings.conversion.utc_offset_minutes,
+        keybindings: KeyBindings::from_settings(&settings.keybindings),
+        labels: settings.display.labels.clone(),
+        learning_max_age_days: settings.learning.max_age_days,
+        learning_score: settings.learning.score_params(),
+    }
+}
+
+/// Settings that are only read at startup (models, dictionaries, learning cache,
+/// romaji table) and differ between `old` and `new`
+fn restart_required_changes(old: &Settings, new: &Settings) -> Vec<&'static str> {
+    let (oc, nc) = (&old.conversion, &new.conversion);
+    let (or, nr) = (&old.romaji, &new.romaji);
+    let switches_light =
+        (oc.strategy == StrategyMode::Light) != (nc.strategy == StrategyMode::Light);
+    [
+        ("conversion.model", oc.model != nc.model),
+        ("conversion.light_model", oc.light_model != nc.light_model),
+        ("conversion.strategy (to or from light)", switches_light),
+        ("conversion.n_threads", oc.n_threads != nc.n_threads),
+        ("conversion.dict_path", oc.dict_path != nc.dict_path),
+        (
+            "conversion.phrase_dict_path",
+            oc.phrase_dict_path != nc.phrase_dict_path,
+        ),
+        (
+            "conversion.dictionaries",
+            oc.dictionaries != nc.dictionaries,
+        ),
+        (
+            "conversion.dictionary_mode",
+            oc.dictionary_mode != nc.dictionary_mode,
+        ),
+        ("conversion.cache_size", oc.cache_size != nc.cache_size),
+        (
+            "learning.enabled",
+            old.learning.enabled != new.learning.enabled,
+        ),
+        (
+            "learning.max_entries",
+            old.learning.max_entries != new.learning.max_entries,
+        ),
+        (
+            "learning.recency_weight",
+            old.learning.recency_weight != new.learning.recency_weight,
+        ),
+        (
+            "learning.frequency_weight",
+            old.learning.frequency_weight != new.learning.frequency_weight,
+        ),
+        (
+            "learning.half_life_days",
+            old.learning.half_life_days != new.learning.half_life_days,
+        ),
+        (
+            "romaji.trailing_n_to_hiragana",
+            or.trailing_n_to_hiragana != nr.trailing_n_to_hiragana,
+        ),
+        (
+            "romaji.punctuation_width",
+            or.punctuation_width != nr.punctuation_width,
+        ),
+        ("romaji.table", or.table != nr.table),
+        ("romaji.custom_romaji", or.custom_romaji != nr.custom_romaji),
+    ]
+    .into_iter()
+    .filter_map(|(name, changed)| changed.then_some(name))
+    .collect()
+}
+
 /// Opaque handle to an IME engine instance
 pub struct KarukanEngine {
     engine: InputMethodEngine,
@@ -101,49 +433,96 @@ pub struct KarukanEngine {
     preedit: PreeditCache,
     candidates: CandidateCache,
     commit: CommitCache,
+    delete_surrounding: DeleteSurroundingCache,
     aux: AuxCache,
     /// Last conversion time in milliseconds (inference only)
     last_conversion_ms: u64,
     /// Last process_key time in milliseconds (input to result, end-to-end)
     last_process_key_ms: u64,
+    /// Strategy of the last model conversion (`KARUKAN_STRATEGY_*`)
+    last_strategy: u32,
+    /// Model name returned by `karukan_get_model_name`
+    model_name: CString,
+    /// Receives commits instead of `CommitCache` when registered
+    commit_callback: Option<CommitCallback>,
 }
 
 impl KarukanEngine {
     fn new() -> Self {
         // Load user settings from config.toml, fall back to defaults
-        let settings = Settings::load().unwrap_or_default();
-
-        let config = EngineConfig {
-            num_candidates: settings.conversion.num_candidates,
-            display_context_len: 10,
-            max_api_context_len: if settings.conversion.use_context {
-                settings.conversion.max_context_length
-            } else {
-                0
-            },
-            short_input_threshold: settings.conversion.short_input_threshold,
-            beam_width: settings.conversion.beam_width,
-            max_latency_ms: settings.conversion.max_latency_ms,
-            strategy: settings.conversion.strategy,
-        };
-        let engine = InputMethodEngine::with_config(config);
+        let mut settings = Settings::load_validated().unwrap_or_else(|e| {
+            tracing::warn!("Invalid config.toml, using default settings: {}", e);
+            Settings::default()
+        });
+        settings.apply_env_overrides();
+
+        let config = engine_config(&settings);
+        let mut engine = InputMethodEngine::with_config(config);
+        if let Some(path) = &settings.romaji.custom_romaji
+            && let Err(e) = engine.load_custom_romaji_rules(std::path::Path::new(path))
+        {
+            tracing::warn!("Failed to load custom romaji rules from {}: {}", path, e);
+        }
         Self {
             engine,
             settings,
             preedit: PreeditCache::default(),
             candidates: CandidateCache::default(),
             commit: CommitCache::default(),
+            delete_surrounding: DeleteSurroundingCache::default(),
             aux: AuxCache::default(),
             last_conversion_ms: 0,
             last_process_key_ms: 0,
+            last_strategy: KARUKAN_STRATEGY_NONE,
+            model_name: CString::default(),
+            commit_callback: None,
         }
     }
 
+    /// Apply the runtime-changeable part of reloaded settings, keeping the loaded
+    /// models, dictionaries and learning cache. Settings only read at startup
+    /// are logged and keep their current value until a restart.
+    fn reload_settings(&mut self, new: Settings) {
+        for name in restart_required_changes(&self.settings, &new) {
+            tracing::warn!("Setting {} changed; restart to apply it", name);
+        }
+
+        let mut applied = self.settings.clone();
+        let (conv, new_conv) = (&mut applied.conversion, &new.conversion);
+        conv.num_candidates = new_conv.num_candidates;
+        conv.page_size = new_conv.page_size;
+        conv.use_context = new_conv.use_context;
+        conv.max_context_length = new_conv.max_context_length;
+        conv.normalize_context = new_conv.normalize_context;
+        conv.short_input_threshold = new_conv.short_input_threshold;
+        conv.beam_width = new_conv.beam_width;
+        conv.max_latency_ms = new_conv.max_latency_ms;
+        conv.loanword_katakana = new_conv.loanword_katakana;
+        conv.predictive = new_conv.predictive;
+        conv.rerank_with_model = new_conv.rerank_with_model;
+        conv.auto_segment = new_conv.auto_segment;
+        conv.debounce_ms = new_conv.debounce_ms;
+        conv.utc_offset_minutes = new_conv.utc_offset_minutes;
+        // Adaptive and main use the models already loaded; light puts another
+        // model in the main slot
+        if (conv.strategy == StrategyMode::Light) == (new_conv.strategy == StrategyMode::Light) {
+            conv.strategy = new_conv.strategy;
+        }
+        applied.romaji.katakana_long_vowel = new.romaji.katakana_long_vowel;
+        applied.display = new.display;
+        applied.keybindings = new.keybindings;
+        applied.learning.max_age_days = new.learning.max_age_days;
+
+        self.engine.update_config(engine_config(&applied));
+        self.settings = applied;
+    }
+
     fn clear_flags(&mut self) {
         self.preedit.dirty = false;
         self.candidates.dirty = false;
         self.candidates.hide = false;
         self.commit.dirty = false;
+        self.delete_surrounding.dirty = false;
         self.aux.dirty = false;
     }
 
@@ -151,39 +530,60 @@ impl KarukanEngine {
     fn sync_timing(&mut self) {
         self.last_conversion_ms = self.engine.last_conversion_ms();
         self.last_process_key_ms = self.engine.last_process_key_ms();
+        self.last_strategy = strategy_code(self.engine.last_strategy());
+    }
+
+    /// Hand committed text to the registered commit callback, or stage it for
+    /// `karukan_engine_get_commit` when none is registered.
+    fn deliver_commit(&mut self, text: String) {
+        let text = CString::new(text).unwrap_or_default();
+        if let Some(callback) = &self.commit_callback {
+            // `text` is dropped right after the call
+            (callback.func)(text.as_ptr(), callback.user_data);
+        } else {
+            self.commit.text = text;
+            self.commit.dirty = true;
+        }
     }
 
     /// Process engine actions and cache results for FFI consumption.
     fn apply_actions(&mut self, actions: Vec<EngineAction>) {
+        // Commits are delivered once every other action is cached, so a
+        // deletion from the same key is always available before the commit
+        let mut commits = Vec::new();
         for action in actions {
             match action {
                 EngineAction::UpdatePreedit(preedit) => {
-                    let caret_chars = preedit.caret();
-                    let caret_bytes = preedit
-                        .text()
-                        .char_indices()
-                        .nth(caret_chars)
-                        .map(|(i, _)| i)
-                        .unwrap_or(preedit.text().len());
-                    self.preedit.caret_bytes = caret_bytes as u32;
+                    let text = preedit.text();
+                    self.preedit.caret_bytes = byte_offset(text, preedit.caret());
+                    self.preedit.attributes = preedit
+                        .attributes()
+                        .iter()
+                        .map(|a| PreeditSpan {
+                            start: byte_offset(text, a.start),
+                            end: byte_offset(text, a.end),
+                            attr: attribute_code(a.attr_type),
+                        })
+                        .collect();
                     self.preedit.text = CString::new(preedit.text()).unwrap_or_default();
                     self.preedit.dirty = true;
                 }
                 EngineAction::ShowCandidates(candidates) => {
                     let page = candidates.page_candidates();
-                    self.candidates.texts = page
-                        .iter()
-                        .filter_map(|c| CString::new(c.text.as_str()).ok())
-                        .collect();
+                    // One entry per candidate, so texts, annotations and
+                    // sources stay index-aligned
+                    self.candidates.texts = page.iter().map(|c| c_string_lossy(&c.text)).collect();
                     self.candidates.annotations = page
                         .iter()
-                        .map(|c| {
-                            let ann = c.annotation.as_deref().unwrap_or("");
-                            CString::new(ann).unwrap_or_default()
-                        })
+                        .map(|c| c_string_lossy(c.annotation.as_deref().unwrap_or("")))
                         .collect();
+                    self.candidates.sources = page.iter().map(|c| source_code(c.source)).collect();
                     self.candidates.count = self.candidates.texts.len();
                     self.candidates.cursor = candidates.page_cursor();
+                    self.candidates.page = candidates.current_page();
+                    self.candidates.total_pages = candidates.total_pages();
+                    self.candidates.page_size = candidates.page_size();
+                    self.candidates.paged = !candidates.is_expanded();
                     self.candidates.dirty = true;
                     self.candidates.hide = false;
                 }
@@ -191,9 +591,11 @@ impl KarukanEngine {
                     self.candidates.hide = true;
                     self.candidates.dirty = true;
                 }
-                EngineAction::Commit(text) => {
-                    self.commit.text = CString::new(text).unwrap_or_default();
-                    self.commit.dirty = true;
+                EngineAction::Commit(text) => commits.push(text),
+                EngineAction::DeleteSurrounding { before, after } => {
+                    self.delete_surrounding.before = before;
+                    self.delete_surrounding.after = after;
+                    self.delete_surrounding.dirty = true;
                 }
                 EngineAction::UpdateAuxText(text) => {
                     self.aux.text = CString::new(text).unwrap_or_default();
@@ -205,5 +607,8 @@ impl KarukanEngine {
                 }
             }
         }
+        for text in commits {
+            self.deliver_commit(text);
+        }
     }
 }
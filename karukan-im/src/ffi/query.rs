@@ -3,7 +3,10 @@
 use std::ffi::{CString, c_char, c_int, c_uint};
 use std::ptr;
 
-use super::{KarukanEngine, ffi_mut, ffi_ref};
+use super::{
+    KARUKAN_MODE_HIRAGANA, KARUKAN_SOURCE_UNKNOWN, KarukanEngine, KarukanMetrics, ffi_mut, ffi_ref,
+    mode_code,
+};
 
 /// Check if there's a preedit update pending
 #[unsafe(no_mangle)]
@@ -34,6 +37,45 @@ pub extern "C" fn karukan_engine_get_preedit_caret(engine: *const KarukanEngine)
     engine.preedit.caret_bytes as c_uint
 }
 
+/// Get the number of styled ranges of the preedit
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_get_preedit_attribute_count(
+    engine: *const KarukanEngine,
+) -> c_uint {
+    let engine = ffi_ref!(engine, 0);
+    engine.preedit.attributes.len() as c_uint
+}
+
+/// Get a styled range of the preedit.
+/// Returns true and writes its byte range and `KARUKAN_PREEDIT_*` style to the
+/// out-pointers (any may be null), or false if the index is out of range.
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_get_preedit_attribute(
+    engine: *const KarukanEngine,
+    index: c_uint,
+    start: *mut u32,
+    end: *mut u32,
+    attr: *mut u32,
+) -> bool {
+    let engine = ffi_ref!(engine, false);
+    let Some(span) = engine.preedit.attributes.get(index as usize) else {
+        return false;
+    };
+    if !start.is_null() {
+        // SAFETY: `start` is non-null (checked above) and the caller passes a writable u32
+        unsafe { *start = span.start };
+    }
+    if !end.is_null() {
+        // SAFETY: `end` is non-null (checked above) and the caller passes a writable u32
+        unsafe { *end = span.end };
+    }
+    if !attr.is_null() {
+        // SAFETY: `attr` is non-null (checked above) and the caller passes a writable u32
+        unsafe { *attr = span.attr };
+    }
+    true
+}
+
 /// Check if there's a commit pending
 #[unsafe(no_mangle)]
 pub extern "C" fn karukan_engine_has_commit(engine: *const KarukanEngine) -> c_int {
@@ -108,6 +150,29 @@ pub extern "C" fn karukan_engine_get_candidate_annotation(
         .unwrap_or(ptr::null())
 }
 
+/// Get a candidate's source (`KARUKAN_SOURCE_*`) by index
+/// Returns `KARUKAN_SOURCE_UNKNOWN` if index is out of range
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_get_candidate_source(
+    engine: *const KarukanEngine,
+    index: c_uint,
+) -> u8 {
+    let engine = ffi_ref!(engine, KARUKAN_SOURCE_UNKNOWN);
+    engine
+        .candidates
+        .sources
+        .get(index as usize)
+        .copied()
+        .unwrap_or(KARUKAN_SOURCE_UNKNOWN)
+}
+
+/// Get the current input mode (`KARUKAN_MODE_*`)
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_get_input_mode(engine: *const KarukanEngine) -> u8 {
+    let engine = ffi_ref!(engine, KARUKAN_MODE_HIRAGANA);
+    mode_code(engine.engine.input_mode())
+}
+
 /// Get the current candidate cursor position
 #[unsafe(no_mangle)]
 pub extern "C" fn karukan_engine_get_candidate_cursor(engine: *const KarukanEngine) -> c_uint {
@@ -115,6 +180,61 @@ pub extern "C" fn karukan_engine_get_candidate_cursor(engine: *const KarukanEngi
     engine.candidates.cursor as c_uint
 }
 
+/// Get the current candidate page (0-indexed)
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_get_candidate_page(engine: *const KarukanEngine) -> c_uint {
+    let engine = ffi_ref!(engine, 0);
+    engine.candidates.page as c_uint
+}
+
+/// Get the total number of candidate pages
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_get_candidate_page_count(engine: *const KarukanEngine) -> c_uint {
+    let engine = ffi_ref!(engine, 0);
+    engine.candidates.total_pages as c_uint
+}
+
+/// Get the number of candidates per page
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_get_candidate_page_size(engine: *const KarukanEngine) -> c_uint {
+    let engine = ffi_ref!(engine, 0);
+    engine.candidates.page_size as c_uint
+}
+
+/// Check whether the candidates are one page (true) or the whole list (false)
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_engine_candidates_paged(engine: *const KarukanEngine) -> bool {
+    let engine = ffi_ref!(engine, true);
+    engine.candidates.paged
+}
+
+/// Take the pending request to delete committed text around the cursor.
+///
+/// Returns true and writes the character counts to `before`/`after` (either
+/// may be null) if a deletion is pending; the request is cleared. Apply it
+/// before the pending commit, if any.
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_take_delete_surrounding(
+    engine: *mut KarukanEngine,
+    before: *mut u32,
+    after: *mut u32,
+) -> bool {
+    let engine = ffi_mut!(engine, false);
+    if !engine.delete_surrounding.dirty {
+        return false;
+    }
+    engine.delete_surrounding.dirty = false;
+    if !before.is_null() {
+        // SAFETY: `before` is non-null (checked above) and the caller passes a writable u32
+        unsafe { *before = engine.delete_surrounding.before };
+    }
+    if !after.is_null() {
+        // SAFETY: `after` is non-null (checked above) and the caller passes a writable u32
+        unsafe { *after = engine.delete_surrounding.after };
+    }
+    true
+}
+
 /// Check if there's an aux text update pending
 #[unsafe(no_mangle)]
 pub extern "C" fn karukan_engine_has_aux(engine: *const KarukanEngine) -> c_int {
@@ -150,6 +270,43 @@ pub extern "C" fn karukan_engine_get_last_process_key_ms(engine: *const KarukanE
     engine.last_process_key_ms
 }
 
+/// Get the name of the loaded model(s), e.g. "main+light" ("unknown" before init)
+/// Returns a pointer to a null-terminated UTF-8 string (valid until the next call)
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_get_model_name(engine: *mut KarukanEngine) -> *const c_char {
+    let engine = ffi_mut!(engine, ptr::null());
+    engine.model_name = CString::new(engine.engine.model_name()).unwrap_or_default();
+    engine.model_name.as_ptr()
+}
+
+/// Fill `metrics` with the metrics of the last processed key.
+/// Returns false if either pointer is null.
+#[unsafe(no_mangle)]
+pub extern "C" fn karukan_get_last_metrics(
+    engine: *const KarukanEngine,
+    metrics: *mut KarukanMetrics,
+) -> bool {
+    let engine = ffi_ref!(engine, false);
+    if metrics.is_null() {
+        return false;
+    }
+    let candidate_count = if engine.candidates.hide {
+        0
+    } else {
+        engine.candidates.count as u32
+    };
+    // SAFETY: `metrics` is non-null (checked above) and the caller passes a writable KarukanMetrics
+    unsafe {
+        *metrics = KarukanMetrics {
+            conversion_ms: engine.last_conversion_ms,
+            process_key_ms: engine.last_process_key_ms,
+            candidate_count,
+            strategy: engine.last_strategy,
+        };
+    }
+    true
+}
+
 /// Save the learning cache to disk if there are unsaved changes.
 /// Called on deactivate (IME switch / window switch) for periodic persistence.
 #[unsafe(no_mangle)]
@@ -182,7 +339,6 @@ pub extern "C" fn karukan_engine_commit(engine: *mut KarukanEngine) -> c_int {
         return 0;
     }
 
-    engine.commit.text = CString::new(text).unwrap_or_default();
-    engine.commit.dirty = true;
+    engine.deliver_commit(text);
     1
 }
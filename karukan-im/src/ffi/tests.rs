@@ -2,7 +2,7 @@ use super::*;
 use input::*;
 use lifecycle::*;
 use query::*;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString, c_char, c_void};
 use std::ptr;
 
 // XKB keysyms for common keys
@@ -13,6 +13,8 @@ const XKB_KEY_RETURN: u32 = 0xff0d;
 const XKB_KEY_ESCAPE: u32 = 0xff1b;
 const XKB_KEY_BACKSPACE: u32 = 0xff08;
 const XKB_KEY_SHIFT_L: u32 = 0xffe1;
+const XKB_KEY_SPACE: u32 = 0x20;
+const XKB_KEY_LEFT: u32 = 0xff51;
 const SHIFT_MASK: u32 = crate::core::keycode::KeyModifiers::SHIFT_MASK;
 
 /// RAII wrapper around a raw `KarukanEngine` pointer.
@@ -111,11 +113,27 @@ fn test_null_engine_safety() {
     assert_eq!(karukan_engine_has_preedit(ptr::null()), 0);
     assert!(karukan_engine_get_preedit(ptr::null()).is_null());
     assert_eq!(karukan_engine_get_preedit_len(ptr::null()), 0);
+    assert_eq!(karukan_engine_get_preedit_attribute_count(ptr::null()), 0);
     assert_eq!(karukan_engine_has_commit(ptr::null()), 0);
     assert!(karukan_engine_get_commit(ptr::null()).is_null());
     assert_eq!(karukan_engine_has_candidates(ptr::null()), 0);
     assert_eq!(karukan_engine_get_candidate_count(ptr::null()), 0);
+    assert_eq!(
+        karukan_engine_get_candidate_source(ptr::null(), 0),
+        KARUKAN_SOURCE_UNKNOWN
+    );
     assert_eq!(karukan_engine_get_last_conversion_ms(ptr::null()), 0);
+    assert_eq!(karukan_engine_get_candidate_page_count(ptr::null()), 0);
+    assert_eq!(karukan_engine_get_candidate_page_size(ptr::null()), 0);
+    assert!(karukan_engine_candidates_paged(ptr::null()));
+    assert!(!karukan_take_delete_surrounding(
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut()
+    ));
+    assert_eq!(karukan_engine_next_page(ptr::null_mut()), 0);
+    assert_eq!(karukan_engine_prev_page(ptr::null_mut()), 0);
+    assert_eq!(karukan_engine_goto_page(ptr::null_mut(), 0), 0);
     karukan_engine_reset(ptr::null_mut());
     karukan_engine_free(ptr::null_mut());
 }
@@ -208,6 +226,46 @@ fn test_reset() {
     assert!(!e.has_candidates());
 }
 
+#[test]
+fn test_reset_with_reason() {
+    let e = TestEngine::new();
+    let text = CString::new("今日は").unwrap();
+    // The left context is shown in the aux text while composing
+    let shows_context = |e: &TestEngine| {
+        e.press(XKB_KEY_A);
+        e.aux().contains("今日は")
+    };
+
+    karukan_engine_set_surrounding_text(e.ptr(), text.as_ptr(), 3);
+    assert!(shows_context(&e));
+    assert_eq!(
+        karukan_reset_with_reason(e.ptr(), KARUKAN_RESET_SPURIOUS),
+        1
+    );
+    assert!(!e.has_preedit());
+    assert!(shows_context(&e));
+    assert_eq!(karukan_reset_with_reason(e.ptr(), 42), 0);
+    assert!(e.has_preedit());
+
+    assert_eq!(
+        karukan_reset_with_reason(e.ptr(), KARUKAN_RESET_FOCUS_OUT),
+        1
+    );
+    assert!(!e.has_preedit());
+    assert!(!shows_context(&e));
+
+    karukan_engine_set_surrounding_text(e.ptr(), text.as_ptr(), 3);
+    assert_eq!(
+        karukan_reset_with_reason(e.ptr(), KARUKAN_RESET_CURSOR_MOVED),
+        1
+    );
+    assert!(!shows_context(&e));
+    assert_eq!(
+        karukan_reset_with_reason(ptr::null_mut(), KARUKAN_RESET_FOCUS_OUT),
+        0
+    );
+}
+
 #[test]
 fn test_key_release_ignored() {
     let e = TestEngine::new();
@@ -245,6 +303,74 @@ fn test_conversion_timing() {
     assert!(timing < 60000); // Should be less than 60 seconds
 }
 
+#[test]
+fn test_metrics_null_safety() {
+    let mut metrics = KarukanMetrics::default();
+    assert!(!karukan_get_last_metrics(ptr::null(), &mut metrics));
+    assert!(karukan_get_model_name(ptr::null_mut()).is_null());
+
+    let e = TestEngine::new();
+    assert!(!karukan_get_last_metrics(e.ptr(), ptr::null_mut()));
+}
+
+#[test]
+fn test_last_metrics_after_key() {
+    let e = TestEngine::new();
+    let mut metrics = KarukanMetrics {
+        conversion_ms: u64::MAX,
+        process_key_ms: u64::MAX,
+        candidate_count: u32::MAX,
+        strategy: u32::MAX,
+    };
+
+    e.press(XKB_KEY_A);
+    e.press(XKB_KEY_I);
+    assert!(karukan_get_last_metrics(e.ptr(), &mut metrics));
+    // Timings are whole milliseconds and may round down to 0 without a model
+    assert!(metrics.conversion_ms < 60000);
+    assert!(metrics.process_key_ms < 60000);
+    assert_eq!(
+        metrics.process_key_ms,
+        karukan_engine_get_last_process_key_ms(e.ptr())
+    );
+    let shown = if karukan_engine_should_hide_candidates(e.ptr()) == 1 {
+        0
+    } else {
+        karukan_engine_get_candidate_count(e.ptr())
+    };
+    assert_eq!(metrics.candidate_count, shown);
+    // No model is loaded in tests, so no model conversion strategy ran
+    assert_eq!(metrics.strategy, KARUKAN_STRATEGY_NONE);
+
+    let name = unsafe { CStr::from_ptr(karukan_get_model_name(e.ptr())) };
+    assert_eq!(name.to_str().unwrap(), "unknown");
+}
+
+#[test]
+fn test_strategy_codes() {
+    assert_eq!(strategy_code(None), KARUKAN_STRATEGY_NONE);
+    assert_eq!(
+        strategy_code(Some(&ConversionStrategy::LightModelOnly)),
+        KARUKAN_STRATEGY_LIGHT_MODEL_ONLY
+    );
+    assert_eq!(
+        strategy_code(Some(&ConversionStrategy::ParallelBeam { beam_width: 3 })),
+        KARUKAN_STRATEGY_PARALLEL_BEAM
+    );
+    assert_eq!(
+        strategy_code(Some(&ConversionStrategy::MainModelBeam { beam_width: 3 })),
+        KARUKAN_STRATEGY_MAIN_MODEL_BEAM
+    );
+    assert_eq!(
+        strategy_code(Some(&ConversionStrategy::MainModelOnly)),
+        KARUKAN_STRATEGY_MAIN_MODEL_ONLY
+    );
+    assert_eq!(
+        strategy_code(Some(&ConversionStrategy::DraftThenRerank { beam_width: 3 })),
+        KARUKAN_STRATEGY_DRAFT_THEN_RERANK
+    );
+}
+
 #[test]
 fn test_surrounding_text_sets_context() {
     let e = TestEngine::new();
@@ -488,3 +614,420 @@ fn test_ffi_standalone_shift_does_not_toggle_mode() {
         "After standalone Shift, 'a' should still produce hiragana"
     );
 }
+
+#[test]
+fn test_page_navigation() {
+    let e = TestEngine::new();
+
+    // Not converting: nothing to page through
+    assert_eq!(karukan_engine_next_page(e.ptr()), 0);
+    assert_eq!(karukan_engine_goto_page(e.ptr(), 0), 0);
+
+    e.press(XKB_KEY_A);
+    e.press(XKB_KEY_SPACE);
+    let pages = karukan_engine_get_candidate_page_count(e.ptr());
+    assert!(pages >= 1);
+    assert_eq!(karukan_engine_get_candidate_page_size(e.ptr()), 9);
+    assert_eq!(karukan_engine_get_candidate_page(e.ptr()), 0);
+
+    // Wrapping navigation always succeeds and refreshes the candidate cache
+    assert_eq!(karukan_engine_next_page(e.ptr()), 1);
+    assert!(e.has_candidates());
+    assert_eq!(karukan_engine_prev_page(e.ptr()), 1);
+    assert_eq!(karukan_engine_get_candidate_page(e.ptr()), 0);
+
+    assert_eq!(karukan_engine_goto_page(e.ptr(), pages - 1), 1);
+    assert_eq!(karukan_engine_get_candidate_page(e.ptr()), pages - 1);
+    assert_eq!(karukan_engine_goto_page(e.ptr(), pages), 0);
+    assert_eq!(karukan_engine_get_candidate_page(e.ptr()), pages - 1);
+}
+
+#[test]
+fn test_expanded_candidates_not_paged() {
+    let e = TestEngine::new();
+    // Paged before the first conversion
+    assert!(karukan_engine_candidates_paged(e.ptr()));
+    for ch in "yajirusi".chars() {
+        e.press(ch as u32);
+    }
+    e.press(XKB_KEY_SPACE);
+    assert!(karukan_engine_candidates_paged(e.ptr()));
+    let page_count = karukan_engine_get_candidate_count(e.ptr());
+
+    let control_mask = crate::core::keycode::KeyModifiers::CONTROL_MASK;
+    assert!(e.press_with(0xff09, control_mask)); // Ctrl+Tab
+    assert!(e.has_candidates());
+    assert!(!karukan_engine_candidates_paged(e.ptr()));
+    assert_eq!(karukan_engine_get_candidate_page_count(e.ptr()), 1);
+    assert!(karukan_engine_get_candidate_count(e.ptr()) >= page_count);
+
+    e.press_with(0xff09, control_mask);
+    assert!(karukan_engine_candidates_paged(e.ptr()));
+    assert_eq!(karukan_engine_get_candidate_count(e.ptr()), page_count);
+
+    // A reset while expanded goes back to the paged default
+    e.press_with(0xff09, control_mask);
+    karukan_engine_reset(e.ptr());
+    assert!(karukan_engine_candidates_paged(e.ptr()));
+}
+
+#[test]
+fn test_candidate_sources_after_conversion() {
+    let e = TestEngine::new();
+    for ch in "yajirusi".chars() {
+        e.press(ch as u32);
+    }
+    e.press(XKB_KEY_SPACE);
+
+    let page = |e: &TestEngine| -> Vec<(String, u8)> {
+        (0..karukan_engine_get_candidate_count(e.ptr()))
+            .map(|i| {
+                let ptr = karukan_engine_get_candidate(e.ptr(), i);
+                let text = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap();
+                (
+                    text.to_string(),
+                    karukan_engine_get_candidate_source(e.ptr(), i),
+                )
+            })
+            .collect()
+    };
+
+    // Symbols come first...
+    let first = page(&e);
+    assert_eq!(first[0], ("→".to_string(), KARUKAN_SOURCE_SYMBOL));
+    assert!(first.iter().all(|(_, s)| *s == KARUKAN_SOURCE_SYMBOL));
+    assert_eq!(
+        karukan_engine_get_candidate_source(e.ptr(), first.len() as u32),
+        KARUKAN_SOURCE_UNKNOWN
+    );
+
+    // ...and the hiragana fallback comes last
+    let pages = karukan_engine_get_candidate_page_count(e.ptr());
+    karukan_engine_goto_page(e.ptr(), pages - 1);
+    let last = page(&e);
+    assert_eq!(
+        last.last().unwrap(),
+        &("やじるし".to_string(), KARUKAN_SOURCE_FALLBACK)
+    );
+}
+
+#[test]
+fn test_set_and_get_input_mode() {
+    let e = TestEngine::new();
+    assert_eq!(karukan_get_input_mode(e.ptr()), KARUKAN_MODE_HIRAGANA);
+
+    for mode in [
+        KARUKAN_MODE_KATAKANA,
+        KARUKAN_MODE_KATAKANA_LOCK,
+        KARUKAN_MODE_ALPHABET,
+        KARUKAN_MODE_KANA_DIRECT,
+        KARUKAN_MODE_HIRAGANA,
+    ] {
+        assert_eq!(karukan_set_input_mode(e.ptr(), mode), 1);
+        assert_eq!(karukan_get_input_mode(e.ptr()), mode);
+    }
+
+    assert_eq!(karukan_set_input_mode(e.ptr(), 42), 0);
+    assert_eq!(karukan_get_input_mode(e.ptr()), KARUKAN_MODE_HIRAGANA);
+    assert_eq!(
+        karukan_set_input_mode(ptr::null_mut(), KARUKAN_MODE_KATAKANA),
+        0
+    );
+    assert_eq!(karukan_get_input_mode(ptr::null()), KARUKAN_MODE_HIRAGANA);
+}
+
+#[test]
+fn test_set_input_mode_handles_pending_input() {
+    let e = TestEngine::new();
+    e.press(XKB_KEY_K);
+    e.press(XKB_KEY_A);
+    assert_eq!(e.preedit(), "か");
+
+    // Leaving katakana keeps the composing text as katakana
+    karukan_set_input_mode(e.ptr(), KARUKAN_MODE_KATAKANA);
+    assert_eq!(e.preedit(), "カ");
+    karukan_set_input_mode(e.ptr(), KARUKAN_MODE_HIRAGANA);
+    assert_eq!(e.preedit(), "カ");
+
+    // Pending romaji is flushed before switching to alphabet
+    e.press(XKB_KEY_K);
+    karukan_set_input_mode(e.ptr(), KARUKAN_MODE_ALPHABET);
+    assert_eq!(e.preedit(), "カk");
+    e.press(XKB_KEY_A);
+    assert_eq!(e.preedit(), "カka");
+}
+
+extern "C" fn collect_commit(text: *const c_char, user_data: *mut c_void) {
+    let commits = unsafe { &mut *(user_data as *mut Vec<String>) };
+    commits.push(
+        unsafe { CStr::from_ptr(text) }
+            .to_str()
+            .unwrap()
+            .to_string(),
+    );
+}
+
+#[test]
+fn test_commit_callback() {
+    let e = TestEngine::new();
+    let mut commits: Vec<String> = Vec::new();
+    karukan_set_commit_callback(
+        e.ptr(),
+        Some(collect_commit),
+        &mut commits as *mut Vec<String> as *mut c_void,
+    );
+
+    e.press(XKB_KEY_K);
+    e.press(XKB_KEY_A);
+    e.press(XKB_KEY_RETURN);
+    assert_eq!(commits, ["か"]);
+    // The commit went to the callback, not the polling cache
+    assert_eq!(karukan_engine_has_commit(e.ptr()), 0);
+
+    karukan_set_commit_callback(e.ptr(), None, ptr::null_mut());
+    e.press(XKB_KEY_A);
+    e.press(XKB_KEY_RETURN);
+    assert_eq!(commits.len(), 1);
+    assert_eq!(karukan_engine_has_commit(e.ptr()), 1);
+    assert_eq!(e.commit_text(), "あ");
+
+    // Null engine is ignored
+    karukan_set_commit_callback(ptr::null_mut(), Some(collect_commit), ptr::null_mut());
+}
+
+#[test]
+fn test_commit_callback_receives_explicit_commit() {
+    let e = TestEngine::new();
+    let mut commits: Vec<String> = Vec::new();
+    karukan_set_commit_callback(
+        e.ptr(),
+        Some(collect_commit),
+        &mut commits as *mut Vec<String> as *mut c_void,
+    );
+
+    e.press(XKB_KEY_K);
+    e.press(XKB_KEY_A);
+    assert_eq!(karukan_engine_commit(e.ptr()), 1);
+    assert_eq!(commits, ["か"]);
+    assert_eq!(karukan_engine_has_commit(e.ptr()), 0);
+}
+
+#[test]
+fn test_source_codes() {
+    assert_eq!(source_code(None), KARUKAN_SOURCE_UNKNOWN);
+    assert_eq!(
+        source_code(Some(CandidateSource::UserDictionary)),
+        KARUKAN_SOURCE_USER_DICTIONARY
+    );
+    assert_eq!(
+        source_code(Some(CandidateSource::Learning)),
+        KARUKAN_SOURCE_LEARNING
+    );
+    assert_eq!(
+        source_code(Some(CandidateSource::Model)),
+        KARUKAN_SOURCE_MODEL
+    );
+    assert_eq!(
+        source_code(Some(CandidateSource::Fallback)),
+        KARUKAN_SOURCE_FALLBACK
+    );
+    assert_eq!(
+        source_code(Some(CandidateSource::Loanword)),
+        KARUKAN_SOURCE_LOANWORD
+    );
+}
+
+#[test]
+fn test_reload_user_dict() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("user.tsv");
+    std::fs::write(&path, "ねこ\t猫\t名詞\t\n").unwrap();
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+    let e = TestEngine::new();
+    assert_eq!(karukan_engine_reload_user_dict(e.ptr(), c_path.as_ptr()), 0);
+
+    let missing = CString::new(dir.path().join("missing.tsv").to_str().unwrap()).unwrap();
+    assert_eq!(
+        karukan_engine_reload_user_dict(e.ptr(), missing.as_ptr()),
+        -1
+    );
+    assert_eq!(
+        karukan_engine_reload_user_dict(ptr::null_mut(), c_path.as_ptr()),
+        -1
+    );
+}
+
+#[test]
+fn test_reload_settings_applies_runtime_settings() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        "[conversion]\nnum_candidates = 5\npage_size = 4\nmodel = \"other-model\"\n",
+    )
+    .unwrap();
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+    let e = TestEngine::new();
+    let model = unsafe { &*e.ptr() }.settings.conversion.model.clone();
+    assert_eq!(karukan_reload_settings(e.ptr(), c_path.as_ptr()), 0);
+
+    let engine = unsafe { &*e.ptr() };
+    assert_eq!(engine.engine.config().num_candidates, 5);
+    // The model is only loaded at startup, so its setting is kept
+    assert_eq!(engine.settings.conversion.model, model);
+
+    // The next conversion pages by the reloaded page size
+    e.press(XKB_KEY_A);
+    e.press(XKB_KEY_SPACE);
+    assert_eq!(karukan_engine_get_candidate_page_size(e.ptr()), 4);
+}
+
+#[test]
+fn test_reload_settings_keeps_settings_on_error() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[conversion\nnum_candidates = 5\n").unwrap();
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+    let e = TestEngine::new();
+    let before = unsafe { &*e.ptr() }.engine.config().num_candidates;
+    assert_eq!(karukan_reload_settings(e.ptr(), c_path.as_ptr()), -1);
+    assert_eq!(unsafe { &*e.ptr() }.engine.config().num_candidates, before);
+    assert_eq!(
+        karukan_reload_settings(ptr::null_mut(), c_path.as_ptr()),
+        -1
+    );
+}
+
+#[test]
+fn test_undo_commit_requests_delete_surrounding() {
+    let e = TestEngine::new();
+    e.press(XKB_KEY_A);
+    e.press(XKB_KEY_RETURN);
+    assert_eq!(e.commit_text(), "あ");
+
+    let (mut before, mut after) = (u32::MAX, u32::MAX);
+    assert!(!karukan_take_delete_surrounding(
+        e.ptr(),
+        &mut before,
+        &mut after
+    ));
+    assert_eq!(before, u32::MAX, "counts untouched when nothing is pending");
+
+    let control_mask = crate::core::keycode::KeyModifiers::CONTROL_MASK;
+    assert!(e.press_with(0x7a, control_mask)); // Ctrl+Z
+    assert_eq!(e.preedit(), "あ");
+    assert!(karukan_take_delete_surrounding(
+        e.ptr(),
+        &mut before,
+        &mut after
+    ));
+    assert_eq!((before, after), (1, 0));
+
+    // Taking clears the request
+    assert!(!karukan_take_delete_surrounding(
+        e.ptr(),
+        ptr::null_mut(),
+        ptr::null_mut()
+    ));
+}
+
+#[test]
+fn test_delete_surrounding_action_is_cached() {
+    let e = TestEngine::new();
+    let engine = unsafe { &mut *e.ptr() };
+    engine.apply_actions(vec![EngineAction::DeleteSurrounding {
+        before: 3,
+        after: 2,
+    }]);
+
+    let (mut before, mut after) = (0, 0);
+    assert!(karukan_take_delete_surrounding(
+        e.ptr(),
+        &mut before,
+        &mut after
+    ));
+    assert_eq!((before, after), (3, 2));
+}
+
+#[test]
+fn test_candidate_with_nul_keeps_indices_aligned() {
+    use crate::core::candidate::{Candidate, CandidateList};
+
+    let e = TestEngine::new();
+    let engine = unsafe { &mut *e.ptr() };
+    let mut bad = Candidate::new("a\0b");
+    bad.annotation = Some("x\0y".to_string());
+    bad.source = Some(CandidateSource::Dictionary);
+    let mut good = Candidate::new("猫");
+    good.source = Some(CandidateSource::Model);
+    engine.apply_actions(vec![EngineAction::ShowCandidates(CandidateList::new(
+        vec![bad, good],
+    ))]);
+
+    assert_eq!(karukan_engine_get_candidate_count(e.ptr()), 2);
+    let text = |i| unsafe { CStr::from_ptr(karukan_engine_get_candidate(e.ptr(), i)) };
+    let annotation =
+        |i| unsafe { CStr::from_ptr(karukan_engine_get_candidate_annotation(e.ptr(), i)) };
+    assert_eq!(text(0).to_str().unwrap(), "ab");
+    assert_eq!(annotation(0).to_str().unwrap(), "xy");
+    assert_eq!(text(1).to_str().unwrap(), "猫");
+    assert_eq!(
+        karukan_engine_get_candidate_source(e.ptr(), 1),
+        KARUKAN_SOURCE_MODEL
+    );
+}
+
+#[test]
+fn test_preedit_caret_bytes_across_segments() {
+    let engine = TestEngine::new();
+    for ch in "kannjik".chars() {
+        engine.press(ch as u32);
+    }
+    // "かんじ" (underlined) + "k" (highlighted): caret after the buffer
+    assert_eq!(engine.preedit(), "かんじk");
+    assert_eq!(
+        karukan_engine_get_preedit_caret(engine.ptr()) as usize,
+        "かんじk".len()
+    );
+
+    // Kana underlined, pending romaji highlighted, as byte ranges
+    let spans: Vec<(u32, u32, u32)> = (0..karukan_engine_get_preedit_attribute_count(engine.ptr()))
+        .map(|i| {
+            let (mut start, mut end, mut attr) = (0, 0, 0);
+            assert!(karukan_engine_get_preedit_attribute(
+                engine.ptr(),
+                i,
+                &mut start,
+                &mut end,
+                &mut attr
+            ));
+            (start, end, attr)
+        })
+        .collect();
+    assert_eq!(
+        spans,
+        [
+            (0, 9, KARUKAN_PREEDIT_UNDERLINE),
+            (9, 10, KARUKAN_PREEDIT_HIGHLIGHT)
+        ]
+    );
+    assert!(!karukan_engine_get_preedit_attribute(
+        engine.ptr(),
+        2,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut()
+    ));
+
+    // Caret inside the kana: byte offset of the character boundary
+    engine.press(XKB_KEY_BACKSPACE);
+    engine.press(XKB_KEY_LEFT);
+    assert_eq!(engine.preedit(), "かんじ");
+    assert_eq!(
+        karukan_engine_get_preedit_caret(engine.ptr()) as usize,
+        "かん".len()
+    );
+}
@@ -0,0 +1,102 @@
+//! Environment variable overrides of the settings file
+//!
+//! Kept in its own test binary so that changing the process environment does
+//! not race with the library tests that load settings.
+
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use karukan_im::config::Settings;
+use karukan_im::config::settings::StrategyMode;
+use tempfile::NamedTempFile;
+
+/// Tests in this file share the process environment
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+const VARS: [&str; 4] = [
+    "KARUKAN_MODEL",
+    "KARUKAN_NUM_CANDIDATES",
+    "KARUKAN_BEAM_WIDTH",
+    "KARUKAN_STRATEGY",
+];
+
+/// Run `f` with the given variables set, removing them afterwards
+fn with_env(vars: &[(&str, &str)], f: impl FnOnce()) {
+    let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    for (name, value) in vars {
+        // SAFETY: every test touching the environment holds ENV_LOCK
+        unsafe { std::env::set_var(name, value) };
+    }
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    for name in VARS {
+        // SAFETY: as above
+        unsafe { std::env::remove_var(name) };
+    }
+    if let Err(e) = result {
+        panic::resume_unwind(e);
+    }
+}
+
+fn load(content: &str) -> Settings {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "{}", content).unwrap();
+    Settings::load_from(file.path()).unwrap()
+}
+
+#[test]
+fn test_env_overrides_win_over_file() {
+    let mut settings = load(
+        r#"
+[conversion]
+model = "file-model"
+num_candidates = 5
+beam_width = 2
+strategy = "main"
+"#,
+    );
+    with_env(
+        &[
+            ("KARUKAN_MODEL", "env-model"),
+            ("KARUKAN_NUM_CANDIDATES", "7"),
+            ("KARUKAN_BEAM_WIDTH", " 4 "),
+            ("KARUKAN_STRATEGY", "light"),
+        ],
+        || settings.apply_env_overrides(),
+    );
+    assert_eq!(settings.conversion.model.as_deref(), Some("env-model"));
+    assert_eq!(settings.conversion.num_candidates, 7);
+    assert_eq!(settings.conversion.beam_width, 4);
+    assert_eq!(settings.conversion.strategy, StrategyMode::Light);
+}
+
+#[test]
+fn test_env_overrides_unset_keep_file_values() {
+    let mut settings = load("[conversion]\nnum_candidates = 5\n");
+    with_env(&[("KARUKAN_BEAM_WIDTH", "6")], || {
+        settings.apply_env_overrides()
+    });
+    assert_eq!(settings.conversion.num_candidates, 5);
+    assert_eq!(settings.conversion.beam_width, 6);
+}
+
+#[test]
+fn test_env_overrides_invalid_values_ignored() {
+    let mut settings = load("[conversion]\nnum_candidates = 5\nbeam_width = 2\n");
+    let default_strategy = settings.conversion.strategy;
+    let default_model = settings.conversion.model.clone();
+    with_env(
+        &[
+            ("KARUKAN_MODEL", "  "),
+            ("KARUKAN_NUM_CANDIDATES", "many"),
+            // Parses, but fails validation
+            ("KARUKAN_BEAM_WIDTH", "0"),
+            ("KARUKAN_STRATEGY", "fast"),
+        ],
+        || settings.apply_env_overrides(),
+    );
+    assert_eq!(settings.conversion.model, default_model);
+    assert_eq!(settings.conversion.num_candidates, 5);
+    assert_eq!(settings.conversion.beam_width, 2);
+    assert_eq!(settings.conversion.strategy, default_strategy);
+}